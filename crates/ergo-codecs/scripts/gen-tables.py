@@ -0,0 +1,89 @@
+#!/usr/bin/env python3
+"""Generate `src/tables.rs` from CPython's codecs
+
+Usage: ./scripts/gen-tables.py > src/tables.rs
+"""
+
+SINGLE_BYTE = [
+    "cp437",
+    "cp850",
+    "cp1250",
+    "cp1251",
+    "cp1252",
+    "iso8859_15",
+    "koi8_r",
+    "mac_roman",
+]
+
+
+def single_byte(name):
+    table = []
+    for b in range(0x80, 0x100):
+        try:
+            c = bytes([b]).decode(name)
+        except UnicodeDecodeError:
+            c = "\0"
+        assert len(c) == 1 and ord(c) <= 0xFFFF
+        table.append(ord(c))
+    return table
+
+
+def shift_jis():
+    leads = list(range(0x81, 0xA0)) + list(range(0xE0, 0xEB))
+    decode = []
+    for lead in leads:
+        for trail in range(0x40, 0xFD):
+            try:
+                c = bytes([lead, trail]).decode("shift_jis")
+            except UnicodeDecodeError:
+                c = "\0"
+            assert len(c) == 1 and ord(c) <= 0xFFFF
+            decode.append(ord(c))
+
+    encode = []
+    for c in range(0x80, 0x10000):
+        if 0xD800 <= c <= 0xDFFF:
+            continue
+        try:
+            b = chr(c).encode("shift_jis")
+        except UnicodeEncodeError:
+            continue
+        encode.append((c, int.from_bytes(b, "big")))
+    return leads, decode, encode
+
+
+def array(values, per_line=12):
+    lines = []
+    for i in range(0, len(values), per_line):
+        lines.append("    " + " ".join(f"{v}," for v in values[i : i + per_line]))
+    return "\n".join(lines)
+
+
+def main():
+    print("// @generated by scripts/gen-tables.py from CPython's codecs; do not edit")
+    print()
+    for name in SINGLE_BYTE:
+        table = single_byte(name)
+        print("#[rustfmt::skip]")
+        print(f"pub(crate) static {name.upper()}: [u16; 128] = [")
+        print(array([f"0x{v:04X}" for v in table]))
+        print("];")
+        print()
+
+    leads, decode, encode = shift_jis()
+    assert leads == list(range(0x81, 0xA0)) + list(range(0xE0, 0xEB))
+    print("/// Indexed by `lead_index * 189 + (trail - 0x40)` where leads are `0x81..=0x9F, 0xE0..=0xEA`")
+    print("#[rustfmt::skip]")
+    print(f"pub(crate) static SHIFT_JIS_DECODE: [u16; {len(decode)}] = [")
+    print(array([f"0x{v:04X}" for v in decode]))
+    print("];")
+    print()
+    print("/// `(char, code)` sorted by `char`, for every non-ASCII character")
+    print("#[rustfmt::skip]")
+    print(f"pub(crate) static SHIFT_JIS_ENCODE: [(u16, u16); {len(encode)}] = [")
+    print(array([f"(0x{c:04X}, 0x{b:04X})" for c, b in encode], per_line=6))
+    print("];")
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,226 @@
+use std::fmt;
+
+/// The error returned when bytes can't be decoded with [`Errors::Strict`][crate::Errors::Strict]
+///
+/// `Display` matches Python's `UnicodeDecodeError`.
+///
+/// # Examples
+///
+/// ```
+/// use ergo_codecs::{Codec, Errors};
+///
+/// let err = Codec::lookup("cp1252").unwrap().decode(b"a\x81", Errors::Strict).unwrap_err();
+///
+/// assert_eq!(err.start(), 1);
+/// assert_eq!(err.end(), 2);
+/// assert_eq!(
+///     err.to_string(),
+///     "'cp1252' codec can't decode byte 0x81 in position 1: character maps to <undefined>"
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodeError {
+    encoding: &'static str,
+    bytes: ergo_core::ByteString,
+    start: usize,
+    reason: &'static str,
+}
+
+impl DecodeError {
+    pub(crate) fn new(
+        encoding: &'static str,
+        bytes: &[u8],
+        start: usize,
+        reason: &'static str,
+    ) -> Self {
+        Self {
+            encoding,
+            bytes: ergo_core::ByteString::from(bytes),
+            start,
+            reason,
+        }
+    }
+
+    /// Python's canonical name for the encoding.
+    pub fn encoding(&self) -> &'static str {
+        self.encoding
+    }
+
+    /// The bytes that could not be decoded.
+    pub fn bytes(&self) -> ergo_core::ByteString {
+        self.bytes.clone()
+    }
+
+    /// The byte offset where the malformed input starts.
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// The byte offset where the malformed input ends.
+    pub fn end(&self) -> usize {
+        self.start + self.bytes.byte_len()
+    }
+
+    /// Why the input could not be decoded.
+    pub fn reason(&self) -> &'static str {
+        self.reason
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bytes = self.bytes.as_bytes();
+        if bytes.len() == 1 {
+            write!(
+                f,
+                "'{}' codec can't decode byte {:#04x} in position {}: {}",
+                self.encoding, bytes[0], self.start, self.reason
+            )
+        } else {
+            write!(
+                f,
+                "'{}' codec can't decode bytes in position {}-{}: {}",
+                self.encoding,
+                self.start,
+                self.end() - 1,
+                self.reason
+            )
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// The error returned when a string can't be encoded with
+/// [`Errors::Strict`][crate::Errors::Strict]
+///
+/// Positions are byte offsets into the string, unlike Python's `UnicodeEncodeError` which
+/// counts code points.
+///
+/// # Examples
+///
+/// ```
+/// use ergo_codecs::{Codec, Errors};
+///
+/// let err = Codec::lookup("ascii").unwrap().encode("naïve", Errors::Strict).unwrap_err();
+///
+/// assert_eq!(err.character(), 'ï');
+/// assert_eq!((err.start(), err.end()), (2, 4));
+/// assert_eq!(
+///     err.to_string(),
+///     "'ascii' codec can't encode character '\\xef' in position 2: ordinal not in range(128)"
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EncodeError {
+    encoding: &'static str,
+    character: char,
+    start: usize,
+    reason: &'static str,
+}
+
+impl EncodeError {
+    pub(crate) fn new(
+        encoding: &'static str,
+        character: char,
+        start: usize,
+        reason: &'static str,
+    ) -> Self {
+        Self {
+            encoding,
+            character,
+            start,
+            reason,
+        }
+    }
+
+    /// Python's canonical name for the encoding.
+    pub fn encoding(&self) -> &'static str {
+        self.encoding
+    }
+
+    /// The character that could not be encoded.
+    pub fn character(&self) -> char {
+        self.character
+    }
+
+    /// The byte offset of the character.
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// The byte offset after the character.
+    pub fn end(&self) -> usize {
+        self.start + self.character.len_utf8()
+    }
+
+    /// Why the character could not be encoded.
+    pub fn reason(&self) -> &'static str {
+        self.reason
+    }
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let escaped = match self.character as u32 {
+            code if code < 0x100 => format!("\\x{:02x}", code),
+            code if code < 0x10000 => format!("\\u{:04x}", code),
+            code => format!("\\U{:08x}", code),
+        };
+        write!(
+            f,
+            "'{}' codec can't encode character '{}' in position {}: {}",
+            self.encoding, escaped, self.start, self.reason
+        )
+    }
+}
+
+impl std::error::Error for EncodeError {}
+
+/// The error returned by [`decode`][crate::decode] and [`encode`][crate::encode]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// No encoding is known by this name, like Python's `LookupError`
+    UnknownEncoding(ergo_core::String),
+    /// No error handler is known by this name, like Python's `LookupError`
+    UnknownErrorHandler(ergo_core::String),
+    /// See [`DecodeError`]
+    Decode(DecodeError),
+    /// See [`EncodeError`]
+    Encode(EncodeError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownEncoding(name) => write!(f, "unknown encoding: {}", name),
+            Self::UnknownErrorHandler(name) => {
+                write!(f, "unknown error handler name '{}'", name)
+            }
+            Self::Decode(err) => fmt::Display::fmt(err, f),
+            Self::Encode(err) => fmt::Display::fmt(err, f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::UnknownEncoding(_) | Self::UnknownErrorHandler(_) => None,
+            Self::Decode(err) => Some(err),
+            Self::Encode(err) => Some(err),
+        }
+    }
+}
+
+impl From<DecodeError> for Error {
+    fn from(err: DecodeError) -> Self {
+        Self::Decode(err)
+    }
+}
+
+impl From<EncodeError> for Error {
+    fn from(err: EncodeError) -> Self {
+        Self::Encode(err)
+    }
+}
@@ -0,0 +1,539 @@
+//! Legacy text encodings, mirroring Python's `codecs`
+//!
+//! Encodings are looked up by their Python names and aliases, like `"latin-1"`, `"cp1252"` or
+//! `"sjis"`, and malformed input is handled according to Python's error handlers (see
+//! [`Errors`]).
+//!
+//! # Examples
+//!
+//! ```
+//! use ergo_codecs::{Errors, StringExt};
+//!
+//! let s = ergo_core::String::decode(b"caf\xe9 \x80", "cp1252", Errors::Strict).unwrap();
+//! assert_eq!(s, "café €");
+//!
+//! let bytes = s.encode("latin-1", Errors::Replace).unwrap();
+//! assert_eq!(bytes, b"caf\xe9 ?");
+//! ```
+
+mod error;
+mod shift_jis;
+mod single_byte;
+mod tables;
+
+pub use error::DecodeError;
+pub use error::EncodeError;
+pub use error::Error;
+
+use ergo_core::ByteString;
+use ergo_core::String;
+
+type StdString = std::string::String;
+
+/// How to handle input that can't be decoded or encoded, mirroring Python's `errors` argument
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Errors {
+    /// Fail with a [`DecodeError`] or [`EncodeError`]
+    Strict,
+    /// Decode to `U+FFFD REPLACEMENT CHARACTER` and encode to `?`
+    Replace,
+    /// Skip the malformed input
+    Ignore,
+    /// Replace with backslashed escape sequences, like `\xff` or `\u20ac`
+    BackslashReplace,
+}
+
+impl std::str::FromStr for Errors {
+    type Err = Error;
+
+    /// Parse Python's error handler names
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "strict" => Ok(Self::Strict),
+            "replace" => Ok(Self::Replace),
+            "ignore" => Ok(Self::Ignore),
+            "backslashreplace" => Ok(Self::BackslashReplace),
+            _ => Err(Error::UnknownErrorHandler(String::from(s))),
+        }
+    }
+}
+
+/// A text encoding
+///
+/// # Examples
+///
+/// ```
+/// use ergo_codecs::{Codec, Errors};
+///
+/// let codec = Codec::lookup("Windows-1252").unwrap();
+/// assert_eq!(codec.name(), "cp1252");
+///
+/// let s = codec.decode(b"\x93quoted\x94", Errors::Strict).unwrap();
+/// assert_eq!(s, "“quoted”");
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Codec {
+    name: &'static str,
+    kind: Kind,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Kind {
+    Ascii,
+    Utf8,
+    Latin1,
+    SingleByte(&'static [u16; 128]),
+    ShiftJis,
+}
+
+/// Python's canonical name, the codec, and the normalized aliases it is known by
+static CODECS: &[(&str, Kind, &[&str])] = &[
+    ("ascii", Kind::Ascii, &["ascii", "us_ascii", "646"]),
+    ("utf-8", Kind::Utf8, &["utf_8", "utf8", "u8", "utf"]),
+    (
+        "iso8859-1",
+        Kind::Latin1,
+        &[
+            "latin_1",
+            "latin1",
+            "latin",
+            "l1",
+            "iso8859_1",
+            "iso_8859_1",
+            "8859",
+            "cp819",
+            "iso_ir_100",
+        ],
+    ),
+    (
+        "cp437",
+        Kind::SingleByte(&tables::CP437),
+        &["cp437", "437", "ibm437"],
+    ),
+    (
+        "cp850",
+        Kind::SingleByte(&tables::CP850),
+        &["cp850", "850", "ibm850"],
+    ),
+    (
+        "cp1250",
+        Kind::SingleByte(&tables::CP1250),
+        &["cp1250", "windows_1250"],
+    ),
+    (
+        "cp1251",
+        Kind::SingleByte(&tables::CP1251),
+        &["cp1251", "windows_1251"],
+    ),
+    (
+        "cp1252",
+        Kind::SingleByte(&tables::CP1252),
+        &["cp1252", "windows_1252"],
+    ),
+    (
+        "iso8859-15",
+        Kind::SingleByte(&tables::ISO8859_15),
+        &["iso8859_15", "iso_8859_15", "latin9", "latin_9", "l9"],
+    ),
+    ("koi8-r", Kind::SingleByte(&tables::KOI8_R), &["koi8_r"]),
+    (
+        "mac-roman",
+        Kind::SingleByte(&tables::MAC_ROMAN),
+        &["mac_roman", "macroman", "macintosh"],
+    ),
+    (
+        "shift_jis",
+        Kind::ShiftJis,
+        &["shift_jis", "shiftjis", "sjis", "s_jis", "csshiftjis"],
+    ),
+];
+
+impl Codec {
+    /// Find an encoding by name, ignoring case and punctuation like Python does.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ergo_codecs::Codec;
+    ///
+    /// assert_eq!(Codec::lookup("Latin-1").unwrap().name(), "iso8859-1");
+    /// assert_eq!(Codec::lookup("SJIS").unwrap().name(), "shift_jis");
+    /// assert!(Codec::lookup("klingon").is_none());
+    /// ```
+    pub fn lookup(encoding: impl AsRef<str>) -> Option<Self> {
+        let normalized = normalize(encoding.as_ref());
+        CODECS
+            .iter()
+            .find(|(_, _, aliases)| aliases.contains(&normalized.as_str()))
+            .map(|(name, kind, _)| Self { name, kind: *kind })
+    }
+
+    /// Python's canonical name for the encoding.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Decodes `bytes` into a [`String`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ergo_codecs::{Codec, Errors};
+    ///
+    /// let codec = Codec::lookup("ascii").unwrap();
+    ///
+    /// let err = codec.decode(b"na\xefve", Errors::Strict).unwrap_err();
+    /// assert_eq!(err.start(), 2);
+    ///
+    /// let s = codec.decode(b"na\xefve", Errors::BackslashReplace).unwrap();
+    /// assert_eq!(s, "na\\xefve");
+    /// ```
+    pub fn decode(&self, bytes: impl AsRef<[u8]>, errors: Errors) -> Result<String, DecodeError> {
+        let bytes = bytes.as_ref();
+        match self.kind {
+            Kind::Ascii => decode_with(self.name, bytes, errors, |bytes| {
+                if bytes[0].is_ascii() {
+                    Step::Char(bytes[0] as char, 1)
+                } else {
+                    Step::Invalid(1, "ordinal not in range(128)")
+                }
+            }),
+            Kind::Utf8 => match std::str::from_utf8(bytes) {
+                Ok(s) => Ok(String::from(s)),
+                Err(_) => decode_with(self.name, bytes, errors, decode_utf8),
+            },
+            Kind::Latin1 => Ok(bytes.iter().map(|b| *b as char).collect()),
+            Kind::SingleByte(table) => decode_with(self.name, bytes, errors, |bytes| {
+                single_byte::decode(table, bytes[0])
+                    .map(|c| Step::Char(c, 1))
+                    .unwrap_or(Step::Invalid(1, "character maps to <undefined>"))
+            }),
+            Kind::ShiftJis => decode_with(self.name, bytes, errors, shift_jis::decode),
+        }
+    }
+
+    /// Encodes `s` into a [`ByteString`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ergo_codecs::{Codec, Errors};
+    ///
+    /// let codec = Codec::lookup("shift_jis").unwrap();
+    ///
+    /// let bytes = codec.encode("日本語", Errors::Strict).unwrap();
+    /// assert_eq!(bytes, b"\x93\xfa\x96\x7b\x8c\xea");
+    ///
+    /// let err = codec.encode("😀", Errors::Strict).unwrap_err();
+    /// assert_eq!(err.character(), '😀');
+    /// ```
+    pub fn encode(&self, s: impl AsRef<str>, errors: Errors) -> Result<ByteString, EncodeError> {
+        let s = s.as_ref();
+        match self.kind {
+            Kind::Ascii => encode_with(self.name, s, errors, "ordinal not in range(128)", |c| {
+                if c.is_ascii() {
+                    Some(Code::Single(c as u8))
+                } else {
+                    None
+                }
+            }),
+            Kind::Utf8 => Ok(ByteString::from(s)),
+            Kind::Latin1 => encode_with(self.name, s, errors, "ordinal not in range(256)", |c| {
+                if (c as u32) < 0x100 {
+                    Some(Code::Single(c as u8))
+                } else {
+                    None
+                }
+            }),
+            Kind::SingleByte(table) => {
+                encode_with(self.name, s, errors, "character maps to <undefined>", |c| {
+                    single_byte::encode(table, c).map(Code::Single)
+                })
+            }
+            Kind::ShiftJis => encode_with(
+                self.name,
+                s,
+                errors,
+                "illegal multibyte sequence",
+                shift_jis::encode,
+            ),
+        }
+    }
+}
+
+/// Decodes `bytes` with the named encoding.
+///
+/// # Examples
+///
+/// ```
+/// use ergo_codecs::Errors;
+///
+/// let s = ergo_codecs::decode(b"\xcf\xf0\xe8\xe2\xe5\xf2", "cp1251", Errors::Strict).unwrap();
+/// assert_eq!(s, "Привет");
+/// ```
+pub fn decode(
+    bytes: impl AsRef<[u8]>,
+    encoding: impl AsRef<str>,
+    errors: Errors,
+) -> Result<String, Error> {
+    let codec = lookup(encoding.as_ref())?;
+    Ok(codec.decode(bytes, errors)?)
+}
+
+/// Encodes `s` with the named encoding.
+///
+/// # Examples
+///
+/// ```
+/// use ergo_codecs::Errors;
+///
+/// let bytes = ergo_codecs::encode("façade", "cp437", Errors::Strict).unwrap();
+/// assert_eq!(bytes, b"fa\x87ade");
+/// ```
+pub fn encode(
+    s: impl AsRef<str>,
+    encoding: impl AsRef<str>,
+    errors: Errors,
+) -> Result<ByteString, Error> {
+    let codec = lookup(encoding.as_ref())?;
+    Ok(codec.encode(s, errors)?)
+}
+
+/// Python's `bytes.decode` and `str.encode` for [`String`]
+pub trait StringExt: Sized {
+    /// See [`decode`]
+    fn decode(
+        bytes: impl AsRef<[u8]>,
+        encoding: impl AsRef<str>,
+        errors: Errors,
+    ) -> Result<Self, Error>;
+
+    /// See [`encode`]
+    fn encode(&self, encoding: impl AsRef<str>, errors: Errors) -> Result<ByteString, Error>;
+}
+
+impl StringExt for String {
+    fn decode(
+        bytes: impl AsRef<[u8]>,
+        encoding: impl AsRef<str>,
+        errors: Errors,
+    ) -> Result<Self, Error> {
+        decode(bytes, encoding, errors)
+    }
+
+    fn encode(&self, encoding: impl AsRef<str>, errors: Errors) -> Result<ByteString, Error> {
+        encode(self, encoding, errors)
+    }
+}
+
+fn lookup(encoding: &str) -> Result<Codec, Error> {
+    Codec::lookup(encoding).ok_or_else(|| Error::UnknownEncoding(String::from(encoding)))
+}
+
+/// Lowercase and collapse punctuation to `_`, like Python's `encodings.normalize_encoding`
+fn normalize(encoding: &str) -> StdString {
+    let mut normalized = StdString::with_capacity(encoding.len());
+    let mut punctuation = false;
+    for c in encoding.trim().chars() {
+        if c.is_ascii_alphanumeric() || c == '.' {
+            if punctuation && !normalized.is_empty() {
+                normalized.push('_');
+            }
+            punctuation = false;
+            normalized.push(c.to_ascii_lowercase());
+        } else {
+            punctuation = true;
+        }
+    }
+    normalized
+}
+
+/// The outcome of decoding from the start of the remaining input
+pub(crate) enum Step {
+    /// The decoded character and how many bytes it took
+    Char(char, usize),
+    /// How many bytes are malformed, and why
+    Invalid(usize, &'static str),
+}
+
+/// An encoded character
+pub(crate) enum Code {
+    Single(u8),
+    Double(u8, u8),
+}
+
+fn decode_with(
+    encoding: &'static str,
+    bytes: &[u8],
+    errors: Errors,
+    mut step: impl FnMut(&[u8]) -> Step,
+) -> Result<String, DecodeError> {
+    let mut decoded = StdString::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        match step(&bytes[index..]) {
+            Step::Char(c, len) => {
+                decoded.push(c);
+                index += len;
+            }
+            Step::Invalid(len, reason) => {
+                let invalid = &bytes[index..index + len];
+                match errors {
+                    Errors::Strict => {
+                        return Err(DecodeError::new(encoding, invalid, index, reason));
+                    }
+                    Errors::Replace => decoded.push(std::char::REPLACEMENT_CHARACTER),
+                    Errors::Ignore => {}
+                    Errors::BackslashReplace => {
+                        for b in invalid {
+                            decoded.push_str(&format!("\\x{:02x}", b));
+                        }
+                    }
+                }
+                index += len;
+            }
+        }
+    }
+    Ok(String::from(decoded))
+}
+
+fn encode_with(
+    encoding: &'static str,
+    s: &str,
+    errors: Errors,
+    reason: &'static str,
+    mut step: impl FnMut(char) -> Option<Code>,
+) -> Result<ByteString, EncodeError> {
+    let mut encoded = Vec::with_capacity(s.len());
+    for (index, c) in s.char_indices() {
+        match step(c) {
+            Some(Code::Single(b)) => encoded.push(b),
+            Some(Code::Double(lead, trail)) => encoded.extend_from_slice(&[lead, trail]),
+            None => match errors {
+                Errors::Strict => {
+                    return Err(EncodeError::new(encoding, c, index, reason));
+                }
+                Errors::Replace => encoded.push(b'?'),
+                Errors::Ignore => {}
+                Errors::BackslashReplace => {
+                    let escaped = match c as u32 {
+                        code if code < 0x100 => format!("\\x{:02x}", code),
+                        code if code < 0x10000 => format!("\\u{:04x}", code),
+                        code => format!("\\U{:08x}", code),
+                    };
+                    encoded.extend_from_slice(escaped.as_bytes());
+                }
+            },
+        }
+    }
+    Ok(ByteString::from(encoded))
+}
+
+fn decode_utf8(bytes: &[u8]) -> Step {
+    let candidate = &bytes[..bytes.len().min(4)];
+    let valid = match std::str::from_utf8(candidate) {
+        Ok(s) => s,
+        Err(err) if 0 < err.valid_up_to() => {
+            // SAFETY: `valid_up_to` is a char boundary
+            unsafe { std::str::from_utf8_unchecked(&candidate[..err.valid_up_to()]) }
+        }
+        Err(err) => {
+            let len = err.error_len().unwrap_or(candidate.len());
+            let reason = match (err.error_len(), candidate[0]) {
+                (None, _) => "unexpected end of data",
+                // A valid lead byte means a later byte of the sequence is at fault
+                (Some(_), 0xC2..=0xF4) => "invalid continuation byte",
+                (Some(_), _) => "invalid start byte",
+            };
+            return Step::Invalid(len, reason);
+        }
+    };
+    let c = valid.chars().next().expect("at least one byte is valid");
+    Step::Char(c, c.len_utf8())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn normalize_like_python() {
+        assert_eq!(normalize("Latin-1"), "latin_1");
+        assert_eq!(normalize(" UTF--8 "), "utf_8");
+        assert_eq!(normalize("-shift jis"), "shift_jis");
+    }
+
+    #[test]
+    fn error_handlers() {
+        let codec = Codec::lookup("cp1252").unwrap();
+        let fixture = b"a\x81b";
+        assert!(codec.decode(fixture, Errors::Strict).is_err());
+        assert_eq!(
+            codec.decode(fixture, Errors::Replace).unwrap(),
+            "a\u{FFFD}b"
+        );
+        assert_eq!(codec.decode(fixture, Errors::Ignore).unwrap(), "ab");
+        assert_eq!(
+            codec.decode(fixture, Errors::BackslashReplace).unwrap(),
+            "a\\x81b"
+        );
+
+        let codec = Codec::lookup("latin-1").unwrap();
+        let fixture = "a€Ā😀";
+        let err = codec.encode(fixture, Errors::Strict).unwrap_err();
+        assert_eq!((err.start(), err.end()), (1, 4));
+        assert_eq!(codec.encode(fixture, Errors::Replace).unwrap(), b"a???");
+        assert_eq!(codec.encode(fixture, Errors::Ignore).unwrap(), b"a");
+        assert_eq!(
+            codec.encode(fixture, Errors::BackslashReplace).unwrap(),
+            &b"a\\u20ac\\u0100\\U0001f600"[..]
+        );
+    }
+
+    #[test]
+    fn single_byte_roundtrip() {
+        for (name, kind, _) in CODECS {
+            if let Kind::SingleByte(_) = kind {
+                let codec = Codec::lookup(name).unwrap();
+                let bytes: Vec<u8> = (0..=255).collect();
+                let decoded = codec.decode(&bytes, Errors::Ignore).unwrap();
+                let encoded = codec.encode(&decoded, Errors::Strict).unwrap();
+                let expected: Vec<u8> = bytes
+                    .iter()
+                    .copied()
+                    .filter(|b| codec.decode([*b], Errors::Strict).is_ok())
+                    .collect();
+                assert_eq!(encoded, expected, "{}", name);
+            }
+        }
+    }
+
+    #[test]
+    fn utf8_errors() {
+        let codec = Codec::lookup("utf-8").unwrap();
+        assert_eq!(
+            codec.decode(b"a\xffb\xe2\x82", Errors::Replace).unwrap(),
+            "a\u{FFFD}b\u{FFFD}"
+        );
+        assert_eq!(
+            codec.decode(b"\xe2\x82\xac\xff", Errors::Ignore).unwrap(),
+            "€"
+        );
+
+        // Reasons and spans from CPython's `bytes.decode("utf-8")`
+        let cases: &[(&[u8], usize, usize, &str)] = &[
+            (b"a\xffb", 1, 2, "invalid start byte"),
+            (b"\x80", 0, 1, "invalid start byte"),
+            (b"\xc0\x80", 0, 1, "invalid start byte"),
+            (b"a\xe2\x28\xa1", 1, 2, "invalid continuation byte"),
+            (b"\xf0\x9f\x98x", 0, 3, "invalid continuation byte"),
+            (b"\xe0\x80\x80", 0, 1, "invalid continuation byte"),
+            (b"\xed\xa0\x80", 0, 1, "invalid continuation byte"),
+            (b"a\xe2\x82", 1, 3, "unexpected end of data"),
+        ];
+        for &(bytes, start, end, reason) in cases {
+            let err = codec.decode(bytes, Errors::Strict).unwrap_err();
+            assert_eq!((err.start(), err.end(), err.reason()), (start, end, reason));
+        }
+    }
+}
@@ -0,0 +1,95 @@
+//! Shift-JIS, as Python's `shift_jis` codec (JIS X 0201 and JIS X 0208)
+
+use crate::tables::{SHIFT_JIS_DECODE, SHIFT_JIS_ENCODE};
+use crate::{Code, Step};
+
+const TRAIL_COUNT: usize = 0xFD - 0x40;
+const ILLEGAL: &str = "illegal multibyte sequence";
+const INCOMPLETE: &str = "incomplete multibyte sequence";
+
+fn lead_index(lead: u8) -> Option<usize> {
+    match lead {
+        0x81..=0x9F => Some(usize::from(lead - 0x81)),
+        0xE0..=0xEA => Some(usize::from(lead - 0xE0) + 31),
+        _ => None,
+    }
+}
+
+pub(crate) fn decode(bytes: &[u8]) -> Step {
+    let lead = bytes[0];
+    match lead {
+        0x00..=0x7F => Step::Char(lead as char, 1),
+        // Half-width katakana
+        0xA1..=0xDF => {
+            let c = std::char::from_u32(0xFF61 + u32::from(lead - 0xA1)).expect("katakana");
+            Step::Char(c, 1)
+        }
+        _ => {
+            let lead = match lead_index(lead) {
+                Some(lead) => lead,
+                None => return Step::Invalid(1, ILLEGAL),
+            };
+            let trail = match bytes.get(1) {
+                Some(trail) => *trail,
+                None => return Step::Invalid(1, INCOMPLETE),
+            };
+            if !(0x40..=0xFC).contains(&trail) {
+                return Step::Invalid(1, ILLEGAL);
+            }
+            match SHIFT_JIS_DECODE[lead * TRAIL_COUNT + usize::from(trail - 0x40)] {
+                0 => Step::Invalid(1, ILLEGAL),
+                code => match std::char::from_u32(u32::from(code)) {
+                    Some(c) => Step::Char(c, 2),
+                    None => Step::Invalid(1, ILLEGAL),
+                },
+            }
+        }
+    }
+}
+
+pub(crate) fn encode(c: char) -> Option<Code> {
+    if c.is_ascii() {
+        return Some(Code::Single(c as u8));
+    }
+    let code = c as u32;
+    if 0x10000 <= code {
+        return None;
+    }
+    let index = SHIFT_JIS_ENCODE
+        .binary_search_by_key(&(code as u16), |(c, _)| *c)
+        .ok()?;
+    match SHIFT_JIS_ENCODE[index].1 {
+        code if code < 0x100 => Some(Code::Single(code as u8)),
+        code => Some(Code::Double((code >> 8) as u8, code as u8)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn tables_roundtrip() {
+        for (c, code) in SHIFT_JIS_ENCODE.iter() {
+            let bytes = match encode(std::char::from_u32(u32::from(*c)).unwrap()) {
+                Some(Code::Single(b)) => vec![b],
+                Some(Code::Double(lead, trail)) => vec![lead, trail],
+                None => panic!("{:#x} is in the table", c),
+            };
+            let expected = if *code < 0x100 {
+                vec![*code as u8]
+            } else {
+                vec![(*code >> 8) as u8, *code as u8]
+            };
+            assert_eq!(bytes, expected);
+        }
+    }
+
+    #[test]
+    fn errors_span_one_byte() {
+        assert!(matches!(decode(b"\x81"), Step::Invalid(1, INCOMPLETE)));
+        assert!(matches!(decode(b"\x81\x20"), Step::Invalid(1, ILLEGAL)));
+        assert!(matches!(decode(b"\xa0"), Step::Invalid(1, ILLEGAL)));
+        assert!(matches!(decode(b"\x88\x9f"), Step::Char('亜', 2)));
+    }
+}
@@ -0,0 +1,24 @@
+//! Table-driven codecs that are ASCII for `0x00..=0x7F`
+
+/// Decodes `byte` with `table`, which maps `0x80..=0xFF` with `0` for unmapped bytes.
+pub(crate) fn decode(table: &[u16; 128], byte: u8) -> Option<char> {
+    if byte.is_ascii() {
+        return Some(byte as char);
+    }
+    match table[usize::from(byte - 0x80)] {
+        0 => None,
+        code => std::char::from_u32(u32::from(code)),
+    }
+}
+
+/// Encodes `c` with `table`; the tables are small enough that a scan beats building an index.
+pub(crate) fn encode(table: &[u16; 128], c: char) -> Option<u8> {
+    if c.is_ascii() {
+        return Some(c as u8);
+    }
+    let code = c as u32;
+    table
+        .iter()
+        .position(|entry| *entry != 0 && u32::from(*entry) == code)
+        .map(|index| index as u8 + 0x80)
+}
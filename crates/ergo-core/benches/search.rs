@@ -0,0 +1,69 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+/// Roughly 4 MiB of English-like text, with the needles only at the end
+fn haystack() -> String {
+    let line = "The quick brown fox jumps over the lazy dog, then naps in the sun.\n";
+    let mut haystack = line.repeat(4 * 1024 * 1024 / line.len());
+    haystack.push_str("Sphinx of black quartz, judge my vow: zebra\n");
+    haystack
+}
+
+const NEEDLES: &[(&str, &str)] = &[
+    ("rare_byte", "Z"),
+    ("common_byte_absent", "!"),
+    ("word", "zebra"),
+    ("sentence", "Sphinx of black quartz"),
+    (
+        "common_prefix_absent",
+        "the lazy dog, then naps in the shade",
+    ),
+];
+
+fn bench_find(c: &mut Criterion) {
+    let haystack = haystack();
+    let ergo = ergo_core::String::from(haystack.as_str());
+
+    let mut group = c.benchmark_group("find");
+    group.throughput(Throughput::Bytes(haystack.len() as u64));
+    for (name, needle) in NEEDLES {
+        group.bench_with_input(BenchmarkId::new("ergo", name), needle, |b, needle| {
+            b.iter(|| black_box(&ergo).find(black_box(*needle)))
+        });
+        group.bench_with_input(BenchmarkId::new("std", name), needle, |b, needle| {
+            b.iter(|| black_box(&haystack).find(black_box(*needle)))
+        });
+    }
+    group.finish();
+}
+
+fn bench_pathological(c: &mut Criterion) {
+    let haystack = "a".repeat(1024 * 1024);
+    let ergo = ergo_core::String::from(haystack.as_str());
+    let needle = format!("{}b", "a".repeat(63));
+
+    let mut group = c.benchmark_group("find_pathological");
+    group.throughput(Throughput::Bytes(haystack.len() as u64));
+    group.bench_function("ergo", |b| {
+        b.iter(|| black_box(&ergo).find(black_box(&needle)))
+    });
+    group.bench_function("std", |b| {
+        b.iter(|| black_box(&haystack).find(black_box(needle.as_str())))
+    });
+    group.finish();
+}
+
+fn bench_split(c: &mut Criterion) {
+    let haystack = "field,".repeat(64 * 1024);
+    let ergo = ergo_core::String::from(haystack.as_str());
+
+    let mut group = c.benchmark_group("split");
+    group.throughput(Throughput::Bytes(haystack.len() as u64));
+    group.bench_function("ergo", |b| b.iter(|| black_box(&ergo).split(",").len()));
+    group.bench_function("std", |b| {
+        b.iter(|| black_box(&haystack).split(',').count())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_find, bench_pathological, bench_split);
+criterion_main!(benches);
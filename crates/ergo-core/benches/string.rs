@@ -0,0 +1,109 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+type StdString = std::string::String;
+
+const FIXTURES: &[(&str, &str)] = &[
+    ("empty", ""),
+    ("inline", "Hello, world!"),
+    ("max_inline", "Hello, world! Hello, wo"),
+    (
+        "shared",
+        "Hello, world! Hello, world! Hello, world! Hello, world!",
+    ),
+];
+
+fn bench_from_str(c: &mut Criterion) {
+    let mut group = c.benchmark_group("from_str");
+    for (name, fixture) in FIXTURES {
+        group.bench_with_input(BenchmarkId::new("ergo", name), fixture, |b, fixture| {
+            b.iter(|| ergo_core::String::from(black_box(*fixture)))
+        });
+        group.bench_with_input(BenchmarkId::new("std", name), fixture, |b, fixture| {
+            b.iter(|| StdString::from(black_box(*fixture)))
+        });
+    }
+    group.finish();
+}
+
+fn bench_clone(c: &mut Criterion) {
+    let mut group = c.benchmark_group("clone");
+    for (name, fixture) in FIXTURES {
+        let ergo = ergo_core::String::from(*fixture);
+        group.bench_with_input(BenchmarkId::new("ergo", name), &ergo, |b, s| {
+            b.iter(|| black_box(s).clone())
+        });
+        let local = ergo_core::LocalString::from(*fixture);
+        group.bench_with_input(BenchmarkId::new("local", name), &local, |b, s| {
+            b.iter(|| black_box(s).clone())
+        });
+        let std = StdString::from(*fixture);
+        group.bench_with_input(BenchmarkId::new("std", name), &std, |b, s| {
+            b.iter(|| black_box(s).clone())
+        });
+    }
+    group.finish();
+}
+
+fn bench_as_str(c: &mut Criterion) {
+    let mut group = c.benchmark_group("as_str");
+    for (name, fixture) in FIXTURES {
+        let ergo = ergo_core::String::from(*fixture);
+        group.bench_with_input(BenchmarkId::new("ergo", name), &ergo, |b, s| {
+            b.iter(|| black_box(s).as_str().len())
+        });
+        let std = StdString::from(*fixture);
+        group.bench_with_input(BenchmarkId::new("std", name), &std, |b, s| {
+            b.iter(|| black_box(s).as_str().len())
+        });
+    }
+    group.finish();
+}
+
+fn bench_slice(c: &mut Criterion) {
+    let mut group = c.benchmark_group("slice");
+    for (name, fixture) in FIXTURES {
+        let ergo = ergo_core::String::from(*fixture);
+        group.bench_with_input(BenchmarkId::new("ergo", name), &ergo, |b, s| {
+            b.iter(|| black_box(s).get(1..))
+        });
+        let std = StdString::from(*fixture);
+        group.bench_with_input(BenchmarkId::new("std", name), &std, |b, s| {
+            b.iter(|| black_box(s).get(1..).map(StdString::from))
+        });
+    }
+    group.finish();
+}
+
+fn bench_lookup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("lookup");
+    for (name, fixture) in FIXTURES {
+        let ergo = ergo_core::String::from(*fixture);
+        let std_map: std::collections::HashMap<_, _> =
+            std::iter::once((ergo.clone(), ())).collect();
+        group.bench_with_input(BenchmarkId::new("std_hasher", name), &ergo, |b, s| {
+            b.iter(|| std_map.get(black_box(s)))
+        });
+        let fast_map: ergo_core::hash::FastHashMap<_, _> =
+            std::iter::once((ergo.clone(), ())).collect();
+        group.bench_with_input(BenchmarkId::new("fast_hasher", name), &ergo, |b, s| {
+            b.iter(|| fast_map.get(black_box(s)))
+        });
+        let hashed = ergo_core::HashedString::from(ergo);
+        let hashed_map: ergo_core::hash::FastHashMap<_, _> =
+            std::iter::once((hashed.clone(), ())).collect();
+        group.bench_with_input(BenchmarkId::new("hashed", name), &hashed, |b, s| {
+            b.iter(|| hashed_map.get(black_box(s)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_from_str,
+    bench_clone,
+    bench_as_str,
+    bench_slice,
+    bench_lookup
+);
+criterion_main!(benches);
@@ -0,0 +1,31 @@
+#!/usr/bin/env python3
+"""Generate `src/string/casefold_table.rs` from CPython's `str.casefold`
+
+Only the characters whose case folding differs from lowercasing are listed; everything else
+folds with `char::to_lowercase`.
+
+Usage: ./scripts/gen-casefold.py > src/string/casefold_table.rs
+"""
+
+
+def escape(s):
+    return "".join(c if c.isprintable() and c.isascii() else "\\u{%x}" % ord(c) for c in s)
+
+
+def main():
+    special = [
+        (c, c.casefold())
+        for c in map(chr, range(0x110000))
+        if not 0xD800 <= ord(c) <= 0xDFFF and c.casefold() != c.lower()
+    ]
+    print("// @generated by scripts/gen-casefold.py from CPython's `str.casefold`; do not edit")
+    print()
+    print("#[rustfmt::skip]")
+    print("pub(super) static CASEFOLD: [(char, &str); %d] = [" % len(special))
+    for c, folded in special:
+        print('    (\'%s\', "%s"),' % (escape(c), escape(folded)))
+    print("];")
+
+
+if __name__ == "__main__":
+    main()
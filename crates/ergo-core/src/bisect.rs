@@ -0,0 +1,258 @@
+//! Binary search on sorted slices and `Vec`s, like Python's `bisect`
+//!
+//! | Python                     | Rust                                                  |
+//! |----------------------------|-------------------------------------------------------|
+//! | `bisect_left(a, x)`        | [`bisect_left(&a, &x)`][bisect_left]                  |
+//! | `bisect(a, x)`             | [`bisect(&a, &x)`][bisect]                            |
+//! | `bisect_left(a, k, key=f)` | [`bisect_left_by_key(&a, &k, f)`][bisect_left_by_key] |
+//! | `insort(a, x)`             | [`insort(&mut a, x)`][insort]                         |
+//! | `insort(a, x, key=f)`      | [`insort_by_key(&mut a, x, f)`][insort_by_key]        |
+//!
+//! Instead of Python's `lo` and `hi` arguments, search a sub-slice and add its start to the
+//! result, as in `lo + bisect_left(&a[lo..hi], &x)`.  A [`List`][crate::List] can be searched
+//! through [`List::as_slice`][crate::List::as_slice].
+//!
+//! # Examples
+//!
+//! ```
+//! use ergo_core::bisect::{bisect, insort};
+//!
+//! let mut deadlines = vec![9, 12, 17];
+//! insort(&mut deadlines, 14);
+//! assert_eq!(deadlines, [9, 12, 14, 17]);
+//!
+//! let grades = [60, 70, 80, 90];
+//! assert_eq!("FDCBA".as_bytes()[bisect(&grades, &85)], b'B');
+//! ```
+
+/// Returns the first index where `x` could be inserted into the sorted `items`, which is before
+/// any items equal to it.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// let items = [1, 2, 2, 3];
+///
+/// assert_eq!(ergo_core::bisect::bisect_left(&items, &2), 1);
+/// assert_eq!(ergo_core::bisect::bisect_left(&items, &5), 4);
+/// ```
+#[inline]
+pub fn bisect_left<T: Ord>(items: &[T], x: &T) -> usize {
+    items.partition_point(|item| item < x)
+}
+
+/// Returns the last index where `x` could be inserted into the sorted `items`, which is after
+/// any items equal to it.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// let items = [1, 2, 2, 3];
+///
+/// assert_eq!(ergo_core::bisect::bisect_right(&items, &2), 3);
+/// assert_eq!(ergo_core::bisect::bisect_right(&items, &0), 0);
+/// ```
+#[inline]
+pub fn bisect_right<T: Ord>(items: &[T], x: &T) -> usize {
+    items.partition_point(|item| item <= x)
+}
+
+/// Same as [`bisect_right`], like Python's `bisect`.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// assert_eq!(ergo_core::bisect::bisect(&[1, 2, 2, 3], &2), 3);
+/// ```
+#[inline]
+pub fn bisect<T: Ord>(items: &[T], x: &T) -> usize {
+    bisect_right(items, x)
+}
+
+/// Returns the first index where an item with key `k` could be inserted into `items`, which
+/// must be sorted by `key`, like Python's `bisect_left(items, k, key=key)`.
+///
+/// As in Python, `k` is a key rather than an item.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// let shifts = [("ada", 9), ("alan", 13), ("grace", 13)];
+/// let index = ergo_core::bisect::bisect_left_by_key(&shifts, &13, |(_, start)| *start);
+///
+/// assert_eq!(index, 1);
+/// ```
+#[inline]
+pub fn bisect_left_by_key<T, K: Ord>(items: &[T], k: &K, mut key: impl FnMut(&T) -> K) -> usize {
+    items.partition_point(|item| key(item) < *k)
+}
+
+/// Returns the last index where an item with key `k` could be inserted into `items`, which must
+/// be sorted by `key`, like Python's `bisect_right(items, k, key=key)`.
+///
+/// As in Python, `k` is a key rather than an item.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// let shifts = [("ada", 9), ("alan", 13), ("grace", 13)];
+/// let index = ergo_core::bisect::bisect_right_by_key(&shifts, &13, |(_, start)| *start);
+///
+/// assert_eq!(index, 3);
+/// ```
+#[inline]
+pub fn bisect_right_by_key<T, K: Ord>(items: &[T], k: &K, mut key: impl FnMut(&T) -> K) -> usize {
+    items.partition_point(|item| key(item) <= *k)
+}
+
+/// Inserts `x` into the sorted `items`, before any items equal to it.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// let mut items = vec![(1, "a"), (3, "c")];
+/// ergo_core::bisect::insort_left(&mut items, (2, "b"));
+///
+/// assert_eq!(items, [(1, "a"), (2, "b"), (3, "c")]);
+/// ```
+#[inline]
+pub fn insort_left<T: Ord>(items: &mut Vec<T>, x: T) {
+    let index = bisect_left(items, &x);
+    items.insert(index, x);
+}
+
+/// Inserts `x` into the sorted `items`, after any items equal to it.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// let mut items = vec![1, 3];
+/// ergo_core::bisect::insort_right(&mut items, 2);
+///
+/// assert_eq!(items, [1, 2, 3]);
+/// ```
+#[inline]
+pub fn insort_right<T: Ord>(items: &mut Vec<T>, x: T) {
+    let index = bisect_right(items, &x);
+    items.insert(index, x);
+}
+
+/// Same as [`insort_right`], like Python's `insort`.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// let mut items = vec![1, 3];
+/// ergo_core::bisect::insort(&mut items, 2);
+///
+/// assert_eq!(items, [1, 2, 3]);
+/// ```
+#[inline]
+pub fn insort<T: Ord>(items: &mut Vec<T>, x: T) {
+    insort_right(items, x)
+}
+
+/// Inserts `x` into `items`, which must be sorted by `key`, before any items with an equal key,
+/// like Python's `insort_left(items, x, key=key)`.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// let mut queue = vec![("build", 1), ("test", 2)];
+/// ergo_core::bisect::insort_left_by_key(&mut queue, ("lint", 2), |(_, priority)| *priority);
+///
+/// assert_eq!(queue, [("build", 1), ("lint", 2), ("test", 2)]);
+/// ```
+#[inline]
+pub fn insort_left_by_key<T, K: Ord>(items: &mut Vec<T>, x: T, mut key: impl FnMut(&T) -> K) {
+    let k = key(&x);
+    let index = bisect_left_by_key(items, &k, key);
+    items.insert(index, x);
+}
+
+/// Inserts `x` into `items`, which must be sorted by `key`, after any items with an equal key,
+/// like Python's `insort_right(items, x, key=key)`.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// let mut queue = vec![("build", 1), ("test", 2)];
+/// ergo_core::bisect::insort_right_by_key(&mut queue, ("lint", 2), |(_, priority)| *priority);
+///
+/// assert_eq!(queue, [("build", 1), ("test", 2), ("lint", 2)]);
+/// ```
+#[inline]
+pub fn insort_right_by_key<T, K: Ord>(items: &mut Vec<T>, x: T, mut key: impl FnMut(&T) -> K) {
+    let k = key(&x);
+    let index = bisect_right_by_key(items, &k, key);
+    items.insert(index, x);
+}
+
+/// Same as [`insort_right_by_key`], like Python's `insort(items, x, key=key)`.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// let mut queue = vec![("build", 1), ("test", 2)];
+/// ergo_core::bisect::insort_by_key(&mut queue, ("lint", 2), |(_, priority)| *priority);
+///
+/// assert_eq!(queue, [("build", 1), ("test", 2), ("lint", 2)]);
+/// ```
+#[inline]
+pub fn insort_by_key<T, K: Ord>(items: &mut Vec<T>, x: T, key: impl FnMut(&T) -> K) {
+    insort_right_by_key(items, x, key)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn matches_linear_search() {
+        for len in 0..12 {
+            let items: Vec<u32> = (0..len).map(|i| i / 3 * 2).collect();
+            for x in 0..10 {
+                let left = items.iter().take_while(|&&item| item < x).count();
+                let right = items.iter().take_while(|&&item| item <= x).count();
+                assert_eq!(bisect_left(&items, &x), left);
+                assert_eq!(bisect_right(&items, &x), right);
+                assert_eq!(bisect_left_by_key(&items, &(x * 10), |i| i * 10), left);
+                assert_eq!(bisect_right_by_key(&items, &(x * 10), |i| i * 10), right);
+            }
+        }
+    }
+
+    #[test]
+    fn insort_keeps_order() {
+        let mut left = Vec::new();
+        let mut right = Vec::new();
+        for (i, k) in [3, 1, 3, 2, 1, 3].iter().enumerate() {
+            insort_left_by_key(&mut left, (*k, i), |&(k, _)| k);
+            insort_by_key(&mut right, (*k, i), |&(k, _)| k);
+        }
+        assert_eq!(left, [(1, 4), (1, 1), (2, 3), (3, 5), (3, 2), (3, 0)]);
+        assert_eq!(right, [(1, 1), (1, 4), (2, 3), (3, 0), (3, 2), (3, 5)]);
+    }
+}
@@ -0,0 +1,558 @@
+use std::borrow::Borrow;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::iter::FromIterator;
+use std::ops;
+
+use crate::dict::{self, Dict};
+use crate::hash::BuildFastHasher;
+
+/// Counts of hashable items, like Python's `collections.Counter`
+///
+/// Missing items count as zero, and only positive counts are stored.  Items are kept in the
+/// order they were first counted.
+///
+/// # Examples
+///
+/// ```
+/// use ergo_core::collections::Counter;
+///
+/// let mut votes: Counter<&str> = ["red", "blue", "red"].iter().copied().collect();
+/// votes.add("green", 2);
+///
+/// assert_eq!(votes["red"], 2);
+/// assert_eq!(votes["purple"], 0);
+/// assert_eq!(votes.most_common(2), [("red", 2), ("green", 2)]);
+/// ```
+pub struct Counter<T, S = BuildFastHasher> {
+    counts: Dict<T, usize, S>,
+}
+
+impl<T> Counter<T> {
+    /// Creates a new empty `Counter`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let c = ergo_core::collections::Counter::<&str>::new();
+    /// assert!(c.is_empty());
+    /// ```
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            counts: Dict::new(),
+        }
+    }
+}
+
+impl<T, S> Counter<T, S> {
+    /// Creates a new empty `Counter` that hashes items with `hasher`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use std::collections::hash_map::RandomState;
+    ///
+    /// let mut c = ergo_core::collections::Counter::with_hasher(RandomState::new());
+    /// c.add("a", 1);
+    ///
+    /// assert_eq!(c.get("a"), 1);
+    /// ```
+    #[inline]
+    pub fn with_hasher(hasher: S) -> Self {
+        Self {
+            counts: Dict::with_hasher(hasher),
+        }
+    }
+
+    /// Returns the number of distinct items.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let c: ergo_core::collections::Counter<_> = "abca".chars().collect();
+    /// assert_eq!(c.len(), 3);
+    /// ```
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Returns `true` if nothing has been counted.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut c = ergo_core::collections::Counter::new();
+    /// assert!(c.is_empty());
+    ///
+    /// c.add('a', 1);
+    /// assert!(!c.is_empty());
+    /// ```
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Returns the sum of all counts.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let c: ergo_core::collections::Counter<_> = "abca".chars().collect();
+    /// assert_eq!(c.total(), 4);
+    /// ```
+    #[inline]
+    pub fn total(&self) -> usize {
+        self.counts.values().sum()
+    }
+
+    /// Iterates over the items and their counts, in the order items were first counted.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let c: ergo_core::collections::Counter<_> = "abca".chars().collect();
+    ///
+    /// let counts: Vec<_> = c.iter().collect();
+    /// assert_eq!(counts, [(&'a', &2), (&'b', &1), (&'c', &1)]);
+    /// ```
+    #[inline]
+    pub fn iter(&self) -> dict::Iter<'_, T, usize> {
+        self.counts.iter()
+    }
+
+    /// Forgets every count.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut c: ergo_core::collections::Counter<_> = "abca".chars().collect();
+    /// c.clear();
+    ///
+    /// assert!(c.is_empty());
+    /// ```
+    #[inline]
+    pub fn clear(&mut self) {
+        self.counts.clear();
+    }
+}
+
+impl<T: Hash + Eq, S: BuildHasher> Counter<T, S> {
+    /// Returns the count for `item`, which is zero when it is missing.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use ergo_core::collections::Counter;
+    /// use ergo_core::String;
+    ///
+    /// let c: Counter<String> = ["a", "b", "a"].iter().map(|&s| String::from(s)).collect();
+    ///
+    /// assert_eq!(c.get("a"), 2);
+    /// assert_eq!(c.get("z"), 0);
+    /// ```
+    #[inline]
+    pub fn get<Q>(&self, item: &Q) -> usize
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.counts.get_or(item, 0)
+    }
+
+    /// Returns up to `n` items with the highest counts, most common first.
+    ///
+    /// Items with equal counts stay in the order they were first counted.  Pass
+    /// [`Counter::len`] to get every item.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let c: ergo_core::collections::Counter<_> = "abracadabra".chars().collect();
+    ///
+    /// assert_eq!(c.most_common(3), [('a', 5), ('b', 2), ('r', 2)]);
+    /// ```
+    pub fn most_common(&self, n: usize) -> Vec<(T, usize)>
+    where
+        T: Clone,
+    {
+        let mut counts: Vec<_> = self.iter().collect();
+        counts.sort_by(|(_, a), (_, b)| b.cmp(a));
+        counts
+            .into_iter()
+            .take(n)
+            .map(|(item, count)| (item.clone(), *count))
+            .collect()
+    }
+}
+
+impl<T: Clone + Hash + Eq, S: BuildHasher> Counter<T, S> {
+    /// Adds `count` to the count for `item`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut c = ergo_core::collections::Counter::new();
+    /// c.add("a", 2);
+    /// c.add("a", 1);
+    ///
+    /// assert_eq!(c["a"], 3);
+    /// ```
+    pub fn add(&mut self, item: T, count: usize) {
+        if count != 0 {
+            *self.counts.get_or_insert(item, 0) += count;
+        }
+    }
+
+    /// Forgets `item`, returning its count.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut c: ergo_core::collections::Counter<_> = "aab".chars().collect();
+    ///
+    /// assert_eq!(c.remove(&'a'), 2);
+    /// assert_eq!(c.remove(&'a'), 0);
+    /// ```
+    #[inline]
+    pub fn remove<Q>(&mut self, item: &Q) -> usize
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.counts.remove(item).unwrap_or(0)
+    }
+
+    /// Counts every item of `iter`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut c: ergo_core::collections::Counter<_> = "ab".chars().collect();
+    /// c.extend("bc".chars());
+    ///
+    /// assert_eq!(c.most_common(3), [('b', 2), ('a', 1), ('c', 1)]);
+    /// ```
+    pub fn extend(&mut self, iter: impl IntoIterator<Item = T>) {
+        for item in iter {
+            self.add(item, 1);
+        }
+    }
+
+    /// Combines the counts of items in either counter with `f`, keeping positive results.
+    fn combine(&self, other: &Self, f: impl Fn(usize, usize) -> usize) -> Self
+    where
+        S: Clone,
+    {
+        let mut combined = Self::with_hasher(self.counts.hasher().clone());
+        let items = self.counts.keys().chain(
+            other
+                .counts
+                .keys()
+                .filter(|item| !self.counts.contains_key(*item)),
+        );
+        for item in items {
+            let count = f(self.get(item), other.get(item));
+            combined.add(item.clone(), count);
+        }
+        combined
+    }
+}
+
+/// Transitional Python API
+impl<T: Clone + Hash + Eq, S: BuildHasher + Clone> Counter<T, S> {
+    #[deprecated = "In Rust, we refer to this as `extend`"]
+    pub fn update(&mut self, iter: impl IntoIterator<Item = T>) {
+        self.extend(iter);
+    }
+
+    #[deprecated = "In Rust, we refer to this as `clone`"]
+    pub fn copy(&self) -> Self {
+        self.clone()
+    }
+}
+
+/// Interop
+impl<T, S> Counter<T, S> {
+    /// Views the counts as a [`Dict`].
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let c: ergo_core::collections::Counter<_> = "aab".chars().collect();
+    ///
+    /// assert_eq!(c.as_dict().get(&'b'), Some(&1));
+    /// ```
+    #[inline]
+    pub fn as_dict(&self) -> &Dict<T, usize, S> {
+        &self.counts
+    }
+}
+
+impl<T: Clone, S: Clone> Clone for Counter<T, S> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            counts: self.counts.clone(),
+        }
+    }
+}
+
+impl<T, S: Default> Default for Counter<T, S> {
+    #[inline]
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<T: Clone + Hash + Eq, S: BuildHasher + Default> FromIterator<T> for Counter<T, S> {
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut counter = Self::default();
+        counter.extend(iter);
+        counter
+    }
+}
+
+impl<T: Clone + Hash + Eq, S: BuildHasher> Extend<T> for Counter<T, S> {
+    #[inline]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        Counter::extend(self, iter);
+    }
+}
+
+impl<T, S> From<Counter<T, S>> for Dict<T, usize, S> {
+    #[inline]
+    fn from(other: Counter<T, S>) -> Self {
+        other.counts
+    }
+}
+
+impl<'c, T, S> IntoIterator for &'c Counter<T, S> {
+    type Item = (&'c T, &'c usize);
+    type IntoIter = dict::Iter<'c, T, usize>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T, Q, S> ops::Index<&Q> for Counter<T, S>
+where
+    T: Borrow<Q> + Hash + Eq,
+    Q: Hash + Eq + ?Sized,
+    S: BuildHasher,
+{
+    type Output = usize;
+
+    /// Returns the count for `item`, which is zero when it is missing.
+    #[inline]
+    fn index(&self, item: &Q) -> &usize {
+        self.counts.get(item).unwrap_or(&0)
+    }
+}
+
+impl<T, S> ops::Add<&Counter<T, S>> for &Counter<T, S>
+where
+    T: Clone + Hash + Eq,
+    S: BuildHasher + Clone,
+{
+    type Output = Counter<T, S>;
+
+    /// Adds the counts, like Python's `+`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ergo_core::collections::Counter;
+    ///
+    /// let a: Counter<_> = "aab".chars().collect();
+    /// let b: Counter<_> = "bc".chars().collect();
+    ///
+    /// assert_eq!(&a + &b, "aabbc".chars().collect());
+    /// ```
+    #[inline]
+    fn add(self, rhs: &Counter<T, S>) -> Counter<T, S> {
+        self.combine(rhs, |a, b| a + b)
+    }
+}
+
+impl<T, S> ops::Sub<&Counter<T, S>> for &Counter<T, S>
+where
+    T: Clone + Hash + Eq,
+    S: BuildHasher + Clone,
+{
+    type Output = Counter<T, S>;
+
+    /// Subtracts the counts, dropping items that reach zero, like Python's `-`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ergo_core::collections::Counter;
+    ///
+    /// let a: Counter<_> = "aab".chars().collect();
+    /// let b: Counter<_> = "abbc".chars().collect();
+    ///
+    /// assert_eq!(&a - &b, "a".chars().collect());
+    /// ```
+    #[inline]
+    fn sub(self, rhs: &Counter<T, S>) -> Counter<T, S> {
+        self.combine(rhs, usize::saturating_sub)
+    }
+}
+
+impl<T, S> ops::BitOr<&Counter<T, S>> for &Counter<T, S>
+where
+    T: Clone + Hash + Eq,
+    S: BuildHasher + Clone,
+{
+    type Output = Counter<T, S>;
+
+    /// Keeps the larger count of each item, like Python's `|`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ergo_core::collections::Counter;
+    ///
+    /// let a: Counter<_> = "aab".chars().collect();
+    /// let b: Counter<_> = "abbc".chars().collect();
+    ///
+    /// assert_eq!(&a | &b, "aabbc".chars().collect());
+    /// ```
+    #[inline]
+    fn bitor(self, rhs: &Counter<T, S>) -> Counter<T, S> {
+        self.combine(rhs, usize::max)
+    }
+}
+
+impl<T, S> ops::BitAnd<&Counter<T, S>> for &Counter<T, S>
+where
+    T: Clone + Hash + Eq,
+    S: BuildHasher + Clone,
+{
+    type Output = Counter<T, S>;
+
+    /// Keeps the smaller count of each item, like Python's `&`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ergo_core::collections::Counter;
+    ///
+    /// let a: Counter<_> = "aab".chars().collect();
+    /// let b: Counter<_> = "abbc".chars().collect();
+    ///
+    /// assert_eq!(&a & &b, "ab".chars().collect());
+    /// ```
+    #[inline]
+    fn bitand(self, rhs: &Counter<T, S>) -> Counter<T, S> {
+        self.combine(rhs, usize::min)
+    }
+}
+
+impl<T, S> ops::AddAssign<&Counter<T, S>> for Counter<T, S>
+where
+    T: Clone + Hash + Eq,
+    S: BuildHasher,
+{
+    #[inline]
+    fn add_assign(&mut self, rhs: &Self) {
+        for (item, count) in rhs {
+            self.add(item.clone(), *count);
+        }
+    }
+}
+
+impl<T, S> ops::SubAssign<&Counter<T, S>> for Counter<T, S>
+where
+    T: Clone + Hash + Eq,
+    S: BuildHasher + Clone,
+{
+    #[inline]
+    fn sub_assign(&mut self, rhs: &Self) {
+        *self = &*self - rhs;
+    }
+}
+
+impl<T: fmt::Debug, S> fmt::Debug for Counter<T, S> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.counts, f)
+    }
+}
+
+impl<T: Hash + Eq, S: BuildHasher> PartialEq for Counter<T, S> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.counts == other.counts
+    }
+}
+
+impl<T: Hash + Eq, S: BuildHasher> Eq for Counter<T, S> {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn arithmetic_matches_python() {
+        // Counter('abbccc') op Counter('bcccdd') in Python
+        let a: Counter<char> = "abbccc".chars().collect();
+        let b: Counter<char> = "bcccdd".chars().collect();
+        let counts = |c: Counter<char>| c.iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>();
+
+        assert_eq!(counts(&a + &b), [('a', 1), ('b', 3), ('c', 6), ('d', 2)]);
+        assert_eq!(counts(&a - &b), [('a', 1), ('b', 1)]);
+        assert_eq!(counts(&b - &a), [('d', 2)]);
+        assert_eq!(counts(&a | &b), [('a', 1), ('b', 2), ('c', 3), ('d', 2)]);
+        assert_eq!(counts(&a & &b), [('b', 1), ('c', 3)]);
+
+        let mut c = a.clone();
+        c += &b;
+        assert_eq!(c, &a + &b);
+        c -= &b;
+        assert_eq!(c, a);
+    }
+
+    #[test]
+    fn zero_counts_are_not_stored() {
+        let mut c = Counter::new();
+        c.add("a", 0);
+        assert!(c.is_empty());
+
+        c.add("a", 1);
+        let d: Counter<&str> = &c - &c;
+        assert!(d.is_empty());
+        assert_eq!(d["a"], 0);
+    }
+}
@@ -0,0 +1,441 @@
+use std::borrow::Borrow;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+
+use crate::dict::{self, Dict};
+use crate::hash::BuildFastHasher;
+
+/// A [`Dict`] that fills in missing values from a factory, like Python's
+/// `collections.defaultdict`
+///
+/// # Examples
+///
+/// ```
+/// use ergo_core::collections::DefaultDict;
+/// use ergo_core::String;
+///
+/// let mut by_initial = DefaultDict::new(Vec::new);
+/// for name in ["ada", "alan", "grace"] {
+///     let initial = String::from(&name[..1]);
+///     by_initial.get_mut(initial).push(name);
+/// }
+///
+/// assert_eq!(by_initial.get("a"), ["ada", "alan"]);
+/// assert_eq!(by_initial.get("z"), Vec::<&str>::new());
+/// ```
+pub struct DefaultDict<K, V, F = fn() -> V, S = BuildFastHasher> {
+    dict: Dict<K, V, S>,
+    default: F,
+}
+
+impl<K, V, F: Fn() -> V> DefaultDict<K, V, F> {
+    /// Creates a new empty `DefaultDict` that calls `default` for missing values.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let d = ergo_core::collections::DefaultDict::<&str, i32, _>::new(|| 0);
+    /// assert!(d.is_empty());
+    /// ```
+    #[inline]
+    pub fn new(default: F) -> Self {
+        Self::with_hasher(default, BuildFastHasher::default())
+    }
+}
+
+impl<K, V, F: Fn() -> V, S> DefaultDict<K, V, F, S> {
+    /// Creates a new empty `DefaultDict` that calls `default` for missing values and hashes
+    /// keys with `hasher`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use std::collections::hash_map::RandomState;
+    ///
+    /// let mut d = ergo_core::collections::DefaultDict::with_hasher(|| 0, RandomState::new());
+    /// *d.get_mut("a") += 1;
+    ///
+    /// assert_eq!(d.get("a"), 1);
+    /// ```
+    #[inline]
+    pub fn with_hasher(default: F, hasher: S) -> Self {
+        Self {
+            dict: Dict::with_hasher(hasher),
+            default,
+        }
+    }
+}
+
+impl<K, V, F, S> DefaultDict<K, V, F, S> {
+    /// Returns the number of entries.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut d = ergo_core::collections::DefaultDict::new(|| 0);
+    /// *d.get_mut("a") += 1;
+    ///
+    /// assert_eq!(d.len(), 1);
+    /// ```
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.dict.len()
+    }
+
+    /// Returns `true` if there are no entries.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let d = ergo_core::collections::DefaultDict::<&str, i32, _>::new(|| 0);
+    /// assert!(d.is_empty());
+    /// ```
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.dict.is_empty()
+    }
+
+    /// Iterates over the keys and values in insertion order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut d = ergo_core::collections::DefaultDict::new(|| 0);
+    /// *d.get_mut("b") += 2;
+    /// *d.get_mut("a") += 1;
+    ///
+    /// let items: Vec<_> = d.iter().collect();
+    /// assert_eq!(items, [(&"b", &2), (&"a", &1)]);
+    /// ```
+    #[inline]
+    pub fn iter(&self) -> dict::Iter<'_, K, V> {
+        self.dict.iter()
+    }
+
+    /// Iterates over the keys in insertion order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut d = ergo_core::collections::DefaultDict::new(|| 0);
+    /// d.get_mut("b");
+    /// d.get_mut("a");
+    ///
+    /// let keys: Vec<_> = d.keys().collect();
+    /// assert_eq!(keys, [&"b", &"a"]);
+    /// ```
+    #[inline]
+    pub fn keys(&self) -> dict::Keys<'_, K, V> {
+        self.dict.keys()
+    }
+
+    /// Iterates over the values in insertion order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut d = ergo_core::collections::DefaultDict::new(|| 0);
+    /// *d.get_mut("b") += 2;
+    /// *d.get_mut("a") += 1;
+    ///
+    /// assert_eq!(d.values().sum::<i32>(), 3);
+    /// ```
+    #[inline]
+    pub fn values(&self) -> dict::Values<'_, K, V> {
+        self.dict.values()
+    }
+
+    /// Removes every entry.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut d = ergo_core::collections::DefaultDict::new(|| 0);
+    /// d.get_mut("a");
+    /// d.clear();
+    ///
+    /// assert!(d.is_empty());
+    /// ```
+    #[inline]
+    pub fn clear(&mut self) {
+        self.dict.clear();
+    }
+}
+
+impl<K: Hash + Eq, V, F: Fn() -> V, S: BuildHasher> DefaultDict<K, V, F, S> {
+    /// Returns the value for `key`, or a new value from the factory when it is missing.
+    ///
+    /// Unlike Python's `d[key]`, a missing key is not inserted; use [`DefaultDict::get_mut`]
+    /// for that.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut d = ergo_core::collections::DefaultDict::new(|| 10);
+    /// *d.get_mut("a") += 1;
+    ///
+    /// assert_eq!(d.get("a"), 11);
+    /// assert_eq!(d.get("b"), 10);
+    /// assert!(!d.contains_key("b"));
+    /// ```
+    #[inline]
+    pub fn get<Q>(&self, key: &Q) -> V
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        V: Clone,
+    {
+        match self.dict.get(key) {
+            Some(value) => value.clone(),
+            None => (self.default)(),
+        }
+    }
+
+    /// Returns a reference to the value for `key`, or [`None`] when it is missing.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut d = ergo_core::collections::DefaultDict::new(|| 0);
+    /// d.get_mut("a");
+    ///
+    /// assert_eq!(d.lookup("a"), Some(&0));
+    /// assert_eq!(d.lookup("b"), None);
+    /// ```
+    #[inline]
+    pub fn lookup<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.dict.get(key)
+    }
+
+    /// Returns `true` if there is an entry for `key`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut d = ergo_core::collections::DefaultDict::new(|| 0);
+    /// d.get_mut("a");
+    ///
+    /// assert!(d.contains_key("a"));
+    /// assert!(!d.contains_key("b"));
+    /// ```
+    #[inline]
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.dict.contains_key(key)
+    }
+}
+
+impl<K: Clone + Hash + Eq, V: Clone, F: Fn() -> V, S: BuildHasher> DefaultDict<K, V, F, S> {
+    /// Returns a mutable reference to the value for `key`, first inserting a value from the
+    /// factory when it is missing, like Python's `d[key]`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut counts = ergo_core::collections::DefaultDict::new(|| 0);
+    /// for word in ["a", "b", "a"] {
+    ///     *counts.get_mut(word) += 1;
+    /// }
+    ///
+    /// assert_eq!(counts.get("a"), 2);
+    /// ```
+    pub fn get_mut(&mut self, key: K) -> &mut V {
+        if !self.dict.contains_key(&key) {
+            self.dict.insert(key.clone(), (self.default)());
+        }
+        self.dict.get_mut(&key).expect("just inserted")
+    }
+
+    /// Sets the value for `key`, returning the previous value.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut d = ergo_core::collections::DefaultDict::new(|| 0);
+    ///
+    /// assert_eq!(d.insert("a", 1), None);
+    /// assert_eq!(d.insert("a", 2), Some(1));
+    /// ```
+    #[inline]
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.dict.insert(key, value)
+    }
+
+    /// Removes the entry for `key`, returning its value.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut d = ergo_core::collections::DefaultDict::new(|| 0);
+    /// d.insert("a", 1);
+    ///
+    /// assert_eq!(d.remove("a"), Some(1));
+    /// assert_eq!(d.remove("a"), None);
+    /// ```
+    #[inline]
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.dict.remove(key)
+    }
+}
+
+/// Interop
+impl<K, V, F, S> DefaultDict<K, V, F, S> {
+    /// Views the entries as a [`Dict`].
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut d = ergo_core::collections::DefaultDict::new(|| 0);
+    /// d.insert("a", 1);
+    ///
+    /// assert_eq!(d.as_dict(), &ergo_core::Dict::from([("a", 1)]));
+    /// ```
+    #[inline]
+    pub fn as_dict(&self) -> &Dict<K, V, S> {
+        &self.dict
+    }
+}
+
+impl<K: Clone, V: Clone, F: Clone, S: Clone> Clone for DefaultDict<K, V, F, S> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            dict: self.dict.clone(),
+            default: self.default.clone(),
+        }
+    }
+}
+
+impl<K, V: Default, S: Default> Default for DefaultDict<K, V, fn() -> V, S> {
+    /// Creates a new empty `DefaultDict` that fills in missing values with [`Default`].
+    #[inline]
+    fn default() -> Self {
+        Self {
+            dict: Dict::default(),
+            default: V::default,
+        }
+    }
+}
+
+impl<K, V, F, S> From<DefaultDict<K, V, F, S>> for Dict<K, V, S> {
+    #[inline]
+    fn from(other: DefaultDict<K, V, F, S>) -> Self {
+        other.dict
+    }
+}
+
+impl<'d, K, V, F, S> IntoIterator for &'d DefaultDict<K, V, F, S> {
+    type Item = (&'d K, &'d V);
+    type IntoIter = dict::Iter<'d, K, V>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, F, S> fmt::Debug for DefaultDict<K, V, F, S> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.dict, f)
+    }
+}
+
+impl<K: Hash + Eq, V: PartialEq, F, S: BuildHasher> PartialEq for DefaultDict<K, V, F, S> {
+    /// Compares the entries, ignoring the factories.
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.dict == other.dict
+    }
+}
+
+impl<K: Hash + Eq, V: Eq, F, S: BuildHasher> Eq for DefaultDict<K, V, F, S> {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn get_does_not_insert() {
+        let d = DefaultDict::<&str, i32, _>::new(|| 5);
+        assert_eq!(d.get("a"), 5);
+        assert_eq!(d.lookup("a"), None);
+        assert!(!d.contains_key("a"));
+        assert!(d.is_empty());
+    }
+
+    #[test]
+    fn get_mut_inserts_the_default_once() {
+        let calls = Cell::new(0);
+        let mut d = DefaultDict::new(|| {
+            calls.set(calls.get() + 1);
+            Vec::new()
+        });
+        d.get_mut("a").push(1);
+        d.get_mut("a").push(2);
+        assert_eq!(calls.get(), 1);
+        assert_eq!(d.lookup("a"), Some(&vec![1, 2]));
+
+        d.insert("b", vec![3]);
+        d.get_mut("b").push(4);
+        assert_eq!(calls.get(), 1);
+        assert_eq!(d.len(), 2);
+    }
+
+    #[test]
+    fn clones_are_independent() {
+        let mut a = DefaultDict::new(|| 0);
+        *a.get_mut("x") += 1;
+        let mut b = a.clone();
+        *b.get_mut("x") += 10;
+        *b.get_mut("y") += 1;
+        *a.get_mut("z") += 1;
+
+        assert_eq!((a.get("x"), a.get("y"), a.get("z")), (1, 0, 1));
+        assert_eq!((b.get("x"), b.get("y"), b.get("z")), (11, 1, 0));
+        assert_eq!(a.len(), 2);
+        assert_eq!(b.len(), 2);
+    }
+}
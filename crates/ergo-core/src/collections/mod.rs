@@ -0,0 +1,31 @@
+//! Specialized mappings, mirroring Python's `collections`
+//!
+//! | Python                    | Rust              |
+//! |---------------------------|-------------------|
+//! | `collections.Counter`     | [`Counter`]       |
+//! | `collections.defaultdict` | [`DefaultDict`]   |
+//! | `collections.OrderedDict` | [`OrderedDict`]   |
+//!
+//! Each is built on [`Dict`][crate::Dict], so they are cheap to clone, remember insertion
+//! order and, when keyed by [`String`][crate::String], can be looked up by `&str`.
+//!
+//! # Examples
+//!
+//! ```
+//! use ergo_core::collections::Counter;
+//! use ergo_core::String;
+//!
+//! let text = String::from("the cat and the hat and the bat");
+//! let words: Counter<String> = text.split(" ").into_iter().collect();
+//!
+//! assert_eq!(words["the"], 3);
+//! assert_eq!(words.most_common(2)[1], (String::from("and"), 2));
+//! ```
+
+mod counter;
+mod default_dict;
+mod ordered_dict;
+
+pub use counter::Counter;
+pub use default_dict::DefaultDict;
+pub use ordered_dict::OrderedDict;
@@ -0,0 +1,523 @@
+use std::borrow::Borrow;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::iter::FromIterator;
+
+use crate::dict::{self, Dict};
+use crate::hash::BuildFastHasher;
+
+/// A [`Dict`] whose order can be rearranged and matters for equality, like Python's
+/// `collections.OrderedDict`
+///
+/// A common use is a least-recently-used cache: move an entry to the end when it is used, and
+/// evict from the start.
+///
+/// # Examples
+///
+/// ```
+/// use ergo_core::collections::OrderedDict;
+///
+/// let mut cache = OrderedDict::new();
+/// for page in ["home", "about", "blog"] {
+///     cache.insert(page, page.len());
+/// }
+///
+/// cache.move_to_end("home", true);
+/// assert_eq!(cache.popitem(false), Some(("about", 5)));
+/// ```
+pub struct OrderedDict<K, V, S = BuildFastHasher> {
+    dict: Dict<K, V, S>,
+}
+
+impl<K, V> OrderedDict<K, V> {
+    /// Creates a new empty `OrderedDict`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let d = ergo_core::collections::OrderedDict::<i32, i32>::new();
+    /// assert!(d.is_empty());
+    /// ```
+    #[inline]
+    pub fn new() -> Self {
+        Self { dict: Dict::new() }
+    }
+}
+
+impl<K, V, S> OrderedDict<K, V, S> {
+    /// Creates a new empty `OrderedDict` that hashes keys with `hasher`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use std::collections::hash_map::RandomState;
+    ///
+    /// let mut d = ergo_core::collections::OrderedDict::with_hasher(RandomState::new());
+    /// d.insert("key", 1);
+    ///
+    /// assert_eq!(d.get("key"), Some(&1));
+    /// ```
+    #[inline]
+    pub fn with_hasher(hasher: S) -> Self {
+        Self {
+            dict: Dict::with_hasher(hasher),
+        }
+    }
+
+    /// Returns the number of entries.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let d = ergo_core::collections::OrderedDict::from([("a", 1), ("b", 2)]);
+    /// assert_eq!(d.len(), 2);
+    /// ```
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.dict.len()
+    }
+
+    /// Returns `true` if there are no entries.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let d = ergo_core::collections::OrderedDict::<i32, i32>::new();
+    /// assert!(d.is_empty());
+    /// ```
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.dict.is_empty()
+    }
+
+    /// Iterates over the keys and values in order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let d = ergo_core::collections::OrderedDict::from([("b", 2), ("a", 1)]);
+    ///
+    /// let items: Vec<_> = d.iter().collect();
+    /// assert_eq!(items, [(&"b", &2), (&"a", &1)]);
+    /// ```
+    #[inline]
+    pub fn iter(&self) -> dict::Iter<'_, K, V> {
+        self.dict.iter()
+    }
+
+    /// Iterates over the keys in order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let d = ergo_core::collections::OrderedDict::from([("b", 2), ("a", 1)]);
+    ///
+    /// let keys: Vec<_> = d.keys().collect();
+    /// assert_eq!(keys, [&"b", &"a"]);
+    /// ```
+    #[inline]
+    pub fn keys(&self) -> dict::Keys<'_, K, V> {
+        self.dict.keys()
+    }
+
+    /// Iterates over the values in order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let d = ergo_core::collections::OrderedDict::from([("b", 2), ("a", 1)]);
+    ///
+    /// let values: Vec<_> = d.values().collect();
+    /// assert_eq!(values, [&2, &1]);
+    /// ```
+    #[inline]
+    pub fn values(&self) -> dict::Values<'_, K, V> {
+        self.dict.values()
+    }
+
+    /// Removes every entry.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut d = ergo_core::collections::OrderedDict::from([("a", 1)]);
+    /// d.clear();
+    ///
+    /// assert!(d.is_empty());
+    /// ```
+    #[inline]
+    pub fn clear(&mut self) {
+        self.dict.clear();
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> OrderedDict<K, V, S> {
+    /// Returns a reference to the value for `key`, or [`None`] when it is missing.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let d = ergo_core::collections::OrderedDict::from([("a", 1)]);
+    ///
+    /// assert_eq!(d.get("a"), Some(&1));
+    /// assert_eq!(d.get("b"), None);
+    /// ```
+    #[inline]
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.dict.get(key)
+    }
+
+    /// Returns the value for `key`, or `default` when it is missing, like Python's `dict.get`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let d = ergo_core::collections::OrderedDict::from([("a", 1)]);
+    ///
+    /// assert_eq!(d.get_or("a", 0), 1);
+    /// assert_eq!(d.get_or("b", 0), 0);
+    /// ```
+    #[inline]
+    pub fn get_or<Q>(&self, key: &Q, default: V) -> V
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        V: Clone,
+    {
+        self.dict.get_or(key, default)
+    }
+
+    /// Returns `true` if there is an entry for `key`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let d = ergo_core::collections::OrderedDict::from([("a", 1)]);
+    ///
+    /// assert!(d.contains_key("a"));
+    /// assert!(!d.contains_key("b"));
+    /// ```
+    #[inline]
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.dict.contains_key(key)
+    }
+}
+
+impl<K: Clone + Hash + Eq, V: Clone, S: BuildHasher> OrderedDict<K, V, S> {
+    /// Sets the value for `key`, returning the previous value.
+    ///
+    /// A new key goes at the end; an existing key keeps its place.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut d = ergo_core::collections::OrderedDict::new();
+    ///
+    /// assert_eq!(d.insert("a", 1), None);
+    /// assert_eq!(d.insert("a", 2), Some(1));
+    /// ```
+    #[inline]
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.dict.insert(key, value)
+    }
+
+    /// Removes the entry for `key`, returning its value.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut d = ergo_core::collections::OrderedDict::from([("a", 1)]);
+    ///
+    /// assert_eq!(d.remove("a"), Some(1));
+    /// assert_eq!(d.remove("a"), None);
+    /// ```
+    #[inline]
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.dict.remove(key)
+    }
+
+    /// Removes the last entry, or the first when `last` is `false`, and returns it.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut d = ergo_core::collections::OrderedDict::from([("a", 1), ("b", 2), ("c", 3)]);
+    ///
+    /// assert_eq!(d.popitem(true), Some(("c", 3)));
+    /// assert_eq!(d.popitem(false), Some(("a", 1)));
+    /// ```
+    #[inline]
+    pub fn popitem(&mut self, last: bool) -> Option<(K, V)> {
+        if last {
+            self.dict.pop_last()
+        } else {
+            self.dict.pop_first()
+        }
+    }
+
+    /// Moves the entry for `key` to the end, or the start when `last` is `false`, returning
+    /// `false` when it is missing.
+    ///
+    /// Moving either way takes amortized constant time.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut d = ergo_core::collections::OrderedDict::from([("a", 1), ("b", 2), ("c", 3)]);
+    ///
+    /// assert!(d.move_to_end("a", true));
+    /// assert!(d.move_to_end("c", false));
+    /// assert!(!d.move_to_end("z", true));
+    ///
+    /// let keys: Vec<_> = d.keys().copied().collect();
+    /// assert_eq!(keys, ["c", "b", "a"]);
+    /// ```
+    #[inline]
+    pub fn move_to_end<Q>(&mut self, key: &Q, last: bool) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.dict.move_entry(key, last)
+    }
+
+    /// Inserts every entry of `iter`, overwriting the values of existing keys.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut d = ergo_core::collections::OrderedDict::from([("a", 1)]);
+    /// d.extend(vec![("b", 2), ("a", 3)]);
+    ///
+    /// assert_eq!(d, ergo_core::collections::OrderedDict::from([("a", 3), ("b", 2)]));
+    /// ```
+    #[inline]
+    pub fn extend(&mut self, iter: impl IntoIterator<Item = (K, V)>) {
+        self.dict.extend(iter);
+    }
+}
+
+/// Transitional Python API
+impl<K: Clone + Hash + Eq, V: Clone, S: BuildHasher + Clone> OrderedDict<K, V, S> {
+    #[deprecated = "In Rust, we refer to this as `iter`"]
+    pub fn items(&self) -> dict::Iter<'_, K, V> {
+        self.iter()
+    }
+
+    #[deprecated = "In Rust, we refer to this as `extend`"]
+    pub fn update(&mut self, iter: impl IntoIterator<Item = (K, V)>) {
+        self.extend(iter);
+    }
+
+    #[deprecated = "In Rust, we refer to this as `clone`"]
+    pub fn copy(&self) -> Self {
+        self.clone()
+    }
+}
+
+/// Interop
+impl<K, V, S> OrderedDict<K, V, S> {
+    /// Views the entries as a [`Dict`].
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let d = ergo_core::collections::OrderedDict::from([("a", 1)]);
+    ///
+    /// assert_eq!(d.as_dict(), &ergo_core::Dict::from([("a", 1)]));
+    /// ```
+    #[inline]
+    pub fn as_dict(&self) -> &Dict<K, V, S> {
+        &self.dict
+    }
+}
+
+impl<K: Clone, V: Clone, S: Clone> Clone for OrderedDict<K, V, S> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            dict: self.dict.clone(),
+        }
+    }
+}
+
+impl<K, V, S: Default> Default for OrderedDict<K, V, S> {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            dict: Dict::default(),
+        }
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher + Default> FromIterator<(K, V)> for OrderedDict<K, V, S> {
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Self {
+            dict: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<K: Hash + Eq, V, const N: usize> From<[(K, V); N]> for OrderedDict<K, V> {
+    #[inline]
+    fn from(other: [(K, V); N]) -> Self {
+        Self {
+            dict: Dict::from(other),
+        }
+    }
+}
+
+impl<K, V, S> From<Dict<K, V, S>> for OrderedDict<K, V, S> {
+    #[inline]
+    fn from(other: Dict<K, V, S>) -> Self {
+        Self { dict: other }
+    }
+}
+
+impl<K, V, S> From<OrderedDict<K, V, S>> for Dict<K, V, S> {
+    #[inline]
+    fn from(other: OrderedDict<K, V, S>) -> Self {
+        other.dict
+    }
+}
+
+impl<K: Clone + Hash + Eq, V: Clone, S: BuildHasher> Extend<(K, V)> for OrderedDict<K, V, S> {
+    #[inline]
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        OrderedDict::extend(self, iter);
+    }
+}
+
+impl<'d, K, V, S> IntoIterator for &'d OrderedDict<K, V, S> {
+    type Item = (&'d K, &'d V);
+    type IntoIter = dict::Iter<'d, K, V>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K: Clone, V: Clone, S> IntoIterator for OrderedDict<K, V, S> {
+    type Item = (K, V);
+    type IntoIter = dict::IntoIter<K, V>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.dict.into_iter()
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, S> fmt::Debug for OrderedDict<K, V, S> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.dict, f)
+    }
+}
+
+impl<K: PartialEq, V: PartialEq, S> PartialEq for OrderedDict<K, V, S> {
+    /// Compares the entries in order, unlike [`Dict`].
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<K: Eq, V: Eq, S> Eq for OrderedDict<K, V, S> {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn equality_respects_order() {
+        let a = OrderedDict::from([(1, 'a'), (2, 'b')]);
+        let b = OrderedDict::from([(2, 'b'), (1, 'a')]);
+        assert_ne!(a, b);
+        assert_eq!(a.as_dict(), b.as_dict());
+    }
+
+    #[test]
+    fn evicting_from_the_front_stays_compact() {
+        let mut lru = OrderedDict::new();
+        for i in 0..10_000 {
+            lru.insert(i, i);
+            lru.move_to_end(&(i / 2), true);
+            if 100 < lru.len() {
+                lru.popitem(false);
+            }
+        }
+        assert_eq!(lru.len(), 100);
+        let keys: Vec<_> = lru.keys().copied().collect();
+        let mut sorted = keys.clone();
+        sorted.sort_unstable();
+        sorted.dedup();
+        assert_eq!(sorted.len(), 100);
+
+        lru.move_to_end(&keys[50], false);
+        assert_eq!(lru.keys().next(), Some(&keys[50]));
+        assert_eq!(lru.popitem(false), Some((keys[50], keys[50])));
+    }
+
+    #[test]
+    fn rotating_through_the_front() {
+        let mut d: OrderedDict<_, _> = (0..1000).map(|i| (i, i)).collect();
+        for _ in 0..100_000 {
+            let (k, v) = d.popitem(true).unwrap();
+            d.insert(k, v);
+            d.move_to_end(&k, false);
+        }
+        let keys: Vec<_> = d.keys().copied().collect();
+        let expected: Vec<_> = (0..1000).collect();
+        assert_eq!(keys, expected);
+        assert_eq!(d.popitem(false), Some((0, 0)));
+        assert_eq!(d.popitem(true), Some((999, 999)));
+    }
+}
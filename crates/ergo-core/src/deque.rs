@@ -0,0 +1,646 @@
+//! A cheaply clonable double-ended queue: [`Deque`]
+
+use std::collections::vec_deque::{self, VecDeque};
+use std::fmt;
+use std::iter::FromIterator;
+use std::ops;
+use std::sync::Arc;
+
+/// A cheaply clonable, copy-on-write double-ended queue, like Python's `collections.deque`
+///
+/// A deque created with [`Deque::with_maxlen`] is bounded: once full, pushing onto one end drops
+/// the item at the other end, which makes it a natural sliding window or "last N" buffer.
+///
+/// Cloning shares the items instead of copying them; mutating a deque whose items are shared
+/// first copies them, leaving other deques unchanged.
+///
+/// # Examples
+///
+/// ```
+/// use ergo_core::Deque;
+///
+/// let mut recent = Deque::with_maxlen(3);
+/// for job in ["build", "test", "lint", "deploy"] {
+///     recent.push_back(job);
+/// }
+/// assert_eq!(recent, ["test", "lint", "deploy"]);
+///
+/// recent.rotate(1);
+/// assert_eq!(recent.pop_front(), Some("deploy"));
+/// ```
+pub struct Deque<T> {
+    items: Arc<VecDeque<T>>,
+    maxlen: Option<usize>,
+}
+
+impl<T> Deque<T> {
+    /// Creates a new empty, unbounded `Deque`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let d = ergo_core::Deque::<i32>::new();
+    ///
+    /// assert!(d.is_empty());
+    /// assert_eq!(d.maxlen(), None);
+    /// ```
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            items: Arc::new(VecDeque::new()),
+            maxlen: None,
+        }
+    }
+
+    /// Creates a new empty `Deque` that holds at most `maxlen` items.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut d = ergo_core::Deque::with_maxlen(2);
+    /// d.extend([1, 2, 3]);
+    ///
+    /// assert_eq!(d, [2, 3]);
+    /// assert_eq!(d.maxlen(), Some(2));
+    /// ```
+    #[inline]
+    pub fn with_maxlen(maxlen: usize) -> Self {
+        Self {
+            // Not preallocated, as `maxlen` is often just a large upper bound
+            items: Arc::new(VecDeque::new()),
+            maxlen: Some(maxlen),
+        }
+    }
+
+    /// Returns the most items the deque can hold, or [`None`] if it is unbounded.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// assert_eq!(ergo_core::Deque::<u8>::with_maxlen(5).maxlen(), Some(5));
+    /// ```
+    #[inline]
+    pub fn maxlen(&self) -> Option<usize> {
+        self.maxlen
+    }
+
+    /// Returns the number of items.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let d = ergo_core::Deque::from([1, 2]);
+    /// assert_eq!(d.len(), 2);
+    /// ```
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns `true` if there are no items.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Returns an iterator over the items, from front to back.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let d = ergo_core::Deque::from([1, 2, 3]);
+    /// assert_eq!(d.iter().sum::<i32>(), 6);
+    /// ```
+    #[inline]
+    pub fn iter(&self) -> vec_deque::Iter<'_, T> {
+        self.items.iter()
+    }
+
+    /// Returns the first item, or [`None`] if the deque is empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let d = ergo_core::Deque::from(['a', 'b']);
+    /// assert_eq!(d.front(), Some(&'a'));
+    /// ```
+    #[inline]
+    pub fn front(&self) -> Option<&T> {
+        self.items.front()
+    }
+
+    /// Returns the last item, or [`None`] if the deque is empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let d = ergo_core::Deque::from(['a', 'b']);
+    /// assert_eq!(d.back(), Some(&'b'));
+    /// ```
+    #[inline]
+    pub fn back(&self) -> Option<&T> {
+        self.items.back()
+    }
+
+    /// Returns `true` if the deque has an item equal to `value`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let d = ergo_core::Deque::from(["x", "y"]);
+    ///
+    /// assert!(d.contains(&"y"));
+    /// assert!(!d.contains(&"z"));
+    /// ```
+    #[inline]
+    pub fn contains(&self, value: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.items.contains(value)
+    }
+
+    /// Removes every item, keeping `maxlen`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut d = ergo_core::Deque::from([1, 2]);
+    /// d.clear();
+    ///
+    /// assert!(d.is_empty());
+    /// ```
+    pub fn clear(&mut self) {
+        match Arc::get_mut(&mut self.items) {
+            Some(items) => items.clear(),
+            None => self.items = Arc::new(VecDeque::new()),
+        }
+    }
+
+    /// Returns `true` if both deques share the same items, so neither has been modified since
+    /// one was cloned from the other.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let d = ergo_core::Deque::from([1]);
+    /// let mut copy = d.clone();
+    /// assert!(d.is_shared_with(&copy));
+    ///
+    /// copy.push_back(2);
+    /// assert!(!d.is_shared_with(&copy));
+    /// ```
+    #[inline]
+    pub fn is_shared_with(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.items, &other.items)
+    }
+}
+
+impl<T: Clone> Deque<T> {
+    /// Adds `value` to the back, dropping the front item if the deque is full.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut d = ergo_core::Deque::with_maxlen(2);
+    /// d.push_back(1);
+    /// d.push_back(2);
+    /// d.push_back(3);
+    ///
+    /// assert_eq!(d, [2, 3]);
+    /// ```
+    pub fn push_back(&mut self, value: T) {
+        let maxlen = self.maxlen;
+        push_back(Arc::make_mut(&mut self.items), maxlen, value);
+    }
+
+    /// Adds `value` to the front, dropping the back item if the deque is full.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut d = ergo_core::Deque::with_maxlen(2);
+    /// d.push_front(1);
+    /// d.push_front(2);
+    /// d.push_front(3);
+    ///
+    /// assert_eq!(d, [3, 2]);
+    /// ```
+    pub fn push_front(&mut self, value: T) {
+        let maxlen = self.maxlen;
+        push_front(Arc::make_mut(&mut self.items), maxlen, value);
+    }
+
+    /// Removes the last item and returns it, or [`None`] if the deque is empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut d = ergo_core::Deque::from([1, 2]);
+    ///
+    /// assert_eq!(d.pop_back(), Some(2));
+    /// assert_eq!(d, [1]);
+    /// ```
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        Arc::make_mut(&mut self.items).pop_back()
+    }
+
+    /// Removes the first item and returns it, or [`None`] if the deque is empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut d = ergo_core::Deque::from([1, 2]);
+    ///
+    /// assert_eq!(d.pop_front(), Some(1));
+    /// assert_eq!(d, [2]);
+    /// ```
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        Arc::make_mut(&mut self.items).pop_front()
+    }
+
+    /// Adds each item in `iter` to the back, dropping front items as needed to stay within
+    /// `maxlen`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut d = ergo_core::Deque::from([1]);
+    /// d.extend([2, 3]);
+    ///
+    /// assert_eq!(d, [1, 2, 3]);
+    /// ```
+    pub fn extend(&mut self, iter: impl IntoIterator<Item = T>) {
+        let maxlen = self.maxlen;
+        let items = Arc::make_mut(&mut self.items);
+        for value in iter {
+            push_back(items, maxlen, value);
+        }
+    }
+
+    /// Adds each item in `iter` to the front, dropping back items as needed to stay within
+    /// `maxlen`.
+    ///
+    /// Like Python's `extendleft`, the items end up in reverse order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut d = ergo_core::Deque::from([3]);
+    /// d.extend_front([2, 1]);
+    ///
+    /// assert_eq!(d, [1, 2, 3]);
+    /// ```
+    pub fn extend_front(&mut self, iter: impl IntoIterator<Item = T>) {
+        let maxlen = self.maxlen;
+        let items = Arc::make_mut(&mut self.items);
+        for value in iter {
+            push_front(items, maxlen, value);
+        }
+    }
+
+    /// Rotates the items `n` steps to the right, or `-n` steps to the left when `n` is negative,
+    /// like Python's `rotate`.
+    ///
+    /// Rotating one step to the right moves the back item to the front.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut d = ergo_core::Deque::from([1, 2, 3, 4]);
+    ///
+    /// d.rotate(1);
+    /// assert_eq!(d, [4, 1, 2, 3]);
+    ///
+    /// d.rotate(-2);
+    /// assert_eq!(d, [2, 3, 4, 1]);
+    /// ```
+    pub fn rotate(&mut self, n: isize) {
+        let len = self.len();
+        if len == 0 {
+            return;
+        }
+        let steps = (n as i128).rem_euclid(len as i128) as usize;
+        if steps != 0 {
+            Arc::make_mut(&mut self.items).rotate_right(steps);
+        }
+    }
+}
+
+/// Pushes `value` onto the back of `items`, first dropping the front item if it is full.
+fn push_back<T>(items: &mut VecDeque<T>, maxlen: Option<usize>, value: T) {
+    match maxlen {
+        Some(0) => return,
+        Some(maxlen) if items.len() == maxlen => {
+            items.pop_front();
+        }
+        _ => {}
+    }
+    items.push_back(value);
+}
+
+/// Pushes `value` onto the front of `items`, first dropping the back item if it is full.
+fn push_front<T>(items: &mut VecDeque<T>, maxlen: Option<usize>, value: T) {
+    match maxlen {
+        Some(0) => return,
+        Some(maxlen) if items.len() == maxlen => {
+            items.pop_back();
+        }
+        _ => {}
+    }
+    items.push_front(value);
+}
+
+/// Transitional Python API
+impl<T: Clone> Deque<T> {
+    #[deprecated = "In Rust, we refer to this as `push_back`"]
+    pub fn append(&mut self, value: T) {
+        self.push_back(value)
+    }
+
+    #[deprecated = "In Rust, we refer to this as `push_front`"]
+    pub fn appendleft(&mut self, value: T) {
+        self.push_front(value)
+    }
+
+    #[deprecated = "In Rust, we refer to this as `pop_back`"]
+    pub fn pop(&mut self) -> Option<T> {
+        self.pop_back()
+    }
+
+    #[deprecated = "In Rust, we refer to this as `pop_front`"]
+    pub fn popleft(&mut self) -> Option<T> {
+        self.pop_front()
+    }
+
+    #[deprecated = "In Rust, we refer to this as `extend_front`"]
+    pub fn extendleft(&mut self, iter: impl IntoIterator<Item = T>) {
+        self.extend_front(iter)
+    }
+
+    #[deprecated = "In Rust, we refer to this as `clone`"]
+    pub fn copy(&self) -> Self {
+        self.clone()
+    }
+}
+
+impl<T> Clone for Deque<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            items: self.items.clone(),
+            maxlen: self.maxlen,
+        }
+    }
+}
+
+impl<T> Default for Deque<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> From<VecDeque<T>> for Deque<T> {
+    #[inline]
+    fn from(other: VecDeque<T>) -> Self {
+        Self {
+            items: Arc::new(other),
+            maxlen: None,
+        }
+    }
+}
+
+impl<T> From<Vec<T>> for Deque<T> {
+    #[inline]
+    fn from(other: Vec<T>) -> Self {
+        Self::from(VecDeque::from(other))
+    }
+}
+
+impl<T, const N: usize> From<[T; N]> for Deque<T> {
+    #[inline]
+    fn from(other: [T; N]) -> Self {
+        Self::from(Vec::from(other))
+    }
+}
+
+impl<T: Clone> From<Deque<T>> for VecDeque<T> {
+    /// Moves the items out, only copying them when they are shared.
+    #[inline]
+    fn from(other: Deque<T>) -> Self {
+        Arc::try_unwrap(other.items).unwrap_or_else(|items| (*items).clone())
+    }
+}
+
+impl<T> FromIterator<T> for Deque<T> {
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::from(iter.into_iter().collect::<VecDeque<T>>())
+    }
+}
+
+impl<T: Clone> Extend<T> for Deque<T> {
+    #[inline]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        Deque::extend(self, iter);
+    }
+}
+
+impl<'d, T> IntoIterator for &'d Deque<T> {
+    type Item = &'d T;
+    type IntoIter = vec_deque::Iter<'d, T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: Clone> IntoIterator for Deque<T> {
+    type Item = T;
+    type IntoIter = vec_deque::IntoIter<T>;
+
+    /// Moves the items out, only copying them when they are shared.
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        VecDeque::from(self).into_iter()
+    }
+}
+
+impl<T> ops::Index<usize> for Deque<T> {
+    type Output = T;
+
+    #[inline]
+    fn index(&self, index: usize) -> &T {
+        &self.items[index]
+    }
+}
+
+impl<T: Clone> ops::IndexMut<usize> for Deque<T> {
+    /// Copies the items out first if they are shared.
+    #[inline]
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        &mut Arc::make_mut(&mut self.items)[index]
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Deque<T> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq for Deque<T> {
+    /// Compares the items, ignoring `maxlen`, like Python.
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.items == other.items
+    }
+}
+
+impl<T: Eq> Eq for Deque<T> {}
+
+impl<T: PartialEq<U>, U> PartialEq<Vec<U>> for Deque<T> {
+    #[inline]
+    fn eq(&self, other: &Vec<U>) -> bool {
+        self.len() == other.len() && self.iter().zip(other).all(|(a, b)| a == b)
+    }
+}
+
+impl<T: PartialEq<U>, U, const N: usize> PartialEq<[U; N]> for Deque<T> {
+    #[inline]
+    fn eq(&self, other: &[U; N]) -> bool {
+        self.len() == N && self.iter().zip(other).all(|(a, b)| a == b)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::{xorshift, SEED};
+
+    /// Applies pseudo-random operations to a bounded `Deque` and to an unbounded model that is
+    /// trimmed by hand.
+    #[test]
+    fn bounded_matches_model() {
+        for maxlen in 0..6 {
+            let mut deque = Deque::with_maxlen(maxlen);
+            let mut model = VecDeque::new();
+            let mut snapshots = Vec::new();
+            let mut state = SEED;
+            for step in 0..2000 {
+                xorshift(&mut state);
+                match state % 8 {
+                    0 | 1 => {
+                        deque.push_back(step);
+                        model.push_back(step);
+                        if model.len() > maxlen {
+                            model.pop_front();
+                        }
+                    }
+                    2 | 3 => {
+                        deque.push_front(step);
+                        model.push_front(step);
+                        if model.len() > maxlen {
+                            model.pop_back();
+                        }
+                    }
+                    4 => assert_eq!(deque.pop_back(), model.pop_back()),
+                    5 => assert_eq!(deque.pop_front(), model.pop_front()),
+                    6 => {
+                        let n = (state >> 8) as isize % 9 - 4;
+                        deque.rotate(n);
+                        if !model.is_empty() {
+                            for _ in 0..n.rem_euclid(model.len() as isize) {
+                                let last = model.pop_back().unwrap();
+                                model.push_front(last);
+                            }
+                        }
+                    }
+                    _ => snapshots.push((deque.clone(), model.clone())),
+                }
+                assert!(deque.len() <= maxlen);
+                assert!(deque.iter().eq(model.iter()));
+            }
+            for (deque, model) in snapshots {
+                assert_eq!(VecDeque::from(deque), model);
+            }
+        }
+    }
+
+    #[test]
+    fn copy_on_write() {
+        let original: Deque<u32> = (0..10).collect();
+        let mut edited = original.clone();
+        edited.rotate(3);
+        edited[0] = 42;
+        edited.push_front(1);
+
+        assert!(original.iter().copied().eq(0..10));
+        assert_eq!(edited[1], 42);
+        assert!(!edited.is_shared_with(&original));
+
+        let mut popped = original.clone();
+        assert_eq!(popped.pop_back(), Some(9));
+        assert_eq!(original.len(), 10);
+    }
+
+    #[test]
+    fn huge_maxlen_allocates_lazily() {
+        let mut d = Deque::with_maxlen(usize::MAX);
+        d.extend(0..10);
+        assert_eq!(d.len(), 10);
+        assert_eq!(d.maxlen(), Some(usize::MAX));
+    }
+
+    #[test]
+    fn extend_front_reverses() {
+        let mut d = Deque::with_maxlen(3);
+        d.extend_front([1, 2, 3, 4]);
+        assert_eq!(d, [4, 3, 2]);
+        d.extend([5]);
+        assert_eq!(d, [3, 2, 5]);
+    }
+}
@@ -0,0 +1,1039 @@
+//! A cheaply clonable mapping that remembers insertion order: [`Dict`]
+
+pub(crate) mod table;
+
+use std::borrow::Borrow;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::iter::{FromIterator, FusedIterator};
+use std::ops;
+use std::sync::Arc;
+
+use crate::hash::{hash_with, BuildFastHasher};
+use table::Table;
+
+/// A cheaply clonable, copy-on-write mapping, like Python's `dict`
+///
+/// Iteration follows insertion order, like Python 3.7+: updating a key's value keeps its place,
+/// while removing and re-inserting it moves it to the end.  Equality ignores order, also like
+/// Python.
+///
+/// Cloning shares the entries instead of copying them, so dicts can be passed around and stored
+/// freely without fighting the borrow checker.  Mutating a dict whose entries are shared first
+/// copies them, leaving other dicts unchanged; mutating a dict that is the only one using its
+/// entries happens in place.
+///
+/// Keys are hashed with [`FastHasher`][crate::hash::FastHasher] by default, which is not
+/// resistant to HashDoS; pick another hasher with [`Dict::with_hasher`] for keys chosen by
+/// untrusted users.
+///
+/// # Examples
+///
+/// ```
+/// use ergo_core::{Dict, String};
+///
+/// let mut stock = Dict::new();
+/// stock.insert(String::from("apples"), 3);
+/// stock.insert(String::from("pears"), 0);
+///
+/// let snapshot = stock.clone();
+/// stock.insert(String::from("apples"), 5);
+/// stock.remove("pears");
+///
+/// assert_eq!(stock.get_or("apples", 0), 5);
+/// assert_eq!(snapshot.get_or("apples", 0), 3);
+/// assert_eq!(snapshot.get_or("kiwis", 0), 0);
+/// ```
+pub struct Dict<K, V, S = BuildFastHasher> {
+    table: Arc<Table<K, V>>,
+    hasher: S,
+}
+
+impl<K, V> Dict<K, V> {
+    /// Creates a new empty `Dict`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let d = ergo_core::Dict::<i32, i32>::new();
+    /// assert!(d.is_empty());
+    /// ```
+    #[inline]
+    pub fn new() -> Self {
+        Self::with_hasher(BuildFastHasher::default())
+    }
+
+    /// Creates a new empty `Dict` with room for `capacity` entries before growing.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let d = ergo_core::Dict::<i32, i32>::with_capacity(10);
+    /// assert!(d.is_empty());
+    /// ```
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            table: Arc::new(Table::with_capacity(capacity)),
+            hasher: BuildFastHasher::default(),
+        }
+    }
+}
+
+impl<K, V, S> Dict<K, V, S> {
+    /// Creates a new empty `Dict` that hashes keys with `hasher`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use std::collections::hash_map::RandomState;
+    ///
+    /// let mut d = ergo_core::Dict::with_hasher(RandomState::new());
+    /// d.insert("key", 1);
+    ///
+    /// assert_eq!(d.get_or("key", 0), 1);
+    /// ```
+    #[inline]
+    pub fn with_hasher(hasher: S) -> Self {
+        Self {
+            table: Arc::new(Table::new()),
+            hasher,
+        }
+    }
+
+    /// Returns the number of entries.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let d = ergo_core::Dict::from([("a", 1), ("b", 2)]);
+    /// assert_eq!(d.len(), 2);
+    /// ```
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    /// Returns `true` if there are no entries.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut d = ergo_core::Dict::new();
+    /// assert!(d.is_empty());
+    ///
+    /// d.insert("a", 1);
+    /// assert!(!d.is_empty());
+    /// ```
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterates over the keys and values in insertion order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let d = ergo_core::Dict::from([("b", 2), ("a", 1)]);
+    ///
+    /// let items: Vec<_> = d.iter().collect();
+    /// assert_eq!(items, [(&"b", &2), (&"a", &1)]);
+    /// ```
+    #[inline]
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter(self.table.iter())
+    }
+
+    /// Iterates over the keys in insertion order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let d = ergo_core::Dict::from([("b", 2), ("a", 1)]);
+    ///
+    /// let keys: Vec<_> = d.keys().collect();
+    /// assert_eq!(keys, [&"b", &"a"]);
+    /// ```
+    #[inline]
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys(self.table.iter())
+    }
+
+    /// Iterates over the values in insertion order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let d = ergo_core::Dict::from([("b", 2), ("a", 1)]);
+    ///
+    /// assert_eq!(d.values().sum::<i32>(), 3);
+    /// ```
+    #[inline]
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values(self.table.iter())
+    }
+
+    /// Returns the hasher that keys are hashed with.
+    #[inline]
+    pub fn hasher(&self) -> &S {
+        &self.hasher
+    }
+
+    /// Removes every entry.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut d = ergo_core::Dict::from([("a", 1)]);
+    /// d.clear();
+    ///
+    /// assert!(d.is_empty());
+    /// ```
+    #[inline]
+    pub fn clear(&mut self) {
+        match Arc::get_mut(&mut self.table) {
+            Some(table) => table.clear(),
+            None => self.table = Arc::new(Table::new()),
+        }
+    }
+
+    /// Returns `true` if both dicts share the same entries, so neither has been modified since
+    /// one was cloned from the other.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let d = ergo_core::Dict::from([("a", 1)]);
+    /// let mut copy = d.clone();
+    /// assert!(d.is_shared_with(&copy));
+    ///
+    /// copy.insert("b", 2);
+    /// assert!(!d.is_shared_with(&copy));
+    /// ```
+    #[inline]
+    pub fn is_shared_with(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.table, &other.table)
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> Dict<K, V, S> {
+    /// Returns a reference to the value for `key`, or [`None`] when it is missing.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use ergo_core::{Dict, String};
+    ///
+    /// let d = Dict::from([(String::from("a"), 1)]);
+    ///
+    /// assert_eq!(d.get("a"), Some(&1));
+    /// assert_eq!(d.get("b"), None);
+    /// ```
+    #[inline]
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.find(key)?;
+        Some(&self.table.bucket(index).value)
+    }
+
+    /// Returns the value for `key`, or `default` when it is missing, like Python's `dict.get`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use ergo_core::{Dict, String};
+    ///
+    /// let d = Dict::from([(String::from("a"), 1)]);
+    ///
+    /// assert_eq!(d.get_or("a", 0), 1);
+    /// assert_eq!(d.get_or("b", 0), 0);
+    /// ```
+    #[inline]
+    pub fn get_or<Q>(&self, key: &Q, default: V) -> V
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        V: Clone,
+    {
+        self.get(key).cloned().unwrap_or(default)
+    }
+
+    /// Returns `true` if there is an entry for `key`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let d = ergo_core::Dict::from([("a", 1)]);
+    ///
+    /// assert!(d.contains_key("a"));
+    /// assert!(!d.contains_key("b"));
+    /// ```
+    #[inline]
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.find(key).is_some()
+    }
+
+    fn hash<Q: Hash + ?Sized>(&self, key: &Q) -> u64 {
+        hash_with(&self.hasher, key)
+    }
+
+    fn find<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if self.is_empty() {
+            return None;
+        }
+        self.table.find(self.hash(key), |k| k.borrow() == key)
+    }
+}
+
+impl<K: Clone + Hash + Eq, V: Clone, S: BuildHasher> Dict<K, V, S> {
+    /// Sets the value for `key`, returning the previous value.
+    ///
+    /// A new key goes at the end of the iteration order; an existing key keeps its place.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut d = ergo_core::Dict::new();
+    ///
+    /// assert_eq!(d.insert("a", 1), None);
+    /// assert_eq!(d.insert("b", 2), None);
+    /// assert_eq!(d.insert("a", 3), Some(1));
+    /// assert_eq!(d, ergo_core::Dict::from([("a", 3), ("b", 2)]));
+    /// ```
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let hash = self.hash(&key);
+        let table = Arc::make_mut(&mut self.table);
+        match table.find(hash, |k| *k == key) {
+            Some(index) => Some(std::mem::replace(&mut table.bucket_mut(index).value, value)),
+            None => {
+                table.push(hash, key, value);
+                None
+            }
+        }
+    }
+
+    /// Returns a mutable reference to the value for `key`, or [`None`] when it is missing.
+    ///
+    /// Copies the entries first if they are shared.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut d = ergo_core::Dict::from([("a", 1)]);
+    /// *d.get_mut("a").unwrap() += 1;
+    ///
+    /// assert_eq!(d.get("a"), Some(&2));
+    /// ```
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.find(key)?;
+        Some(&mut Arc::make_mut(&mut self.table).bucket_mut(index).value)
+    }
+
+    /// Returns a mutable reference to the value for `key`, first inserting `default` when it is
+    /// missing.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut counts = ergo_core::Dict::new();
+    /// for word in ["a", "b", "a"] {
+    ///     *counts.get_or_insert(word, 0) += 1;
+    /// }
+    ///
+    /// assert_eq!(counts, ergo_core::Dict::from([("a", 2), ("b", 1)]));
+    /// ```
+    pub fn get_or_insert(&mut self, key: K, default: V) -> &mut V {
+        let hash = self.hash(&key);
+        let table = Arc::make_mut(&mut self.table);
+        let index = match table.find(hash, |k| *k == key) {
+            Some(index) => index,
+            None => table.push(hash, key, default),
+        };
+        &mut table.bucket_mut(index).value
+    }
+
+    /// Removes the entry for `key`, returning its value.
+    ///
+    /// The order of the remaining entries is unchanged.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut d = ergo_core::Dict::from([("a", 1), ("b", 2)]);
+    ///
+    /// assert_eq!(d.remove("a"), Some(1));
+    /// assert_eq!(d.remove("a"), None);
+    /// ```
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.find(key)?;
+        let hash = self.hash(key);
+        Arc::make_mut(&mut self.table)
+            .remove(hash, |k| k.borrow() == key)
+            .map(|bucket| bucket.value)
+    }
+
+    /// Removes the entry for `key`, returning its value, or `default` when it is missing.
+    ///
+    /// Like Python's `dict.pop`; use [`Dict::remove`] to tell a missing key apart from one
+    /// holding `default`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut d = ergo_core::Dict::from([("a", 1)]);
+    ///
+    /// assert_eq!(d.pop("a", 0), 1);
+    /// assert_eq!(d.pop("a", 0), 0);
+    /// ```
+    #[inline]
+    pub fn pop<Q>(&mut self, key: &Q, default: V) -> V
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.remove(key).unwrap_or(default)
+    }
+
+    /// Removes the most recently inserted entry and returns it, or [`None`] if the dict is
+    /// empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut d = ergo_core::Dict::from([("a", 1), ("b", 2)]);
+    ///
+    /// assert_eq!(d.pop_last(), Some(("b", 2)));
+    /// assert_eq!(d.pop_last(), Some(("a", 1)));
+    /// assert_eq!(d.pop_last(), None);
+    /// ```
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        if self.is_empty() {
+            return None;
+        }
+        Arc::make_mut(&mut self.table)
+            .pop()
+            .map(|bucket| (bucket.key, bucket.value))
+    }
+
+    /// Removes the least recently inserted entry and returns it.
+    pub(crate) fn pop_first(&mut self) -> Option<(K, V)> {
+        if self.is_empty() {
+            return None;
+        }
+        Arc::make_mut(&mut self.table)
+            .pop_front()
+            .map(|bucket| (bucket.key, bucket.value))
+    }
+
+    /// Moves the entry for `key` to the end of the order, or the start when `last` is `false`,
+    /// returning `false` when it is missing.
+    pub(crate) fn move_entry<Q>(&mut self, key: &Q, last: bool) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = match self.find(key) {
+            Some(index) => index,
+            None => return false,
+        };
+        let table = Arc::make_mut(&mut self.table);
+        if last {
+            table.move_to_back(index);
+        } else {
+            table.move_to_front(index);
+        }
+        true
+    }
+
+    /// Inserts every entry of `iter`, overwriting the values of existing keys.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut d = ergo_core::Dict::from([("a", 1), ("b", 2)]);
+    /// d.extend(ergo_core::Dict::from([("b", 3), ("c", 4)]));
+    ///
+    /// assert_eq!(d, ergo_core::Dict::from([("a", 1), ("b", 3), ("c", 4)]));
+    /// ```
+    pub fn extend(&mut self, iter: impl IntoIterator<Item = (K, V)>) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+/// Transitional Python API
+impl<K: Clone + Hash + Eq, V: Clone, S: BuildHasher + Clone> Dict<K, V, S> {
+    #[deprecated = "In Rust, we refer to this as `iter`"]
+    pub fn items(&self) -> Iter<'_, K, V> {
+        self.iter()
+    }
+
+    #[deprecated = "In Rust, we refer to this as `extend`"]
+    pub fn update(&mut self, iter: impl IntoIterator<Item = (K, V)>) {
+        self.extend(iter);
+    }
+
+    #[deprecated = "In Rust, we refer to this as `get_or_insert`"]
+    pub fn setdefault(&mut self, key: K, default: V) -> &mut V {
+        self.get_or_insert(key, default)
+    }
+
+    #[deprecated = "In Rust, we refer to this as `pop_last`"]
+    pub fn popitem(&mut self) -> Option<(K, V)> {
+        self.pop_last()
+    }
+
+    #[deprecated = "In Rust, we refer to this as `clone`"]
+    pub fn copy(&self) -> Self {
+        self.clone()
+    }
+}
+
+impl<K: Clone, V: Clone, S: Clone> Clone for Dict<K, V, S> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            table: self.table.clone(),
+            hasher: self.hasher.clone(),
+        }
+    }
+}
+
+impl<K, V, S: Default> Default for Dict<K, V, S> {
+    #[inline]
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher + Default> FromIterator<(K, V)> for Dict<K, V, S> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let iter = iter.into_iter();
+        let hasher = S::default();
+        let mut table = Table::with_capacity(iter.size_hint().0);
+        for (key, value) in iter {
+            let hash = hash_with(&hasher, &key);
+            match table.find(hash, |k| *k == key) {
+                Some(index) => table.bucket_mut(index).value = value,
+                None => {
+                    table.push(hash, key, value);
+                }
+            }
+        }
+        Self {
+            table: Arc::new(table),
+            hasher,
+        }
+    }
+}
+
+impl<K: Hash + Eq, V> From<Vec<(K, V)>> for Dict<K, V> {
+    #[inline]
+    fn from(other: Vec<(K, V)>) -> Self {
+        other.into_iter().collect()
+    }
+}
+
+impl<K: Hash + Eq, V, const N: usize> From<[(K, V); N]> for Dict<K, V> {
+    #[inline]
+    fn from(other: [(K, V); N]) -> Self {
+        Vec::from(other).into_iter().collect()
+    }
+}
+
+impl<K: Clone + Hash + Eq, V: Clone, S: BuildHasher> Extend<(K, V)> for Dict<K, V, S> {
+    #[inline]
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        Dict::extend(self, iter);
+    }
+}
+
+impl<'d, K, V, S> IntoIterator for &'d Dict<K, V, S> {
+    type Item = (&'d K, &'d V);
+    type IntoIter = Iter<'d, K, V>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K: Clone, V: Clone, S> IntoIterator for Dict<K, V, S> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    /// Moves the entries out, only copying them when they are shared.
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        let table = Arc::try_unwrap(self.table).unwrap_or_else(|table| (*table).clone());
+        IntoIter(table.into_iter())
+    }
+}
+
+impl<K, Q, V, S> ops::Index<&Q> for Dict<K, V, S>
+where
+    K: Borrow<Q> + Hash + Eq,
+    Q: Hash + Eq + ?Sized,
+    S: BuildHasher,
+{
+    type Output = V;
+
+    /// # Panics
+    ///
+    /// Panics if `key` is missing.
+    #[inline]
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("key not found in `Dict`")
+    }
+}
+
+impl<K, V, S> ops::BitOr<&Dict<K, V, S>> for &Dict<K, V, S>
+where
+    K: Clone + Hash + Eq,
+    V: Clone,
+    S: BuildHasher + Clone,
+{
+    type Output = Dict<K, V, S>;
+
+    /// Merges the dicts, with `rhs`'s values winning, like Python's `|`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let defaults = ergo_core::Dict::from([("color", "auto"), ("pager", "less")]);
+    /// let config = ergo_core::Dict::from([("pager", "more")]);
+    ///
+    /// let merged = &defaults | &config;
+    /// assert_eq!(merged, ergo_core::Dict::from([("color", "auto"), ("pager", "more")]));
+    /// ```
+    fn bitor(self, rhs: &Dict<K, V, S>) -> Dict<K, V, S> {
+        let mut merged = self.clone();
+        merged |= rhs;
+        merged
+    }
+}
+
+impl<K, V, S> ops::BitOr for Dict<K, V, S>
+where
+    K: Clone + Hash + Eq,
+    V: Clone,
+    S: BuildHasher,
+{
+    type Output = Self;
+
+    #[inline]
+    fn bitor(mut self, rhs: Self) -> Self {
+        self |= rhs;
+        self
+    }
+}
+
+impl<K, V, S> ops::BitOrAssign<&Dict<K, V, S>> for Dict<K, V, S>
+where
+    K: Clone + Hash + Eq,
+    V: Clone,
+    S: BuildHasher,
+{
+    #[inline]
+    fn bitor_assign(&mut self, rhs: &Self) {
+        self.extend(rhs.iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
+}
+
+impl<K, V, S> ops::BitOrAssign for Dict<K, V, S>
+where
+    K: Clone + Hash + Eq,
+    V: Clone,
+    S: BuildHasher,
+{
+    #[inline]
+    fn bitor_assign(&mut self, rhs: Self) {
+        if self.is_empty() {
+            // The table was hashed with `rhs`'s hasher, so take both
+            *self = rhs;
+        } else {
+            self.extend(rhs);
+        }
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, S> fmt::Debug for Dict<K, V, S> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Hash + Eq, V: PartialEq, S: BuildHasher> PartialEq for Dict<K, V, S> {
+    /// Compares the entries, ignoring their order.
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && (self.is_shared_with(other)
+                || self
+                    .iter()
+                    .all(|(key, value)| other.get(key) == Some(value)))
+    }
+}
+
+impl<K: Hash + Eq, V: Eq, S: BuildHasher> Eq for Dict<K, V, S> {}
+
+/// Iterates over a [`Dict`]'s keys and values in insertion order
+///
+/// Returned by [`Dict::iter`].
+#[derive(Clone, Debug)]
+pub struct Iter<'d, K, V>(table::Iter<'d, K, V>);
+
+impl<'d, K, V> Iterator for Iter<'d, K, V> {
+    type Item = (&'d K, &'d V);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|bucket| (&bucket.key, &bucket.value))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'d, K, V> DoubleEndedIterator for Iter<'d, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0
+            .next_back()
+            .map(|bucket| (&bucket.key, &bucket.value))
+    }
+}
+
+impl<'d, K, V> ExactSizeIterator for Iter<'d, K, V> {}
+
+impl<'d, K, V> FusedIterator for Iter<'d, K, V> {}
+
+/// Iterates over a [`Dict`]'s keys in insertion order
+///
+/// Returned by [`Dict::keys`].
+#[derive(Clone, Debug)]
+pub struct Keys<'d, K, V>(table::Iter<'d, K, V>);
+
+impl<'d, K, V> Iterator for Keys<'d, K, V> {
+    type Item = &'d K;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|bucket| &bucket.key)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'d, K, V> DoubleEndedIterator for Keys<'d, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|bucket| &bucket.key)
+    }
+}
+
+impl<'d, K, V> ExactSizeIterator for Keys<'d, K, V> {}
+
+impl<'d, K, V> FusedIterator for Keys<'d, K, V> {}
+
+/// Iterates over a [`Dict`]'s values in insertion order
+///
+/// Returned by [`Dict::values`].
+#[derive(Clone, Debug)]
+pub struct Values<'d, K, V>(table::Iter<'d, K, V>);
+
+impl<'d, K, V> Iterator for Values<'d, K, V> {
+    type Item = &'d V;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|bucket| &bucket.value)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'d, K, V> DoubleEndedIterator for Values<'d, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|bucket| &bucket.value)
+    }
+}
+
+impl<'d, K, V> ExactSizeIterator for Values<'d, K, V> {}
+
+impl<'d, K, V> FusedIterator for Values<'d, K, V> {}
+
+/// Moves a [`Dict`]'s keys and values out in insertion order
+///
+/// Returned by [`Dict`]'s [`IntoIterator`] implementation.
+#[derive(Debug)]
+pub struct IntoIter<K, V>(table::IntoIter<K, V>);
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|bucket| (bucket.key, bucket.value))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IntoIter<K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|bucket| (bucket.key, bucket.value))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {}
+
+impl<K, V> FusedIterator for IntoIter<K, V> {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::{xorshift, SEED};
+    use crate::String;
+    use std::hash::Hasher;
+
+    /// A key whose hash only has three values, so lookups probe past collisions
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    struct Colliding(u32);
+
+    impl Hash for Colliding {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            state.write_u32(self.0 % 3);
+        }
+    }
+
+    /// Applies pseudo-random operations to a `Dict` and to a model keeping its entries in a
+    /// `Vec`.
+    fn check_against_model<K: Clone + Hash + Eq + fmt::Debug>(key: impl Fn(u32) -> K) {
+        let mut dict = Dict::new();
+        let mut model: Vec<(K, u32)> = Vec::new();
+        let mut snapshots = Vec::new();
+        let mut state = SEED;
+        for step in 0..4000 {
+            xorshift(&mut state);
+            let k = key(state % 64);
+            match state % 10 {
+                0..=2 => {
+                    let expected = match model.iter_mut().find(|(m, _)| *m == k) {
+                        Some((_, v)) => Some(std::mem::replace(v, step)),
+                        None => {
+                            model.push((k.clone(), step));
+                            None
+                        }
+                    };
+                    assert_eq!(dict.insert(k, step), expected);
+                }
+                3..=4 => {
+                    let expected = model
+                        .iter()
+                        .position(|(m, _)| *m == k)
+                        .map(|i| model.remove(i).1);
+                    assert_eq!(dict.remove(&k), expected);
+                }
+                5 => assert_eq!(dict.pop_last(), model.pop()),
+                6 => {
+                    let expected = if model.is_empty() {
+                        None
+                    } else {
+                        Some(model.remove(0))
+                    };
+                    assert_eq!(dict.pop_first(), expected);
+                }
+                7..=8 => {
+                    let last = state % 10 == 7;
+                    let expected = match model.iter().position(|(m, _)| *m == k) {
+                        Some(i) => {
+                            let entry = model.remove(i);
+                            if last {
+                                model.push(entry);
+                            } else {
+                                model.insert(0, entry);
+                            }
+                            true
+                        }
+                        None => false,
+                    };
+                    assert_eq!(dict.move_entry(&k, last), expected);
+                }
+                _ => snapshots.push((dict.clone(), model.clone())),
+            }
+            assert_eq!(dict.len(), model.len());
+        }
+        snapshots.push((dict, model));
+        for (dict, model) in snapshots {
+            let items: Vec<_> = dict.into_iter().collect();
+            assert_eq!(items, model);
+        }
+    }
+
+    #[test]
+    fn matches_model() {
+        check_against_model(|k| k);
+        check_against_model(Colliding);
+        check_against_model(|k| String::from(k.to_string()));
+    }
+
+    #[test]
+    fn borrowed_lookups() {
+        let mut d: Dict<String, i32> = Dict::new();
+        d.insert(String::from("one"), 1);
+        d.insert(String::from("two"), 2);
+
+        assert_eq!(d["one"], 1);
+        assert_eq!(d.get("two"), Some(&2));
+        assert!(d.contains_key("two"));
+        assert_eq!(d.remove("one"), Some(1));
+        assert_eq!(d.get("one"), None);
+    }
+
+    #[test]
+    fn equality_ignores_order() {
+        let a = Dict::from([(1, "a"), (2, "b")]);
+        let b = Dict::from([(2, "b"), (1, "a")]);
+        assert_eq!(a, b);
+        assert_ne!(a, Dict::from([(1, "a"), (2, "c")]));
+        assert_ne!(a, Dict::from([(1, "a")]));
+    }
+
+    #[test]
+    fn copy_on_write() {
+        let original: Dict<u32, u32> = (0..100).map(|i| (i, i)).collect();
+        let mut copy = original.clone();
+        assert!(copy.is_shared_with(&original));
+
+        *copy.get_mut(&5).unwrap() = 500;
+        copy.remove(&6);
+        assert!(!copy.is_shared_with(&original));
+        assert_eq!(original[&5], 5);
+        assert_eq!(original[&6], 6);
+        assert_eq!(copy[&5], 500);
+        assert!(!copy.contains_key(&6));
+
+        let entries: Vec<_> = original.clone().into_iter().collect();
+        assert_eq!(entries.len(), 100);
+        assert_eq!(original.len(), 100);
+    }
+
+    #[test]
+    fn reinserting_moves_to_the_end() {
+        let mut d = Dict::from([("a", 1), ("b", 2), ("c", 3)]);
+        d.remove("a");
+        d.insert("a", 4);
+
+        let keys: Vec<_> = d.keys().copied().collect();
+        assert_eq!(keys, ["b", "c", "a"]);
+        assert_eq!(d.iter().next_back(), Some((&"a", &4)));
+    }
+
+    #[test]
+    fn merge_keeps_left_order() {
+        let mut left = Dict::from([("a", 1), ("b", 2)]);
+        let right = Dict::from([("c", 3), ("a", 4)]);
+
+        let merged = &left | &right;
+        let items: Vec<_> = merged.into_iter().collect();
+        assert_eq!(items, [("a", 4), ("b", 2), ("c", 3)]);
+
+        left |= right;
+        assert_eq!(left.len(), 3);
+        assert_eq!(format!("{:?}", left), r#"{"a": 4, "b": 2, "c": 3}"#);
+    }
+
+    #[test]
+    fn merge_into_empty_keeps_hasher() {
+        use std::collections::hash_map::RandomState;
+
+        let mut left = Dict::with_hasher(RandomState::new());
+        let mut right = Dict::with_hasher(RandomState::new());
+        for i in 0..100 {
+            right.insert(i, i);
+        }
+
+        left |= right;
+        assert_eq!(left.len(), 100);
+        assert!((0..100).all(|i| left.contains_key(&i)));
+        for i in 0..100 {
+            assert_eq!(left.insert(i, i + 1), Some(i));
+        }
+        assert_eq!(left.len(), 100);
+    }
+}
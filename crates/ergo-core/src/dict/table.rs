@@ -0,0 +1,346 @@
+use std::collections::{vec_deque, VecDeque};
+
+/// A slot that has never held an entry, ending a probe sequence
+const EMPTY: usize = usize::MAX;
+/// A slot whose entry was removed; probing continues past it
+const DELETED: usize = usize::MAX - 1;
+const MIN_SLOTS: usize = 8;
+
+/// An entry, with its hash cached so growing never rehashes keys
+#[derive(Clone, Debug)]
+pub(crate) struct Bucket<K, V> {
+    pub(crate) hash: u64,
+    pub(crate) key: K,
+    pub(crate) value: V,
+}
+
+/// A hash table that remembers insertion order, laid out like CPython's compact dict
+///
+/// Entries are appended to `entries` in insertion order, and `slots` is an open-addressed index
+/// into them.  Removing an entry leaves a hole in `entries` rather than shifting the rest, so
+/// indices stay valid; holes are compacted away when the table is rebuilt, and dropped right
+/// away from the front.
+///
+/// Indices start at `offset` rather than zero, so an entry can be moved to the front by giving
+/// it the index before the first one.  When there is none left, rebuilding makes room for as
+/// many entries as the table holds, so moving to the front takes amortized constant time.
+///
+/// The table never hashes: callers pass in each key's hash along with a predicate matching it.
+#[derive(Clone, Debug)]
+pub(crate) struct Table<K, V> {
+    entries: VecDeque<Option<Bucket<K, V>>>,
+    slots: Vec<usize>,
+    len: usize,
+    /// Slots that aren't [`EMPTY`], kept below two thirds so probes always terminate quickly
+    filled: usize,
+    /// The index of the first entry, which is never a hole
+    offset: usize,
+}
+
+impl<K, V> Table<K, V> {
+    #[inline]
+    pub(crate) const fn new() -> Self {
+        Self {
+            entries: VecDeque::new(),
+            slots: Vec::new(),
+            len: 0,
+            filled: 0,
+            offset: 0,
+        }
+    }
+
+    pub(crate) fn with_capacity(capacity: usize) -> Self {
+        let mut table = Self::new();
+        if capacity != 0 {
+            table.entries.reserve_exact(capacity);
+            table.slots = vec![EMPTY; slots_for(capacity)];
+        }
+        table
+    }
+
+    #[inline]
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns the index of the entry matching `eq`.
+    pub(crate) fn find(&self, hash: u64, mut eq: impl FnMut(&K) -> bool) -> Option<usize> {
+        self.find_slot(hash, &mut eq).map(|slot| self.slots[slot])
+    }
+
+    #[inline]
+    pub(crate) fn bucket(&self, index: usize) -> &Bucket<K, V> {
+        self.entries[index - self.offset]
+            .as_ref()
+            .expect("live entry")
+    }
+
+    #[inline]
+    pub(crate) fn bucket_mut(&mut self, index: usize) -> &mut Bucket<K, V> {
+        self.entries[index - self.offset]
+            .as_mut()
+            .expect("live entry")
+    }
+
+    /// Appends an entry for a key that isn't in the table, returning its index.
+    pub(crate) fn push(&mut self, hash: u64, key: K, value: V) -> usize {
+        if self.slots.len() * 2 < (self.filled + 1) * 3 {
+            self.rebuild(self.len * 2 + 1, self.offset);
+        }
+        let index = self.offset + self.entries.len();
+        self.index(hash, index);
+        self.entries.push_back(Some(Bucket { hash, key, value }));
+        self.len += 1;
+        index
+    }
+
+    /// Inserts an entry for a key that isn't in the table before all the others.
+    fn push_front(&mut self, hash: u64, key: K, value: V) {
+        if self.slots.len() * 2 < (self.filled + 1) * 3 {
+            self.rebuild(self.len * 2 + 1, self.len + 1);
+        } else if self.offset == 0 {
+            self.rebuild(self.len + 1, self.len + 1);
+        }
+        self.offset -= 1;
+        self.index(hash, self.offset);
+        self.entries.push_front(Some(Bucket { hash, key, value }));
+        self.len += 1;
+    }
+
+    /// Removes the entry matching `eq`, keeping the order of the rest.
+    pub(crate) fn remove(
+        &mut self,
+        hash: u64,
+        mut eq: impl FnMut(&K) -> bool,
+    ) -> Option<Bucket<K, V>> {
+        let slot = self.find_slot(hash, &mut eq)?;
+        Some(self.take(slot))
+    }
+
+    /// Removes the most recently inserted entry.
+    pub(crate) fn pop(&mut self) -> Option<Bucket<K, V>> {
+        let bucket = loop {
+            if let Some(bucket) = self.entries.pop_back()? {
+                break bucket;
+            }
+        };
+        let slot = self.slot_of(bucket.hash, self.offset + self.entries.len());
+        self.slots[slot] = DELETED;
+        self.len -= 1;
+        Some(bucket)
+    }
+
+    /// Removes the least recently inserted entry.
+    pub(crate) fn pop_front(&mut self) -> Option<Bucket<K, V>> {
+        let first = self
+            .entries
+            .front()?
+            .as_ref()
+            .expect("holes leave the front");
+        let slot = self.slot_of(first.hash, self.offset);
+        Some(self.take(slot))
+    }
+
+    /// Moves the entry at `index` to the end of the order.
+    pub(crate) fn move_to_back(&mut self, index: usize) {
+        let slot = self.slot_of(self.bucket(index).hash, index);
+        let bucket = self.take(slot);
+        self.push(bucket.hash, bucket.key, bucket.value);
+    }
+
+    /// Moves the entry at `index` to the start of the order.
+    pub(crate) fn move_to_front(&mut self, index: usize) {
+        let slot = self.slot_of(self.bucket(index).hash, index);
+        let bucket = self.take(slot);
+        self.push_front(bucket.hash, bucket.key, bucket.value);
+    }
+
+    pub(crate) fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Iterates over the entries in insertion order.
+    #[inline]
+    pub(crate) fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            entries: self.entries.iter(),
+            remaining: self.len,
+        }
+    }
+
+    /// Moves the entries out in insertion order.
+    #[inline]
+    pub(crate) fn into_iter(self) -> IntoIter<K, V> {
+        IntoIter {
+            entries: self.entries.into_iter(),
+            remaining: self.len,
+        }
+    }
+
+    /// Removes the entry that `slot` points at, compacting the entries once they are mostly
+    /// holes.
+    fn take(&mut self, slot: usize) -> Bucket<K, V> {
+        let index = std::mem::replace(&mut self.slots[slot], DELETED);
+        let bucket = self.entries[index - self.offset]
+            .take()
+            .expect("live entry");
+        self.len -= 1;
+        while let Some(None) = self.entries.front() {
+            self.entries.pop_front();
+            self.offset += 1;
+        }
+        if self.len * 2 + MIN_SLOTS < self.entries.len() {
+            self.rebuild(self.len, self.offset);
+        }
+        bucket
+    }
+
+    /// Returns the slot pointing at the entry matching `eq`.
+    fn find_slot(&self, hash: u64, eq: &mut impl FnMut(&K) -> bool) -> Option<usize> {
+        if self.slots.is_empty() {
+            return None;
+        }
+        for slot in probe(hash, self.slots.len()) {
+            match self.slots[slot] {
+                EMPTY => return None,
+                DELETED => {}
+                index => {
+                    let bucket = self.bucket(index);
+                    if bucket.hash == hash && eq(&bucket.key) {
+                        return Some(slot);
+                    }
+                }
+            }
+        }
+        unreachable!("probing visits every slot")
+    }
+
+    /// Returns the slot pointing at the entry at `index`.
+    fn slot_of(&self, hash: u64, index: usize) -> usize {
+        probe(hash, self.slots.len())
+            .find(|&slot| self.slots[slot] == index)
+            .expect("live entries have a slot")
+    }
+
+    /// Points an [`EMPTY`] slot for `hash` at `index`.
+    fn index(&mut self, hash: u64, index: usize) {
+        let slot = probe(hash, self.slots.len())
+            .find(|&slot| self.slots[slot] == EMPTY)
+            .expect("tables are at most two thirds full");
+        self.slots[slot] = index;
+        self.filled += 1;
+    }
+
+    /// Compacts the entries and reindexes them from `offset` in a table with room for
+    /// `capacity` entries.
+    fn rebuild(&mut self, capacity: usize, offset: usize) {
+        if self.entries.len() != self.len {
+            self.entries.retain(Option::is_some);
+        }
+        self.slots.clear();
+        self.slots.resize(slots_for(capacity), EMPTY);
+        self.filled = 0;
+        self.offset = offset;
+        for index in 0..self.entries.len() {
+            let hash = self.entries[index].as_ref().expect("compacted").hash;
+            self.index(hash, offset + index);
+        }
+    }
+}
+
+/// Walks the slots for `hash` in a table of `slots` slots
+///
+/// Triangular strides visit every slot of a power-of-two table before repeating.
+fn probe(hash: u64, slots: usize) -> impl Iterator<Item = usize> {
+    let mask = slots - 1;
+    let mut slot = hash as usize & mask;
+    let mut stride = 0;
+    std::iter::from_fn(move || {
+        let current = slot;
+        stride += 1;
+        slot = (slot + stride) & mask;
+        Some(current)
+    })
+    .take(slots)
+}
+
+/// The number of slots for `capacity` entries to fill at most two thirds of them
+fn slots_for(capacity: usize) -> usize {
+    (capacity * 3 / 2 + 1).next_power_of_two().max(MIN_SLOTS)
+}
+
+/// Iterates over a [`Table`]'s entries in insertion order
+#[derive(Clone, Debug)]
+pub(crate) struct Iter<'t, K, V> {
+    entries: vec_deque::Iter<'t, Option<Bucket<K, V>>>,
+    remaining: usize,
+}
+
+impl<'t, K, V> Iterator for Iter<'t, K, V> {
+    type Item = &'t Bucket<K, V>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(bucket) = self.entries.next()? {
+                self.remaining -= 1;
+                return Some(bucket);
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'t, K, V> DoubleEndedIterator for Iter<'t, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(bucket) = self.entries.next_back()? {
+                self.remaining -= 1;
+                return Some(bucket);
+            }
+        }
+    }
+}
+
+/// Moves a [`Table`]'s entries out in insertion order
+#[derive(Debug)]
+pub(crate) struct IntoIter<K, V> {
+    entries: vec_deque::IntoIter<Option<Bucket<K, V>>>,
+    remaining: usize,
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = Bucket<K, V>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(bucket) = self.entries.next()? {
+                self.remaining -= 1;
+                return Some(bucket);
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IntoIter<K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(bucket) = self.entries.next_back()? {
+                self.remaining -= 1;
+                return Some(bucket);
+            }
+        }
+    }
+}
@@ -0,0 +1,317 @@
+//! Shell-style wildcards, mirroring Python's `fnmatch`
+//!
+//! | Pattern  | Meaning                            |
+//! |----------|------------------------------------|
+//! | `*`      | matches everything                 |
+//! | `?`      | matches any single character       |
+//! | `[seq]`  | matches any character in `seq`     |
+//! | `[!seq]` | matches any character not in `seq` |
+//!
+//! Unlike shell globbing, `*` and `?` also match `/` and leading `.`s.  A `[` without a closing
+//! `]` matches itself.
+//!
+//! # Examples
+//!
+//! ```
+//! use ergo_core::fnmatch::Pattern;
+//!
+//! let pattern = Pattern::new("http.*.latency_[!p]*");
+//! let metrics = ["http.get.latency_ms", "http.get.latency_p99", "db.latency_ms"];
+//!
+//! assert_eq!(pattern.filter(&metrics), ["http.get.latency_ms"]);
+//! ```
+
+use crate::String;
+
+/// A compiled wildcard pattern
+///
+/// Matching runs the pattern as an automaton, taking time linear in the length of the name for
+/// a given pattern, with no backtracking.
+///
+/// # Examples
+///
+/// ```
+/// use ergo_core::fnmatch::Pattern;
+///
+/// let pattern = Pattern::new("*.[ch]");
+///
+/// assert!(pattern.matches("main.c"));
+/// assert!(pattern.matches("include/util.h"));
+/// assert!(!pattern.matches("main.rs"));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pattern {
+    tokens: Vec<Token>,
+    ignore_case: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Literal(char),
+    Any,
+    Star,
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+impl Token {
+    fn accepts(&self, c: char) -> bool {
+        match self {
+            Token::Literal(literal) => *literal == c,
+            Token::Any => true,
+            Token::Star => true,
+            Token::Class { negated, ranges } => {
+                let contains = ranges
+                    .iter()
+                    .any(|(start, end)| (*start..=*end).contains(&c));
+                contains != *negated
+            }
+        }
+    }
+}
+
+impl Pattern {
+    /// Compiles a case-sensitive pattern, like Python's `fnmatchcase`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let pattern = ergo_core::fnmatch::Pattern::new("*.TXT");
+    ///
+    /// assert!(pattern.matches("README.TXT"));
+    /// assert!(!pattern.matches("readme.txt"));
+    /// ```
+    pub fn new(pattern: impl AsRef<str>) -> Self {
+        Self {
+            tokens: parse(pattern.as_ref()),
+            ignore_case: false,
+        }
+    }
+
+    /// Compiles a case-insensitive pattern, like Python's `fnmatch` on Windows.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let pattern = ergo_core::fnmatch::Pattern::case_insensitive("*.TXT");
+    ///
+    /// assert!(pattern.matches("readme.txt"));
+    /// assert!(pattern.matches("Notes.Txt"));
+    /// ```
+    pub fn case_insensitive(pattern: impl AsRef<str>) -> Self {
+        Self {
+            tokens: parse(&pattern.as_ref().to_lowercase()),
+            ignore_case: true,
+        }
+    }
+
+    /// Returns `true` if all of `name` matches the pattern.
+    pub fn matches(&self, name: impl AsRef<str>) -> bool {
+        let name = name.as_ref();
+        if self.ignore_case {
+            self.run(name.chars().flat_map(char::to_lowercase))
+        } else {
+            self.run(name.chars())
+        }
+    }
+
+    /// Returns the names that match the pattern, like Python's `fnmatch.filter`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let pattern = ergo_core::fnmatch::Pattern::new("test_*.py");
+    /// let files = ["test_a.py", "a.py", "test_b.py"];
+    ///
+    /// assert_eq!(pattern.filter(&files), ["test_a.py", "test_b.py"]);
+    /// ```
+    pub fn filter<S: AsRef<str>>(&self, names: impl IntoIterator<Item = S>) -> Vec<String> {
+        names
+            .into_iter()
+            .filter(|name| self.matches(name))
+            .map(|name| String::from(name.as_ref()))
+            .collect()
+    }
+
+    /// Simulates the pattern as a nondeterministic automaton, where state `i` means the first
+    /// `i` tokens have matched.
+    fn run(&self, name: impl Iterator<Item = char>) -> bool {
+        let len = self.tokens.len();
+        let mut current = vec![false; len + 1];
+        let mut next = vec![false; len + 1];
+        current[0] = true;
+        self.close(&mut current);
+
+        for c in name {
+            next.iter_mut().for_each(|state| *state = false);
+            let mut alive = false;
+            for (i, token) in self.tokens.iter().enumerate() {
+                if current[i] && token.accepts(c) {
+                    // `*` consumes the char and stays put, everything else advances
+                    let target = if *token == Token::Star { i } else { i + 1 };
+                    next[target] = true;
+                    alive = true;
+                }
+            }
+            if !alive {
+                return false;
+            }
+            std::mem::swap(&mut current, &mut next);
+            self.close(&mut current);
+        }
+        current[len]
+    }
+
+    /// Follows the empty transitions: a `*` can match nothing.
+    fn close(&self, states: &mut [bool]) {
+        for (i, token) in self.tokens.iter().enumerate() {
+            if states[i] && *token == Token::Star {
+                states[i + 1] = true;
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for Pattern {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::new(s))
+    }
+}
+
+fn parse(pattern: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut rest = pattern;
+    while let Some(c) = rest.chars().next() {
+        rest = &rest[c.len_utf8()..];
+        let token = match c {
+            '*' => {
+                // Consecutive stars are equivalent to one
+                if tokens.last() == Some(&Token::Star) {
+                    continue;
+                }
+                Token::Star
+            }
+            '?' => Token::Any,
+            '[' => match parse_class(rest) {
+                Some((token, len)) => {
+                    rest = &rest[len..];
+                    token
+                }
+                None => Token::Literal('['),
+            },
+            c => Token::Literal(c),
+        };
+        tokens.push(token);
+    }
+    tokens
+}
+
+/// Parses the class after a `[`, returning it and the length through the closing `]`.
+fn parse_class(s: &str) -> Option<(Token, usize)> {
+    let (negated, body) = match s.strip_prefix('!') {
+        Some(body) => (true, body),
+        None => (false, s),
+    };
+    // A `]` first in the class is a member rather than the end
+    let first = if body.starts_with(']') { 1 } else { 0 };
+    let close = first + body[first..].find(']')?;
+
+    let chars: Vec<char> = body[..close].chars().collect();
+    let mut ranges = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if i + 2 < chars.len() && chars[i + 1] == '-' {
+            ranges.push((chars[i], chars[i + 2]));
+            i += 3;
+        } else {
+            ranges.push((chars[i], chars[i]));
+            i += 1;
+        }
+    }
+    let len = s.len() - body.len() + close + 1;
+    Some((Token::Class { negated, ranges }, len))
+}
+
+/// Returns `true` if `name` matches `pattern`, case-sensitively like Python's `fnmatch` on
+/// POSIX.
+///
+/// Compile a [`Pattern`] to match many names.
+///
+/// # Examples
+///
+/// ```
+/// assert!(ergo_core::fnmatch::fnmatch("data_2024.csv", "data_*.csv"));
+/// assert!(!ergo_core::fnmatch::fnmatch("data_2024.tsv", "data_*.csv"));
+/// ```
+pub fn fnmatch(name: impl AsRef<str>, pattern: impl AsRef<str>) -> bool {
+    Pattern::new(pattern).matches(name)
+}
+
+/// Returns the names that match `pattern`.
+///
+/// # Examples
+///
+/// ```
+/// let names = ["a.rs", "b.py", "c.rs"];
+/// assert_eq!(ergo_core::fnmatch::filter(&names, "*.rs"), ["a.rs", "c.rs"]);
+/// ```
+pub fn filter<S: AsRef<str>>(
+    names: impl IntoIterator<Item = S>,
+    pattern: impl AsRef<str>,
+) -> Vec<String> {
+    Pattern::new(pattern).filter(names)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn matches_python() {
+        // Expected values are from CPython 3.12 `fnmatch.fnmatchcase`
+        let cases = [
+            ("abc", "abc", true),
+            ("abc", "a?c", true),
+            ("abc", "a*", true),
+            ("abc", "*c", true),
+            ("abc", "*b*", true),
+            ("abc", "*d*", false),
+            ("", "*", true),
+            ("", "?", false),
+            ("a/b", "a*b", true),
+            ("b", "[abc]", true),
+            ("d", "[!abc]", true),
+            ("a", "[!abc]", false),
+            ("]", "[]]", true),
+            ("]", "[!]]", false),
+            ("-", "[a-]", true),
+            ("q", "[a-z]", true),
+            ("[", "[", true),
+            ("[ab", "[ab", true),
+            ("!", "[!]", false),
+            ("[!]", "[!]", true),
+            ("b", "[z-a]", false),
+            ("-", "[a-c-e]", true),
+            ("d", "[a-c-e]", false),
+            ("é", "?", true),
+            (
+                "aaaaaaaaaaaaaaaaaaaaaaaaaaaaab",
+                "*a*a*a*a*a*a*a*a*a*c",
+                false,
+            ),
+        ];
+        for (name, pattern, expected) in cases.iter() {
+            assert_eq!(
+                fnmatch(name, pattern),
+                *expected,
+                "{:?} {:?}",
+                name,
+                pattern
+            );
+        }
+    }
+}
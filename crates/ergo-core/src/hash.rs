@@ -0,0 +1,157 @@
+//! A fast, non-cryptographic hasher for in-memory collections
+//!
+//! [`FastHasher`] is the multiply-rotate hash used inside `rustc` (often called FxHash).  It is
+//! several times faster than the standard library's SipHash for short keys like identifiers and
+//! column values, but it is not resistant to HashDoS: use the standard
+//! [`RandomState`][std::collections::hash_map::RandomState] for keys chosen by untrusted users.
+//!
+//! # Examples
+//!
+//! ```
+//! use ergo_core::hash::FastHashMap;
+//!
+//! let mut counts = FastHashMap::default();
+//! for word in "the cat and the hat".split(' ') {
+//!     *counts.entry(ergo_core::String::from(word)).or_insert(0) += 1;
+//! }
+//!
+//! assert_eq!(counts[&ergo_core::String::from("the")], 2);
+//! ```
+
+use std::convert::TryInto;
+use std::hash::{BuildHasherDefault, Hasher};
+
+const SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+/// A fast, non-cryptographic [`Hasher`]
+///
+/// See the [module documentation][self] for when not to use it.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct FastHasher {
+    hash: u64,
+}
+
+impl FastHasher {
+    #[inline]
+    fn add(&mut self, word: u64) {
+        self.hash = (self.hash.rotate_left(5) ^ word).wrapping_mul(SEED);
+    }
+}
+
+impl Hasher for FastHasher {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.chunks_exact(8);
+        for chunk in &mut chunks {
+            self.add(u64::from_le_bytes(chunk.try_into().unwrap()));
+        }
+        let mut rest = chunks.remainder();
+        if 4 <= rest.len() {
+            self.add(u32::from_le_bytes(rest[..4].try_into().unwrap()) as u64);
+            rest = &rest[4..];
+        }
+        if 2 <= rest.len() {
+            self.add(u16::from_le_bytes(rest[..2].try_into().unwrap()) as u64);
+            rest = &rest[2..];
+        }
+        if let Some(&byte) = rest.first() {
+            self.add(byte as u64);
+        }
+    }
+
+    #[inline]
+    fn write_u8(&mut self, i: u8) {
+        self.add(i as u64);
+    }
+
+    #[inline]
+    fn write_u16(&mut self, i: u16) {
+        self.add(i as u64);
+    }
+
+    #[inline]
+    fn write_u32(&mut self, i: u32) {
+        self.add(i as u64);
+    }
+
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        self.add(i);
+    }
+
+    #[inline]
+    fn write_usize(&mut self, i: usize) {
+        self.add(i as u64);
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+/// Creates [`FastHasher`]s for [`HashMap`][std::collections::HashMap] and
+/// [`HashSet`][std::collections::HashSet]
+pub type BuildFastHasher = BuildHasherDefault<FastHasher>;
+
+/// A [`HashMap`][std::collections::HashMap] using [`FastHasher`]
+///
+/// Create one with `FastHashMap::default()`.
+pub type FastHashMap<K, V> = std::collections::HashMap<K, V, BuildFastHasher>;
+
+/// A [`HashSet`][std::collections::HashSet] using [`FastHasher`]
+///
+/// Create one with `FastHashSet::default()`.
+pub type FastHashSet<T> = std::collections::HashSet<T, BuildFastHasher>;
+
+/// Hashes `value` with a [`FastHasher`].
+///
+/// # Examples
+///
+/// ```
+/// use ergo_core::hash::hash;
+///
+/// assert_eq!(hash("key"), hash(&ergo_core::String::from("key")));
+/// assert_ne!(hash("key"), hash("other key"));
+/// ```
+pub fn hash<T: std::hash::Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = FastHasher::default();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Hashes `value` with a hasher from `build`, like `BuildHasher::hash_one` from Rust 1.71.
+#[allow(clippy::manual_hash_one)]
+pub(crate) fn hash_with<S: std::hash::BuildHasher, T: std::hash::Hash + ?Sized>(
+    build: &S,
+    value: &T,
+) -> u64 {
+    let mut hasher = build.build_hasher();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn write_uses_every_byte() {
+        let fixture = b"0123456789abcdef0123456";
+        let hashes: FastHashSet<u64> = (0..fixture.len())
+            .map(|len| {
+                let mut hasher = FastHasher::default();
+                hasher.write(&fixture[..len]);
+                hasher.finish()
+            })
+            .chain((0..fixture.len()).map(|i| {
+                let mut changed = *fixture;
+                changed[i] ^= 1;
+                let mut hasher = FastHasher::default();
+                hasher.write(&changed);
+                hasher.finish()
+            }))
+            .collect();
+        assert_eq!(hashes.len(), 2 * fixture.len());
+    }
+}
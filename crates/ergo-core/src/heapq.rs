@@ -0,0 +1,502 @@
+//! Min-heaps on plain slices and `Vec`s, like Python's `heapq`
+//!
+//! A heap is an ordinary `Vec` whose items are arranged so that `heap[0]` is always the
+//! smallest, which is handy when a [`BinaryHeap`][std::collections::BinaryHeap] would get in the
+//! way: the heap can still be indexed, sliced, iterated and serialized like any other `Vec`.
+//!
+//! | Python                         | Rust                                      |
+//! |--------------------------------|-------------------------------------------|
+//! | `heapify(h)`                   | [`heapify(&mut h)`][heapify]              |
+//! | `heappush(h, x)`               | [`heappush(&mut h, x)`][heappush]         |
+//! | `heappop(h)`                   | [`heappop(&mut h)`][heappop]              |
+//! | `nlargest(n, xs)`              | [`nlargest(n, xs)`][nlargest]             |
+//! | `nsmallest(n, xs, key=f)`      | [`nsmallest_by_key(n, xs, f)`][nsmallest_by_key] |
+//! | `merge(xs, ys)`                | [`merge([xs, ys])`][merge]                |
+//!
+//! For a max-heap, wrap the items in [`Reverse`][std::cmp::Reverse].
+//!
+//! # Examples
+//!
+//! ```
+//! use ergo_core::heapq::{heappop, heappush};
+//!
+//! let mut queue = Vec::new();
+//! heappush(&mut queue, (30, "lint"));
+//! heappush(&mut queue, (10, "build"));
+//! heappush(&mut queue, (20, "test"));
+//!
+//! assert_eq!(queue[0], (10, "build"));
+//! assert_eq!(heappop(&mut queue), Some((10, "build")));
+//! assert_eq!(heappop(&mut queue), Some((20, "test")));
+//! ```
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::FusedIterator;
+
+/// Rearranges `items` into a heap, in linear time.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use ergo_core::heapq::{heapify, heappop};
+///
+/// let mut heap = vec![5, 1, 4, 2, 3];
+/// heapify(&mut heap);
+///
+/// assert_eq!(heap[0], 1);
+/// assert_eq!(heappop(&mut heap), Some(1));
+/// assert_eq!(heappop(&mut heap), Some(2));
+/// ```
+pub fn heapify<T: Ord>(items: &mut [T]) {
+    for pos in (0..items.len() / 2).rev() {
+        sift_down(items, pos, &mut T::lt);
+    }
+}
+
+/// Adds `item` to `heap`, keeping it a heap.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use ergo_core::heapq::heappush;
+///
+/// let mut heap = vec![];
+/// heappush(&mut heap, 2);
+/// heappush(&mut heap, 1);
+///
+/// assert_eq!(heap[0], 1);
+/// ```
+pub fn heappush<T: Ord>(heap: &mut Vec<T>, item: T) {
+    heap.push(item);
+    let last = heap.len() - 1;
+    sift_up(heap, last, &mut T::lt);
+}
+
+/// Removes the smallest item from `heap` and returns it, or [`None`] if it is empty.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use ergo_core::heapq::{heapify, heappop};
+///
+/// let mut heap = vec![3, 1, 2];
+/// heapify(&mut heap);
+///
+/// assert_eq!(heappop(&mut heap), Some(1));
+/// assert_eq!(heappop(&mut heap), Some(2));
+/// assert_eq!(heappop(&mut heap), Some(3));
+/// assert_eq!(heappop(&mut heap), None);
+/// ```
+pub fn heappop<T: Ord>(heap: &mut Vec<T>) -> Option<T> {
+    pop(heap, &mut T::lt)
+}
+
+/// Returns the `n` largest items, largest first.
+///
+/// Equal items keep their order from `items`, so this matches Python's `nlargest` and
+/// `sorted(items, reverse=True)[:n]`.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// let scores = [72, 95, 88, 100, 61];
+/// assert_eq!(ergo_core::heapq::nlargest(3, scores), [100, 95, 88]);
+/// ```
+pub fn nlargest<T: Ord>(n: usize, items: impl IntoIterator<Item = T>) -> Vec<T> {
+    select(n, items.into_iter().enumerate(), |(i, a), (j, b)| {
+        b.cmp(a).then(i.cmp(j)) == Ordering::Less
+    })
+    .into_iter()
+    .map(|(_, item)| item)
+    .collect()
+}
+
+/// Returns the `n` smallest items, smallest first.
+///
+/// Equal items keep their order from `items`, so this matches Python's `nsmallest` and
+/// `sorted(items)[:n]`.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// let scores = [72, 95, 88, 100, 61];
+/// assert_eq!(ergo_core::heapq::nsmallest(2, scores), [61, 72]);
+/// ```
+pub fn nsmallest<T: Ord>(n: usize, items: impl IntoIterator<Item = T>) -> Vec<T> {
+    select(n, items.into_iter().enumerate(), |(i, a), (j, b)| {
+        a.cmp(b).then(i.cmp(j)) == Ordering::Less
+    })
+    .into_iter()
+    .map(|(_, item)| item)
+    .collect()
+}
+
+/// Returns the `n` items with the largest keys, largest first, like Python's
+/// `nlargest(n, items, key=key)`.
+///
+/// `key` is called once per item, and items with equal keys keep their order from `items`.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// let jobs = [("build", 3), ("test", 8), ("lint", 1), ("deploy", 8)];
+/// let slowest = ergo_core::heapq::nlargest_by_key(2, jobs, |(_, minutes)| *minutes);
+///
+/// assert_eq!(slowest, [("test", 8), ("deploy", 8)]);
+/// ```
+pub fn nlargest_by_key<T, K: Ord>(
+    n: usize,
+    items: impl IntoIterator<Item = T>,
+    mut key: impl FnMut(&T) -> K,
+) -> Vec<T> {
+    let keyed = items
+        .into_iter()
+        .enumerate()
+        .map(|(i, item)| (key(&item), i, item));
+    select(n, keyed, |(k, i, _), (l, j, _)| {
+        l.cmp(k).then(i.cmp(j)) == Ordering::Less
+    })
+    .into_iter()
+    .map(|(_, _, item)| item)
+    .collect()
+}
+
+/// Returns the `n` items with the smallest keys, smallest first, like Python's
+/// `nsmallest(n, items, key=key)`.
+///
+/// `key` is called once per item, and items with equal keys keep their order from `items`.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// let jobs = [("build", 3), ("test", 8), ("lint", 1), ("deploy", 8)];
+/// let quickest = ergo_core::heapq::nsmallest_by_key(2, jobs, |(_, minutes)| *minutes);
+///
+/// assert_eq!(quickest, [("lint", 1), ("build", 3)]);
+/// ```
+pub fn nsmallest_by_key<T, K: Ord>(
+    n: usize,
+    items: impl IntoIterator<Item = T>,
+    mut key: impl FnMut(&T) -> K,
+) -> Vec<T> {
+    let keyed = items
+        .into_iter()
+        .enumerate()
+        .map(|(i, item)| (key(&item), i, item));
+    select(n, keyed, |(k, i, _), (l, j, _)| {
+        k.cmp(l).then(i.cmp(j)) == Ordering::Less
+    })
+    .into_iter()
+    .map(|(_, _, item)| item)
+    .collect()
+}
+
+/// Lazily merges already-sorted inputs into one sorted iterator, like Python's `merge`.
+///
+/// Equal items come out in the order of the inputs they came from.  Each input is only advanced
+/// once its previous item has been returned.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// let morning = vec![9, 10, 11];
+/// let afternoon = vec![10, 14];
+/// let merged: Vec<_> = ergo_core::heapq::merge([morning, afternoon]).collect();
+///
+/// assert_eq!(merged, [9, 10, 10, 11, 14]);
+/// ```
+pub fn merge<I>(
+    inputs: impl IntoIterator<Item = I>,
+) -> Merge<I::IntoIter, impl FnMut(&I::Item, &I::Item) -> bool>
+where
+    I: IntoIterator,
+    I::Item: Ord,
+{
+    Merge::new(inputs, |a: &I::Item, b: &I::Item| a < b)
+}
+
+/// Lazily merges inputs already sorted by `key` into one iterator sorted by `key`, like Python's
+/// `merge(*inputs, key=key)`.
+///
+/// Unlike [`nsmallest_by_key`], `key` is called on every comparison.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// let builds = vec![(1, "build a"), (5, "build b")];
+/// let deploys = vec![(3, "deploy a")];
+/// let timeline: Vec<_> = ergo_core::heapq::merge_by_key([builds, deploys], |(at, _)| *at)
+///     .map(|(_, event)| event)
+///     .collect();
+///
+/// assert_eq!(timeline, ["build a", "deploy a", "build b"]);
+/// ```
+pub fn merge_by_key<I, K: Ord>(
+    inputs: impl IntoIterator<Item = I>,
+    mut key: impl FnMut(&I::Item) -> K,
+) -> Merge<I::IntoIter, impl FnMut(&I::Item, &I::Item) -> bool>
+where
+    I: IntoIterator,
+{
+    Merge::new(inputs, move |a: &I::Item, b: &I::Item| key(a) < key(b))
+}
+
+/// Merges sorted iterators
+///
+/// Returned by [`merge`] and [`merge_by_key`].
+pub struct Merge<I: Iterator, F> {
+    /// The next item of each input that still has one, with the input's index
+    heap: Vec<(I::Item, usize)>,
+    inputs: Vec<I>,
+    less: F,
+}
+
+impl<I: Iterator, F: FnMut(&I::Item, &I::Item) -> bool> Merge<I, F> {
+    fn new(inputs: impl IntoIterator<Item = impl IntoIterator<IntoIter = I>>, mut less: F) -> Self {
+        let mut inputs: Vec<I> = inputs.into_iter().map(IntoIterator::into_iter).collect();
+        let mut heap: Vec<_> = (inputs.iter_mut().enumerate())
+            .filter_map(|(i, input)| Some((input.next()?, i)))
+            .collect();
+        for pos in (0..heap.len() / 2).rev() {
+            sift_down(&mut heap, pos, &mut by_input(&mut less));
+        }
+        Self { heap, inputs, less }
+    }
+}
+
+/// Orders merge entries by item, then by the index of the input they came from.
+fn by_input<T>(
+    less: &mut impl FnMut(&T, &T) -> bool,
+) -> impl FnMut(&(T, usize), &(T, usize)) -> bool + '_ {
+    move |(a, i), (b, j)| less(a, b) || (!less(b, a) && i < j)
+}
+
+impl<I: Iterator, F: FnMut(&I::Item, &I::Item) -> bool> Iterator for Merge<I, F> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        let Self { heap, inputs, less } = self;
+        let mut less = by_input(less);
+        let input = heap.first()?.1;
+        match inputs[input].next() {
+            Some(next) => {
+                let item = std::mem::replace(&mut heap[0].0, next);
+                sift_down(heap, 0, &mut less);
+                Some(item)
+            }
+            None => pop(heap, &mut less).map(|(item, _)| item),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inputs.iter().map(Iterator::size_hint).fold(
+            (self.heap.len(), Some(self.heap.len())),
+            |(lo, hi), (l, h)| {
+                let hi = match (hi, h) {
+                    (Some(hi), Some(h)) => hi.checked_add(h),
+                    _ => None,
+                };
+                (lo.saturating_add(l), hi)
+            },
+        )
+    }
+}
+
+impl<I: FusedIterator, F: FnMut(&I::Item, &I::Item) -> bool> FusedIterator for Merge<I, F> {}
+
+impl<I: Iterator + fmt::Debug, F> fmt::Debug for Merge<I, F>
+where
+    I::Item: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Merge")
+            .field("heap", &self.heap)
+            .field("inputs", &self.inputs)
+            .finish()
+    }
+}
+
+/// Keeps the `n` smallest of `items` by `less`, which must be a strict total order, and returns
+/// them sorted.
+fn select<T>(
+    n: usize,
+    items: impl Iterator<Item = T>,
+    mut less: impl FnMut(&T, &T) -> bool,
+) -> Vec<T> {
+    if n == 0 {
+        return Vec::new();
+    }
+    // A max-heap of the smallest items so far, so the largest of them is the first to go
+    let mut greater = |a: &T, b: &T| less(b, a);
+    let mut kept = Vec::new();
+    for item in items {
+        if kept.len() < n {
+            kept.push(item);
+            let last = kept.len() - 1;
+            sift_up(&mut kept, last, &mut greater);
+        } else if greater(&kept[0], &item) {
+            kept[0] = item;
+            sift_down(&mut kept, 0, &mut greater);
+        }
+    }
+    kept.sort_by(|a, b| {
+        if less(a, b) {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        }
+    });
+    kept
+}
+
+/// Removes the first item of `heap` and restores the heap order.
+fn pop<T>(heap: &mut Vec<T>, less: &mut impl FnMut(&T, &T) -> bool) -> Option<T> {
+    let last = heap.pop()?;
+    if heap.is_empty() {
+        return Some(last);
+    }
+    let first = std::mem::replace(&mut heap[0], last);
+    sift_down(heap, 0, less);
+    Some(first)
+}
+
+/// Moves the item at `pos` up until its parent is not greater than it.
+fn sift_up<T>(heap: &mut [T], mut pos: usize, less: &mut impl FnMut(&T, &T) -> bool) {
+    while pos > 0 {
+        let parent = (pos - 1) / 2;
+        if !less(&heap[pos], &heap[parent]) {
+            break;
+        }
+        heap.swap(pos, parent);
+        pos = parent;
+    }
+}
+
+/// Moves the item at `pos` down until neither child is less than it.
+fn sift_down<T>(heap: &mut [T], mut pos: usize, less: &mut impl FnMut(&T, &T) -> bool) {
+    loop {
+        let left = 2 * pos + 1;
+        if left >= heap.len() {
+            break;
+        }
+        let right = left + 1;
+        let child = if right < heap.len() && less(&heap[right], &heap[left]) {
+            right
+        } else {
+            left
+        };
+        if !less(&heap[child], &heap[pos]) {
+            break;
+        }
+        heap.swap(pos, child);
+        pos = child;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::{xorshift, SEED};
+
+    fn is_heap<T: Ord>(heap: &[T]) -> bool {
+        (1..heap.len()).all(|i| heap[(i - 1) / 2] <= heap[i])
+    }
+
+    /// Yields pseudo-random numbers below `limit`.
+    fn numbers(count: usize, limit: u32) -> impl Iterator<Item = u32> {
+        let mut state = SEED;
+        (0..count).map(move |_| xorshift(&mut state) % limit)
+    }
+
+    #[test]
+    fn heap_sorts() {
+        for len in 0..40 {
+            let items: Vec<u32> = numbers(len, 10).collect();
+            let mut heap = items.clone();
+            heapify(&mut heap);
+            assert!(is_heap(&heap));
+
+            let mut pushed = Vec::new();
+            for &item in &items {
+                heappush(&mut pushed, item);
+                assert!(is_heap(&pushed));
+            }
+
+            let mut sorted = items;
+            sorted.sort_unstable();
+            for heap in [heap, pushed] {
+                let mut heap = heap;
+                let popped: Vec<u32> = std::iter::from_fn(|| heappop(&mut heap)).collect();
+                assert_eq!(popped, sorted);
+            }
+        }
+    }
+
+    #[test]
+    fn selection_is_stable() {
+        let items: Vec<(u32, usize)> = numbers(200, 8).zip(0..).collect();
+        for n in [0, 1, 5, 50, 200, 300] {
+            let mut ascending = items.clone();
+            ascending.sort_by_key(|&(k, _)| k);
+            let mut descending = items.clone();
+            descending.sort_by_key(|&(k, _)| std::cmp::Reverse(k));
+
+            let take = n.min(items.len());
+            assert_eq!(
+                nsmallest_by_key(n, items.iter().copied(), |&(k, _)| k),
+                ascending[..take]
+            );
+            assert_eq!(
+                nlargest_by_key(n, items.iter().copied(), |&(k, _)| k),
+                descending[..take]
+            );
+            assert_eq!(nsmallest(n, items.iter().copied()), {
+                let mut all = items.clone();
+                all.sort_unstable();
+                all.truncate(take);
+                all
+            });
+        }
+    }
+
+    #[test]
+    fn merge_is_stable() {
+        let inputs: Vec<Vec<(u32, usize)>> = (0..5)
+            .map(|input| {
+                let mut items: Vec<_> = numbers(20 + input * 7, 10)
+                    .skip(input * 3)
+                    .map(|k| (k, input))
+                    .collect();
+                items.sort_unstable();
+                items
+            })
+            .collect();
+        let mut expected: Vec<_> = inputs.iter().flatten().copied().collect();
+        expected.sort_by_key(|&(k, _)| k);
+
+        let merged = merge_by_key(inputs.clone(), |&(k, _)| k);
+        assert_eq!(merged.size_hint(), (expected.len(), Some(expected.len())));
+        assert_eq!(merged.collect::<Vec<_>>(), expected);
+        assert!(merge(inputs).eq(expected));
+        assert_eq!(merge(Vec::<Vec<u8>>::new()).next(), None);
+    }
+}
@@ -0,0 +1,393 @@
+//! Arithmetic on magnitudes: unsigned numbers stored as little-endian `u32` limbs
+//!
+//! Every function takes and returns normalized magnitudes, without trailing zero limbs, so zero
+//! is the empty slice.
+
+use std::cmp::Ordering;
+
+pub(super) const BITS: usize = 32;
+
+/// Below this many limbs, schoolbook multiplication beats Karatsuba
+const KARATSUBA_THRESHOLD: usize = 32;
+
+pub(super) fn normalize(mut limbs: Vec<u32>) -> Vec<u32> {
+    while limbs.last() == Some(&0) {
+        limbs.pop();
+    }
+    limbs
+}
+
+fn trim(limbs: &[u32]) -> &[u32] {
+    let len = limbs.iter().rposition(|&l| l != 0).map_or(0, |i| i + 1);
+    &limbs[..len]
+}
+
+pub(super) fn from_u128(mut value: u128) -> Vec<u32> {
+    let mut limbs = Vec::new();
+    while value != 0 {
+        limbs.push(value as u32);
+        value >>= BITS;
+    }
+    limbs
+}
+
+/// Returns the magnitude as a `u128`, if it fits.
+pub(super) fn to_u128(limbs: &[u32]) -> Option<u128> {
+    if limbs.len() > 4 {
+        return None;
+    }
+    Some(
+        limbs
+            .iter()
+            .rev()
+            .fold(0, |acc, &l| acc << BITS | l as u128),
+    )
+}
+
+pub(super) fn cmp(a: &[u32], b: &[u32]) -> Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+pub(super) fn bit_length(limbs: &[u32]) -> u64 {
+    match limbs.last() {
+        Some(top) => (limbs.len() * BITS) as u64 - top.leading_zeros() as u64,
+        None => 0,
+    }
+}
+
+pub(super) fn add(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let (a, b) = if a.len() < b.len() { (b, a) } else { (a, b) };
+    let mut sum = a.to_vec();
+    add_assign(&mut sum, b, 0);
+    sum
+}
+
+/// Adds `b`, shifted left by `offset` limbs, to `acc`.
+fn add_assign(acc: &mut Vec<u32>, b: &[u32], offset: usize) {
+    if acc.len() < offset + b.len() {
+        acc.resize(offset + b.len(), 0);
+    }
+    let mut carry = 0;
+    for (i, limb) in acc[offset..].iter_mut().enumerate() {
+        if i >= b.len() && carry == 0 {
+            break;
+        }
+        let sum = *limb as u64 + b.get(i).copied().unwrap_or_default() as u64 + carry;
+        *limb = sum as u32;
+        carry = sum >> BITS;
+    }
+    if carry != 0 {
+        acc.push(carry as u32);
+    }
+}
+
+/// Returns `a - b`, where `a >= b`.
+pub(super) fn sub(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut difference = a.to_vec();
+    sub_assign(&mut difference, b);
+    normalize(difference)
+}
+
+/// Subtracts `b` from `acc`, where `acc >= b`, leaving `acc` unnormalized.
+fn sub_assign(acc: &mut [u32], b: &[u32]) {
+    let mut borrow = 0;
+    for (i, limb) in acc.iter_mut().enumerate() {
+        if i >= b.len() && borrow == 0 {
+            break;
+        }
+        let difference = *limb as i64 - b.get(i).copied().unwrap_or_default() as i64 - borrow;
+        *limb = difference as u32;
+        borrow = (difference < 0) as i64;
+    }
+    debug_assert_eq!(borrow, 0, "subtraction underflowed");
+}
+
+pub(super) fn mul(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let (a, b) = if a.len() < b.len() { (b, a) } else { (a, b) };
+    if b.is_empty() {
+        return Vec::new();
+    }
+    if b.len() < KARATSUBA_THRESHOLD {
+        return schoolbook(a, b);
+    }
+
+    let half = a.len() - a.len() / 2;
+    let (a0, a1) = (trim(&a[..half]), &a[half..]);
+    if b.len() <= half {
+        // Too lopsided to split `b`, so multiply each half of `a` by all of it
+        let mut product = mul(a0, b);
+        add_assign(&mut product, &mul(a1, b), half);
+        return normalize(product);
+    }
+
+    // (a1·B + a0)(b1·B + b0) = a1·b1·B² + ((a0 + a1)(b0 + b1) - a0·b0 - a1·b1)·B + a0·b0
+    let (b0, b1) = (trim(&b[..half]), &b[half..]);
+    let mut product = mul(a0, b0);
+    let high = mul(a1, b1);
+    let mut middle = mul(&add(a0, a1), &add(b0, b1));
+    sub_assign(&mut middle, &product);
+    sub_assign(&mut middle, &high);
+    add_assign(&mut product, trim(&middle), half);
+    add_assign(&mut product, &high, 2 * half);
+    normalize(product)
+}
+
+fn schoolbook(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut product = vec![0; a.len() + b.len()];
+    for (i, &x) in b.iter().enumerate() {
+        let mut carry = 0;
+        for (j, &y) in a.iter().enumerate() {
+            let sum = x as u64 * y as u64 + product[i + j] as u64 + carry;
+            product[i + j] = sum as u32;
+            carry = sum >> BITS;
+        }
+        product[i + a.len()] = carry as u32;
+    }
+    normalize(product)
+}
+
+/// Multiplies `acc` by `factor` and adds `addend`, in place.
+pub(super) fn mul_add_small(acc: &mut Vec<u32>, factor: u32, addend: u32) {
+    let mut carry = addend as u64;
+    for limb in acc.iter_mut() {
+        let sum = *limb as u64 * factor as u64 + carry;
+        *limb = sum as u32;
+        carry = sum >> BITS;
+    }
+    if carry != 0 {
+        acc.push(carry as u32);
+    }
+}
+
+/// Divides `acc` by `divisor` in place, returning the remainder.
+pub(super) fn div_rem_small(acc: &mut Vec<u32>, divisor: u32) -> u32 {
+    let mut rem = 0;
+    for limb in acc.iter_mut().rev() {
+        let dividend = rem << BITS | *limb as u64;
+        *limb = (dividend / divisor as u64) as u32;
+        rem = dividend % divisor as u64;
+    }
+    while acc.last() == Some(&0) {
+        acc.pop();
+    }
+    rem as u32
+}
+
+/// Returns the quotient and remainder of `a / b`, where `b` is not zero.
+///
+/// This is Knuth's Algorithm D, from _The Art of Computer Programming_, volume 2, §4.3.1.
+pub(super) fn div_rem(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    assert!(!b.is_empty(), "attempt to divide by zero");
+    if cmp(a, b) == Ordering::Less {
+        return (Vec::new(), a.to_vec());
+    }
+    if b.len() == 1 {
+        let mut quotient = a.to_vec();
+        let rem = div_rem_small(&mut quotient, b[0]);
+        return (quotient, from_u128(rem.into()));
+    }
+
+    // Shift so the divisor's top limb has its high bit set, which keeps each estimated quotient
+    // limb within 2 of the truth
+    let shift = b[b.len() - 1].leading_zeros() as usize;
+    let v = shl(b, shift);
+    let mut u = shl(a, shift);
+    u.resize(a.len() + 1, 0);
+
+    let n = v.len();
+    let (v_top, v_next) = (v[n - 1] as u64, v[n - 2] as u64);
+    let mut quotient = vec![0; a.len() - n + 1];
+    for j in (0..quotient.len()).rev() {
+        let numerator = (u[j + n] as u64) << BITS | u[j + n - 1] as u64;
+        let mut q = numerator / v_top;
+        let mut r = numerator % v_top;
+        while q >> BITS != 0 || q * v_next > (r << BITS | u[j + n - 2] as u64) {
+            q -= 1;
+            r += v_top;
+            if r >> BITS != 0 {
+                break;
+            }
+        }
+
+        // u[j..=j + n] -= q·v
+        let mut borrow = 0;
+        let mut carry = 0;
+        for i in 0..n {
+            let product = q * v[i] as u64 + carry;
+            carry = product >> BITS;
+            let difference = u[i + j] as i64 - borrow - (product as u32) as i64;
+            u[i + j] = difference as u32;
+            borrow = (difference < 0) as i64;
+        }
+        let difference = u[j + n] as i64 - borrow - carry as i64;
+        u[j + n] = difference as u32;
+
+        if difference < 0 {
+            // `q` was one too large, so add `v` back
+            q -= 1;
+            let mut carry = 0;
+            for i in 0..n {
+                let sum = u[i + j] as u64 + v[i] as u64 + carry;
+                u[i + j] = sum as u32;
+                carry = sum >> BITS;
+            }
+            u[j + n] = u[j + n].wrapping_add(carry as u32);
+        }
+        quotient[j] = q as u32;
+    }
+
+    (normalize(quotient), shr(trim(&u[..n]), shift))
+}
+
+pub(super) fn shl(limbs: &[u32], bits: usize) -> Vec<u32> {
+    if limbs.is_empty() {
+        return Vec::new();
+    }
+    let (whole, part) = (bits / BITS, bits % BITS);
+    let mut shifted = vec![0; whole];
+    shifted.reserve(limbs.len() + 1);
+    match part {
+        0 => shifted.extend_from_slice(limbs),
+        _ => {
+            let mut carry = 0;
+            for &limb in limbs {
+                shifted.push(limb << part | carry);
+                carry = limb >> (BITS - part);
+            }
+            shifted.push(carry);
+        }
+    }
+    normalize(shifted)
+}
+
+pub(super) fn shr(limbs: &[u32], bits: usize) -> Vec<u32> {
+    let (whole, part) = (bits / BITS, bits % BITS);
+    if whole >= limbs.len() {
+        return Vec::new();
+    }
+    let limbs = &limbs[whole..];
+    let shifted = match part {
+        0 => limbs.to_vec(),
+        _ => (0..limbs.len())
+            .map(|i| {
+                let high = limbs.get(i + 1).map_or(0, |&l| l << (BITS - part));
+                limbs[i] >> part | high
+            })
+            .collect(),
+    };
+    normalize(shifted)
+}
+
+/// Returns `true` if any of the lowest `bits` bits are set.
+pub(super) fn any_below(limbs: &[u32], bits: usize) -> bool {
+    let (whole, part) = (bits / BITS, bits % BITS);
+    if limbs.iter().take(whole).any(|&l| l != 0) {
+        return true;
+    }
+    match limbs.get(whole) {
+        Some(&limb) if part > 0 => limb << (BITS - part) != 0,
+        _ => false,
+    }
+}
+
+/// Returns the lowest `len` limbs of the two's complement of a signed magnitude.
+pub(super) fn to_twos_complement(negative: bool, limbs: &[u32], len: usize) -> Vec<u32> {
+    let mut twos = limbs.to_vec();
+    twos.resize(len, 0);
+    if negative {
+        for limb in &mut twos {
+            *limb = !*limb;
+        }
+        increment(&mut twos);
+    }
+    twos
+}
+
+/// Converts a two's complement number back into a sign and magnitude.
+pub(super) fn from_twos_complement(mut twos: Vec<u32>) -> (bool, Vec<u32>) {
+    let negative = twos.last().copied().unwrap_or_default() >> (BITS - 1) != 0;
+    if negative {
+        for limb in &mut twos {
+            *limb = !*limb;
+        }
+        increment(&mut twos);
+    }
+    (negative, normalize(twos))
+}
+
+/// Adds one, wrapping around on overflow.
+fn increment(limbs: &mut [u32]) {
+    for limb in limbs {
+        let (sum, overflowed) = limb.overflowing_add(1);
+        *limb = sum;
+        if !overflowed {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::{xorshift, SEED};
+
+    fn random_limbs(state: &mut u32, len: usize) -> Vec<u32> {
+        let limbs = (0..len)
+            .map(|_| {
+                let random = xorshift(state);
+                // Favour runs of all-zero and all-one limbs, which exercise carries
+                match random % 4 {
+                    0 => 0,
+                    1 => u32::MAX,
+                    _ => random,
+                }
+            })
+            .collect();
+        normalize(limbs)
+    }
+
+    #[test]
+    fn karatsuba_matches_schoolbook() {
+        let mut state = SEED;
+        for &(a_len, b_len) in &[(32, 32), (64, 33), (100, 40), (200, 150), (257, 31)] {
+            let a = random_limbs(&mut state, a_len);
+            let b = random_limbs(&mut state, b_len);
+            assert_eq!(
+                mul(&a, &b),
+                schoolbook(&a, &b),
+                "{} × {} limbs",
+                a_len,
+                b_len
+            );
+        }
+    }
+
+    #[test]
+    fn division_inverts_multiplication() {
+        let mut state = SEED;
+        for a_len in 0..12 {
+            for b_len in 1..8 {
+                let a = random_limbs(&mut state, a_len);
+                let b = random_limbs(&mut state, b_len);
+                if b.is_empty() {
+                    continue;
+                }
+                let (q, r) = div_rem(&a, &b);
+                assert_eq!(cmp(&r, &b), Ordering::Less);
+                assert_eq!(add(&mul(&q, &b), &r), a, "{:?} / {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn shifts_round_trip() {
+        let limbs = vec![0x8000_0001, 0xdead_beef, 1];
+        for bits in 0..100 {
+            assert_eq!(shr(&shl(&limbs, bits), bits), limbs, "{} bits", bits);
+            assert!(!any_below(&shl(&limbs, bits), bits));
+            assert_eq!(any_below(&limbs, bits), bits > 0);
+        }
+    }
+}
@@ -0,0 +1,1530 @@
+//! Integers that never overflow, like Python's `int`: [`Int`]
+
+mod limbs;
+
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::iter::{Product, Sum};
+use std::ops;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::String;
+
+/// An integer of any size, like Python's `int`
+///
+/// An `Int` is twice the size of an [`i64`].  Values that fit in an `i64` are stored inline and
+/// take a fast path through arithmetic; larger ones share a reference-counted buffer, so cloning
+/// never copies, like a long [`String`].
+///
+/// Arithmetic follows Python: `/` is floor division, `//` in Python, and `%` takes the sign of
+/// the divisor, so `a == (a / b) * b + a % b` always holds.  Bitwise operators and shifts act as
+/// if negative numbers had infinitely many leading one bits in two's complement.
+///
+/// | Python            | Rust                              |
+/// |-------------------|-----------------------------------|
+/// | `a // b`, `a % b` | `&a / &b`, `&a % &b`              |
+/// | `divmod(a, b)`    | `a.divmod(&b)`                    |
+/// | `a ** 100`        | `a.pow(100)`                      |
+/// | `pow(a, b, m)`    | `a.pow_mod(&b, &m)`               |
+/// | `int(s, 16)`      | `Int::from_str_radix(s, 16)`      |
+/// | `hex(a)`          | `format!("{:#x}", a)`             |
+/// | `a.bit_length()`  | `a.bit_length()`                  |
+///
+/// # Examples
+///
+/// ```
+/// use ergo_core::Int;
+///
+/// let factorial: Int = (1..=25).map(Int::from).product();
+/// assert_eq!(factorial.to_string(), "15511210043330985984000000");
+///
+/// let big = Int::from(u64::MAX) * Int::from(u64::MAX);
+/// assert_eq!(&big % &Int::from(-1_000_000), -891_775);
+/// assert_eq!(format!("{:#x}", big), "0xfffffffffffffffe0000000000000001");
+///
+/// let mut n = Int::from(i64::MAX);
+/// n += 1;
+/// assert!(n > i64::MAX);
+/// ```
+#[derive(Clone)]
+pub struct Int(IntInner);
+
+#[derive(Clone)]
+enum IntInner {
+    Inline(i64),
+    /// Always outside the range of an `i64`, so every value has one representation
+    Shared(Arc<BigInt>),
+}
+
+struct BigInt {
+    negative: bool,
+    limbs: Vec<u32>,
+}
+
+/// A borrowed sign and magnitude, for arithmetic on either representation
+struct Parts<'i> {
+    negative: bool,
+    limbs: Cow<'i, [u32]>,
+}
+
+impl Int {
+    /// Creates an `Int` equal to zero.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// assert_eq!(ergo_core::Int::new(), 0);
+    /// ```
+    #[inline]
+    pub const fn new() -> Self {
+        Self(IntInner::Inline(0))
+    }
+
+    /// Returns `true` if the number is zero.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use ergo_core::Int;
+    ///
+    /// assert!(Int::new().is_zero());
+    /// assert!(!Int::from(-1).is_zero());
+    /// ```
+    #[inline]
+    pub fn is_zero(&self) -> bool {
+        matches!(self.0, IntInner::Inline(0))
+    }
+
+    /// Returns `true` if the number is less than zero.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use ergo_core::Int;
+    ///
+    /// assert!(Int::from(-1).is_negative());
+    /// assert!(!Int::new().is_negative());
+    /// ```
+    #[inline]
+    pub fn is_negative(&self) -> bool {
+        match &self.0 {
+            IntInner::Inline(i) => *i < 0,
+            IntInner::Shared(big) => big.negative,
+        }
+    }
+
+    /// Returns `true` if the number is greater than zero.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use ergo_core::Int;
+    ///
+    /// assert!(Int::from(1).is_positive());
+    /// assert!(!Int::new().is_positive());
+    /// ```
+    #[inline]
+    pub fn is_positive(&self) -> bool {
+        !self.is_negative() && !self.is_zero()
+    }
+
+    /// Returns `-1`, `0` or `1` for negative numbers, zero and positive numbers.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use ergo_core::Int;
+    ///
+    /// assert_eq!(Int::from(-7).signum(), -1);
+    /// assert_eq!(Int::from(u128::MAX).signum(), 1);
+    /// ```
+    pub fn signum(&self) -> Self {
+        Self::from(match self.cmp(&Self::new()) {
+            Ordering::Less => -1,
+            Ordering::Equal => 0,
+            Ordering::Greater => 1,
+        })
+    }
+
+    /// Returns the absolute value, like Python's `abs`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use ergo_core::Int;
+    ///
+    /// assert_eq!(Int::from(i64::MIN).abs(), Int::from(1_u64 << 63));
+    /// ```
+    pub fn abs(&self) -> Self {
+        match self.is_negative() {
+            true => -self,
+            false => self.clone(),
+        }
+    }
+
+    /// Returns the number of bits needed to represent the absolute value, without leading zeros.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use ergo_core::Int;
+    ///
+    /// assert_eq!(Int::from(-37).bit_length(), 6);
+    /// assert_eq!(Int::new().bit_length(), 0);
+    /// ```
+    pub fn bit_length(&self) -> u64 {
+        match &self.0 {
+            IntInner::Inline(i) => 64 - i.unsigned_abs().leading_zeros() as u64,
+            IntInner::Shared(big) => limbs::bit_length(&big.limbs),
+        }
+    }
+
+    /// Returns the number of one bits in the absolute value, like Python's `int.bit_count`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use ergo_core::Int;
+    ///
+    /// assert_eq!(Int::from(-37).bit_count(), 3);
+    /// ```
+    pub fn bit_count(&self) -> u64 {
+        match &self.0 {
+            IntInner::Inline(i) => i.unsigned_abs().count_ones().into(),
+            IntInner::Shared(big) => big.limbs.iter().map(|l| u64::from(l.count_ones())).sum(),
+        }
+    }
+
+    /// Raises the number to the power of `exp`, like Python's `**`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use ergo_core::Int;
+    ///
+    /// assert_eq!(Int::from(-3).pow(3), -27);
+    /// assert_eq!(Int::from(2).pow(100).to_string(), "1267650600228229401496703205376");
+    /// ```
+    pub fn pow(&self, mut exp: u32) -> Self {
+        let mut base = self.clone();
+        let mut result = Self::from(1);
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = &result * &base;
+            }
+            exp >>= 1;
+            if exp > 0 {
+                base = &base * &base;
+            }
+        }
+        result
+    }
+
+    /// Returns `self` to the power of `exp`, modulo `modulus`, like Python's three-argument
+    /// `pow`.
+    ///
+    /// The result takes the sign of `modulus`, like `%`.  A negative `exp` raises the modular
+    /// inverse of `self` instead.  Returns [`None`] if `modulus` is zero, or if `exp` is negative
+    /// and `self` has no inverse modulo `modulus`, where Python raises `ValueError`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use ergo_core::Int;
+    ///
+    /// let m = Int::from(1_000_000_007);
+    /// assert_eq!(Int::from(2).pow_mod(&Int::from(1_000_000), &m), Some(Int::from(235_042_059)));
+    /// assert_eq!(Int::from(38).pow_mod(&Int::from(-1), &Int::from(97)), Some(Int::from(23)));
+    /// assert_eq!(Int::from(2).pow_mod(&Int::from(-1), &Int::from(4)), None);
+    /// assert_eq!(Int::from(2).pow_mod(&Int::from(3), &Int::from(-5)), Some(Int::from(-2)));
+    /// ```
+    pub fn pow_mod(&self, exp: &Int, modulus: &Int) -> Option<Self> {
+        if modulus.is_zero() {
+            return None;
+        }
+        let m = modulus.abs();
+        let mut base = self % &m;
+        if exp.is_negative() {
+            base = base.mod_inverse(&m)?;
+        }
+
+        let exp = exp.abs();
+        let exp = exp.parts();
+        let mut result = Self::from(1) % &m;
+        for bit in (0..limbs::bit_length(&exp.limbs)).rev() {
+            result = &(&result * &result) % &m;
+            let limb = exp.limbs[(bit / limbs::BITS as u64) as usize];
+            if limb >> (bit % limbs::BITS as u64) & 1 == 1 {
+                result = &(&result * &base) % &m;
+            }
+        }
+
+        if modulus.is_negative() && !result.is_zero() {
+            result = &result + modulus;
+        }
+        Some(result)
+    }
+
+    /// Returns the inverse of `self` modulo the positive `m`, using the extended Euclidean
+    /// algorithm.
+    fn mod_inverse(&self, m: &Int) -> Option<Self> {
+        let (mut a, mut b) = (self % m, m.clone());
+        let (mut x, mut y) = (Self::from(1), Self::new());
+        while !b.is_zero() {
+            let (q, r) = a.divmod(&b);
+            let next = &x - &(&q * &y);
+            a = std::mem::replace(&mut b, r);
+            x = std::mem::replace(&mut y, next);
+        }
+        match a == 1 {
+            true => Some(&x % m),
+            false => None,
+        }
+    }
+
+    /// Returns the quotient and remainder of floor division, like Python's `divmod`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `other` is zero.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use ergo_core::Int;
+    ///
+    /// assert_eq!(Int::from(7).divmod(&Int::from(-2)), (Int::from(-4), Int::from(-1)));
+    /// ```
+    pub fn divmod(&self, other: &Int) -> (Self, Self) {
+        self.checked_divmod(other)
+            .expect("attempt to divide by zero")
+    }
+
+    /// Returns the quotient and remainder of floor division, or [`None`] if `other` is zero.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use ergo_core::Int;
+    ///
+    /// assert_eq!(Int::from(-7).checked_divmod(&Int::from(2)), Some((Int::from(-4), Int::from(1))));
+    /// assert_eq!(Int::from(1).checked_divmod(&Int::new()), None);
+    /// ```
+    pub fn checked_divmod(&self, other: &Int) -> Option<(Self, Self)> {
+        if other.is_zero() {
+            return None;
+        }
+        if let (IntInner::Inline(a), IntInner::Inline(b)) = (&self.0, &other.0) {
+            let (a, b) = (i128::from(*a), i128::from(*b));
+            let (q, r) = (a.div_euclid(b), a.rem_euclid(b));
+            // Euclidean division keeps the remainder positive, so move it to the sign of `b`
+            return Some(match r != 0 && b < 0 {
+                true => (Self::from(q - 1), Self::from(r + b)),
+                false => (Self::from(q), Self::from(r)),
+            });
+        }
+
+        let (a, b) = (self.parts(), other.parts());
+        let (q, r) = limbs::div_rem(&a.limbs, &b.limbs);
+        let negative = a.negative != b.negative;
+        Some(match negative && !r.is_empty() {
+            true => (
+                Self::from_parts(true, limbs::add(&q, &[1])),
+                Self::from_parts(b.negative, limbs::sub(&b.limbs, &r)),
+            ),
+            false => (
+                Self::from_parts(negative, q),
+                Self::from_parts(a.negative, r),
+            ),
+        })
+    }
+
+    /// Converts to the nearest [`f64`], or to infinity if the number is too large, where Python
+    /// raises `OverflowError`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use ergo_core::Int;
+    ///
+    /// assert_eq!(Int::from(-3).to_f64(), -3.0);
+    /// assert_eq!(Int::from(10).pow(30).to_f64(), 1e30);
+    /// assert_eq!(Int::from(10).pow(400).to_f64(), f64::INFINITY);
+    /// ```
+    pub fn to_f64(&self) -> f64 {
+        let big = match &self.0 {
+            IntInner::Inline(i) => return *i as f64,
+            IntInner::Shared(big) => big,
+        };
+        // Round the top 64 bits, keeping a sticky bit for anything below them so converting to
+        // 53 bits rounds correctly
+        let shift = limbs::bit_length(&big.limbs) - 64;
+        let top = limbs::to_u128(&limbs::shr(&big.limbs, shift as usize)).unwrap_or_default();
+        let sticky = limbs::any_below(&big.limbs, shift as usize) as u128;
+        let magnitude = (top | sticky) as u64 as f64 * 2_f64.powi(shift.min(2048) as i32);
+        match big.negative {
+            true => -magnitude,
+            false => magnitude,
+        }
+    }
+
+    /// Parses an integer in the given `radix`, like Python's `int(s, radix)`.
+    ///
+    /// Accepts surrounding whitespace, a leading `+` or `-`, and single underscores between
+    /// digits.  Digits past 9 are letters in either case.  With a radix of 16, 8 or 2, the digits
+    /// may start with `0x`, `0o` or `0b`; with a radix of 0, the prefix picks the radix, and
+    /// numbers without one are decimal.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `radix` is not 0 or between 2 and 36.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use ergo_core::{Int, String};
+    ///
+    /// assert_eq!(Int::from_str_radix("ff", 16), Ok(Int::from(255)));
+    /// assert_eq!(Int::from_str_radix(" -0b_1010 ", 0), Ok(Int::from(-10)));
+    /// assert_eq!(Int::from_str_radix(String::from("zz"), 36), Ok(Int::from(1295)));
+    /// assert_eq!(Int::from_str_radix("1__0", 10).unwrap_err().offset(), 2);
+    /// ```
+    pub fn from_str_radix(s: impl AsRef<str>, radix: u32) -> Result<Self, ParseIntError> {
+        assert!(
+            radix == 0 || (2..=36).contains(&radix),
+            "radix must be 0 or between 2 and 36, not {}",
+            radix
+        );
+        let s = s.as_ref();
+        let bytes = s.as_bytes();
+        let mut offset = bytes.len() - s.trim_start().len();
+        let end = s.trim_end().len().max(offset);
+        let error = |offset| Err(ParseIntError { offset });
+
+        let negative = bytes.get(offset) == Some(&b'-');
+        if matches!(bytes.get(offset), Some(b'-') | Some(b'+')) {
+            offset += 1;
+        }
+
+        let prefix = match bytes.get(offset..offset + 2) {
+            Some([b'0', p]) => match p.to_ascii_lowercase() {
+                b'x' => Some(16),
+                b'o' => Some(8),
+                b'b' => Some(2),
+                _ => None,
+            },
+            _ => None,
+        };
+        let radix = match (radix, prefix) {
+            (0, Some(prefix))
+            | (prefix @ 16, Some(16))
+            | (prefix @ 8, Some(8))
+            | (prefix @ 2, Some(2)) => {
+                offset += 2;
+                // Python allows an underscore between the prefix and the first digit
+                if bytes.get(offset) == Some(&b'_') {
+                    offset += 1;
+                }
+                prefix
+            }
+            (0, None) => {
+                // Without a prefix, only zero may start with a zero, so `010` isn't mistaken for
+                // octal
+                let digits = &bytes[offset..end];
+                if digits.first() == Some(&b'0') {
+                    if let Some(i) = digits.iter().position(|&b| b != b'0' && b != b'_') {
+                        return error(offset + i);
+                    }
+                }
+                10
+            }
+            (radix, _) => radix,
+        };
+
+        // Accumulate as many digits as fit in a `u32` at a time
+        let (chunk_digits, chunk_size) = chunk(radix);
+        let mut magnitude = Vec::new();
+        let (mut chunk_value, mut digits_in_chunk) = (0, 0);
+        let mut previous = None;
+        for (i, &b) in bytes[..end].iter().enumerate().skip(offset) {
+            if b == b'_' && previous == Some(b'_') {
+                return error(i);
+            }
+            if b == b'_' && previous.is_some() {
+                previous = Some(b);
+                continue;
+            }
+            let digit = match (b as char).to_digit(radix) {
+                Some(digit) => digit,
+                None => return error(i),
+            };
+            chunk_value = chunk_value * radix + digit;
+            digits_in_chunk += 1;
+            if digits_in_chunk == chunk_digits {
+                limbs::mul_add_small(&mut magnitude, chunk_size, chunk_value);
+                chunk_value = 0;
+                digits_in_chunk = 0;
+            }
+            previous = Some(b);
+        }
+        match previous {
+            None | Some(b'_') => return error(end),
+            Some(_) => {}
+        }
+        if digits_in_chunk > 0 {
+            limbs::mul_add_small(&mut magnitude, radix.pow(digits_in_chunk), chunk_value);
+        }
+        Ok(Self::from_parts(negative, limbs::normalize(magnitude)))
+    }
+
+    /// Formats the number in the given `radix`, using lowercase letters for digits past 9.
+    ///
+    /// Unlike Python's `hex` and friends, this adds no prefix; format with `{:#x}`, `{:#o}` or
+    /// `{:#b}` for that.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `radix` is not between 2 and 36.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use ergo_core::Int;
+    ///
+    /// assert_eq!(Int::from(-255).to_str_radix(16), "-ff");
+    /// assert_eq!(Int::from(1295).to_str_radix(36), "zz");
+    /// assert_eq!(Int::from(1_u128 << 64).to_str_radix(2).len(), 65);
+    /// ```
+    pub fn to_str_radix(&self, radix: u32) -> String {
+        assert!(
+            (2..=36).contains(&radix),
+            "radix must be between 2 and 36, not {}",
+            radix
+        );
+        let mut digits = self.magnitude_digits(radix);
+        if self.is_negative() {
+            digits.push(b'-');
+        }
+        digits.reverse();
+        // SAFETY: digits and `-` are ASCII
+        String::from(unsafe { std::string::String::from_utf8_unchecked(digits) })
+    }
+
+    /// Returns the ASCII digits of the absolute value in `radix`, least significant first.
+    fn magnitude_digits(&self, radix: u32) -> Vec<u8> {
+        let digit = |d: u32| std::char::from_digit(d, radix).unwrap_or_default() as u8;
+        let mut digits = Vec::new();
+        let mut magnitude = match &self.0 {
+            IntInner::Inline(i) => {
+                let mut i = i.unsigned_abs();
+                loop {
+                    digits.push(digit((i % radix as u64) as u32));
+                    i /= radix as u64;
+                    if i == 0 {
+                        return digits;
+                    }
+                }
+            }
+            IntInner::Shared(big) => big.limbs.clone(),
+        };
+
+        let (chunk_digits, chunk_size) = chunk(radix);
+        while !magnitude.is_empty() {
+            let mut rem = limbs::div_rem_small(&mut magnitude, chunk_size);
+            for _ in 0..chunk_digits {
+                digits.push(digit(rem % radix));
+                rem /= radix;
+                if magnitude.is_empty() && rem == 0 {
+                    break;
+                }
+            }
+        }
+        digits
+    }
+
+    fn from_parts(negative: bool, limbs: Vec<u32>) -> Self {
+        match limbs::to_u128(&limbs) {
+            Some(magnitude) if magnitude <= i64::MAX as u128 + negative as u128 => {
+                let i = magnitude as i64;
+                Self(IntInner::Inline(if negative {
+                    i.wrapping_neg()
+                } else {
+                    i
+                }))
+            }
+            _ => Self(IntInner::Shared(Arc::new(BigInt { negative, limbs }))),
+        }
+    }
+
+    fn parts(&self) -> Parts<'_> {
+        match &self.0 {
+            IntInner::Inline(i) => Parts {
+                negative: *i < 0,
+                limbs: Cow::Owned(limbs::from_u128(i.unsigned_abs().into())),
+            },
+            IntInner::Shared(big) => Parts {
+                negative: big.negative,
+                limbs: Cow::Borrowed(&big.limbs),
+            },
+        }
+    }
+
+    /// Returns the number as an `i128`, if it fits.
+    fn to_i128(&self) -> Option<i128> {
+        match &self.0 {
+            IntInner::Inline(i) => Some((*i).into()),
+            IntInner::Shared(big) => {
+                let magnitude = limbs::to_u128(&big.limbs)?;
+                match big.negative {
+                    true if magnitude <= i128::MIN.unsigned_abs() => {
+                        Some((magnitude as i128).wrapping_neg())
+                    }
+                    false => i128::try_from(magnitude).ok(),
+                    true => None,
+                }
+            }
+        }
+    }
+
+    /// Adds two numbers with the given signs.
+    fn add_parts(a: Parts<'_>, b: Parts<'_>) -> Self {
+        if a.negative == b.negative {
+            return Self::from_parts(a.negative, limbs::add(&a.limbs, &b.limbs));
+        }
+        match limbs::cmp(&a.limbs, &b.limbs) {
+            Ordering::Less => Self::from_parts(b.negative, limbs::sub(&b.limbs, &a.limbs)),
+            _ => Self::from_parts(a.negative, limbs::sub(&a.limbs, &b.limbs)),
+        }
+    }
+
+    /// Applies a bitwise operation to the infinite two's complement representations.
+    fn bitwise(&self, other: &Int, op: impl Fn(u32, u32) -> u32) -> Self {
+        let (a, b) = (self.parts(), other.parts());
+        let len = a.limbs.len().max(b.limbs.len()) + 1;
+        let a = limbs::to_twos_complement(a.negative, &a.limbs, len);
+        let b = limbs::to_twos_complement(b.negative, &b.limbs, len);
+        let twos = a.iter().zip(&b).map(|(&x, &y)| op(x, y)).collect();
+        let (negative, magnitude) = limbs::from_twos_complement(twos);
+        Self::from_parts(negative, magnitude)
+    }
+}
+
+/// Returns how many digits in `radix` fit in a `u32`, and `radix` to that power.
+fn chunk(radix: u32) -> (u32, u32) {
+    let mut digits = 1;
+    let mut size = radix;
+    while let Some(next) = size.checked_mul(radix) {
+        digits += 1;
+        size = next;
+    }
+    (digits, size)
+}
+
+/// The error returned by [`Int::from_str_radix`] and parsing with [`str::parse`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ParseIntError {
+    offset: usize,
+}
+
+impl ParseIntError {
+    /// The byte offset of the invalid or missing digit
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl fmt::Display for ParseIntError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid or missing digit at offset {}", self.offset)
+    }
+}
+
+impl std::error::Error for ParseIntError {}
+
+/// The error returned when converting an [`Int`] to a primitive integer too small to hold it
+///
+/// # Examples
+///
+/// ```
+/// use ergo_core::Int;
+/// use std::convert::TryFrom;
+///
+/// assert_eq!(u8::try_from(&Int::from(255)), Ok(255));
+/// assert!(u8::try_from(&Int::from(256)).is_err());
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TryFromIntError(());
+
+impl fmt::Display for TryFromIntError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("out of range integral type conversion attempted")
+    }
+}
+
+impl std::error::Error for TryFromIntError {}
+
+impl Default for Int {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<i64> for Int {
+    #[inline]
+    fn from(other: i64) -> Self {
+        Self(IntInner::Inline(other))
+    }
+}
+
+macro_rules! impl_from_small {
+    ($($int:ty),*) => {
+        $(
+            impl From<$int> for Int {
+                #[inline]
+                fn from(other: $int) -> Self {
+                    Self(IntInner::Inline(other.into()))
+                }
+            }
+        )*
+    };
+}
+
+impl_from_small!(i8, i16, i32, u8, u16, u32);
+
+macro_rules! impl_from_large {
+    ($($int:ty),*) => {
+        $(
+            impl From<$int> for Int {
+                #[inline]
+                fn from(other: $int) -> Self {
+                    match i64::try_from(other) {
+                        Ok(i) => Self(IntInner::Inline(i)),
+                        Err(_) => Self::from_parts(other < 0, limbs::from_u128(other.unsigned_abs() as u128)),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_from_large!(isize, i128);
+
+macro_rules! impl_from_unsigned {
+    ($($int:ty),*) => {
+        $(
+            impl From<$int> for Int {
+                #[inline]
+                fn from(other: $int) -> Self {
+                    match i64::try_from(other) {
+                        Ok(i) => Self(IntInner::Inline(i)),
+                        Err(_) => Self::from_parts(false, limbs::from_u128(other as u128)),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_from_unsigned!(u64, usize, u128);
+
+macro_rules! impl_try_into_int {
+    ($($int:ty),*) => {
+        $(
+            impl<'i> TryFrom<&'i Int> for $int {
+                type Error = TryFromIntError;
+
+                #[inline]
+                fn try_from(other: &'i Int) -> Result<Self, TryFromIntError> {
+                    other
+                        .to_i128()
+                        .and_then(|i| <$int>::try_from(i).ok())
+                        .ok_or(TryFromIntError(()))
+                }
+            }
+
+            impl TryFrom<Int> for $int {
+                type Error = TryFromIntError;
+
+                #[inline]
+                fn try_from(other: Int) -> Result<Self, TryFromIntError> {
+                    <$int>::try_from(&other)
+                }
+            }
+        )*
+    };
+}
+
+impl_try_into_int!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, usize);
+
+impl<'i> TryFrom<&'i Int> for u128 {
+    type Error = TryFromIntError;
+
+    #[inline]
+    fn try_from(other: &'i Int) -> Result<Self, TryFromIntError> {
+        match &other.0 {
+            IntInner::Inline(i) => u128::try_from(*i).ok(),
+            IntInner::Shared(big) if !big.negative => limbs::to_u128(&big.limbs),
+            IntInner::Shared(_) => None,
+        }
+        .ok_or(TryFromIntError(()))
+    }
+}
+
+impl TryFrom<Int> for u128 {
+    type Error = TryFromIntError;
+
+    #[inline]
+    fn try_from(other: Int) -> Result<Self, TryFromIntError> {
+        u128::try_from(&other)
+    }
+}
+
+impl FromStr for Int {
+    type Err = ParseIntError;
+
+    /// Parses a decimal integer, like Python's `int(s)`.
+    ///
+    /// See [`Int::from_str_radix`] for the accepted syntax.
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, ParseIntError> {
+        Self::from_str_radix(s, 10)
+    }
+}
+
+impl<'i> From<&'i Int> for String {
+    /// Formats the number in decimal.
+    #[inline]
+    fn from(other: &'i Int) -> Self {
+        other.to_str_radix(10)
+    }
+}
+
+impl From<Int> for String {
+    /// Formats the number in decimal.
+    #[inline]
+    fn from(other: Int) -> Self {
+        other.to_str_radix(10)
+    }
+}
+
+impl<'i> ops::Add for &'i Int {
+    type Output = Int;
+
+    fn add(self, other: &'i Int) -> Int {
+        match (&self.0, &other.0) {
+            (IntInner::Inline(a), IntInner::Inline(b)) => {
+                Int::from(i128::from(*a) + i128::from(*b))
+            }
+            _ => Int::add_parts(self.parts(), other.parts()),
+        }
+    }
+}
+
+impl<'i> ops::Sub for &'i Int {
+    type Output = Int;
+
+    fn sub(self, other: &'i Int) -> Int {
+        match (&self.0, &other.0) {
+            (IntInner::Inline(a), IntInner::Inline(b)) => {
+                Int::from(i128::from(*a) - i128::from(*b))
+            }
+            _ => {
+                let mut other = other.parts();
+                other.negative = !other.negative;
+                Int::add_parts(self.parts(), other)
+            }
+        }
+    }
+}
+
+impl<'i> ops::Mul for &'i Int {
+    type Output = Int;
+
+    fn mul(self, other: &'i Int) -> Int {
+        match (&self.0, &other.0) {
+            (IntInner::Inline(a), IntInner::Inline(b)) => {
+                Int::from(i128::from(*a) * i128::from(*b))
+            }
+            _ => {
+                let (a, b) = (self.parts(), other.parts());
+                Int::from_parts(a.negative != b.negative, limbs::mul(&a.limbs, &b.limbs))
+            }
+        }
+    }
+}
+
+impl<'i> ops::Div for &'i Int {
+    type Output = Int;
+
+    /// Divides, rounding towards negative infinity like Python's `//`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `other` is zero.
+    #[inline]
+    fn div(self, other: &'i Int) -> Int {
+        self.divmod(other).0
+    }
+}
+
+impl<'i> ops::Rem for &'i Int {
+    type Output = Int;
+
+    /// Returns the remainder of floor division, which takes the sign of `other` like Python's
+    /// `%`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `other` is zero.
+    #[inline]
+    fn rem(self, other: &'i Int) -> Int {
+        self.divmod(other).1
+    }
+}
+
+impl<'i> ops::BitAnd for &'i Int {
+    type Output = Int;
+
+    fn bitand(self, other: &'i Int) -> Int {
+        match (&self.0, &other.0) {
+            (IntInner::Inline(a), IntInner::Inline(b)) => Int::from(a & b),
+            _ => self.bitwise(other, |a, b| a & b),
+        }
+    }
+}
+
+impl<'i> ops::BitOr for &'i Int {
+    type Output = Int;
+
+    fn bitor(self, other: &'i Int) -> Int {
+        match (&self.0, &other.0) {
+            (IntInner::Inline(a), IntInner::Inline(b)) => Int::from(a | b),
+            _ => self.bitwise(other, |a, b| a | b),
+        }
+    }
+}
+
+impl<'i> ops::BitXor for &'i Int {
+    type Output = Int;
+
+    fn bitxor(self, other: &'i Int) -> Int {
+        match (&self.0, &other.0) {
+            (IntInner::Inline(a), IntInner::Inline(b)) => Int::from(a ^ b),
+            _ => self.bitwise(other, |a, b| a ^ b),
+        }
+    }
+}
+
+impl ops::Shl<u32> for &Int {
+    type Output = Int;
+
+    fn shl(self, bits: u32) -> Int {
+        match &self.0 {
+            IntInner::Inline(i) if bits < 64 => Int::from(i128::from(*i) << bits),
+            _ => {
+                let parts = self.parts();
+                Int::from_parts(parts.negative, limbs::shl(&parts.limbs, bits as usize))
+            }
+        }
+    }
+}
+
+impl ops::Shr<u32> for &Int {
+    type Output = Int;
+
+    /// Shifts right, rounding towards negative infinity like Python's `>>`.
+    fn shr(self, bits: u32) -> Int {
+        match &self.0 {
+            IntInner::Inline(i) => Int::from(i >> bits.min(63)),
+            IntInner::Shared(big) => {
+                let shifted = Int::from_parts(big.negative, limbs::shr(&big.limbs, bits as usize));
+                match big.negative && limbs::any_below(&big.limbs, bits as usize) {
+                    true => &shifted - &Int::from(1),
+                    false => shifted,
+                }
+            }
+        }
+    }
+}
+
+impl ops::Neg for &Int {
+    type Output = Int;
+
+    fn neg(self) -> Int {
+        match &self.0 {
+            IntInner::Inline(i) => Int::from(-i128::from(*i)),
+            IntInner::Shared(big) => Int::from_parts(!big.negative, big.limbs.clone()),
+        }
+    }
+}
+
+impl ops::Not for &Int {
+    type Output = Int;
+
+    /// Returns `-self - 1`, flipping every bit of the two's complement like Python's `~`.
+    fn not(self) -> Int {
+        match &self.0 {
+            IntInner::Inline(i) => Int::from(!i),
+            IntInner::Shared(_) => &-self - &Int::from(1),
+        }
+    }
+}
+
+macro_rules! impl_owned_operator {
+    ($($trait:ident $method:ident $assign_trait:ident $assign_method:ident),*) => {
+        $(
+            impl ops::$trait for Int {
+                type Output = Int;
+
+                #[inline]
+                fn $method(self, other: Int) -> Int {
+                    ops::$trait::$method(&self, &other)
+                }
+            }
+
+            impl<'i> ops::$trait<&'i Int> for Int {
+                type Output = Int;
+
+                #[inline]
+                fn $method(self, other: &'i Int) -> Int {
+                    ops::$trait::$method(&self, other)
+                }
+            }
+
+            impl<'i> ops::$trait<Int> for &'i Int {
+                type Output = Int;
+
+                #[inline]
+                fn $method(self, other: Int) -> Int {
+                    ops::$trait::$method(self, &other)
+                }
+            }
+
+            impl ops::$trait<i64> for Int {
+                type Output = Int;
+
+                #[inline]
+                fn $method(self, other: i64) -> Int {
+                    ops::$trait::$method(&self, &Int::from(other))
+                }
+            }
+
+            impl<'i> ops::$trait<i64> for &'i Int {
+                type Output = Int;
+
+                #[inline]
+                fn $method(self, other: i64) -> Int {
+                    ops::$trait::$method(self, &Int::from(other))
+                }
+            }
+
+            impl ops::$assign_trait for Int {
+                #[inline]
+                fn $assign_method(&mut self, other: Int) {
+                    *self = ops::$trait::$method(&*self, &other);
+                }
+            }
+
+            impl<'i> ops::$assign_trait<&'i Int> for Int {
+                #[inline]
+                fn $assign_method(&mut self, other: &'i Int) {
+                    *self = ops::$trait::$method(&*self, other);
+                }
+            }
+
+            impl ops::$assign_trait<i64> for Int {
+                #[inline]
+                fn $assign_method(&mut self, other: i64) {
+                    *self = ops::$trait::$method(&*self, &Int::from(other));
+                }
+            }
+        )*
+    };
+}
+
+impl_owned_operator!(
+    Add add AddAssign add_assign,
+    Sub sub SubAssign sub_assign,
+    Mul mul MulAssign mul_assign,
+    Div div DivAssign div_assign,
+    Rem rem RemAssign rem_assign,
+    BitAnd bitand BitAndAssign bitand_assign,
+    BitOr bitor BitOrAssign bitor_assign,
+    BitXor bitxor BitXorAssign bitxor_assign
+);
+
+macro_rules! impl_owned_shift {
+    ($($trait:ident $method:ident $assign_trait:ident $assign_method:ident),*) => {
+        $(
+            impl ops::$trait<u32> for Int {
+                type Output = Int;
+
+                #[inline]
+                fn $method(self, bits: u32) -> Int {
+                    ops::$trait::$method(&self, bits)
+                }
+            }
+
+            impl ops::$assign_trait<u32> for Int {
+                #[inline]
+                fn $assign_method(&mut self, bits: u32) {
+                    *self = ops::$trait::$method(&*self, bits);
+                }
+            }
+        )*
+    };
+}
+
+impl_owned_shift!(Shl shl ShlAssign shl_assign, Shr shr ShrAssign shr_assign);
+
+impl ops::Neg for Int {
+    type Output = Int;
+
+    #[inline]
+    fn neg(self) -> Int {
+        -&self
+    }
+}
+
+impl ops::Not for Int {
+    type Output = Int;
+
+    #[inline]
+    fn not(self) -> Int {
+        !&self
+    }
+}
+
+impl Sum for Int {
+    fn sum<I: Iterator<Item = Int>>(iter: I) -> Self {
+        iter.fold(Int::new(), |acc, i| acc + i)
+    }
+}
+
+impl<'i> Sum<&'i Int> for Int {
+    fn sum<I: Iterator<Item = &'i Int>>(iter: I) -> Self {
+        iter.fold(Int::new(), |acc, i| acc + i)
+    }
+}
+
+impl Product for Int {
+    fn product<I: Iterator<Item = Int>>(iter: I) -> Self {
+        iter.fold(Int::from(1), |acc, i| acc * i)
+    }
+}
+
+impl<'i> Product<&'i Int> for Int {
+    fn product<I: Iterator<Item = &'i Int>>(iter: I) -> Self {
+        iter.fold(Int::from(1), |acc, i| acc * i)
+    }
+}
+
+impl PartialEq for Int {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Int {}
+
+impl PartialEq<i64> for Int {
+    #[inline]
+    fn eq(&self, other: &i64) -> bool {
+        matches!(self.0, IntInner::Inline(i) if i == *other)
+    }
+}
+
+impl PartialEq<Int> for i64 {
+    #[inline]
+    fn eq(&self, other: &Int) -> bool {
+        other == self
+    }
+}
+
+impl PartialOrd for Int {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Int {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (&self.0, &other.0) {
+            (IntInner::Inline(a), IntInner::Inline(b)) => a.cmp(b),
+            // Shared numbers are beyond every inline one
+            (IntInner::Inline(_), IntInner::Shared(b)) => match b.negative {
+                true => Ordering::Greater,
+                false => Ordering::Less,
+            },
+            (IntInner::Shared(a), IntInner::Inline(_)) => match a.negative {
+                true => Ordering::Less,
+                false => Ordering::Greater,
+            },
+            (IntInner::Shared(a), IntInner::Shared(b)) => match (a.negative, b.negative) {
+                (false, false) => limbs::cmp(&a.limbs, &b.limbs),
+                (true, true) => limbs::cmp(&b.limbs, &a.limbs),
+                (false, true) => Ordering::Greater,
+                (true, false) => Ordering::Less,
+            },
+        }
+    }
+}
+
+impl PartialOrd<i64> for Int {
+    #[inline]
+    fn partial_cmp(&self, other: &i64) -> Option<Ordering> {
+        Some(self.cmp(&Int::from(*other)))
+    }
+}
+
+impl Hash for Int {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match &self.0 {
+            IntInner::Inline(i) => i.hash(state),
+            IntInner::Shared(big) => {
+                big.negative.hash(state);
+                big.limbs.hash(state);
+            }
+        }
+    }
+}
+
+impl fmt::Display for Int {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_radix(f, 10, "")
+    }
+}
+
+impl fmt::Debug for Int {
+    /// Formats the number in decimal, like Python's `repr`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl fmt::Binary for Int {
+    /// Formats the absolute value in binary, with a `-` for negative numbers like Python's
+    /// `bin`, rather than two's complement like Rust's primitive integers.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_radix(f, 2, "0b")
+    }
+}
+
+impl fmt::Octal for Int {
+    /// Formats the absolute value in octal, with a `-` for negative numbers like Python's `oct`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_radix(f, 8, "0o")
+    }
+}
+
+impl fmt::LowerHex for Int {
+    /// Formats the absolute value in hexadecimal, with a `-` for negative numbers like
+    /// Python's `hex`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_radix(f, 16, "0x")
+    }
+}
+
+impl fmt::UpperHex for Int {
+    /// Formats the absolute value in hexadecimal, with a `-` for negative numbers.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut digits = self.magnitude_digits(16);
+        digits.make_ascii_uppercase();
+        Self::pad_digits(f, !self.is_negative(), "0x", digits)
+    }
+}
+
+impl Int {
+    fn fmt_radix(&self, f: &mut fmt::Formatter<'_>, radix: u32, prefix: &str) -> fmt::Result {
+        Self::pad_digits(f, !self.is_negative(), prefix, self.magnitude_digits(radix))
+    }
+
+    fn pad_digits(
+        f: &mut fmt::Formatter<'_>,
+        is_nonnegative: bool,
+        prefix: &str,
+        mut digits: Vec<u8>,
+    ) -> fmt::Result {
+        digits.reverse();
+        // SAFETY: digits are ASCII
+        let digits = unsafe { std::str::from_utf8_unchecked(&digits) };
+        f.pad_integral(is_nonnegative, prefix, digits)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::{xorshift, SEED};
+
+    /// Returns an `i128` of random size, with runs of zero and one bits to exercise carries
+    fn random_i128(state: &mut u32) -> i128 {
+        let bits = xorshift(state) % 128;
+        let value = match xorshift(state) % 3 {
+            0 => u128::MAX,
+            _ => (0..4).fold(0, |acc, _| acc << 32 | xorshift(state) as u128),
+        };
+        let value = (value >> (127 - bits)) as i128;
+        match xorshift(state) % 2 {
+            0 => value,
+            _ => -value,
+        }
+    }
+
+    #[test]
+    fn matches_i128_model() {
+        let mut state = SEED;
+        for _ in 0..2000 {
+            let (a, b) = (random_i128(&mut state) >> 1, random_i128(&mut state) >> 1);
+            let (x, y) = (Int::from(a), Int::from(b));
+            assert_eq!(i128::try_from(&x + &y), Ok(a + b), "{} + {}", a, b);
+            assert_eq!(i128::try_from(&x - &y), Ok(a - b), "{} - {}", a, b);
+            assert_eq!(i128::try_from(&x & &y), Ok(a & b), "{} & {}", a, b);
+            assert_eq!(i128::try_from(&x | &y), Ok(a | b), "{} | {}", a, b);
+            assert_eq!(i128::try_from(&x ^ &y), Ok(a ^ b), "{} ^ {}", a, b);
+            assert_eq!(i128::try_from(!&x), Ok(!a), "~{}", a);
+            assert_eq!(x.cmp(&y), a.cmp(&b), "{} cmp {}", a, b);
+            if b != 0 {
+                let (q, r) = x.divmod(&y);
+                let (mut model_q, mut model_r) = (a / b, a % b);
+                if model_r != 0 && (model_r < 0) != (b < 0) {
+                    model_q -= 1;
+                    model_r += b;
+                }
+                assert_eq!(i128::try_from(q), Ok(model_q), "{} // {}", a, b);
+                assert_eq!(i128::try_from(r), Ok(model_r), "{} % {}", a, b);
+            }
+            let bits = xorshift(&mut state) % 100;
+            assert_eq!(
+                i128::try_from(&x >> bits),
+                Ok(a >> bits.min(127)),
+                "{} >> {}",
+                a,
+                bits
+            );
+            if let Some(product) = a.checked_mul(b) {
+                assert_eq!(i128::try_from(&x * &y), Ok(product), "{} * {}", a, b);
+            }
+            assert_eq!(x.to_string(), a.to_string());
+            assert_eq!(format!("{:x}", x.abs()), format!("{:x}", a.unsigned_abs()));
+            assert_eq!(a.to_string().parse(), Ok(x));
+        }
+    }
+
+    #[test]
+    fn big_identities_hold() {
+        let mut state = SEED;
+        let random_big = |state: &mut u32| {
+            let limbs = xorshift(state) % 12;
+            (0..limbs).fold(Int::from(random_i128(state)), |acc, _| {
+                (acc << 32) + i64::from(xorshift(state))
+            })
+        };
+        for _ in 0..300 {
+            let a = random_big(&mut state);
+            let b = random_big(&mut state);
+            if !b.is_zero() {
+                let (q, r) = a.divmod(&b);
+                assert_eq!(&(&q * &b) + &r, a);
+                assert!(r.is_zero() || r.is_negative() == b.is_negative());
+                assert!(r.abs() < b.abs());
+            }
+            assert_eq!(&(&a + &b) - &b, a);
+            assert_eq!(&(&a ^ &b) ^ &b, a);
+            assert_eq!(&(&a & &b) + &(&a | &b), &a + &b);
+            assert_eq!(!!a.clone(), a);
+            let bits = xorshift(&mut state) % 200;
+            assert_eq!(&a >> bits, &a / &Int::from(2).pow(bits));
+            assert_eq!(&a << bits, &a * &Int::from(2).pow(bits));
+            for radix in 2..=36 {
+                assert_eq!(
+                    Int::from_str_radix(a.to_str_radix(radix), radix),
+                    Ok(a.clone())
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn big_results_match_python() {
+        let a = -Int::from(3).pow(200);
+        let b = Int::from(7).pow(50);
+        let mask = &b ^ &-Int::from(2).pow(150);
+        // From CPython
+        let expected = [
+            (
+                &a / &b,
+                "-147689269781346654697366079240021362541982658661987021",
+            ),
+            (&a % &b, "755410807900735363553565675570880206995228"),
+            (
+                &a >> 77,
+                "-1757685936167993363346857650721997806815138470657725538786868596636591422",
+            ),
+            (
+                &a & &mask,
+                "-265613988875874769338781322035779626829233452653394717003040405632102414931252\
+                 218878261313847279",
+            ),
+        ];
+        for (actual, expected) in &expected {
+            assert_eq!(actual.to_string(), *expected);
+        }
+        assert_eq!(
+            format!("{:#x}", a),
+            "-0x1fd5863c3eb0469ec21a937a76f3432ffd73d97e447606b683ecf6f6e4a7ae225bfaff1eaaf8b0a1"
+        );
+    }
+
+    #[test]
+    fn demotes_to_inline() {
+        let big = Int::from(i64::MAX) + 1;
+        assert!(matches!(big.0, IntInner::Shared(_)));
+        assert!(matches!((big - 1).0, IntInner::Inline(i64::MAX)));
+        let min = Int::from(i64::MIN);
+        assert!(matches!((-&min).0, IntInner::Shared(_)));
+        assert!(matches!((-(-&min)).0, IntInner::Inline(i64::MIN)));
+        assert_eq!(Int::from(u128::MAX) - Int::from(u128::MAX), 0);
+        assert!(matches!(
+            Int::from(i128::from(i64::MIN)).0,
+            IntInner::Inline(_)
+        ));
+    }
+
+    #[test]
+    fn parses_like_python() {
+        let ok = [
+            ("0", 10, 0),
+            ("-0", 10, 0),
+            ("  +42\n", 10, 42),
+            ("1_000", 10, 1000),
+            ("0x_ff", 16, 255),
+            ("0XFF", 0, 255),
+            ("0o17", 0, 15),
+            ("0b101", 2, 5),
+            ("000", 0, 0),
+            ("0_0", 0, 0),
+            ("010", 10, 10),
+            ("b", 16, 11),
+        ];
+        for &(s, radix, expected) in &ok {
+            assert_eq!(
+                Int::from_str_radix(s, radix),
+                Ok(Int::from(expected)),
+                "{:?}",
+                s
+            );
+        }
+
+        let err = [
+            ("", 10, 0),
+            ("  ", 10, 2),
+            ("-", 10, 1),
+            ("_1", 10, 0),
+            ("1_", 10, 2),
+            ("1__0", 10, 2),
+            ("010", 0, 1),
+            ("0x", 16, 2),
+            ("0b2", 2, 2),
+            ("12a", 10, 2),
+            ("0x10", 10, 1),
+        ];
+        for &(s, radix, offset) in &err {
+            assert_eq!(
+                Int::from_str_radix(s, radix),
+                Err(ParseIntError { offset }),
+                "{:?}",
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn formats_like_python() {
+        let n = Int::from(-255);
+        assert_eq!(format!("{:#x}", n), "-0xff");
+        assert_eq!(format!("{:#X}", n), "-0xFF");
+        assert_eq!(format!("{:#o}", n), "-0o377");
+        assert_eq!(format!("{:#b}", n), "-0b11111111");
+        assert_eq!(format!("{:>8}", n), "    -255");
+        assert_eq!(format!("{:+}", Int::from(7)), "+7");
+        assert_eq!(format!("{:08}", n), "-0000255");
+
+        let googol = Int::from(10).pow(100);
+        let digits = googol.to_string();
+        assert_eq!(digits.len(), 101);
+        assert!(digits.starts_with("10000"));
+        assert_eq!(googol.to_f64(), 1e100);
+        assert_eq!((-googol).to_f64(), -1e100);
+        // Rounds half to even, with bits below the top 64 breaking ties
+        assert_eq!(Int::from((1_u128 << 53) + 1).to_f64(), 9007199254740992.0);
+        let above_half = (Int::from(1) << 80) + (Int::from(1) << 27) + 1;
+        assert_eq!(above_half.to_f64(), 2_f64.powi(80) + 2_f64.powi(28));
+    }
+
+    #[test]
+    fn pow_mod_matches_python() {
+        // From CPython's `pow(a, b, m)`
+        let cases = [
+            (3, 200, 1_000_000_007, 136_318_165),
+            (-3, 3, 7, 1),
+            (2, 10, -3, -2),
+            (0, 0, 5, 1),
+            (5, 0, 1, 0),
+            (3, -2, 11, 5),
+            (-5, -1, 12, 7),
+        ];
+        for &(base, exp, modulus, expected) in &cases {
+            assert_eq!(
+                Int::from(base).pow_mod(&Int::from(exp), &Int::from(modulus)),
+                Some(Int::from(expected)),
+                "pow({}, {}, {})",
+                base,
+                exp,
+                modulus
+            );
+        }
+        let big = Int::from(2).pow(521) - 1;
+        let fermat = Int::from(3).pow_mod(&(&big - 1), &big);
+        assert_eq!(fermat, Some(Int::from(1)));
+        assert_eq!(Int::from(6).pow_mod(&Int::from(-1), &Int::from(9)), None);
+        assert_eq!(Int::from(6).pow_mod(&Int::from(1), &Int::new()), None);
+    }
+}
@@ -1,3 +1,31 @@
+pub mod bisect;
+pub mod collections;
+pub mod deque;
+pub mod dict;
+pub mod fnmatch;
+pub mod hash;
+pub mod heapq;
+pub mod int;
+pub mod list;
+pub mod persistent;
+pub mod range;
+mod record;
+pub mod rope;
+pub mod set;
 pub mod string;
+#[cfg(test)]
+mod test_util;
+pub mod value;
 
+pub use deque::Deque;
+pub use dict::Dict;
+pub use int::Int;
+pub use list::List;
+pub use rope::Rope;
+pub use set::FrozenSet;
+pub use set::Set;
+pub use string::ByteString;
+pub use string::HashedString;
+pub use string::LocalString;
 pub use string::String;
+pub use value::Value;
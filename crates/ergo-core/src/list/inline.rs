@@ -0,0 +1,132 @@
+use std::marker::PhantomData;
+use std::mem::{align_of, size_of, ManuallyDrop, MaybeUninit};
+
+const WORDS: usize = 2;
+
+/// Items stored directly in the [`List`][super::List], in the space a shared buffer's offsets
+/// would take
+///
+/// How many fit depends on the size of `T`; types that are zero-sized or more aligned than a
+/// `usize` never fit.
+pub(super) struct Inline<T> {
+    buffer: [MaybeUninit<usize>; WORDS],
+    len: u8,
+    _items: PhantomData<T>,
+}
+
+impl<T> Inline<T> {
+    pub(super) const CAPACITY: usize =
+        if size_of::<T>() == 0 || align_of::<usize>() < align_of::<T>() {
+            0
+        } else {
+            WORDS * size_of::<usize>() / size_of::<T>()
+        };
+
+    #[inline]
+    pub(super) const fn new() -> Self {
+        Self {
+            buffer: [MaybeUninit::uninit(); WORDS],
+            len: 0,
+            _items: PhantomData,
+        }
+    }
+
+    #[inline]
+    pub(super) fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    #[inline]
+    pub(super) fn as_slice(&self) -> &[T] {
+        if self.len == 0 {
+            return &[];
+        }
+        // SAFETY: the first `len` items are initialized, and a non-empty list means `T` fits the
+        // buffer's alignment
+        unsafe { std::slice::from_raw_parts(self.buffer.as_ptr() as *const T, self.len()) }
+    }
+
+    #[inline]
+    pub(super) fn as_mut_slice(&mut self) -> &mut [T] {
+        if self.len == 0 {
+            return &mut [];
+        }
+        // SAFETY: see `as_slice`
+        unsafe { std::slice::from_raw_parts_mut(self.buffer.as_mut_ptr() as *mut T, self.len()) }
+    }
+
+    /// Appends `item`, handing it back when full.
+    #[inline]
+    pub(super) fn push(&mut self, item: T) -> Result<(), T> {
+        if Self::CAPACITY <= self.len() {
+            return Err(item);
+        }
+        // SAFETY: there is room after the initialized items, with `T`'s alignment
+        unsafe {
+            (self.buffer.as_mut_ptr() as *mut T)
+                .add(self.len())
+                .write(item);
+        }
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Removes the last item.
+    #[inline]
+    pub(super) fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        // SAFETY: the item was initialized, and is no longer counted by `len`
+        unsafe { Some((self.buffer.as_ptr() as *const T).add(self.len()).read()) }
+    }
+
+    /// Moves the items out into a `Vec`.
+    pub(super) fn into_vec(self) -> Vec<T> {
+        let mut this = ManuallyDrop::new(self);
+        let len = this.len();
+        if len == 0 {
+            return Vec::new();
+        }
+        let mut items = Vec::with_capacity(len);
+        // SAFETY: the items are moved out exactly once; `this` is never dropped
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                this.buffer.as_mut_ptr() as *const T,
+                items.as_mut_ptr(),
+                len,
+            );
+            items.set_len(len);
+        }
+        items
+    }
+}
+
+impl<T: Clone> Inline<T> {
+    /// Copies `items` when they fit.
+    pub(super) fn try_from_slice(items: &[T]) -> Option<Self> {
+        if Self::CAPACITY < items.len() {
+            return None;
+        }
+        let mut inline = Self::new();
+        for item in items {
+            // A panicking `clone` leaves `inline` holding only the items cloned so far
+            let _ = inline.push(item.clone());
+        }
+        Some(inline)
+    }
+}
+
+impl<T> Drop for Inline<T> {
+    fn drop(&mut self) {
+        // SAFETY: the initialized items are dropped exactly once
+        unsafe { std::ptr::drop_in_place(self.as_mut_slice()) }
+    }
+}
+
+impl<T: Clone> Clone for Inline<T> {
+    fn clone(&self) -> Self {
+        Self::try_from_slice(self.as_slice()).expect("same length")
+    }
+}
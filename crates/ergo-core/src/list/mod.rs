@@ -0,0 +1,897 @@
+//! A cheaply clonable sequence: [`List`]
+
+mod inline;
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::iter::FromIterator;
+use std::ops::{self, Bound, RangeBounds};
+use std::sync::Arc;
+
+use inline::Inline;
+
+/// A cheaply clonable, copy-on-write list, like Python's `list`
+///
+/// Like [`String`][crate::String], a `List` is one of:
+/// - inline, for a few small items (two words' worth, like two `u64`s or four `u32`s)
+/// - a slice of a shared, reference-counted buffer
+///
+/// Cloning and slicing share the buffer instead of copying, so lists can be passed around and
+/// stored freely without fighting the borrow checker.  Mutating a list whose buffer is shared
+/// first copies out its items, leaving other lists unchanged; mutating a list that is the only
+/// one using its buffer happens in place, like a `Vec`.
+///
+/// # Examples
+///
+/// ```
+/// let mut scores = ergo_core::List::from(vec![72, 95, 88]);
+///
+/// let snapshot = scores.clone();
+/// scores.push(100);
+/// scores.sort();
+///
+/// assert_eq!(scores, [72, 88, 95, 100]);
+/// assert_eq!(snapshot, [72, 95, 88]);
+/// ```
+pub struct List<T>(Repr<T>);
+
+enum Repr<T> {
+    Inline(Inline<T>),
+    Shared {
+        items: Arc<Vec<T>>,
+        start: usize,
+        len: usize,
+    },
+}
+
+impl<T> List<T> {
+    /// Creates a new empty `List`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let l = ergo_core::List::<i32>::new();
+    /// assert!(l.is_empty());
+    /// ```
+    #[inline]
+    pub const fn new() -> Self {
+        Self(Repr::Inline(Inline::new()))
+    }
+
+    /// Returns the number of items.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let l = ergo_core::List::from(vec!['a', 'b']);
+    /// assert_eq!(l.len(), 2);
+    /// ```
+    #[inline]
+    pub fn len(&self) -> usize {
+        match &self.0 {
+            Repr::Inline(inline) => inline.len(),
+            Repr::Shared { len, .. } => *len,
+        }
+    }
+
+    /// Returns `true` if there are no items.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns an iterator over the items.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let l = ergo_core::List::from(vec![1, 2, 3]);
+    /// assert_eq!(l.iter().sum::<i32>(), 6);
+    /// ```
+    #[inline]
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.as_slice().iter()
+    }
+
+    /// Returns `true` if the list has an item equal to `value`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let l = ergo_core::List::from(vec!["x", "y"]);
+    ///
+    /// assert!(l.contains(&"y"));
+    /// assert!(!l.contains(&"z"));
+    /// ```
+    #[inline]
+    pub fn contains(&self, value: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.as_slice().contains(value)
+    }
+
+    /// Returns the index of the first item equal to `value`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let l = ergo_core::List::from(vec!["x", "y", "y"]);
+    ///
+    /// assert_eq!(l.position(&"y"), Some(1));
+    /// assert_eq!(l.position(&"z"), None);
+    /// ```
+    #[inline]
+    pub fn position(&self, value: &T) -> Option<usize>
+    where
+        T: PartialEq,
+    {
+        self.iter().position(|item| item == value)
+    }
+
+    /// Returns the number of items equal to `value`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let l = ergo_core::List::from(vec![1, 2, 1, 1]);
+    /// assert_eq!(l.count(&1), 3);
+    /// ```
+    #[inline]
+    pub fn count(&self, value: &T) -> usize
+    where
+        T: PartialEq,
+    {
+        self.iter().filter(|item| *item == value).count()
+    }
+
+    /// Removes all items.
+    ///
+    /// Other lists sharing the buffer are unchanged.
+    #[inline]
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Returns `true` if `self` and `other` are slices of the same buffer.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let l = ergo_core::List::from((0..100).collect::<Vec<_>>());
+    /// let tail = l.get(50..).unwrap();
+    ///
+    /// assert!(tail.is_shared_with(&l));
+    /// ```
+    pub fn is_shared_with(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (Repr::Shared { items: a, .. }, Repr::Shared { items: b, .. }) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+impl<T: Clone> List<T> {
+    /// Returns a sublist, or [`None`] when the range is out of bounds.
+    ///
+    /// Sublists share `self`'s buffer unless they fit inline.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let l = ergo_core::List::from(vec![1, 2, 3, 4]);
+    ///
+    /// assert_eq!(l.get(1..3).unwrap(), [2, 3]);
+    /// assert!(l.get(3..5).is_none());
+    /// ```
+    pub fn get(&self, range: impl RangeBounds<usize>) -> Option<Self> {
+        let (start, end) = bounds(self.len(), range)?;
+        let sub = &self.as_slice()[start..end];
+        if let Some(inline) = Inline::try_from_slice(sub) {
+            return Some(Self(Repr::Inline(inline)));
+        }
+        match &self.0 {
+            Repr::Shared {
+                items,
+                start: offset,
+                ..
+            } => Some(Self(Repr::Shared {
+                items: items.clone(),
+                start: offset + start,
+                len: end - start,
+            })),
+            Repr::Inline(_) => unreachable!("sublists of inline lists fit inline"),
+        }
+    }
+
+    /// Appends `value` to the end.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut l = ergo_core::List::new();
+    /// l.push("a");
+    /// l.push("b");
+    ///
+    /// assert_eq!(l, ["a", "b"]);
+    /// ```
+    pub fn push(&mut self, value: T) {
+        if let Repr::Inline(inline) = &mut self.0 {
+            match inline.push(value) {
+                Ok(()) => {}
+                Err(value) => self.modify(|items| items.push(value)),
+            }
+        } else {
+            self.modify(|items| items.push(value));
+        }
+    }
+
+    /// Removes the last item and returns it, or [`None`] if the list is empty.
+    ///
+    /// Only the removed item is copied when the buffer is shared.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut l = ergo_core::List::from(vec![1, 2]);
+    ///
+    /// assert_eq!(l.pop(), Some(2));
+    /// assert_eq!(l, [1]);
+    /// ```
+    pub fn pop(&mut self) -> Option<T> {
+        match &mut self.0 {
+            Repr::Inline(inline) => inline.pop(),
+            Repr::Shared { items, start, len } => {
+                if *len == 0 {
+                    return None;
+                }
+                if *start + *len == items.len() {
+                    if let Some(items) = Arc::get_mut(items) {
+                        *len -= 1;
+                        return items.pop();
+                    }
+                }
+                *len -= 1;
+                Some(items[*start + *len].clone())
+            }
+        }
+    }
+
+    /// Removes the item at `index` and returns it, like Python's `list.pop(index)`.
+    ///
+    /// Negative indices count back from the end.  Returns [`None`] if `index` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut l = ergo_core::List::from(vec!["a", "b", "c", "d"]);
+    ///
+    /// assert_eq!(l.pop_at(1), Some("b"));
+    /// assert_eq!(l.pop_at(-1), Some("d"));
+    /// assert_eq!(l.pop_at(2), None);
+    /// assert_eq!(l, ["a", "c"]);
+    /// ```
+    pub fn pop_at(&mut self, index: isize) -> Option<T> {
+        let index = crate::range::py_index(self.len(), index)?;
+        if index + 1 == self.len() {
+            return self.pop();
+        }
+        Some(self.modify(|items| items.remove(index)))
+    }
+
+    /// Inserts `value` at `index`, shifting the items after it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the length.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut l = ergo_core::List::from(vec![1, 3]);
+    /// l.insert(1, 2);
+    ///
+    /// assert_eq!(l, [1, 2, 3]);
+    /// ```
+    pub fn insert(&mut self, index: usize, value: T) {
+        let len = self.len();
+        assert!(
+            index <= len,
+            "insertion index (is {}) should be <= len (is {})",
+            index,
+            len
+        );
+        self.modify(|items| items.insert(index, value));
+    }
+
+    /// Removes the item at `index` and returns it, shifting the items after it.
+    ///
+    /// Unlike Python's `list.remove`, this takes an index; see [`List::remove_item`] to remove by
+    /// value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut l = ergo_core::List::from(vec!["a", "b", "c"]);
+    ///
+    /// let index = l.position(&"b").unwrap();
+    /// assert_eq!(l.remove(index), "b");
+    /// assert_eq!(l, ["a", "c"]);
+    /// ```
+    pub fn remove(&mut self, index: usize) -> T {
+        let len = self.len();
+        assert!(
+            index < len,
+            "removal index (is {}) should be < len (is {})",
+            index,
+            len
+        );
+        self.modify(|items| items.remove(index))
+    }
+
+    /// Removes the first item equal to `value` and returns it, like Python's `list.remove`.
+    ///
+    /// Returns [`None`] if no item is equal to `value`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut l = ergo_core::List::from(vec!["a", "b", "a"]);
+    ///
+    /// assert_eq!(l.remove_item(&"a"), Some("a"));
+    /// assert_eq!(l.remove_item(&"c"), None);
+    /// assert_eq!(l, ["b", "a"]);
+    /// ```
+    pub fn remove_item(&mut self, value: &T) -> Option<T>
+    where
+        T: PartialEq,
+    {
+        let index = self.position(value)?;
+        Some(self.remove(index))
+    }
+
+    /// Appends every item of `iter`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut l = ergo_core::List::from(vec![1]);
+    /// l.extend(vec![2, 3]);
+    ///
+    /// assert_eq!(l, [1, 2, 3]);
+    /// ```
+    pub fn extend(&mut self, iter: impl IntoIterator<Item = T>) {
+        self.modify(|items| items.extend(iter));
+    }
+
+    /// Reverses the order of the items, in place.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut l = ergo_core::List::from(vec![1, 2, 3]);
+    /// l.reverse();
+    ///
+    /// assert_eq!(l, [3, 2, 1]);
+    /// ```
+    pub fn reverse(&mut self) {
+        self.make_mut_slice().reverse();
+    }
+
+    /// Sorts the items, keeping equal items in order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut l = ergo_core::List::from(vec![3, 1, 2]);
+    /// l.sort();
+    ///
+    /// assert_eq!(l, [1, 2, 3]);
+    /// ```
+    pub fn sort(&mut self)
+    where
+        T: Ord,
+    {
+        self.make_mut_slice().sort();
+    }
+
+    /// Sorts the items by the key `f` extracts, keeping items with equal keys in order, like
+    /// Python's `list.sort(key=f)`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut l = ergo_core::List::from(vec!["ccc", "a", "bb"]);
+    /// l.sort_by_key(|s| s.len());
+    ///
+    /// assert_eq!(l, ["a", "bb", "ccc"]);
+    /// ```
+    pub fn sort_by_key<K: Ord>(&mut self, f: impl FnMut(&T) -> K) {
+        self.make_mut_slice().sort_by_key(f);
+    }
+
+    /// Runs `f` on the items as a `Vec`, copying them out first if the buffer is shared.
+    fn modify<R>(&mut self, f: impl FnOnce(&mut Vec<T>) -> R) -> R {
+        if let Repr::Shared {
+            items,
+            start: 0,
+            len,
+        } = &mut self.0
+        {
+            if *len == items.len() {
+                if let Some(items) = Arc::get_mut(items) {
+                    let result = f(items);
+                    *len = items.len();
+                    return result;
+                }
+            }
+        }
+        let mut items = std::mem::take(self).into_vec();
+        let result = f(&mut items);
+        *self = Self::from(items);
+        result
+    }
+
+    /// Returns the items for changing in place, copying them out first if the buffer is shared.
+    fn make_mut_slice(&mut self) -> &mut [T] {
+        let unique = match &mut self.0 {
+            Repr::Inline(_) => true,
+            Repr::Shared { items, .. } => Arc::get_mut(items).is_some(),
+        };
+        if !unique {
+            *self = Self::from(self.as_slice().to_vec());
+        }
+        match &mut self.0 {
+            Repr::Inline(inline) => inline.as_mut_slice(),
+            Repr::Shared { items, start, len } => {
+                let items = Arc::get_mut(items).expect("just unshared");
+                &mut items[*start..*start + *len]
+            }
+        }
+    }
+
+    /// Moves the items into a `Vec`, only copying them when the buffer is shared.
+    fn into_vec(self) -> Vec<T> {
+        match self.0 {
+            Repr::Inline(inline) => inline.into_vec(),
+            Repr::Shared { items, start, len } => match Arc::try_unwrap(items) {
+                Ok(mut items) => {
+                    items.truncate(start + len);
+                    items.drain(..start);
+                    items
+                }
+                Err(items) => items[start..start + len].to_vec(),
+            },
+        }
+    }
+}
+
+/// Resolves `range` to `start..end` within `len`.
+fn bounds(len: usize, range: impl RangeBounds<usize>) -> Option<(usize, usize)> {
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => start.checked_add(1)?,
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end.checked_add(1)?,
+        Bound::Excluded(&end) => end,
+        Bound::Unbounded => len,
+    };
+    if start <= end && end <= len {
+        Some((start, end))
+    } else {
+        None
+    }
+}
+
+/// Transitional Python API
+impl<T: Clone> List<T> {
+    #[deprecated = "In Rust, we refer to this as `push`"]
+    pub fn append(&mut self, value: T) {
+        self.push(value);
+    }
+
+    #[deprecated = "In Rust, we refer to this as `position`"]
+    pub fn index(&self, value: &T) -> Option<usize>
+    where
+        T: PartialEq,
+    {
+        self.position(value)
+    }
+
+    #[deprecated = "In Rust, we refer to this as `clone`"]
+    pub fn copy(&self) -> Self {
+        self.clone()
+    }
+}
+
+/// Interop
+impl<T> List<T> {
+    /// Extracts a slice containing the entire `List`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let l = ergo_core::List::from(vec![1, 2]);
+    /// assert_eq!(l.as_slice(), &[1, 2]);
+    /// ```
+    #[inline]
+    pub fn as_slice(&self) -> &[T] {
+        match &self.0 {
+            Repr::Inline(inline) => inline.as_slice(),
+            Repr::Shared { items, start, len } => &items[*start..*start + *len],
+        }
+    }
+}
+
+impl<T: Clone> Clone for List<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        match &self.0 {
+            Repr::Inline(inline) => Self(Repr::Inline(inline.clone())),
+            Repr::Shared { items, start, len } => Self(Repr::Shared {
+                items: items.clone(),
+                start: *start,
+                len: *len,
+            }),
+        }
+    }
+}
+
+impl<T> Default for List<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> From<Vec<T>> for List<T> {
+    fn from(other: Vec<T>) -> Self {
+        if other.len() <= Inline::<T>::CAPACITY {
+            let mut inline = Inline::new();
+            for item in other {
+                if inline.push(item).is_err() {
+                    unreachable!("checked the capacity");
+                }
+            }
+            Self(Repr::Inline(inline))
+        } else {
+            Self(Repr::Shared {
+                start: 0,
+                len: other.len(),
+                items: Arc::new(other),
+            })
+        }
+    }
+}
+
+impl<'s, T: Clone> From<&'s [T]> for List<T> {
+    #[inline]
+    fn from(other: &'s [T]) -> Self {
+        match Inline::try_from_slice(other) {
+            Some(inline) => Self(Repr::Inline(inline)),
+            None => Self::from(other.to_vec()),
+        }
+    }
+}
+
+impl<T, const N: usize> From<[T; N]> for List<T> {
+    #[inline]
+    fn from(other: [T; N]) -> Self {
+        Self::from(Vec::from(other))
+    }
+}
+
+impl<T: Clone> From<List<T>> for Vec<T> {
+    /// Moves the items out, only copying them when the buffer is shared.
+    #[inline]
+    fn from(other: List<T>) -> Self {
+        other.into_vec()
+    }
+}
+
+impl<T> FromIterator<T> for List<T> {
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::from(iter.into_iter().collect::<Vec<T>>())
+    }
+}
+
+impl<T: Clone> Extend<T> for List<T> {
+    #[inline]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        List::extend(self, iter);
+    }
+}
+
+impl<'l, T> IntoIterator for &'l List<T> {
+    type Item = &'l T;
+    type IntoIter = std::slice::Iter<'l, T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: Clone> IntoIterator for List<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.into_vec().into_iter()
+    }
+}
+
+impl<T> ops::Index<usize> for List<T> {
+    type Output = T;
+
+    #[inline]
+    fn index(&self, index: usize) -> &T {
+        &self.as_slice()[index]
+    }
+}
+
+impl<T: Clone> ops::IndexMut<usize> for List<T> {
+    /// Copies the items out first if the buffer is shared.
+    #[inline]
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        &mut self.make_mut_slice()[index]
+    }
+}
+
+impl<T> ops::Index<ops::RangeFull> for List<T> {
+    type Output = [T];
+
+    #[inline]
+    fn index(&self, _index: ops::RangeFull) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T> AsRef<[T]> for List<T> {
+    #[inline]
+    fn as_ref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T> std::borrow::Borrow<[T]> for List<T> {
+    #[inline]
+    fn borrow(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for List<T> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_slice(), f)
+    }
+}
+
+impl<T: PartialEq<U>, U> PartialEq<List<U>> for List<T> {
+    #[inline]
+    fn eq(&self, other: &List<U>) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<T: Eq> Eq for List<T> {}
+
+impl<T: PartialEq<U>, U> PartialEq<[U]> for List<T> {
+    #[inline]
+    fn eq(&self, other: &[U]) -> bool {
+        self.as_slice() == other
+    }
+}
+
+impl<'a, T: PartialEq<U>, U> PartialEq<&'a [U]> for List<T> {
+    #[inline]
+    fn eq(&self, other: &&'a [U]) -> bool {
+        self.as_slice() == *other
+    }
+}
+
+impl<T: PartialEq<U>, U> PartialEq<Vec<U>> for List<T> {
+    #[inline]
+    fn eq(&self, other: &Vec<U>) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<T: PartialEq<U>, U, const N: usize> PartialEq<[U; N]> for List<T> {
+    #[inline]
+    fn eq(&self, other: &[U; N]) -> bool {
+        self.as_slice() == &other[..]
+    }
+}
+
+impl<T: PartialOrd> PartialOrd for List<T> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.as_slice().partial_cmp(other.as_slice())
+    }
+}
+
+impl<T: Ord> Ord for List<T> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_slice().cmp(other.as_slice())
+    }
+}
+
+impl<T: Hash> Hash for List<T> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_slice().hash(state);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    /// Counts live values, to check that every item is dropped exactly once
+    #[derive(Debug, PartialEq)]
+    struct Tracked(u16, Rc<Cell<isize>>);
+
+    impl Tracked {
+        fn new(value: u16, live: &Rc<Cell<isize>>) -> Self {
+            live.set(live.get() + 1);
+            Self(value, live.clone())
+        }
+    }
+
+    impl Clone for Tracked {
+        fn clone(&self) -> Self {
+            Self::new(self.0, &self.1)
+        }
+    }
+
+    impl Drop for Tracked {
+        fn drop(&mut self) {
+            self.1.set(self.1.get() - 1);
+        }
+    }
+
+    #[test]
+    fn inline_capacity() {
+        assert_eq!(Inline::<u8>::CAPACITY, 2 * std::mem::size_of::<usize>());
+        assert_eq!(
+            Inline::<u64>::CAPACITY,
+            2 * std::mem::size_of::<usize>() / 8
+        );
+        assert_eq!(Inline::<()>::CAPACITY, 0);
+        assert_eq!(Inline::<u128>::CAPACITY, 0);
+        assert_eq!(Inline::<[u8; 100]>::CAPACITY, 0);
+
+        let l = List::from(vec![(); 3]);
+        assert_eq!(l.len(), 3);
+        let mut l = List::from(vec![1u128, 2]);
+        l.push(3);
+        assert_eq!(l, [1, 2, 3]);
+    }
+
+    #[test]
+    fn items_drop_once() {
+        let live = Rc::new(Cell::new(0));
+        {
+            let mut l = List::new();
+            for i in 0..10 {
+                l.push(Tracked::new(i, &live));
+            }
+            let snapshot = l.clone();
+            let tail = l.get(5..).unwrap();
+            l.reverse();
+            l.remove(3);
+            assert_eq!(l.pop().map(|t| t.0), Some(0));
+            assert_eq!(snapshot.len(), 10);
+            assert_eq!(tail[0].0, 5);
+
+            let small: List<Tracked> = l.get(..1).unwrap();
+            let moved: Vec<Tracked> = small.into();
+            assert_eq!(moved.len(), 1);
+        }
+        assert_eq!(live.get(), 0);
+    }
+
+    #[test]
+    fn copy_on_write() {
+        let original = List::from((0..100).collect::<Vec<u32>>());
+        let mut edited = original.clone();
+        edited[0] = 42;
+        edited.push(100);
+        assert_eq!(original[0], 0);
+        assert_eq!(original.len(), 100);
+        assert!(!edited.is_shared_with(&original));
+
+        let mut tail = original.get(90..).unwrap();
+        assert!(tail.is_shared_with(&original));
+        assert_eq!(tail.pop(), Some(99));
+        assert!(tail.is_shared_with(&original));
+        tail.sort_by_key(|x| std::cmp::Reverse(*x));
+        assert_eq!(tail, [98, 97, 96, 95, 94, 93, 92, 91, 90]);
+        assert_eq!(original.as_slice()[90..].len(), 10);
+    }
+
+    #[test]
+    fn python_pop_and_remove() {
+        let original = List::from((0..10).collect::<Vec<i32>>());
+        let mut l = original.get(2..).unwrap();
+        let mut model: Vec<i32> = (2..10).collect();
+        for &index in [-1, 0, 3, -3, 7, -8, 1].iter() {
+            let expected = crate::range::py_index(model.len(), index).map(|i| model.remove(i));
+            assert_eq!(l.pop_at(index), expected, "pop({})", index);
+            assert_eq!(l, model);
+        }
+        assert_eq!(l, [3, 7, 8]);
+        assert_eq!(l.remove_item(&7), Some(7));
+        assert_eq!(l.remove_item(&7), None);
+        assert_eq!(l.remove_item(&3), Some(3));
+        assert_eq!(l.remove_item(&8), Some(8));
+        assert!(l.is_empty());
+        assert_eq!(l.pop_at(0), None);
+        assert_eq!(l.pop_at(-1), None);
+        assert_eq!(original, (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn unique_buffers_change_in_place() {
+        let mut items = Vec::with_capacity(200);
+        items.extend(0..100u32);
+        let mut l = List::from(items);
+        let before = l.as_slice().as_ptr();
+        l.insert(0, 7);
+        l.remove(0);
+        l.reverse();
+        assert_eq!(l.as_slice().as_ptr(), before);
+    }
+}
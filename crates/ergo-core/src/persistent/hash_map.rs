@@ -0,0 +1,741 @@
+//! A persistent hash map: [`HashMap`]
+
+use std::borrow::Borrow;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::iter::{FromIterator, FusedIterator};
+use std::ops;
+use std::sync::Arc;
+
+use crate::hash::{hash_with, BuildFastHasher};
+
+/// The number of hash bits each level of the trie consumes
+const BITS: u32 = 5;
+
+/// An immutable hash map with cheap updates
+///
+/// A `HashMap` is a hash array mapped trie (HAMT): a tree with up to 32 children per node,
+/// picked by successive 5-bit chunks of each key's hash.  Inserting and removing take
+/// `O(log n)` time and return a new map that shares everything but the path to the change with
+/// the original, so old versions can be kept around as cheap snapshots.  Cloning is `O(1)`.
+///
+/// Iteration order depends on the hashes, so it is arbitrary but the same for maps with the same
+/// keys and hasher.  Keys are hashed with [`FastHasher`][crate::hash::FastHasher] by default,
+/// which is not resistant to HashDoS; pick another hasher with [`HashMap::with_hasher`] for keys
+/// chosen by untrusted users.
+///
+/// # Examples
+///
+/// ```
+/// use ergo_core::persistent::HashMap;
+///
+/// let empty = HashMap::new();
+/// let before = empty.insert("alice", 10).insert("bob", 20);
+/// let after = before.insert("alice", 15).remove("bob");
+///
+/// assert_eq!(before.get("alice"), Some(&10));
+/// assert_eq!(after.get("alice"), Some(&15));
+/// assert_eq!(after.get("bob"), None);
+/// assert!(empty.is_empty());
+/// ```
+pub struct HashMap<K, V, S = BuildFastHasher> {
+    root: Arc<Branch<K, V>>,
+    len: usize,
+    hasher: S,
+}
+
+/// A trie node, holding a slot for each 5-bit chunk in `bitmap`
+#[derive(Clone, Debug)]
+struct Branch<K, V> {
+    bitmap: u32,
+    slots: Vec<Slot<K, V>>,
+}
+
+#[derive(Clone, Debug)]
+enum Slot<K, V> {
+    Leaf {
+        hash: u64,
+        key: K,
+        value: V,
+    },
+    /// Entries whose keys have the same hash
+    Collision {
+        hash: u64,
+        entries: Vec<(K, V)>,
+    },
+    Branch(Arc<Branch<K, V>>),
+}
+
+/// Returns the bit for the 5-bit chunk of `hash` at `shift`.
+fn bit(hash: u64, shift: u32) -> u32 {
+    1 << ((hash >> shift) & ((1 << BITS) - 1))
+}
+
+impl<K, V> Branch<K, V> {
+    const fn new() -> Self {
+        Self {
+            bitmap: 0,
+            slots: Vec::new(),
+        }
+    }
+
+    /// Returns the position in `slots` of the slot for `bit`.
+    fn position(&self, bit: u32) -> usize {
+        (self.bitmap & (bit - 1)).count_ones() as usize
+    }
+
+    /// Builds a branch holding two slots whose hashes differ.
+    fn pair(shift: u32, a: (u64, Slot<K, V>), b: (u64, Slot<K, V>)) -> Self {
+        let (a_bit, b_bit) = (bit(a.0, shift), bit(b.0, shift));
+        let slots = match a_bit.cmp(&b_bit) {
+            std::cmp::Ordering::Equal => {
+                vec![Slot::Branch(Arc::new(Self::pair(shift + BITS, a, b)))]
+            }
+            std::cmp::Ordering::Less => vec![a.1, b.1],
+            std::cmp::Ordering::Greater => vec![b.1, a.1],
+        };
+        Self {
+            bitmap: a_bit | b_bit,
+            slots,
+        }
+    }
+
+    fn get<Q>(&self, hash: u64, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        let mut branch = self;
+        let mut shift = 0;
+        loop {
+            let bit = bit(hash, shift);
+            if branch.bitmap & bit == 0 {
+                return None;
+            }
+            match &branch.slots[branch.position(bit)] {
+                Slot::Leaf {
+                    hash: h,
+                    key: k,
+                    value,
+                } => {
+                    return if *h == hash && k.borrow() == key {
+                        Some(value)
+                    } else {
+                        None
+                    };
+                }
+                Slot::Collision { hash: h, entries } => {
+                    return entries
+                        .iter()
+                        .find(|(k, _)| *h == hash && k.borrow() == key)
+                        .map(|(_, v)| v);
+                }
+                Slot::Branch(child) => {
+                    branch = child;
+                    shift += BITS;
+                }
+            }
+        }
+    }
+}
+
+impl<K: Clone + Eq, V: Clone> Branch<K, V> {
+    /// Sets the value for `key`, copying shared nodes on the way, and returns `true` if the key
+    /// is new.
+    fn insert(&mut self, shift: u32, hash: u64, key: K, value: V) -> bool {
+        let bit = bit(hash, shift);
+        let position = self.position(bit);
+        if self.bitmap & bit == 0 {
+            self.bitmap |= bit;
+            self.slots.insert(position, Slot::Leaf { hash, key, value });
+            return true;
+        }
+        let slot = &mut self.slots[position];
+        match slot {
+            Slot::Branch(child) => Arc::make_mut(child).insert(shift + BITS, hash, key, value),
+            Slot::Leaf {
+                hash: h,
+                key: k,
+                value: v,
+            } if *h == hash => {
+                if *k == key {
+                    *v = value;
+                    return false;
+                }
+                let entries = vec![(k.clone(), v.clone()), (key, value)];
+                *slot = Slot::Collision { hash, entries };
+                true
+            }
+            Slot::Collision { hash: h, entries } if *h == hash => {
+                match entries.iter_mut().find(|(k, _)| *k == key) {
+                    Some((_, v)) => {
+                        *v = value;
+                        false
+                    }
+                    None => {
+                        entries.push((key, value));
+                        true
+                    }
+                }
+            }
+            Slot::Leaf { hash: h, .. } | Slot::Collision { hash: h, .. } => {
+                let old_hash = *h;
+                let empty = Slot::Collision {
+                    hash: 0,
+                    entries: Vec::new(),
+                };
+                let old = std::mem::replace(slot, empty);
+                let new = Slot::Leaf { hash, key, value };
+                let pair = Self::pair(shift + BITS, (old_hash, old), (hash, new));
+                *slot = Slot::Branch(Arc::new(pair));
+                true
+            }
+        }
+    }
+
+    /// Removes the entry for `key`, which must be present, copying shared nodes on the way.
+    fn remove<Q>(&mut self, shift: u32, hash: u64, key: &Q)
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        let bit = bit(hash, shift);
+        let position = self.position(bit);
+        let slot = &mut self.slots[position];
+        match slot {
+            Slot::Leaf { .. } => {
+                self.bitmap &= !bit;
+                self.slots.remove(position);
+            }
+            Slot::Collision { entries, .. } => {
+                entries.retain(|(k, _)| k.borrow() != key);
+                if entries.len() == 1 {
+                    let (key, value) = entries.pop().expect("checked the length");
+                    *slot = Slot::Leaf { hash, key, value };
+                }
+            }
+            Slot::Branch(child) => {
+                let child = Arc::make_mut(child);
+                child.remove(shift + BITS, hash, key);
+                // A branch left with a single entry is replaced by that entry
+                if let [Slot::Leaf { .. }] | [Slot::Collision { .. }] = &child.slots[..] {
+                    *slot = child.slots.pop().expect("checked the length");
+                }
+            }
+        }
+    }
+}
+
+impl<K, V> HashMap<K, V> {
+    /// Creates a new empty `HashMap`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let m = ergo_core::persistent::HashMap::<i32, i32>::new();
+    /// assert!(m.is_empty());
+    /// ```
+    #[inline]
+    pub fn new() -> Self {
+        Self::with_hasher(BuildFastHasher::default())
+    }
+}
+
+impl<K, V, S> HashMap<K, V, S> {
+    /// Creates a new empty `HashMap` that hashes keys with `hasher`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use std::collections::hash_map::RandomState;
+    ///
+    /// let m = ergo_core::persistent::HashMap::with_hasher(RandomState::new()).insert("a", 1);
+    /// assert_eq!(m.get("a"), Some(&1));
+    /// ```
+    #[inline]
+    pub fn with_hasher(hasher: S) -> Self {
+        Self {
+            root: Arc::new(Branch::new()),
+            len: 0,
+            hasher,
+        }
+    }
+
+    /// Returns the number of entries.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let m = ergo_core::persistent::HashMap::from([("a", 1), ("b", 2)]);
+    /// assert_eq!(m.len(), 2);
+    /// ```
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if there are no entries.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Iterates over the keys and values, in an arbitrary order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let m = ergo_core::persistent::HashMap::from([("a", 1), ("b", 2)]);
+    ///
+    /// let mut items: Vec<_> = m.iter().collect();
+    /// items.sort();
+    /// assert_eq!(items, [(&"a", &1), (&"b", &2)]);
+    /// ```
+    #[inline]
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            branches: vec![self.root.slots.iter()],
+            collision: [].iter(),
+            remaining: self.len,
+        }
+    }
+
+    /// Iterates over the keys, in an arbitrary order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let m = ergo_core::persistent::HashMap::from([("a", 1), ("b", 2)]);
+    ///
+    /// let mut keys: Vec<_> = m.keys().collect();
+    /// keys.sort();
+    /// assert_eq!(keys, [&"a", &"b"]);
+    /// ```
+    #[inline]
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys(self.iter())
+    }
+
+    /// Iterates over the values, in an arbitrary order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let m = ergo_core::persistent::HashMap::from([("a", 1), ("b", 2)]);
+    /// assert_eq!(m.values().sum::<i32>(), 3);
+    /// ```
+    #[inline]
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values(self.iter())
+    }
+
+    /// Returns the map's hasher.
+    #[inline]
+    pub fn hasher(&self) -> &S {
+        &self.hasher
+    }
+
+    /// Returns `true` if both maps are the same version, so neither was derived from the other
+    /// by an update.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let m = ergo_core::persistent::HashMap::from([("a", 1)]);
+    /// let copy = m.clone();
+    /// let bigger = m.insert("b", 2);
+    ///
+    /// assert!(m.is_shared_with(&copy));
+    /// assert!(!m.is_shared_with(&bigger));
+    /// ```
+    #[inline]
+    pub fn is_shared_with(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.root, &other.root)
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> HashMap<K, V, S> {
+    /// Returns the value for `key`, or [`None`] if it is missing.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let m = ergo_core::persistent::HashMap::from([("a", 1)]);
+    ///
+    /// assert_eq!(m.get("a"), Some(&1));
+    /// assert_eq!(m.get("b"), None);
+    /// ```
+    #[inline]
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.root.get(hash_with(&self.hasher, key), key)
+    }
+
+    /// Returns `true` if there is an entry for `key`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let m = ergo_core::persistent::HashMap::from([("a", 1)]);
+    ///
+    /// assert!(m.contains_key("a"));
+    /// assert!(!m.contains_key("b"));
+    /// ```
+    #[inline]
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+}
+
+impl<K: Clone + Hash + Eq, V: Clone, S: BuildHasher + Clone> HashMap<K, V, S> {
+    /// Returns a map with the value for `key` set to `value`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let m = ergo_core::persistent::HashMap::new().insert("a", 1);
+    /// let m2 = m.insert("a", 2);
+    ///
+    /// assert_eq!(m["a"], 1);
+    /// assert_eq!(m2["a"], 2);
+    /// ```
+    #[must_use = "this returns the updated map as a new map, \
+                  without modifying the original"]
+    pub fn insert(&self, key: K, value: V) -> Self {
+        let mut map = self.clone();
+        map.insert_mut(key, value);
+        map
+    }
+
+    /// Returns a map without the entry for `key`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let m = ergo_core::persistent::HashMap::from([("a", 1), ("b", 2)]);
+    ///
+    /// assert_eq!(m.remove("a").len(), 1);
+    /// assert_eq!(m.remove("z").len(), 2);
+    /// ```
+    #[must_use = "this returns the updated map as a new map, \
+                  without modifying the original"]
+    pub fn remove<Q>(&self, key: &Q) -> Self
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let mut map = self.clone();
+        let hash = hash_with(&self.hasher, key);
+        if self.root.get(hash, key).is_some() {
+            Arc::make_mut(&mut map.root).remove(0, hash, key);
+            map.len -= 1;
+        }
+        map
+    }
+
+    /// Sets the value for `key` in place, copying only the nodes that are shared.
+    fn insert_mut(&mut self, key: K, value: V) {
+        let hash = hash_with(&self.hasher, &key);
+        if Arc::make_mut(&mut self.root).insert(0, hash, key, value) {
+            self.len += 1;
+        }
+    }
+}
+
+impl<K, V, S: Clone> Clone for HashMap<K, V, S> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            root: self.root.clone(),
+            len: self.len,
+            hasher: self.hasher.clone(),
+        }
+    }
+}
+
+impl<K, V, S: Default> Default for HashMap<K, V, S> {
+    #[inline]
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<K, V, S> FromIterator<(K, V)> for HashMap<K, V, S>
+where
+    K: Clone + Hash + Eq,
+    V: Clone,
+    S: BuildHasher + Clone + Default,
+{
+    /// Builds the map in place, without copying any nodes.
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::default();
+        for (key, value) in iter {
+            map.insert_mut(key, value);
+        }
+        map
+    }
+}
+
+impl<K: Clone + Hash + Eq, V: Clone, const N: usize> From<[(K, V); N]> for HashMap<K, V> {
+    #[inline]
+    fn from(other: [(K, V); N]) -> Self {
+        Vec::from(other).into_iter().collect()
+    }
+}
+
+impl<'m, K, V, S> IntoIterator for &'m HashMap<K, V, S> {
+    type Item = (&'m K, &'m V);
+    type IntoIter = Iter<'m, K, V>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K, V, S, Q> ops::Index<&Q> for HashMap<K, V, S>
+where
+    K: Hash + Eq + Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+    S: BuildHasher,
+{
+    type Output = V;
+
+    /// Returns the value for `key`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no entry for `key`.
+    #[inline]
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("key not found in `HashMap`")
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, S> fmt::Debug for HashMap<K, V, S> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Hash + Eq, V: PartialEq, S: BuildHasher> PartialEq for HashMap<K, V, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len
+            && (self.is_shared_with(other) || self.iter().all(|(k, v)| other.get(k) == Some(v)))
+    }
+}
+
+impl<K: Hash + Eq, V: Eq, S: BuildHasher> Eq for HashMap<K, V, S> {}
+
+/// Iterates over a [`HashMap`]'s keys and values
+///
+/// Returned by [`HashMap::iter`].
+#[derive(Clone, Debug)]
+pub struct Iter<'m, K, V> {
+    /// The remaining slots of each branch on the way down to the current one
+    branches: Vec<std::slice::Iter<'m, Slot<K, V>>>,
+    collision: std::slice::Iter<'m, (K, V)>,
+    remaining: usize,
+}
+
+impl<'m, K, V> Iterator for Iter<'m, K, V> {
+    type Item = (&'m K, &'m V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((key, value)) = self.collision.next() {
+                self.remaining -= 1;
+                return Some((key, value));
+            }
+            match self.branches.last_mut()?.next() {
+                None => {
+                    self.branches.pop();
+                }
+                Some(Slot::Leaf { key, value, .. }) => {
+                    self.remaining -= 1;
+                    return Some((key, value));
+                }
+                Some(Slot::Collision { entries, .. }) => self.collision = entries.iter(),
+                Some(Slot::Branch(branch)) => self.branches.push(branch.slots.iter()),
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'m, K, V> ExactSizeIterator for Iter<'m, K, V> {}
+
+impl<'m, K, V> FusedIterator for Iter<'m, K, V> {}
+
+/// Iterates over a [`HashMap`]'s keys
+///
+/// Returned by [`HashMap::keys`].
+#[derive(Clone, Debug)]
+pub struct Keys<'m, K, V>(Iter<'m, K, V>);
+
+impl<'m, K, V> Iterator for Keys<'m, K, V> {
+    type Item = &'m K;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(key, _)| key)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'m, K, V> ExactSizeIterator for Keys<'m, K, V> {}
+
+impl<'m, K, V> FusedIterator for Keys<'m, K, V> {}
+
+/// Iterates over a [`HashMap`]'s values
+///
+/// Returned by [`HashMap::values`].
+#[derive(Clone, Debug)]
+pub struct Values<'m, K, V>(Iter<'m, K, V>);
+
+impl<'m, K, V> Iterator for Values<'m, K, V> {
+    type Item = &'m V;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(_, value)| value)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'m, K, V> ExactSizeIterator for Values<'m, K, V> {}
+
+impl<'m, K, V> FusedIterator for Values<'m, K, V> {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::{xorshift, SEED};
+    use crate::String;
+    use std::collections::BTreeMap;
+    use std::hash::Hasher;
+
+    /// A key whose hash only has a few values, so entries collide
+    #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+    struct Colliding(u32);
+
+    impl Hash for Colliding {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            state.write_u32(self.0 % 5);
+        }
+    }
+
+    /// Counts the branches, so tests can check that removals collapse them.
+    fn branches<K, V>(branch: &Branch<K, V>) -> usize {
+        let children = branch.slots.iter().map(|slot| match slot {
+            Slot::Branch(child) => branches(child),
+            _ => 0,
+        });
+        1 + children.sum::<usize>()
+    }
+
+    /// Applies pseudo-random updates to a `HashMap` and to a `BTreeMap`, keeping every version
+    /// of both to check that updates never change older versions.
+    fn check_against_model<K>(key: impl Fn(u32) -> K)
+    where
+        K: Clone + Hash + Ord + fmt::Debug,
+    {
+        let mut versions = vec![(HashMap::new(), BTreeMap::new())];
+        let mut state = SEED;
+        for step in 0..3000u32 {
+            xorshift(&mut state);
+            let (map, model) = &versions[state as usize % versions.len()];
+            let k = key((state >> 8) % 100);
+            let mut model = model.clone();
+            let map = match state % 3 {
+                0 => {
+                    model.remove(&k);
+                    map.remove(&k)
+                }
+                _ => {
+                    model.insert(k.clone(), step);
+                    map.insert(k, step)
+                }
+            };
+            assert_eq!(map.len(), model.len());
+            versions.push((map, model));
+        }
+        for (map, model) in versions {
+            let mut items: Vec<_> = map.iter().collect();
+            items.sort();
+            assert!(items.into_iter().eq(model.iter()));
+            for (k, v) in &model {
+                assert_eq!(map.get(k), Some(v));
+            }
+        }
+    }
+
+    #[test]
+    fn matches_model() {
+        check_against_model(|k| k);
+        check_against_model(Colliding);
+        check_against_model(|k| String::from(k.to_string()));
+    }
+
+    #[test]
+    fn removals_collapse_branches() {
+        let full: HashMap<u32, u32> = (0..1000).map(|i| (i, i)).collect();
+        let mut map = full.clone();
+        for i in 0..999 {
+            map = map.remove(&i);
+        }
+        assert_eq!(map.iter().collect::<Vec<_>>(), [(&999, &999)]);
+        assert_eq!(branches(&map.root), 1);
+        assert_eq!(full.len(), 1000);
+        assert_eq!(full, (0..1000).map(|i| (i, i)).collect());
+    }
+
+    #[test]
+    fn borrowed_lookups() {
+        let m: HashMap<String, i32> = HashMap::new().insert(String::from("one"), 1);
+        assert_eq!(m["one"], 1);
+        assert!(m.remove("one").is_empty());
+    }
+}
@@ -0,0 +1,42 @@
+//! Immutable collections with cheap updates: [`Vector`], [`HashMap`] and [`OrderedMap`]
+//!
+//! Like [`String`][crate::String] and [`Rope`][crate::Rope], these are values: cloning is
+//! `O(1)`, and instead of changing a collection in place, updates take `O(log n)` time and
+//! return a new version that shares most of its structure with the old one.  Keeping every
+//! version around, for undo or to compare before and after, costs little more than keeping the
+//! latest.
+//!
+//! | Type             | Like          | Order          | Lookup by |
+//! |------------------|---------------|----------------|-----------|
+//! | [`Vector`]       | `Vec`         | by position    | index     |
+//! | [`HashMap`]      | `HashMap`     | arbitrary      | [`Hash`]  |
+//! | [`OrderedMap`]   | `BTreeMap`    | sorted by key  | [`Ord`]   |
+//!
+//! When a collection is only ever updated by its single owner, the copy-on-write
+//! [`List`][crate::List] and [`Dict`][crate::Dict] are faster.
+//!
+//! # Examples
+//!
+//! ```
+//! use ergo_core::persistent::HashMap;
+//!
+//! let mut history = vec![HashMap::new()];
+//! for (account, balance) in [("alice", 10), ("bob", 5), ("alice", 7)] {
+//!     let latest = history.last().unwrap().insert(account, balance);
+//!     history.push(latest);
+//! }
+//!
+//! assert_eq!(history[2]["alice"], 10);
+//! assert_eq!(history[3]["alice"], 7);
+//! ```
+//!
+//! [`Hash`]: std::hash::Hash
+
+pub mod hash_map;
+pub mod ordered_map;
+mod tree;
+pub mod vector;
+
+pub use hash_map::HashMap;
+pub use ordered_map::OrderedMap;
+pub use vector::Vector;
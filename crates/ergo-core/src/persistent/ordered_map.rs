@@ -0,0 +1,569 @@
+//! A persistent sorted map: [`OrderedMap`]
+
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::iter::{FromIterator, FusedIterator};
+use std::ops::{self, Bound, RangeBounds};
+use std::sync::Arc;
+
+use super::tree::{self, Tree};
+
+/// An immutable map sorted by key, with cheap updates
+///
+/// An `OrderedMap` is a balanced tree, like a [`BTreeMap`][std::collections::BTreeMap] that is
+/// never modified in place.  Inserting and removing take `O(log n)` time and return a new map
+/// that shares everything but the path to the change with the original, so old versions can be
+/// kept around as cheap snapshots.  Cloning is `O(1)`.
+///
+/// Unlike [`OrderedDict`][crate::collections::OrderedDict], which remembers insertion order,
+/// entries are always in key order.
+///
+/// # Examples
+///
+/// ```
+/// use ergo_core::persistent::OrderedMap;
+///
+/// let schedule = OrderedMap::from([(9, "standup"), (14, "review")]);
+/// let busier = schedule.insert(11, "pairing");
+///
+/// assert_eq!(busier.values().collect::<Vec<_>>(), [&"standup", &"pairing", &"review"]);
+/// assert_eq!(busier.range(10..).next(), Some((&11, &"pairing")));
+/// assert_eq!(schedule.len(), 2);
+/// ```
+pub struct OrderedMap<K, V> {
+    root: Tree<(K, V)>,
+}
+
+impl<K, V> OrderedMap<K, V> {
+    /// Creates a new empty `OrderedMap`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let m = ergo_core::persistent::OrderedMap::<i32, i32>::new();
+    /// assert!(m.is_empty());
+    /// ```
+    #[inline]
+    pub const fn new() -> Self {
+        Self { root: None }
+    }
+
+    /// Returns the number of entries.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let m = ergo_core::persistent::OrderedMap::from([("a", 1), ("b", 2)]);
+    /// assert_eq!(m.len(), 2);
+    /// ```
+    #[inline]
+    pub fn len(&self) -> usize {
+        tree::len(&self.root)
+    }
+
+    /// Returns `true` if there are no entries.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Returns the entry with the smallest key, or [`None`] if the map is empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let m = ergo_core::persistent::OrderedMap::from([("b", 2), ("a", 1)]);
+    /// assert_eq!(m.first(), Some((&"a", &1)));
+    /// ```
+    #[inline]
+    pub fn first(&self) -> Option<(&K, &V)> {
+        self.iter().next()
+    }
+
+    /// Returns the entry with the largest key, or [`None`] if the map is empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let m = ergo_core::persistent::OrderedMap::from([("b", 2), ("a", 1)]);
+    /// assert_eq!(m.last(), Some((&"b", &2)));
+    /// ```
+    #[inline]
+    pub fn last(&self) -> Option<(&K, &V)> {
+        self.iter().next_back()
+    }
+
+    /// Iterates over the keys and values, sorted by key.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let m = ergo_core::persistent::OrderedMap::from([("b", 2), ("a", 1)]);
+    ///
+    /// let items: Vec<_> = m.iter().collect();
+    /// assert_eq!(items, [(&"a", &1), (&"b", &2)]);
+    /// ```
+    #[inline]
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter(tree::Iter::new(&self.root, 0, self.len()))
+    }
+
+    /// Iterates over the keys, in order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let m = ergo_core::persistent::OrderedMap::from([("b", 2), ("a", 1)]);
+    ///
+    /// let keys: Vec<_> = m.keys().collect();
+    /// assert_eq!(keys, [&"a", &"b"]);
+    /// ```
+    #[inline]
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys(self.iter())
+    }
+
+    /// Iterates over the values, sorted by key.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let m = ergo_core::persistent::OrderedMap::from([("b", 2), ("a", 1)]);
+    ///
+    /// let values: Vec<_> = m.values().collect();
+    /// assert_eq!(values, [&1, &2]);
+    /// ```
+    #[inline]
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values(self.iter())
+    }
+
+    /// Returns `true` if both maps are the same version, so neither was derived from the other
+    /// by an update.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let m = ergo_core::persistent::OrderedMap::from([("a", 1)]);
+    /// let copy = m.clone();
+    /// let bigger = m.insert("b", 2);
+    ///
+    /// assert!(m.is_shared_with(&copy));
+    /// assert!(!m.is_shared_with(&bigger));
+    /// ```
+    #[inline]
+    pub fn is_shared_with(&self, other: &Self) -> bool {
+        match (&self.root, &other.root) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (a, b) => a.is_none() && b.is_none(),
+        }
+    }
+}
+
+impl<K: Ord, V> OrderedMap<K, V> {
+    /// Returns the position of `key`, or where it would be inserted.
+    fn search<Q>(&self, key: &Q) -> Result<usize, usize>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        tree::search(&self.root, |(k, _)| k.borrow().cmp(key))
+    }
+
+    /// Returns the value for `key`, or [`None`] if it is missing.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let m = ergo_core::persistent::OrderedMap::from([("a", 1)]);
+    ///
+    /// assert_eq!(m.get("a"), Some(&1));
+    /// assert_eq!(m.get("b"), None);
+    /// ```
+    #[inline]
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let index = self.search(key).ok()?;
+        tree::get(&self.root, index).map(|(_, value)| value)
+    }
+
+    /// Returns `true` if there is an entry for `key`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let m = ergo_core::persistent::OrderedMap::from([("a", 1)]);
+    ///
+    /// assert!(m.contains_key("a"));
+    /// assert!(!m.contains_key("b"));
+    /// ```
+    #[inline]
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.search(key).is_ok()
+    }
+
+    /// Iterates over the entries whose keys are in `range`, in order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let m: ergo_core::persistent::OrderedMap<_, _> = (1..=5).map(|i| (i, i * 10)).collect();
+    ///
+    /// let values: Vec<_> = m.range(2..4).map(|(_, v)| *v).collect();
+    /// assert_eq!(values, [20, 30]);
+    /// ```
+    pub fn range<Q>(&self, range: impl RangeBounds<Q>) -> Iter<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let start = match range.start_bound() {
+            Bound::Included(key) => self.search(key).unwrap_or_else(|index| index),
+            Bound::Excluded(key) => self.search(key).map_or_else(|index| index, |i| i + 1),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(key) => self.search(key).map_or_else(|index| index, |i| i + 1),
+            Bound::Excluded(key) => self.search(key).unwrap_or_else(|index| index),
+            Bound::Unbounded => self.len(),
+        };
+        Iter(tree::Iter::new(&self.root, start, end))
+    }
+}
+
+impl<K: Clone + Ord, V: Clone> OrderedMap<K, V> {
+    /// Returns a map with the value for `key` set to `value`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let m = ergo_core::persistent::OrderedMap::new().insert("a", 1);
+    /// let m2 = m.insert("a", 2);
+    ///
+    /// assert_eq!(m["a"], 1);
+    /// assert_eq!(m2["a"], 2);
+    /// ```
+    #[must_use = "this returns the updated map as a new map, \
+                  without modifying the original"]
+    pub fn insert(&self, key: K, value: V) -> Self {
+        let root = match self.search(&key) {
+            Ok(index) => tree::set(&self.root, index, (key, value)),
+            Err(index) => tree::insert(&self.root, index, (key, value)),
+        };
+        Self { root }
+    }
+
+    /// Returns a map without the entry for `key`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let m = ergo_core::persistent::OrderedMap::from([("a", 1), ("b", 2)]);
+    ///
+    /// assert_eq!(m.remove("a").len(), 1);
+    /// assert_eq!(m.remove("z").len(), 2);
+    /// ```
+    #[must_use = "this returns the updated map as a new map, \
+                  without modifying the original"]
+    pub fn remove<Q>(&self, key: &Q) -> Self
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        match self.search(key) {
+            Ok(index) => Self {
+                root: tree::remove(&self.root, index).0,
+            },
+            Err(_) => self.clone(),
+        }
+    }
+}
+
+impl<K, V> Clone for OrderedMap<K, V> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            root: self.root.clone(),
+        }
+    }
+}
+
+impl<K, V> Default for OrderedMap<K, V> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for OrderedMap<K, V> {
+    /// Sorts the entries and builds a balanced map in one go.  Like
+    /// [`BTreeMap`][std::collections::BTreeMap], the last value for a repeated key wins.
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut entries: Vec<(K, V)> = iter.into_iter().collect();
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+        // Reversing keeps the last of each run of equal keys.
+        entries.reverse();
+        entries.dedup_by(|(a, _), (b, _)| a == b);
+        entries.reverse();
+        let len = entries.len();
+        Self {
+            root: tree::build(&mut entries.into_iter(), len),
+        }
+    }
+}
+
+impl<K: Ord, V, const N: usize> From<[(K, V); N]> for OrderedMap<K, V> {
+    #[inline]
+    fn from(other: [(K, V); N]) -> Self {
+        Vec::from(other).into_iter().collect()
+    }
+}
+
+impl<'m, K, V> IntoIterator for &'m OrderedMap<K, V> {
+    type Item = (&'m K, &'m V);
+    type IntoIter = Iter<'m, K, V>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K, V, Q> ops::Index<&Q> for OrderedMap<K, V>
+where
+    K: Ord + Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    type Output = V;
+
+    /// Returns the value for `key`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no entry for `key`.
+    #[inline]
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("key not found in `OrderedMap`")
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for OrderedMap<K, V> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: PartialEq, V: PartialEq> PartialEq for OrderedMap<K, V> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other)
+    }
+}
+
+impl<K: Eq, V: Eq> Eq for OrderedMap<K, V> {}
+
+impl<K: PartialOrd, V: PartialOrd> PartialOrd for OrderedMap<K, V> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other)
+    }
+}
+
+impl<K: Ord, V: Ord> Ord for OrderedMap<K, V> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other)
+    }
+}
+
+impl<K: Hash, V: Hash> Hash for OrderedMap<K, V> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len());
+        for item in self {
+            item.hash(state);
+        }
+    }
+}
+
+/// Iterates over an [`OrderedMap`]'s keys and values
+///
+/// Returned by [`OrderedMap::iter`] and [`OrderedMap::range`].
+#[derive(Clone, Debug)]
+pub struct Iter<'m, K, V>(tree::Iter<'m, (K, V)>);
+
+impl<'m, K, V> Iterator for Iter<'m, K, V> {
+    type Item = (&'m K, &'m V);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(key, value)| (key, value))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'m, K, V> DoubleEndedIterator for Iter<'m, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(key, value)| (key, value))
+    }
+}
+
+impl<'m, K, V> ExactSizeIterator for Iter<'m, K, V> {}
+
+impl<'m, K, V> FusedIterator for Iter<'m, K, V> {}
+
+/// Iterates over an [`OrderedMap`]'s keys
+///
+/// Returned by [`OrderedMap::keys`].
+#[derive(Clone, Debug)]
+pub struct Keys<'m, K, V>(Iter<'m, K, V>);
+
+impl<'m, K, V> Iterator for Keys<'m, K, V> {
+    type Item = &'m K;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(key, _)| key)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'m, K, V> DoubleEndedIterator for Keys<'m, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(key, _)| key)
+    }
+}
+
+impl<'m, K, V> ExactSizeIterator for Keys<'m, K, V> {}
+
+impl<'m, K, V> FusedIterator for Keys<'m, K, V> {}
+
+/// Iterates over an [`OrderedMap`]'s values
+///
+/// Returned by [`OrderedMap::values`].
+#[derive(Clone, Debug)]
+pub struct Values<'m, K, V>(Iter<'m, K, V>);
+
+impl<'m, K, V> Iterator for Values<'m, K, V> {
+    type Item = &'m V;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(_, value)| value)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'m, K, V> DoubleEndedIterator for Values<'m, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(_, value)| value)
+    }
+}
+
+impl<'m, K, V> ExactSizeIterator for Values<'m, K, V> {}
+
+impl<'m, K, V> FusedIterator for Values<'m, K, V> {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::{xorshift, SEED};
+    use std::collections::BTreeMap;
+
+    /// Applies pseudo-random updates to an `OrderedMap` and to a `BTreeMap`, keeping every
+    /// version of both to check that updates never change older versions.
+    #[test]
+    fn matches_model() {
+        let mut versions = vec![(OrderedMap::new(), BTreeMap::new())];
+        let mut state = SEED;
+        for step in 0..3000u32 {
+            xorshift(&mut state);
+            let (map, model) = &versions[state as usize % versions.len()];
+            let k = (state >> 8) % 100;
+            let mut model = model.clone();
+            let map = match state % 3 {
+                0 => {
+                    model.remove(&k);
+                    map.remove(&k)
+                }
+                _ => {
+                    model.insert(k, step);
+                    map.insert(k, step)
+                }
+            };
+            tree::check(&map.root);
+            versions.push((map, model));
+        }
+        for (map, model) in versions {
+            assert!(map.iter().eq(model.iter()));
+            let (low, high) = (30, 60);
+            assert!(map.range(low..high).eq(model.range(low..high)));
+            assert!(map
+                .range(low..=high)
+                .rev()
+                .eq(model.range(low..=high).rev()));
+            assert!(map
+                .range((Bound::Excluded(low), Bound::Unbounded))
+                .eq(model.range((Bound::Excluded(low), Bound::Unbounded))));
+        }
+    }
+
+    #[test]
+    fn collecting_keeps_last_value() {
+        let m: OrderedMap<_, _> = vec![(2, 'a'), (1, 'b'), (2, 'c'), (1, 'd')]
+            .into_iter()
+            .collect();
+        assert_eq!(m, OrderedMap::from([(1, 'd'), (2, 'c')]));
+    }
+}
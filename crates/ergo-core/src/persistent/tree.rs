@@ -0,0 +1,384 @@
+//! Immutable AVL trees that count their items, behind [`Vector`][super::Vector] and
+//! [`OrderedMap`][super::OrderedMap]
+//!
+//! Items are addressed by their in-order index, so every operation is `O(log n)`.  Updates
+//! rebuild only the path to the change and share the rest of the tree.
+
+use std::cmp::Ordering;
+use std::iter::FusedIterator;
+use std::sync::Arc;
+
+/// A node and everything below it
+#[derive(Debug)]
+pub(super) struct Node<T> {
+    pub(super) item: T,
+    pub(super) left: Tree<T>,
+    pub(super) right: Tree<T>,
+    len: usize,
+    height: u8,
+}
+
+pub(super) type Tree<T> = Option<Arc<Node<T>>>;
+
+pub(super) fn len<T>(tree: &Tree<T>) -> usize {
+    tree.as_ref().map_or(0, |node| node.len)
+}
+
+fn height<T>(tree: &Tree<T>) -> u8 {
+    tree.as_ref().map_or(0, |node| node.height)
+}
+
+/// Builds a node from subtrees that are already balanced with each other.
+fn node<T>(left: Tree<T>, item: T, right: Tree<T>) -> Tree<T> {
+    Some(Arc::new(Node {
+        len: len(&left) + 1 + len(&right),
+        height: height(&left).max(height(&right)) + 1,
+        item,
+        left,
+        right,
+    }))
+}
+
+/// Builds a node from subtrees whose heights differ by at most two, rotating to restore the
+/// balance.
+fn balance<T: Clone>(left: Tree<T>, item: T, right: Tree<T>) -> Tree<T> {
+    let (left_height, right_height) = (height(&left), height(&right));
+    if left_height > right_height + 1 {
+        let l = left.as_ref().expect("taller than its sibling");
+        if height(&l.left) >= height(&l.right) {
+            let right = node(l.right.clone(), item, right);
+            node(l.left.clone(), l.item.clone(), right)
+        } else {
+            let lr = l.right.as_ref().expect("taller than its sibling");
+            let left = node(l.left.clone(), l.item.clone(), lr.left.clone());
+            let right = node(lr.right.clone(), item, right);
+            node(left, lr.item.clone(), right)
+        }
+    } else if right_height > left_height + 1 {
+        let r = right.as_ref().expect("taller than its sibling");
+        if height(&r.right) >= height(&r.left) {
+            let left = node(left, item, r.left.clone());
+            node(left, r.item.clone(), r.right.clone())
+        } else {
+            let rl = r.left.as_ref().expect("taller than its sibling");
+            let left = node(left, item, rl.left.clone());
+            let right = node(rl.right.clone(), r.item.clone(), r.right.clone());
+            node(left, rl.item.clone(), right)
+        }
+    } else {
+        node(left, item, right)
+    }
+}
+
+/// Builds a balanced tree from the next `len` items.
+pub(super) fn build<T>(items: &mut impl Iterator<Item = T>, len: usize) -> Tree<T> {
+    if len == 0 {
+        return None;
+    }
+    let left = build(items, len / 2);
+    let item = items.next().expect("enough items");
+    let right = build(items, len - len / 2 - 1);
+    node(left, item, right)
+}
+
+/// Concatenates `left`, `item` and `right`, whatever their heights.
+pub(super) fn join<T: Clone>(left: Tree<T>, item: T, right: Tree<T>) -> Tree<T> {
+    let (left_height, right_height) = (height(&left), height(&right));
+    if left_height > right_height + 1 {
+        let l = left.as_ref().expect("taller than its sibling");
+        let right = join(l.right.clone(), item, right);
+        balance(l.left.clone(), l.item.clone(), right)
+    } else if right_height > left_height + 1 {
+        let r = right.as_ref().expect("taller than its sibling");
+        let left = join(left, item, r.left.clone());
+        balance(left, r.item.clone(), r.right.clone())
+    } else {
+        node(left, item, right)
+    }
+}
+
+/// Concatenates `left` and `right`.
+pub(super) fn concat<T: Clone>(left: Tree<T>, right: Tree<T>) -> Tree<T> {
+    if right.is_none() {
+        return left;
+    }
+    let (right, first) = remove(&right, 0);
+    join(left, first, right)
+}
+
+/// Splits `tree` into its first `index` items and the rest.
+pub(super) fn split<T: Clone>(tree: &Tree<T>, index: usize) -> (Tree<T>, Tree<T>) {
+    match tree {
+        None => (None, None),
+        Some(n) => {
+            let left_len = len(&n.left);
+            if index <= left_len {
+                let (before, after) = split(&n.left, index);
+                (before, join(after, n.item.clone(), n.right.clone()))
+            } else {
+                let (before, after) = split(&n.right, index - left_len - 1);
+                (join(n.left.clone(), n.item.clone(), before), after)
+            }
+        }
+    }
+}
+
+/// Returns the item at `index`.
+pub(super) fn get<T>(mut tree: &Tree<T>, mut index: usize) -> Option<&T> {
+    while let Some(n) = tree {
+        let left_len = len(&n.left);
+        match index.cmp(&left_len) {
+            Ordering::Less => tree = &n.left,
+            Ordering::Equal => return Some(&n.item),
+            Ordering::Greater => {
+                index -= left_len + 1;
+                tree = &n.right;
+            }
+        }
+    }
+    None
+}
+
+/// Returns a tree with the item at `index` replaced by `item`.
+///
+/// `index` must be in bounds.
+pub(super) fn set<T: Clone>(tree: &Tree<T>, index: usize, item: T) -> Tree<T> {
+    let n = tree.as_ref().expect("index in bounds");
+    let left_len = len(&n.left);
+    match index.cmp(&left_len) {
+        Ordering::Less => node(set(&n.left, index, item), n.item.clone(), n.right.clone()),
+        Ordering::Equal => node(n.left.clone(), item, n.right.clone()),
+        Ordering::Greater => {
+            let right = set(&n.right, index - left_len - 1, item);
+            node(n.left.clone(), n.item.clone(), right)
+        }
+    }
+}
+
+/// Returns a tree with `item` inserted at `index`, which must be at most the length.
+pub(super) fn insert<T: Clone>(tree: &Tree<T>, index: usize, item: T) -> Tree<T> {
+    match tree {
+        None => node(None, item, None),
+        Some(n) => {
+            let left_len = len(&n.left);
+            if index <= left_len {
+                let left = insert(&n.left, index, item);
+                balance(left, n.item.clone(), n.right.clone())
+            } else {
+                let right = insert(&n.right, index - left_len - 1, item);
+                balance(n.left.clone(), n.item.clone(), right)
+            }
+        }
+    }
+}
+
+/// Returns a tree without the item at `index`, which must be in bounds, and that item.
+pub(super) fn remove<T: Clone>(tree: &Tree<T>, index: usize) -> (Tree<T>, T) {
+    let n = tree.as_ref().expect("index in bounds");
+    let left_len = len(&n.left);
+    match index.cmp(&left_len) {
+        Ordering::Less => {
+            let (left, item) = remove(&n.left, index);
+            (balance(left, n.item.clone(), n.right.clone()), item)
+        }
+        Ordering::Equal => (concat(n.left.clone(), n.right.clone()), n.item.clone()),
+        Ordering::Greater => {
+            let (right, item) = remove(&n.right, index - left_len - 1);
+            (balance(n.left.clone(), n.item.clone(), right), item)
+        }
+    }
+}
+
+/// Binary searches a tree sorted by `cmp`, which compares an item with the target.
+///
+/// Returns `Ok` with the index of a matching item, or `Err` with the index where a matching
+/// item could be inserted.
+pub(super) fn search<T>(
+    mut tree: &Tree<T>,
+    mut cmp: impl FnMut(&T) -> Ordering,
+) -> Result<usize, usize> {
+    let mut offset = 0;
+    while let Some(n) = tree {
+        match cmp(&n.item) {
+            Ordering::Less => {
+                offset += len(&n.left) + 1;
+                tree = &n.right;
+            }
+            Ordering::Equal => return Ok(offset + len(&n.left)),
+            Ordering::Greater => tree = &n.left,
+        }
+    }
+    Err(offset)
+}
+
+/// Iterates over the items of a tree from index `start` up to `end`
+#[derive(Debug)]
+pub(super) struct Iter<'t, T> {
+    /// Nodes whose items and right subtrees are still to come from the front
+    front: Vec<&'t Node<T>>,
+    /// Nodes whose items and left subtrees are still to come from the back
+    back: Vec<&'t Node<T>>,
+    remaining: usize,
+}
+
+impl<'t, T> Iter<'t, T> {
+    pub(super) fn new(tree: &'t Tree<T>, start: usize, end: usize) -> Self {
+        let mut iter = Self {
+            front: Vec::new(),
+            back: Vec::new(),
+            remaining: end.saturating_sub(start),
+        };
+        if iter.remaining > 0 {
+            iter.seek_front(tree, start);
+            iter.seek_back(tree, end - 1);
+        }
+        iter
+    }
+
+    fn seek_front(&mut self, mut tree: &'t Tree<T>, mut index: usize) {
+        while let Some(n) = tree {
+            let left_len = len(&n.left);
+            if index <= left_len {
+                self.front.push(n);
+                if index == left_len {
+                    break;
+                }
+                tree = &n.left;
+            } else {
+                index -= left_len + 1;
+                tree = &n.right;
+            }
+        }
+    }
+
+    fn seek_back(&mut self, mut tree: &'t Tree<T>, mut index: usize) {
+        while let Some(n) = tree {
+            let left_len = len(&n.left);
+            if index >= left_len {
+                self.back.push(n);
+                if index == left_len {
+                    break;
+                }
+                index -= left_len + 1;
+                tree = &n.right;
+            } else {
+                tree = &n.left;
+            }
+        }
+    }
+}
+
+impl<'t, T> Clone for Iter<'t, T> {
+    fn clone(&self) -> Self {
+        Self {
+            front: self.front.clone(),
+            back: self.back.clone(),
+            remaining: self.remaining,
+        }
+    }
+}
+
+impl<'t, T> Iterator for Iter<'t, T> {
+    type Item = &'t T;
+
+    fn next(&mut self) -> Option<&'t T> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let n = self.front.pop().expect("counted");
+        let mut tree = &n.right;
+        while let Some(next) = tree {
+            self.front.push(next);
+            tree = &next.left;
+        }
+        Some(&n.item)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'t, T> DoubleEndedIterator for Iter<'t, T> {
+    fn next_back(&mut self) -> Option<&'t T> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let n = self.back.pop().expect("counted");
+        let mut tree = &n.left;
+        while let Some(next) = tree {
+            self.back.push(next);
+            tree = &next.right;
+        }
+        Some(&n.item)
+    }
+}
+
+impl<'t, T> ExactSizeIterator for Iter<'t, T> {}
+
+impl<'t, T> FusedIterator for Iter<'t, T> {}
+
+#[cfg(test)]
+pub(super) fn check<T>(tree: &Tree<T>) {
+    if let Some(n) = tree {
+        check(&n.left);
+        check(&n.right);
+        assert_eq!(n.len, len(&n.left) + 1 + len(&n.right));
+        assert_eq!(n.height, height(&n.left).max(height(&n.right)) + 1);
+        assert!((height(&n.left) as i8 - height(&n.right) as i8).abs() <= 1);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn items(tree: &Tree<u32>) -> Vec<u32> {
+        Iter::new(tree, 0, len(tree)).copied().collect()
+    }
+
+    #[test]
+    fn split_and_join_stay_balanced() {
+        for total in 0..40u32 {
+            let tree = build(&mut (0..total), total as usize);
+            check(&tree);
+            for index in 0..=total as usize {
+                let (before, after) = split(&tree, index);
+                check(&before);
+                check(&after);
+                assert_eq!(items(&before), (0..index as u32).collect::<Vec<_>>());
+                assert_eq!(items(&after), (index as u32..total).collect::<Vec<_>>());
+
+                let joined = concat(after, before);
+                check(&joined);
+                let mut expected: Vec<u32> = (index as u32..total).collect();
+                expected.extend(0..index as u32);
+                assert_eq!(items(&joined), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn iterates_ranges_from_both_ends() {
+        let tree = build(&mut (0..50u32), 50);
+        for start in 0..=50 {
+            for end in start..=50 {
+                let iter = Iter::new(&tree, start, end);
+                assert_eq!(iter.len(), end - start);
+                assert!(iter.clone().copied().eq(start as u32..end as u32));
+                assert!(iter.rev().copied().eq((start as u32..end as u32).rev()));
+
+                let mut iter = Iter::new(&tree, start, end);
+                let mut expected = start as u32..end as u32;
+                while let Some(&front) = iter.next() {
+                    assert_eq!(Some(front), expected.next());
+                    assert_eq!(iter.next_back().copied(), expected.next_back());
+                }
+                assert_eq!(expected.next(), None);
+            }
+        }
+    }
+}
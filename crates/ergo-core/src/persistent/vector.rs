@@ -0,0 +1,571 @@
+//! A persistent sequence: [`Vector`]
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::iter::{FromIterator, FusedIterator};
+use std::ops::{self, Bound, RangeBounds};
+use std::sync::Arc;
+
+use super::tree::{self, Tree};
+
+/// An immutable sequence with cheap updates, like a `Vec` that is never modified in place
+///
+/// A `Vector` is a balanced tree.  Updating, inserting, removing, slicing and concatenating
+/// take `O(log n)` time and return a new vector that shares everything but the path to the
+/// change with the original, so old versions can be kept around as cheap snapshots.  Cloning is
+/// `O(1)`.
+///
+/// # Examples
+///
+/// ```
+/// use ergo_core::persistent::Vector;
+///
+/// let v1 = Vector::from(["plan", "build"]);
+/// let v2 = v1.push_back("ship");
+/// let v3 = v2.set(0, "replan");
+///
+/// assert_eq!(v1, ["plan", "build"]);
+/// assert_eq!(v2, ["plan", "build", "ship"]);
+/// assert_eq!(v3, ["replan", "build", "ship"]);
+/// ```
+pub struct Vector<T> {
+    root: Tree<T>,
+}
+
+impl<T> Vector<T> {
+    /// Creates a new empty `Vector`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let v = ergo_core::persistent::Vector::<i32>::new();
+    /// assert!(v.is_empty());
+    /// ```
+    #[inline]
+    pub const fn new() -> Self {
+        Self { root: None }
+    }
+
+    /// Returns the number of items.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let v = ergo_core::persistent::Vector::from([1, 2]);
+    /// assert_eq!(v.len(), 2);
+    /// ```
+    #[inline]
+    pub fn len(&self) -> usize {
+        tree::len(&self.root)
+    }
+
+    /// Returns `true` if there are no items.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Returns the item at `index`, or [`None`] if it is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let v = ergo_core::persistent::Vector::from(['a', 'b']);
+    ///
+    /// assert_eq!(v.get(1), Some(&'b'));
+    /// assert_eq!(v.get(2), None);
+    /// ```
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<&T> {
+        tree::get(&self.root, index)
+    }
+
+    /// Returns the first item, or [`None`] if the vector is empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let v = ergo_core::persistent::Vector::from(['a', 'b']);
+    /// assert_eq!(v.first(), Some(&'a'));
+    /// ```
+    #[inline]
+    pub fn first(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    /// Returns the last item, or [`None`] if the vector is empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let v = ergo_core::persistent::Vector::from(['a', 'b']);
+    /// assert_eq!(v.last(), Some(&'b'));
+    /// ```
+    #[inline]
+    pub fn last(&self) -> Option<&T> {
+        self.get(self.len().wrapping_sub(1))
+    }
+
+    /// Returns an iterator over the items.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let v = ergo_core::persistent::Vector::from([1, 2, 3]);
+    /// assert_eq!(v.iter().sum::<i32>(), 6);
+    /// ```
+    #[inline]
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter(tree::Iter::new(&self.root, 0, self.len()))
+    }
+
+    /// Returns `true` if both vectors are the same version, so neither was derived from the
+    /// other by an update.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let v = ergo_core::persistent::Vector::from([1]);
+    /// let copy = v.clone();
+    /// let longer = v.push_back(2);
+    ///
+    /// assert!(v.is_shared_with(&copy));
+    /// assert!(!v.is_shared_with(&longer));
+    /// ```
+    #[inline]
+    pub fn is_shared_with(&self, other: &Self) -> bool {
+        match (&self.root, &other.root) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (a, b) => a.is_none() && b.is_none(),
+        }
+    }
+
+    fn expect_index(&self, index: usize) {
+        let len = self.len();
+        assert!(
+            index < len,
+            "index out of bounds: the len is {} but the index is {}",
+            len,
+            index
+        );
+    }
+}
+
+impl<T: Clone> Vector<T> {
+    /// Returns a vector with `value` added to the back.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let v = ergo_core::persistent::Vector::from([1]);
+    /// assert_eq!(v.push_back(2), [1, 2]);
+    /// ```
+    #[must_use = "this returns the updated vector as a new vector, \
+                  without modifying the original"]
+    #[inline]
+    pub fn push_back(&self, value: T) -> Self {
+        self.insert(self.len(), value)
+    }
+
+    /// Returns a vector with `value` added to the front.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let v = ergo_core::persistent::Vector::from([2]);
+    /// assert_eq!(v.push_front(1), [1, 2]);
+    /// ```
+    #[must_use = "this returns the updated vector as a new vector, \
+                  without modifying the original"]
+    #[inline]
+    pub fn push_front(&self, value: T) -> Self {
+        self.insert(0, value)
+    }
+
+    /// Returns a vector with the item at `index` replaced by `value`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let v = ergo_core::persistent::Vector::from([1, 0, 3]);
+    /// assert_eq!(v.set(1, 2), [1, 2, 3]);
+    /// ```
+    #[must_use = "this returns the updated vector as a new vector, \
+                  without modifying the original"]
+    pub fn set(&self, index: usize, value: T) -> Self {
+        self.expect_index(index);
+        Self {
+            root: tree::set(&self.root, index, value),
+        }
+    }
+
+    /// Returns a vector with `value` inserted at `index`, before the item there.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the length.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let v = ergo_core::persistent::Vector::from([1, 3]);
+    /// assert_eq!(v.insert(1, 2), [1, 2, 3]);
+    /// ```
+    #[must_use = "this returns the updated vector as a new vector, \
+                  without modifying the original"]
+    pub fn insert(&self, index: usize, value: T) -> Self {
+        let len = self.len();
+        assert!(
+            index <= len,
+            "insertion index (is {}) should be <= len (is {})",
+            index,
+            len
+        );
+        Self {
+            root: tree::insert(&self.root, index, value),
+        }
+    }
+
+    /// Returns a vector without the item at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let v = ergo_core::persistent::Vector::from([1, 2, 3]);
+    /// assert_eq!(v.remove(1), [1, 3]);
+    /// ```
+    #[must_use = "this returns the updated vector as a new vector, \
+                  without modifying the original"]
+    pub fn remove(&self, index: usize) -> Self {
+        self.expect_index(index);
+        let (root, _) = tree::remove(&self.root, index);
+        Self { root }
+    }
+
+    /// Returns the items in `range` as a new vector.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let v = ergo_core::persistent::Vector::from([1, 2, 3, 4]);
+    ///
+    /// assert_eq!(v.slice(1..3), [2, 3]);
+    /// assert_eq!(v.slice(..), v);
+    /// ```
+    pub fn slice(&self, range: impl RangeBounds<usize>) -> Self {
+        let (start, end) = bounds(self.len(), range);
+        let (_, rest) = tree::split(&self.root, start);
+        let (middle, _) = tree::split(&rest, end - start);
+        Self { root: middle }
+    }
+
+    /// Returns a vector with the items of `other` after the items of this one.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let v = ergo_core::persistent::Vector::from([1, 2]);
+    /// assert_eq!(v.concat(&v), [1, 2, 1, 2]);
+    /// ```
+    #[must_use = "this returns the joined vector as a new vector, \
+                  without modifying the original"]
+    #[inline]
+    pub fn concat(&self, other: &Self) -> Self {
+        Self {
+            root: tree::concat(self.root.clone(), other.root.clone()),
+        }
+    }
+}
+
+/// Resolves `range` to `(start, end)` within `len`, panicking when it is out of bounds.
+fn bounds(len: usize, range: impl RangeBounds<usize>) -> (usize, usize) {
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => start.checked_add(1).expect("start in bounds"),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end.checked_add(1).expect("end in bounds"),
+        Bound::Excluded(&end) => end,
+        Bound::Unbounded => len,
+    };
+    assert!(
+        start <= end,
+        "slice index starts at {} but ends at {}",
+        start,
+        end
+    );
+    assert!(
+        end <= len,
+        "range end index {} out of range for vector of length {}",
+        end,
+        len
+    );
+    (start, end)
+}
+
+impl<T> Clone for Vector<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            root: self.root.clone(),
+        }
+    }
+}
+
+impl<T> Default for Vector<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> From<Vec<T>> for Vector<T> {
+    /// Builds a balanced vector in `O(n)` time.
+    #[inline]
+    fn from(other: Vec<T>) -> Self {
+        let len = other.len();
+        Self {
+            root: tree::build(&mut other.into_iter(), len),
+        }
+    }
+}
+
+impl<'s, T: Clone> From<&'s [T]> for Vector<T> {
+    #[inline]
+    fn from(other: &'s [T]) -> Self {
+        Self {
+            root: tree::build(&mut other.iter().cloned(), other.len()),
+        }
+    }
+}
+
+impl<T, const N: usize> From<[T; N]> for Vector<T> {
+    #[inline]
+    fn from(other: [T; N]) -> Self {
+        Self::from(Vec::from(other))
+    }
+}
+
+impl<T: Clone> From<&Vector<T>> for Vec<T> {
+    #[inline]
+    fn from(other: &Vector<T>) -> Self {
+        other.iter().cloned().collect()
+    }
+}
+
+impl<T> FromIterator<T> for Vector<T> {
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::from(iter.into_iter().collect::<Vec<T>>())
+    }
+}
+
+impl<'v, T> IntoIterator for &'v Vector<T> {
+    type Item = &'v T;
+    type IntoIter = Iter<'v, T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T> ops::Index<usize> for Vector<T> {
+    type Output = T;
+
+    #[inline]
+    fn index(&self, index: usize) -> &T {
+        self.expect_index(index);
+        self.get(index).expect("checked the index")
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Vector<T> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq<U>, U> PartialEq<Vector<U>> for Vector<T> {
+    #[inline]
+    fn eq(&self, other: &Vector<U>) -> bool {
+        self.len() == other.len() && self.iter().zip(other).all(|(a, b)| a == b)
+    }
+}
+
+impl<T: Eq> Eq for Vector<T> {}
+
+impl<T: PartialEq<U>, U> PartialEq<[U]> for Vector<T> {
+    #[inline]
+    fn eq(&self, other: &[U]) -> bool {
+        self.len() == other.len() && self.iter().zip(other).all(|(a, b)| a == b)
+    }
+}
+
+impl<T: PartialEq<U>, U> PartialEq<Vec<U>> for Vector<T> {
+    #[inline]
+    fn eq(&self, other: &Vec<U>) -> bool {
+        *self == other[..]
+    }
+}
+
+impl<T: PartialEq<U>, U, const N: usize> PartialEq<[U; N]> for Vector<T> {
+    #[inline]
+    fn eq(&self, other: &[U; N]) -> bool {
+        *self == other[..]
+    }
+}
+
+impl<T: PartialOrd> PartialOrd for Vector<T> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other)
+    }
+}
+
+impl<T: Ord> Ord for Vector<T> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other)
+    }
+}
+
+impl<T: Hash> Hash for Vector<T> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len());
+        for item in self {
+            item.hash(state);
+        }
+    }
+}
+
+/// Iterates over a [`Vector`]'s items
+///
+/// Returned by [`Vector::iter`].
+#[derive(Clone, Debug)]
+pub struct Iter<'v, T>(tree::Iter<'v, T>);
+
+impl<'v, T> Iterator for Iter<'v, T> {
+    type Item = &'v T;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'v, T> DoubleEndedIterator for Iter<'v, T> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back()
+    }
+}
+
+impl<'v, T> ExactSizeIterator for Iter<'v, T> {}
+
+impl<'v, T> FusedIterator for Iter<'v, T> {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::{xorshift, SEED};
+
+    /// Applies pseudo-random updates to a `Vector` and to a `Vec`, keeping every version of
+    /// both to check that updates never change older versions.
+    #[test]
+    fn matches_model() {
+        let mut versions = vec![(Vector::new(), Vec::new())];
+        let mut state = SEED;
+        for step in 0..3000u32 {
+            xorshift(&mut state);
+            let (vector, model): &(Vector<u32>, Vec<u32>) =
+                &versions[state as usize % versions.len()];
+            let at = (state >> 8) as usize % (model.len() + 1);
+            let (vector, model) = match state % 6 {
+                0 | 1 => {
+                    let mut model = model.clone();
+                    model.insert(at, step);
+                    (vector.insert(at, step), model)
+                }
+                2 if at < model.len() => {
+                    let mut model = model.clone();
+                    model.remove(at);
+                    (vector.remove(at), model)
+                }
+                3 if at < model.len() => {
+                    let mut model = model.clone();
+                    model[at] = step;
+                    (vector.set(at, step), model)
+                }
+                4 => {
+                    let end = at + (state >> 16) as usize % (model.len() - at + 1);
+                    (vector.slice(at..end), model[at..end].to_vec())
+                }
+                _ => {
+                    let (other, other_model) = &versions[step as usize % versions.len()];
+                    let mut model = model.clone();
+                    model.extend(other_model);
+                    (vector.concat(other), model)
+                }
+            };
+            tree::check(&vector.root);
+            assert_eq!(vector, model);
+            if model.len() < 200 {
+                versions.push((vector, model));
+            }
+        }
+        for (vector, model) in versions {
+            assert_eq!(vector, model);
+            assert!(vector.iter().rev().eq(model.iter().rev()));
+        }
+    }
+}
@@ -0,0 +1,614 @@
+//! Fitting ranges to a sequence's length instead of panicking
+//!
+//! Rust's indexing panics when a range runs past the end, while Python's slicing clamps it.
+//! Python also counts negative indices back from the end and supports a step, which walks the
+//! sequence backwards when negative:
+//!
+//! | Python       | Rust                       |
+//! |--------------|----------------------------|
+//! | `v[-1]`      | `v.py_get(-1)`             |
+//! | `v[-3:]`     | `v.py_get(-3..)`           |
+//! | `v[1:-1]`    | `v.py_get(1..-1)`          |
+//! | `v[::2]`     | `v.py_slice(.., 2)`        |
+//! | `v[::-1]`    | `v.py_slice(.., -1)`       |
+//!
+//! Clippy's `reversed_empty_ranges` lint doesn't know about negative indices, so literal ranges
+//! like `1..-1` need an `#[allow]`.
+//!
+//! # Examples
+//!
+//! ```
+//! use ergo_core::range::PySliceExt;
+//!
+//! let v = vec![0, 1, 2, 3, 4, 5];
+//!
+//! assert_eq!(v.py_get(-1), Some(&5));
+//! assert_eq!(v.py_get(-3..), Some(&[3, 4, 5][..]));
+//! assert_eq!(v.py_get(-10..10), Some(&v[..]));
+//! assert_eq!(v.py_slice(.., -2), [5, 3, 1]);
+//! ```
+
+use std::ops::{Bound, Range, RangeBounds, RangeInclusive};
+
+/// Clamps `range` to a sequence of `len` items, returning [`None`] when it selects nothing.
+///
+/// Unlike Python's slicing, indices are never negative; a start past the end selects nothing
+/// rather than panicking.
+///
+/// # Examples
+///
+/// ```
+/// use ergo_core::range::coerce_range;
+///
+/// assert_eq!(coerce_range(5, 3..10), Some(3..=4));
+/// assert_eq!(coerce_range(5, ..=2), Some(0..=2));
+/// assert_eq!(coerce_range(5, 7..), None);
+/// assert_eq!(coerce_range(0, ..), None);
+/// ```
+pub fn coerce_range(len: usize, range: impl RangeBounds<usize>) -> Option<RangeInclusive<usize>> {
+    if len == 0 {
+        return None;
+    }
+
+    let range_start = match range.start_bound() {
+        Bound::Included(s) => *s,
+        Bound::Excluded(s) => {
+            if *s == usize::MAX {
+                return None;
+            } else {
+                s + 1
+            }
+        }
+        Bound::Unbounded => 0,
+    };
+    let range_end = match range.end_bound() {
+        Bound::Included(s) => *s,
+        Bound::Excluded(s) => {
+            if *s == 0 {
+                return None;
+            } else {
+                s - 1
+            }
+        }
+        Bound::Unbounded => usize::MAX,
+    }
+    .min(len - 1);
+
+    if len <= range_start || range_end < range_start {
+        None
+    } else {
+        Some(range_start..=range_end)
+    }
+}
+
+/// Resolves a Python-style `index` into a sequence of `len` items, where `-1` is the last item.
+///
+/// # Examples
+///
+/// ```
+/// use ergo_core::range::py_index;
+///
+/// assert_eq!(py_index(5, 1), Some(1));
+/// assert_eq!(py_index(5, -1), Some(4));
+/// assert_eq!(py_index(5, 5), None);
+/// assert_eq!(py_index(5, -6), None);
+/// ```
+pub fn py_index(len: usize, index: isize) -> Option<usize> {
+    let index = resolve(len, index);
+    if 0 <= index && index < len as i128 {
+        Some(index as usize)
+    } else {
+        None
+    }
+}
+
+/// Resolves a Python-style `range` over a sequence of `len` items, like `v[start:stop]`.
+///
+/// Negative bounds count back from the end and bounds past either end are clamped, so this
+/// never fails; a range that selects nothing is empty.
+///
+/// # Examples
+///
+/// ```
+/// use ergo_core::range::py_range;
+///
+/// assert_eq!(py_range(5, -2..), 3..5);
+/// assert_eq!(py_range(5, 1..-1), 1..4);
+/// assert_eq!(py_range(5, ..=-2), 0..4);
+/// assert_eq!(py_range(5, -10..10), 0..5);
+/// assert!(py_range(5, 4..2).is_empty());
+/// ```
+pub fn py_range(len: usize, range: impl RangeBounds<isize>) -> Range<usize> {
+    let indices = py_slice(len, range, 1);
+    indices.next..indices.next + indices.remaining
+}
+
+/// Resolves a Python-style `range` and `step` over a sequence of `len` items, like
+/// `v[start:stop:step]`, iterating over the selected indices.
+///
+/// With a negative `step`, the range is walked backwards from its start, so the start should be
+/// the larger index; unbounded ends default to the last and first items respectively.  An
+/// inclusive end is included in whichever direction the step goes.
+///
+/// # Panics
+///
+/// Panics if `step` is zero.
+///
+/// # Examples
+///
+/// ```
+/// use ergo_core::range::py_slice;
+///
+/// assert!(py_slice(6, .., 2).eq([0, 2, 4]));
+/// assert!(py_slice(6, .., -1).eq([5, 4, 3, 2, 1, 0]));
+/// assert!(py_slice(6, -2..1, -1).eq([4, 3, 2]));
+/// assert!(py_slice(6, 4..=0, -2).eq([4, 2, 0]));
+/// ```
+pub fn py_slice(len: usize, range: impl RangeBounds<isize>, step: isize) -> Indices {
+    assert!(step != 0, "slice step cannot be zero");
+
+    // Positions between `lower` and `upper` are valid starts in the step's direction, with the
+    // stop one step past the last item
+    let (lower, upper) = if 0 < step {
+        (0, len as i128)
+    } else {
+        (-1, len as i128 - 1)
+    };
+    let (first, last) = if 0 < step {
+        (lower, upper)
+    } else {
+        (upper, lower)
+    };
+    let forward = step.signum() as i128;
+
+    let start = match range.start_bound() {
+        Bound::Included(&index) => resolve(len, index),
+        Bound::Excluded(&index) => resolve(len, index) + forward,
+        Bound::Unbounded => first,
+    }
+    .max(lower)
+    .min(upper);
+    let stop = match range.end_bound() {
+        Bound::Included(&index) => resolve(len, index) + forward,
+        Bound::Excluded(&index) => resolve(len, index),
+        Bound::Unbounded => last,
+    }
+    .max(lower)
+    .min(upper);
+
+    let distance = (stop - start) * forward;
+    if distance <= 0 {
+        return Indices {
+            next: 0,
+            remaining: 0,
+            step,
+        };
+    }
+    let stride = step.unsigned_abs() as i128;
+    Indices {
+        next: start as usize,
+        remaining: ((distance - 1) / stride + 1) as usize,
+        step,
+    }
+}
+
+fn resolve(len: usize, index: isize) -> i128 {
+    if index < 0 {
+        index as i128 + len as i128
+    } else {
+        index as i128
+    }
+}
+
+/// Iterates over the indices selected by [`py_slice`]
+#[derive(Clone, Debug)]
+pub struct Indices {
+    next: usize,
+    remaining: usize,
+    step: isize,
+}
+
+impl Indices {
+    fn nth_index(&self, n: usize) -> usize {
+        (self.next as isize + n as isize * self.step) as usize
+    }
+}
+
+impl Iterator for Indices {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<usize> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let index = self.next;
+        self.remaining -= 1;
+        if self.remaining != 0 {
+            self.next = self.nth_index(1);
+        }
+        Some(index)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl DoubleEndedIterator for Indices {
+    #[inline]
+    fn next_back(&mut self) -> Option<usize> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        Some(self.nth_index(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for Indices {}
+
+impl std::iter::FusedIterator for Indices {}
+
+/// A Python-style index or range for [`PySliceExt::py_get`]
+///
+/// Implemented for `isize`, selecting one item, and ranges of `isize`, selecting a subslice.
+pub trait PyIndex<T> {
+    type Output: ?Sized;
+
+    /// Returns the selected part of `items`, or [`None`] when an index is out of bounds.
+    fn get(self, items: &[T]) -> Option<&Self::Output>;
+}
+
+impl<T> PyIndex<T> for isize {
+    type Output = T;
+
+    #[inline]
+    fn get(self, items: &[T]) -> Option<&T> {
+        items.get(py_index(items.len(), self)?)
+    }
+}
+
+macro_rules! impl_py_index_range {
+    ($($range:ty),* $(,)?) => {
+        $(
+            impl<T> PyIndex<T> for $range {
+                type Output = [T];
+
+                #[inline]
+                fn get(self, items: &[T]) -> Option<&[T]> {
+                    items.get(py_range(items.len(), self))
+                }
+            }
+        )*
+    };
+}
+
+impl_py_index_range!(
+    std::ops::Range<isize>,
+    std::ops::RangeFrom<isize>,
+    std::ops::RangeFull,
+    std::ops::RangeInclusive<isize>,
+    std::ops::RangeTo<isize>,
+    std::ops::RangeToInclusive<isize>,
+    (Bound<isize>, Bound<isize>),
+);
+
+/// Python-style indexing for slices and `Vec`s
+///
+/// See the [module documentation][self] for how Python's indexing maps to these methods.
+pub trait PySliceExt<T> {
+    /// Returns an item or subslice, counting negative indices from the end.
+    ///
+    /// Ranges are clamped to the slice, like Python's slicing, so only an out-of-bounds index
+    /// returns [`None`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ergo_core::range::PySliceExt;
+    ///
+    /// let v = [10, 20, 30, 40];
+    ///
+    /// assert_eq!(v.py_get(-2), Some(&30));
+    /// assert_eq!(v.py_get(4), None);
+    /// assert_eq!(v.py_get(..-1), Some(&[10, 20, 30][..]));
+    /// assert_eq!(v.py_get(3..1), Some(&[][..]));
+    /// ```
+    fn py_get<I: PyIndex<T>>(&self, index: I) -> Option<&I::Output>;
+
+    /// Copies the items selected by `range` and `step`, like `v[start:stop:step]`.
+    ///
+    /// See [`py_slice`] for how the range and step are resolved.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `step` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ergo_core::range::PySliceExt;
+    ///
+    /// let v = vec!['a', 'b', 'c', 'd', 'e'];
+    ///
+    /// assert_eq!(v.py_slice(.., -1), ['e', 'd', 'c', 'b', 'a']);
+    /// assert_eq!(v.py_slice(1.., 2), ['b', 'd']);
+    /// assert_eq!(v.py_slice(-1..0, -2), ['e', 'c']);
+    /// ```
+    fn py_slice(&self, range: impl RangeBounds<isize>, step: isize) -> Vec<T>
+    where
+        T: Clone;
+}
+
+impl<T> PySliceExt<T> for [T] {
+    #[inline]
+    fn py_get<I: PyIndex<T>>(&self, index: I) -> Option<&I::Output> {
+        index.get(self)
+    }
+
+    fn py_slice(&self, range: impl RangeBounds<isize>, step: isize) -> Vec<T>
+    where
+        T: Clone,
+    {
+        py_slice(self.len(), range, step)
+            .map(|index| self[index].clone())
+            .collect()
+    }
+}
+
+impl<T> PySliceExt<T> for Vec<T> {
+    #[inline]
+    fn py_get<I: PyIndex<T>>(&self, index: I) -> Option<&I::Output> {
+        index.get(self.as_slice())
+    }
+
+    #[inline]
+    fn py_slice(&self, range: impl RangeBounds<isize>, step: isize) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.as_slice().py_slice(range, step)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const MAX_LEN: usize = 7;
+
+    fn bounds<T: Copy>(values: impl Iterator<Item = T> + Clone) -> Vec<Bound<T>> {
+        let mut bounds = vec![Bound::Unbounded];
+        bounds.extend(values.clone().map(Bound::Included));
+        bounds.extend(values.map(Bound::Excluded));
+        bounds
+    }
+
+    #[test]
+    fn coerce_range_matches_clamped_slicing() {
+        let bounds = bounds(0..MAX_LEN + 3);
+        for len in 0..=MAX_LEN {
+            for &start in &bounds {
+                for &end in &bounds {
+                    let first = match start {
+                        Bound::Included(s) => s,
+                        Bound::Excluded(s) => s + 1,
+                        Bound::Unbounded => 0,
+                    };
+                    let stop = match end {
+                        Bound::Included(e) => e + 1,
+                        Bound::Excluded(e) => e,
+                        Bound::Unbounded => len,
+                    }
+                    .min(len);
+                    let expected = if first < stop {
+                        Some(first..=stop - 1)
+                    } else {
+                        None
+                    };
+                    assert_eq!(
+                        coerce_range(len, (start, end)),
+                        expected,
+                        "len {} {:?}",
+                        len,
+                        (start, end)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn coerce_range_extremes() {
+        assert_eq!(
+            coerce_range(5, (Bound::Excluded(usize::MAX), Bound::Unbounded)),
+            None
+        );
+        assert_eq!(coerce_range(5, ..=usize::MAX), Some(0..=4));
+        assert_eq!(coerce_range(usize::MAX, ..), Some(0..=usize::MAX - 1));
+    }
+
+    #[test]
+    fn py_index_counts_from_both_ends() {
+        for len in 0..=MAX_LEN {
+            for index in -(MAX_LEN as isize) - 2..MAX_LEN as isize + 2 {
+                let expected =
+                    (0..len).find(|&i| i as isize == index || i as isize - len as isize == index);
+                assert_eq!(py_index(len, index), expected, "len {} [{}]", len, index);
+            }
+        }
+        assert_eq!(py_index(3, isize::MIN), None);
+        assert_eq!(py_index(3, isize::MAX), None);
+    }
+
+    /// Python's `v[start:stop:step]`, as the indices that `start` reaches going towards `stop`
+    fn python(len: usize, start: Option<isize>, stop: Option<isize>, step: isize) -> Vec<usize> {
+        let len = len as isize;
+        let resolve = |i: isize, lower: isize, upper: isize| {
+            if i < 0 { i + len } else { i }.max(lower).min(upper)
+        };
+        if 0 < step {
+            let start = start.map_or(0, |i| resolve(i, 0, len));
+            let stop = stop.map_or(len, |i| resolve(i, 0, len));
+            (0..len)
+                .filter(|&i| start <= i && i < stop && (i - start) % step == 0)
+                .map(|i| i as usize)
+                .collect()
+        } else {
+            let start = start.map_or(len - 1, |i| resolve(i, -1, len - 1));
+            let stop = stop.map_or(-1, |i| resolve(i, -1, len - 1));
+            (0..len)
+                .rev()
+                .filter(|&i| stop < i && i <= start && (start - i) % step == 0)
+                .map(|i| i as usize)
+                .collect()
+        }
+    }
+
+    fn indices(len: usize, range: (Bound<isize>, Bound<isize>), step: isize) -> Vec<usize> {
+        py_slice(len, range, step).collect()
+    }
+
+    #[test]
+    fn py_slice_matches_python() {
+        let limit = MAX_LEN as isize + 2;
+        let ends: Vec<Option<isize>> = std::iter::once(None)
+            .chain((-limit..=limit).map(Some))
+            .collect();
+        let bound = |end: Option<isize>| end.map_or(Bound::Unbounded, Bound::Included);
+        let excluded = |end: Option<isize>| end.map_or(Bound::Unbounded, Bound::Excluded);
+        for len in 0..=MAX_LEN {
+            for &start in &ends {
+                for &stop in &ends {
+                    for &step in &[-4, -3, -2, -1, 1, 2, 3, 4] {
+                        assert_eq!(
+                            indices(len, (bound(start), excluded(stop)), step),
+                            python(len, start, stop, step),
+                            "len {} [{:?}:{:?}:{}]",
+                            len,
+                            start,
+                            stop,
+                            step
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn py_slice_inclusive_ends() {
+        let limit = MAX_LEN as isize + 2;
+        for len in 0..=MAX_LEN {
+            for start in -limit..=limit {
+                for end in -limit..=limit {
+                    for &step in &[-3, -2, -1, 1, 2, 3] {
+                        let start = Bound::Included(start);
+                        let through = resolve(len, end);
+                        let expected: Vec<_> = indices(len, (start, Bound::Unbounded), step)
+                            .into_iter()
+                            .take_while(|&i| {
+                                if 0 < step {
+                                    i as i128 <= through
+                                } else {
+                                    through <= i as i128
+                                }
+                            })
+                            .collect();
+                        assert_eq!(
+                            indices(len, (start, Bound::Included(end)), step),
+                            expected,
+                            "len {} {:?}..={} step {}",
+                            len,
+                            start,
+                            end,
+                            step
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[allow(clippy::reversed_empty_ranges)]
+    fn py_slice_examples_from_python() {
+        let v: Vec<usize> = (0..6).collect();
+        assert_eq!(v.py_slice(-3.., 1), [3, 4, 5]);
+        assert_eq!(v.py_slice(.., -1), [5, 4, 3, 2, 1, 0]);
+        assert_eq!(v.py_slice(-2..1, -1), [4, 3, 2]);
+        assert_eq!(v.py_slice(1..-1, 2), [1, 3]);
+        assert_eq!(v.py_slice(10..-10, -2), [5, 3, 1]);
+        assert_eq!(v.py_slice(-10..10, 3), [0, 3]);
+        assert_eq!(v.py_slice(4.., -2), [4, 2, 0]);
+    }
+
+    #[test]
+    fn py_slice_extreme_steps() {
+        assert_eq!(
+            indices(5, (Bound::Unbounded, Bound::Unbounded), isize::MAX),
+            [0]
+        );
+        assert_eq!(
+            indices(5, (Bound::Unbounded, Bound::Unbounded), isize::MIN),
+            [4]
+        );
+        assert_eq!(
+            indices(
+                5,
+                (Bound::Included(isize::MIN), Bound::Included(isize::MAX)),
+                1
+            ),
+            [0, 1, 2, 3, 4]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "slice step cannot be zero")]
+    fn py_slice_zero_step() {
+        py_slice(5, .., 0);
+    }
+
+    #[test]
+    fn indices_from_both_ends() {
+        let mut indices = py_slice(10, 1.., 3);
+        assert_eq!(indices.len(), 3);
+        assert_eq!(indices.next_back(), Some(7));
+        assert_eq!(indices.next(), Some(1));
+        assert_eq!(indices.len(), 1);
+        assert_eq!(indices.next_back(), Some(4));
+        assert_eq!(indices.next(), None);
+        assert_eq!(indices.next_back(), None);
+
+        let reversed: Vec<_> = py_slice(10, .., -4).rev().collect();
+        assert_eq!(reversed, [1, 5, 9]);
+    }
+
+    #[test]
+    fn py_get_ranges_never_fail() {
+        let v = [1, 2, 3];
+        for start in -5..5 {
+            for end in -5..5 {
+                let range = py_range(v.len(), start..end);
+                assert_eq!(v.py_get(start..end), Some(&v[range.clone()]));
+                assert_eq!(
+                    v.py_get(start..),
+                    v.py_get((Bound::Included(start), Bound::Unbounded))
+                );
+                assert_eq!(
+                    v.py_get(..end),
+                    v.py_get((Bound::Unbounded, Bound::Excluded(end)))
+                );
+                assert_eq!(
+                    v.py_get(start..=end),
+                    Some(&v[py_range(v.len(), start..=end)])
+                );
+                assert_eq!(v.py_get(..=end), v.py_get(0..=end));
+            }
+        }
+        assert_eq!(v.py_get(..), Some(&v[..]));
+        assert_eq!(Vec::<u8>::new().py_get(-1..), Some(&[][..]));
+    }
+}
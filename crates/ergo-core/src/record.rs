@@ -0,0 +1,294 @@
+/// Declares a struct with the conveniences of Python's `namedtuple` and `dataclass`
+///
+/// Besides the struct itself, `record!` generates:
+///
+/// | Python                  | Rust                                 |
+/// |-------------------------|--------------------------------------|
+/// | `Point(1, 2)`           | `Point::new(1, 2)`                   |
+/// | `Point(x=1, y=2)`       | `Point { x: 1, y: 2 }`               |
+/// | `Point(*pair)`          | `Point::from(pair)`                  |
+/// | `tuple(p)`              | `<(i64, i64)>::from(p)`              |
+/// | `p._replace(x=3)`       | `p.replace(\|p\| p.x = 3)`           |
+/// | `p._asdict()`           | `p.as_dict()`                        |
+/// | `Point._fields`         | `Point::fields()`                    |
+/// | `repr(p)`, `str(p)`     | `format!("{:?}", p)`, `p.to_string()`, both `Point(x=1, y=2)` |
+///
+/// Records derive [`Clone`], [`PartialEq`] and [`PartialOrd`], comparing field by field in
+/// declaration order like tuples.  Add `#[derive(Eq, Ord, Hash)]` when every field supports
+/// them, to sort records or use them as [`Dict`][crate::Dict] keys; these are not derived by
+/// default so records can hold `f64`s.  Every field must implement [`Debug`][std::fmt::Debug].
+///
+/// # Examples
+///
+/// ```
+/// ergo_core::record! {
+///     /// A point on the plane
+///     pub struct Point {
+///         pub x: f64,
+///         pub y: f64,
+///     }
+/// }
+///
+/// let p = Point::new(1.0, 2.0);
+/// let q = p.replace(|q| q.y = 5.0);
+///
+/// assert_eq!(q, Point { x: 1.0, y: 5.0 });
+/// assert!(p < q);
+/// assert_eq!(q.to_string(), "Point(x=1.0, y=5.0)");
+/// assert_eq!(Point::fields(), ["x", "y"]);
+///
+/// let coords: ergo_core::Dict<ergo_core::String, f64> = q.as_dict();
+/// assert_eq!(coords["y"], 5.0);
+///
+/// // Fields of mixed types convert into a dynamic `Value`
+/// let row: ergo_core::Dict<ergo_core::String, ergo_core::Value> = q.as_dict();
+/// assert_eq!(ergo_core::Value::from(row).to_string(), "{'x': 1.0, 'y': 5.0}");
+/// ```
+///
+/// Hashable records can be used as keys:
+///
+/// ```
+/// use ergo_core::{Dict, String};
+///
+/// ergo_core::record! {
+///     #[derive(Eq, Ord, Hash)]
+///     struct Shift {
+///         person: String,
+///         day: u8,
+///     }
+/// }
+///
+/// let mut hours = Dict::new();
+/// hours.insert(Shift::new(String::from("ada"), 1), 8);
+///
+/// assert_eq!(hours[&Shift::from((String::from("ada"), 1))], 8);
+/// ```
+///
+/// The generated methods are documented, so a `pub` record only needs docs on the struct and
+/// its fields to pass `#![deny(missing_docs)]`:
+///
+/// ```
+/// #![deny(missing_docs)]
+/// #![doc = "Geometry"]
+///
+/// ergo_core::record! {
+///     /// A point on the plane
+///     pub struct Point {
+///         /// The horizontal coordinate
+///         pub x: f64,
+///         /// The vertical coordinate
+///         pub y: f64,
+///     }
+/// }
+/// # fn main() {}
+/// ```
+#[macro_export]
+macro_rules! record {
+    (
+        $(#[$attr:meta])*
+        $vis:vis struct $name:ident {
+            $(
+                $(#[$field_attr:meta])*
+                $field_vis:vis $field:ident : $ty:ty
+            ),+ $(,)?
+        }
+    ) => {
+        $(#[$attr])*
+        #[derive(Clone, PartialEq, PartialOrd)]
+        $vis struct $name {
+            $(
+                $(#[$field_attr])*
+                $field_vis $field: $ty,
+            )*
+        }
+
+        #[allow(dead_code)]
+        impl $name {
+            #[doc = ::std::concat!(
+                "Creates a `", ::std::stringify!($name), "` from its fields, in order."
+            )]
+            #[allow(clippy::too_many_arguments)]
+            #[inline]
+            pub fn new($($field: $ty),*) -> Self {
+                Self { $($field),* }
+            }
+
+            #[doc = ::std::concat!(
+                "Returns the names of the fields of `", ::std::stringify!($name), "`, in order."
+            )]
+            pub fn fields() -> $crate::List<$crate::String> {
+                $crate::List::from(::std::vec![
+                    $($crate::String::from(::std::stringify!($field))),*
+                ])
+            }
+
+            #[doc = ::std::concat!(
+                "Returns a copy of this `", ::std::stringify!($name),
+                "`, with the changes made by `update`."
+            )]
+            #[must_use = "this returns the updated record as a new record, \
+                          without modifying the original"]
+            #[inline]
+            pub fn replace(&self, update: impl FnOnce(&mut Self)) -> Self {
+                let mut record = self.clone();
+                update(&mut record);
+                record
+            }
+
+            #[doc = ::std::concat!(
+                "Returns the fields of this `", ::std::stringify!($name),
+                "` as a `Dict` from field name to value, in order."
+            )]
+            #[doc = ""]
+            #[doc = "Use `Value` as `V` when the fields have different types."]
+            pub fn as_dict<V>(&self) -> $crate::Dict<$crate::String, V>
+            where
+                $($ty: ::std::convert::Into<V>,)*
+            {
+                ::std::vec![$((
+                    $crate::String::from(::std::stringify!($field)),
+                    ::std::convert::Into::<V>::into(::std::clone::Clone::clone(&self.$field)),
+                )),*]
+                .into_iter()
+                .collect()
+            }
+        }
+
+        impl ::std::convert::From<($($ty,)*)> for $name {
+            #[inline]
+            fn from(($($field,)*): ($($ty,)*)) -> Self {
+                Self { $($field),* }
+            }
+        }
+
+        impl ::std::convert::From<$name> for ($($ty,)*) {
+            #[inline]
+            fn from(record: $name) -> Self {
+                ($(record.$field,)*)
+            }
+        }
+
+        impl ::std::fmt::Debug for $name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                let fields: &[(&str, &dyn ::std::fmt::Debug)] =
+                    &[$((::std::stringify!($field), &self.$field)),*];
+                f.write_str(::std::stringify!($name))?;
+                f.write_str("(")?;
+                for (i, (name, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    ::std::write!(f, "{}={:?}", name, value)?;
+                }
+                f.write_str(")")
+            }
+        }
+
+        impl ::std::fmt::Display for $name {
+            /// Formats the record like [`Debug`][std::fmt::Debug], like Python's `str` of a
+            /// named tuple.
+            #[inline]
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                ::std::fmt::Debug::fmt(self, f)
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Dict, String};
+
+    crate::record! {
+        #[derive(Eq, Ord, Hash)]
+        struct Task {
+            priority: u8,
+            name: String,
+        }
+    }
+
+    crate::record! {
+        struct Single {
+            value: f64,
+        }
+    }
+
+    mod shadowed {
+        //! Macros of the same names as the ones `record!` uses from `std`
+
+        #![allow(unused_macros)]
+
+        macro_rules! vec {
+            ($($tt:tt)*) => {
+                compile_error!("record! used a local vec!")
+            };
+        }
+
+        macro_rules! write {
+            ($($tt:tt)*) => {
+                compile_error!("record! used a local write!")
+            };
+        }
+
+        macro_rules! stringify {
+            ($($tt:tt)*) => {
+                compile_error!("record! used a local stringify!")
+            };
+        }
+
+        crate::record! {
+            pub(super) struct Shadowed {
+                pub(super) value: i64,
+            }
+        }
+    }
+
+    #[test]
+    fn orders_like_tuples() {
+        let mut tasks = vec![
+            Task::new(2, String::from("lint")),
+            Task::new(1, String::from("test")),
+            Task::new(1, String::from("build")),
+        ];
+        tasks.sort();
+        let tuples: Vec<(u8, String)> = tasks.into_iter().map(Into::into).collect();
+        assert_eq!(
+            tuples,
+            [
+                (1, String::from("build")),
+                (1, String::from("test")),
+                (2, String::from("lint"))
+            ]
+        );
+    }
+
+    #[test]
+    fn formats_like_python() {
+        let task = Task::new(1, String::from("build"));
+        assert_eq!(format!("{:?}", task), "Task(priority=1, name='build')");
+        assert_eq!(Single::from((0.5,)).to_string(), "Single(value=0.5)");
+        assert_eq!(Single::fields(), ["value"]);
+    }
+
+    #[test]
+    fn as_dict_keeps_field_order() {
+        crate::record! {
+            struct Size {
+                width: u32,
+                height: u16,
+            }
+        }
+        let dict: Dict<String, u64> = Size::new(3, 4).as_dict();
+        let items: Vec<_> = dict.iter().map(|(k, v)| (k.as_str(), *v)).collect();
+        assert_eq!(items, [("width", 3), ("height", 4)]);
+    }
+
+    #[test]
+    fn ignores_local_macros() {
+        let record = shadowed::Shadowed::new(7);
+        assert_eq!(record.to_string(), "Shadowed(value=7)");
+        assert_eq!(shadowed::Shadowed::fields(), ["value"]);
+        let dict: Dict<String, i64> = record.as_dict();
+        assert_eq!(dict["value"], 7);
+    }
+}
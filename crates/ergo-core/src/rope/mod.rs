@@ -0,0 +1,733 @@
+//! Text for editing: [`Rope`]
+
+mod node;
+
+use std::fmt;
+use std::ops::{Bound, RangeBounds};
+
+use crate::String;
+use node::{Leaves, Tree};
+
+type StdString = std::string::String;
+
+/// An immutable string for editing large text
+///
+/// A `Rope` is a balanced tree of [`String`] chunks.  Inserting, removing and slicing take
+/// `O(log n)` time and return a new rope that shares everything but the path to the edit with
+/// the original, so keeping old versions around for undo is cheap.  Ropes built from a large
+/// `String` share its buffer.
+///
+/// The tree also counts [`char`]s and lines, so converting between byte, char and line offsets
+/// is `O(log n)` too.  Positions are byte offsets, like for `String`.
+///
+/// # Examples
+///
+/// ```
+/// use ergo_core::Rope;
+///
+/// let template = Rope::from("Hello, {name}!\nWelcome back.\n");
+///
+/// let start = template.find("{name}").unwrap();
+/// let greeting = template.remove(start..start + 6).insert(start, "Ferris");
+///
+/// assert_eq!(greeting, "Hello, Ferris!\nWelcome back.\n");
+/// assert_eq!(greeting.line(1).unwrap(), "Welcome back.\n");
+/// assert_eq!(template, "Hello, {name}!\nWelcome back.\n");
+/// ```
+#[derive(Clone, Default)]
+pub struct Rope {
+    root: Tree,
+}
+
+impl Rope {
+    /// Creates a new empty `Rope`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let r = ergo_core::Rope::new();
+    /// assert!(r.is_empty());
+    /// ```
+    #[inline]
+    pub const fn new() -> Self {
+        Self { root: None }
+    }
+
+    fn info(&self) -> node::Info {
+        self.root
+            .as_ref()
+            .map(|node| node.info())
+            .unwrap_or_default()
+    }
+
+    /// Returns the length of this `Rope`, in bytes, not [`char`]s or graphemes.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let r = ergo_core::Rope::from("föo");
+    /// assert_eq!(r.byte_len(), 4);
+    /// ```
+    #[inline]
+    pub fn byte_len(&self) -> usize {
+        self.info().bytes
+    }
+
+    /// Returns the length of this `Rope`, in [`char`]s.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let r = ergo_core::Rope::from("föo");
+    /// assert_eq!(r.char_len(), 3);
+    /// ```
+    #[inline]
+    pub fn char_len(&self) -> usize {
+        self.info().chars
+    }
+
+    /// Returns the number of lines, which is one more than the number of `\n`s.
+    ///
+    /// A trailing `\n` starts an empty last line, and an empty rope has one empty line, so every
+    /// byte offset is on a line.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// assert_eq!(ergo_core::Rope::from("one\ntwo").line_count(), 2);
+    /// assert_eq!(ergo_core::Rope::from("one\ntwo\n").line_count(), 3);
+    /// assert_eq!(ergo_core::Rope::new().line_count(), 1);
+    /// ```
+    #[inline]
+    pub fn line_count(&self) -> usize {
+        self.info().newlines + 1
+    }
+
+    /// Returns `true` if `self` has a length of zero bytes.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Returns a subrope.
+    ///
+    /// This is the non-panicking alternative to [`Rope::slice`].  Returns [`None`] when the
+    /// range is out of bounds or not on [`char`] boundaries.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let r = ergo_core::Rope::from("Hello World");
+    ///
+    /// assert_eq!(r.get(6..).unwrap(), "World");
+    /// assert!(r.get(6..20).is_none());
+    /// ```
+    pub fn get(&self, range: impl RangeBounds<usize>) -> Option<Self> {
+        let (start, end) = bounds(self.byte_len(), range)?;
+        if !(self.is_char_boundary(start) && self.is_char_boundary(end)) {
+            return None;
+        }
+        Some(self.slice(start..end))
+    }
+
+    /// Returns a subrope.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds or not on [`char`] boundaries.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let r = ergo_core::Rope::from("Hello World");
+    /// assert_eq!(r.slice(..5), "Hello");
+    /// ```
+    pub fn slice(&self, range: impl RangeBounds<usize>) -> Self {
+        let (start, end) = self.expect_bounds(range);
+        let (_, rest) = node::split(&self.root, start);
+        let (middle, _) = node::split(&rest, end - start);
+        Self { root: middle }
+    }
+
+    /// Returns a rope with `text` inserted at byte `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds or not on a [`char`] boundary.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let r = ergo_core::Rope::from("Hello World");
+    /// assert_eq!(r.insert(5, ","), "Hello, World");
+    /// ```
+    pub fn insert(&self, index: usize, text: impl Into<Rope>) -> Self {
+        let (start, _) = self.expect_bounds(index..index);
+        let (before, after) = node::split(&self.root, start);
+        let root = node::join(node::join(before, text.into().root), after);
+        Self { root }
+    }
+
+    /// Returns a rope with the bytes in `range` removed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds or not on [`char`] boundaries.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let r = ergo_core::Rope::from("Hello, World");
+    /// assert_eq!(r.remove(5..7), "HelloWorld");
+    /// ```
+    pub fn remove(&self, range: impl RangeBounds<usize>) -> Self {
+        let (start, end) = self.expect_bounds(range);
+        let (before, rest) = node::split(&self.root, start);
+        let (_, after) = node::split(&rest, end - start);
+        Self {
+            root: node::join(before, after),
+        }
+    }
+
+    /// Appends `text` onto the end of this `Rope`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let r = ergo_core::Rope::from("Hello");
+    /// assert_eq!(r.join_str(" World"), "Hello World");
+    /// ```
+    #[must_use = "this returns the joined rope as a new rope, \
+                  without modifying the original"]
+    pub fn join_str(&self, text: impl Into<Rope>) -> Self {
+        Self {
+            root: node::join(self.root.clone(), text.into().root),
+        }
+    }
+
+    /// Returns line `line`, including its `\n` if it has one, or [`None`] past the last line.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let r = ergo_core::Rope::from("one\ntwo");
+    ///
+    /// assert_eq!(r.line(0).unwrap(), "one\n");
+    /// assert_eq!(r.line(1).unwrap(), "two");
+    /// assert!(r.line(2).is_none());
+    /// ```
+    pub fn line(&self, line: usize) -> Option<Self> {
+        if self.line_count() <= line {
+            return None;
+        }
+        let start = self.line_to_byte(line);
+        let end = if line + 1 < self.line_count() {
+            self.line_to_byte(line + 1)
+        } else {
+            self.byte_len()
+        };
+        Some(self.slice(start..end))
+    }
+
+    /// Returns the byte offset where line `line` starts.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `line` is not less than [`Rope::line_count`].
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let r = ergo_core::Rope::from("one\ntwo\n");
+    ///
+    /// assert_eq!(r.line_to_byte(1), 4);
+    /// assert_eq!(r.line_to_byte(2), 8);
+    /// ```
+    pub fn line_to_byte(&self, line: usize) -> usize {
+        assert!(
+            line < self.line_count(),
+            "line {} is out of bounds of a rope with {} lines",
+            line,
+            self.line_count()
+        );
+        if line == 0 {
+            return 0;
+        }
+        let (text, before) =
+            node::locate(&self.root, line, |info| info.newlines).expect("line is in bounds");
+        let newline = text
+            .as_str()
+            .bytes()
+            .enumerate()
+            .filter(|(_, b)| *b == b'\n')
+            .nth(line - before.newlines - 1)
+            .map(|(i, _)| i)
+            .expect("the leaf holds the newline");
+        before.bytes + newline + 1
+    }
+
+    /// Returns the line that byte `index` is on.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than [`Rope::byte_len`].
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let r = ergo_core::Rope::from("one\ntwo\n");
+    ///
+    /// assert_eq!(r.byte_to_line(3), 0);
+    /// assert_eq!(r.byte_to_line(4), 1);
+    /// assert_eq!(r.byte_to_line(8), 2);
+    /// ```
+    pub fn byte_to_line(&self, index: usize) -> usize {
+        let (text, before, offset) = self.locate_byte(index);
+        let newlines = text.as_str().as_bytes()[..offset]
+            .iter()
+            .filter(|&&b| b == b'\n')
+            .count();
+        before.newlines + newlines
+    }
+
+    /// Returns the number of [`char`]s before byte `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than [`Rope::byte_len`] or not on a [`char`] boundary.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let r = ergo_core::Rope::from("föo");
+    /// assert_eq!(r.byte_to_char(3), 2);
+    /// ```
+    pub fn byte_to_char(&self, index: usize) -> usize {
+        let (text, before, offset) = self.locate_byte(index);
+        let text = text.as_str();
+        assert!(
+            text.is_char_boundary(offset),
+            "byte index {} is not a char boundary",
+            index
+        );
+        before.chars + text[..offset].chars().count()
+    }
+
+    /// Returns the byte offset of [`char`] `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than [`Rope::char_len`].
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let r = ergo_core::Rope::from("föo");
+    /// assert_eq!(r.char_to_byte(2), 3);
+    /// ```
+    pub fn char_to_byte(&self, index: usize) -> usize {
+        assert!(
+            index <= self.char_len(),
+            "char index {} is out of bounds of a rope of {} chars",
+            index,
+            self.char_len()
+        );
+        match node::locate(&self.root, index, |info| info.chars) {
+            Some((text, before)) => {
+                let text = text.as_str();
+                let offset = text
+                    .char_indices()
+                    .map(|(i, _)| i)
+                    .chain(std::iter::once(text.len()))
+                    .nth(index - before.chars)
+                    .expect("the leaf holds the char");
+                before.bytes + offset
+            }
+            None => 0,
+        }
+    }
+
+    /// Returns the byte index of the first occurrence of `needle`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let r = ergo_core::Rope::from("Hello World");
+    /// assert_eq!(r.find("World"), Some(6));
+    /// ```
+    pub fn find(&self, needle: impl AsRef<str>) -> Option<usize> {
+        let needle = needle.as_ref();
+        if needle.is_empty() {
+            return Some(0);
+        }
+        // A match spanning chunks starts in the last `overlap` bytes before a chunk
+        let overlap = needle.len() - 1;
+        let mut tail = StdString::new();
+        let mut offset = 0;
+        for chunk in self.chunks() {
+            if !tail.is_empty() {
+                let mut window = tail.clone();
+                let head = chunk.as_str();
+                window.push_str(&head[..ceil_char_boundary(head, overlap.min(head.len()))]);
+                if let Some(index) = window.find(needle) {
+                    return Some(offset - tail.len() + index);
+                }
+            }
+            if let Some(index) = chunk.find(needle) {
+                return Some(offset + index);
+            }
+            offset += chunk.byte_len();
+
+            let chunk = chunk.as_str();
+            if overlap <= chunk.len() {
+                tail.clear();
+                tail.push_str(&chunk[ceil_char_boundary(chunk, chunk.len() - overlap)..]);
+            } else {
+                tail.push_str(chunk);
+                let start = ceil_char_boundary(&tail, tail.len().saturating_sub(overlap));
+                tail.drain(..start);
+            }
+        }
+        None
+    }
+
+    /// Returns an iterator over the chunks of the rope, in order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let r = ergo_core::Rope::from("Hello").join_str(" World");
+    ///
+    /// let text: String = r.chunks().map(|chunk| chunk.as_str()).collect();
+    /// assert_eq!(text, "Hello World");
+    /// ```
+    #[inline]
+    pub fn chunks(&self) -> Chunks<'_> {
+        Chunks(Leaves::new(&self.root))
+    }
+
+    /// Returns an iterator over the [`char`]s of the rope.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let r = ergo_core::Rope::from("föo");
+    /// assert!(r.chars().eq("föo".chars()));
+    /// ```
+    #[inline]
+    pub fn chars(&self) -> impl Iterator<Item = char> + '_ {
+        self.chunks().flat_map(|chunk| chunk.as_str().chars())
+    }
+
+    /// Returns `true` if byte `index` is the start or end of a [`char`].
+    fn is_char_boundary(&self, index: usize) -> bool {
+        match node::locate(&self.root, index, |info| info.bytes) {
+            Some((text, before)) => text.as_str().is_char_boundary(index - before.bytes),
+            None => index == 0,
+        }
+    }
+
+    fn locate_byte(&self, index: usize) -> (&String, node::Info, usize) {
+        assert!(
+            index <= self.byte_len(),
+            "byte index {} is out of bounds of a rope of {} bytes",
+            index,
+            self.byte_len()
+        );
+        match node::locate(&self.root, index, |info| info.bytes) {
+            Some((text, before)) => (text, before, index - before.bytes),
+            None => (&EMPTY, node::Info::default(), 0),
+        }
+    }
+
+    fn expect_bounds(&self, range: impl RangeBounds<usize>) -> (usize, usize) {
+        let len = self.byte_len();
+        let (start, end) = bounds(len, range).unwrap_or_else(|| {
+            panic!("range is out of bounds of a rope of {} bytes", len);
+        });
+        for index in [start, end].iter() {
+            assert!(
+                self.is_char_boundary(*index),
+                "byte index {} is not a char boundary",
+                index
+            );
+        }
+        (start, end)
+    }
+}
+
+static EMPTY: String = String::new();
+
+/// Rounds `index` up to the next [`char`] boundary of `s`.
+fn ceil_char_boundary(s: &str, mut index: usize) -> usize {
+    while !s.is_char_boundary(index) {
+        index += 1;
+    }
+    index
+}
+
+/// Resolves `range` to `start..end` within `len`.
+fn bounds(len: usize, range: impl RangeBounds<usize>) -> Option<(usize, usize)> {
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => start.checked_add(1)?,
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end.checked_add(1)?,
+        Bound::Excluded(&end) => end,
+        Bound::Unbounded => len,
+    };
+    if start <= end && end <= len {
+        Some((start, end))
+    } else {
+        None
+    }
+}
+
+/// An iterator over the chunks of a [`Rope`]
+///
+/// Created by [`Rope::chunks`].
+#[derive(Clone, Debug)]
+pub struct Chunks<'r>(Leaves<'r>);
+
+impl<'r> Iterator for Chunks<'r> {
+    type Item = &'r String;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'r String> {
+        self.0.next()
+    }
+}
+
+impl<'s> From<&'s str> for Rope {
+    #[inline]
+    fn from(other: &'s str) -> Self {
+        String::from(other).into()
+    }
+}
+
+impl From<StdString> for Rope {
+    #[inline]
+    fn from(other: StdString) -> Self {
+        String::from(other).into()
+    }
+}
+
+impl From<String> for Rope {
+    #[inline]
+    fn from(other: String) -> Self {
+        Self {
+            root: node::build(&other),
+        }
+    }
+}
+
+impl<'s> From<&'s String> for Rope {
+    #[inline]
+    fn from(other: &'s String) -> Self {
+        Self {
+            root: node::build(other),
+        }
+    }
+}
+
+impl From<&Rope> for Rope {
+    #[inline]
+    fn from(other: &Rope) -> Self {
+        other.clone()
+    }
+}
+
+impl From<&Rope> for String {
+    /// Concatenates the chunks, sharing the buffer when there is only one.
+    fn from(other: &Rope) -> Self {
+        let mut chunks = other.chunks();
+        match (chunks.next(), chunks.next()) {
+            (None, _) => String::new(),
+            (Some(only), None) => only.clone(),
+            _ => other.chunks().map(String::as_str).collect(),
+        }
+    }
+}
+
+impl From<Rope> for String {
+    #[inline]
+    fn from(other: Rope) -> Self {
+        Self::from(&other)
+    }
+}
+
+impl fmt::Display for Rope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for chunk in self.chunks() {
+            f.write_str(chunk.as_str())?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Rope {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&String::from(self), f)
+    }
+}
+
+impl PartialEq for Rope {
+    fn eq(&self, other: &Self) -> bool {
+        self.byte_len() == other.byte_len()
+            && self
+                .chunks()
+                .flat_map(|chunk| chunk.as_str().bytes())
+                .eq(other.chunks().flat_map(|chunk| chunk.as_str().bytes()))
+    }
+}
+
+impl Eq for Rope {}
+
+impl PartialEq<str> for Rope {
+    fn eq(&self, other: &str) -> bool {
+        let mut rest = other.as_bytes();
+        self.byte_len() == other.len()
+            && self.chunks().all(|chunk| {
+                let (head, tail) = rest.split_at(chunk.byte_len());
+                rest = tail;
+                head == chunk.as_str().as_bytes()
+            })
+    }
+}
+
+impl<'a> PartialEq<&'a str> for Rope {
+    #[inline]
+    fn eq(&self, other: &&'a str) -> bool {
+        self == *other
+    }
+}
+
+impl PartialEq<String> for Rope {
+    #[inline]
+    fn eq(&self, other: &String) -> bool {
+        self == other.as_str()
+    }
+}
+
+impl PartialEq<StdString> for Rope {
+    #[inline]
+    fn eq(&self, other: &StdString) -> bool {
+        self == other.as_str()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::{xorshift, SEED};
+
+    fn check(rope: &Rope, model: &str) {
+        if let Some(root) = &rope.root {
+            node::check(root);
+        }
+        assert_eq!(rope, model);
+        assert_eq!(rope.char_len(), model.chars().count());
+        assert_eq!(rope.line_count(), model.matches('\n').count() + 1);
+    }
+
+    #[test]
+    fn edits_match_std() {
+        let mut state = SEED;
+        let words = ["a", "föo ", "line\n", "老虎", &"x".repeat(3000)];
+        let mut rope = Rope::new();
+        let mut model = StdString::new();
+        for _ in 0..500 {
+            let boundaries: Vec<usize> = model
+                .char_indices()
+                .map(|(i, _)| i)
+                .chain(std::iter::once(model.len()))
+                .collect();
+            let at = boundaries[xorshift(&mut state) as usize % boundaries.len()];
+            if xorshift(&mut state) as usize % 3 == 2 {
+                let end = boundaries[xorshift(&mut state) as usize % boundaries.len()];
+                let (start, end) = (at.min(end), at.max(end));
+                rope = rope.remove(start..end);
+                model.replace_range(start..end, "");
+            } else {
+                let word = words[xorshift(&mut state) as usize % words.len()];
+                rope = rope.insert(at, word);
+                model.insert_str(at, word);
+            }
+            check(&rope, &model);
+        }
+    }
+
+    #[test]
+    fn offsets_match_std() {
+        let model = "first line\nzweite Zeile\n\n第四行\nlast".repeat(200);
+        let rope = Rope::from(model.as_str()).insert(5, "x").remove(5..6);
+        check(&rope, &model);
+
+        for (i, _) in model.char_indices().step_by(7) {
+            assert_eq!(rope.byte_to_char(i), model[..i].chars().count());
+            assert_eq!(rope.byte_to_line(i), model[..i].matches('\n').count());
+        }
+        for (c, (i, _)) in model.char_indices().enumerate().step_by(7) {
+            assert_eq!(rope.char_to_byte(c), i);
+        }
+        let lines: Vec<&str> = model.split_inclusive('\n').collect();
+        for (n, line) in lines.iter().enumerate() {
+            assert_eq!(rope.line(n).unwrap(), *line);
+        }
+        assert_eq!(rope.char_to_byte(rope.char_len()), model.len());
+    }
+
+    #[test]
+    fn find_spans_chunks() {
+        let text = "a".repeat(node::MAX_LEAF - 2) + "needle" + &"b".repeat(node::MAX_LEAF);
+        let rope = Rope::from(text.as_str());
+        assert!(1 < rope.chunks().count());
+        assert_eq!(rope.find("needle"), text.find("needle"));
+        assert_eq!(rope.find("ab"), text.find("ab"));
+        assert_eq!(rope.find("missing"), None);
+    }
+
+    #[test]
+    fn large_strings_share_buffer() {
+        let text = String::from("0123456789".repeat(1000));
+        let rope = Rope::from(&text);
+        assert!(rope.chunks().all(|chunk| chunk.is_shared_with(&text)));
+    }
+}
@@ -0,0 +1,305 @@
+use std::sync::Arc;
+
+use crate::String;
+
+/// Leaves built from text are at most this many bytes, unless a single [`char`] is longer.
+pub(super) const MAX_LEAF: usize = 1024;
+
+/// The sizes of a subtree, kept up to date so lookups can descend without scanning text
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub(super) struct Info {
+    pub(super) bytes: usize,
+    pub(super) chars: usize,
+    pub(super) newlines: usize,
+}
+
+impl Info {
+    pub(super) fn of(text: &str) -> Self {
+        Self {
+            bytes: text.len(),
+            chars: text.chars().count(),
+            newlines: text.bytes().filter(|&b| b == b'\n').count(),
+        }
+    }
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            bytes: self.bytes + other.bytes,
+            chars: self.chars + other.chars,
+            newlines: self.newlines + other.newlines,
+        }
+    }
+}
+
+/// A node of an AVL tree whose in-order leaves spell out the text
+///
+/// Nodes are immutable and shared between ropes; edits rebuild only the path to the change.
+/// Leaves are never empty.
+#[derive(Debug)]
+pub(super) enum Node {
+    Leaf {
+        text: String,
+        info: Info,
+    },
+    Branch {
+        left: Arc<Node>,
+        right: Arc<Node>,
+        info: Info,
+        height: u8,
+    },
+}
+
+pub(super) type Tree = Option<Arc<Node>>;
+
+impl Node {
+    pub(super) fn info(&self) -> Info {
+        match self {
+            Node::Leaf { info, .. } => *info,
+            Node::Branch { info, .. } => *info,
+        }
+    }
+
+    pub(super) fn height(&self) -> u8 {
+        match self {
+            Node::Leaf { .. } => 0,
+            Node::Branch { height, .. } => *height,
+        }
+    }
+}
+
+fn leaf(text: String) -> Arc<Node> {
+    debug_assert!(!text.is_empty());
+    let info = Info::of(text.as_str());
+    Arc::new(Node::Leaf { text, info })
+}
+
+fn branch(left: Arc<Node>, right: Arc<Node>) -> Arc<Node> {
+    Arc::new(Node::Branch {
+        info: left.info().add(right.info()),
+        height: left.height().max(right.height()) + 1,
+        left,
+        right,
+    })
+}
+
+/// Builds a perfectly balanced tree over `text`, with leaves sharing its buffer.
+pub(super) fn build(text: &String) -> Tree {
+    let s = text.as_str();
+    let mut level = Vec::with_capacity(s.len() / MAX_LEAF + 1);
+    let mut start = 0;
+    while start < s.len() {
+        let mut end = (start + MAX_LEAF).min(s.len());
+        while !s.is_char_boundary(end) {
+            end += 1;
+        }
+        level.push(leaf(text.get(start..end).expect("on char boundaries")));
+        start = end;
+    }
+
+    while 1 < level.len() {
+        let mut nodes = level.into_iter();
+        let mut next = Vec::with_capacity(nodes.len() / 2 + 1);
+        while let Some(left) = nodes.next() {
+            next.push(match nodes.next() {
+                Some(right) => branch(left, right),
+                None => left,
+            });
+        }
+        level = next;
+    }
+    level.pop()
+}
+
+/// Concatenates two trees, keeping them balanced and merging small adjacent leaves.
+pub(super) fn join(left: Tree, right: Tree) -> Tree {
+    match (left, right) {
+        (None, tree) | (tree, None) => tree,
+        (Some(left), Some(right)) => Some(join_nodes(left, right)),
+    }
+}
+
+fn join_nodes(left: Arc<Node>, right: Arc<Node>) -> Arc<Node> {
+    let (left_height, right_height) = (left.height(), right.height());
+    if right_height + 1 < left_height {
+        match &*left {
+            Node::Branch {
+                left: outer,
+                right: inner,
+                ..
+            } => balance(outer.clone(), join_nodes(inner.clone(), right)),
+            Node::Leaf { .. } => unreachable!("leaves have height 0"),
+        }
+    } else if left_height + 1 < right_height {
+        match &*right {
+            Node::Branch {
+                left: inner,
+                right: outer,
+                ..
+            } => balance(join_nodes(left, inner.clone()), outer.clone()),
+            Node::Leaf { .. } => unreachable!("leaves have height 0"),
+        }
+    } else {
+        match (&*left, &*right) {
+            (Node::Leaf { text: a, info: x }, Node::Leaf { text: b, info: y })
+                if x.bytes + y.bytes <= MAX_LEAF =>
+            {
+                leaf(a.join_str(b))
+            }
+            _ => branch(left, right),
+        }
+    }
+}
+
+/// Joins subtrees whose heights differ by at most 2, rotating to restore the AVL invariant.
+fn balance(left: Arc<Node>, right: Arc<Node>) -> Arc<Node> {
+    let (left_height, right_height) = (left.height(), right.height());
+    if right_height + 1 < left_height {
+        let (a, b) = children(&left);
+        if a.height() < b.height() {
+            // Left-right case
+            let (b1, b2) = children(&b);
+            branch(branch(a, b1), branch(b2, right))
+        } else {
+            branch(a, branch(b, right))
+        }
+    } else if left_height + 1 < right_height {
+        let (a, b) = children(&right);
+        if b.height() < a.height() {
+            // Right-left case
+            let (a1, a2) = children(&a);
+            branch(branch(left, a1), branch(a2, b))
+        } else {
+            branch(branch(left, a), b)
+        }
+    } else {
+        branch(left, right)
+    }
+}
+
+fn children(node: &Arc<Node>) -> (Arc<Node>, Arc<Node>) {
+    match &**node {
+        Node::Branch { left, right, .. } => (left.clone(), right.clone()),
+        Node::Leaf { .. } => unreachable!("taller subtrees are branches"),
+    }
+}
+
+/// Splits a tree into the text before and after byte `index`.
+///
+/// # Panics
+///
+/// Panics if `index` isn't on a [`char`] boundary.
+pub(super) fn split(tree: &Tree, index: usize) -> (Tree, Tree) {
+    let node = match tree {
+        Some(node) => node,
+        None => return (None, None),
+    };
+    if index == 0 {
+        return (None, Some(node.clone()));
+    }
+    if node.info().bytes <= index {
+        return (Some(node.clone()), None);
+    }
+    match &**node {
+        Node::Leaf { text, .. } => {
+            assert!(
+                text.as_str().is_char_boundary(index),
+                "byte index {} is not a char boundary",
+                index
+            );
+            let (before, after) = text.split_at(index);
+            (Some(leaf(before)), Some(leaf(after)))
+        }
+        Node::Branch { left, right, .. } => {
+            let left_bytes = left.info().bytes;
+            if index <= left_bytes {
+                let (before, after) = split(&Some(left.clone()), index);
+                (before, join(after, Some(right.clone())))
+            } else {
+                let (before, after) = split(&Some(right.clone()), index - left_bytes);
+                (join(Some(left.clone()), before), after)
+            }
+        }
+    }
+}
+
+/// Descends to the leaf holding the position where `measure` reaches `target`, returning it with
+/// the [`Info`] of everything before it.
+///
+/// `measure` picks the dimension, like chars or newlines; positions on a boundary between
+/// leaves go left.
+pub(super) fn locate(
+    tree: &Tree,
+    target: usize,
+    measure: impl Fn(Info) -> usize,
+) -> Option<(&String, Info)> {
+    let mut node = tree.as_ref()?;
+    let mut before = Info::default();
+    loop {
+        match &**node {
+            Node::Leaf { text, .. } => return Some((text, before)),
+            Node::Branch { left, right, .. } => {
+                let left_info = left.info();
+                if target <= measure(before.add(left_info)) {
+                    node = left;
+                } else {
+                    before = before.add(left_info);
+                    node = right;
+                }
+            }
+        }
+    }
+}
+
+/// Iterates over the leaves in order.
+#[derive(Clone, Debug)]
+pub(super) struct Leaves<'r> {
+    stack: Vec<&'r Node>,
+}
+
+impl<'r> Leaves<'r> {
+    pub(super) fn new(tree: &'r Tree) -> Self {
+        Self {
+            stack: tree.iter().map(|node| &**node).collect(),
+        }
+    }
+}
+
+impl<'r> Iterator for Leaves<'r> {
+    type Item = &'r String;
+
+    fn next(&mut self) -> Option<&'r String> {
+        loop {
+            match self.stack.pop()? {
+                Node::Leaf { text, .. } => return Some(text),
+                Node::Branch { left, right, .. } => {
+                    self.stack.push(right);
+                    self.stack.push(left);
+                }
+            }
+        }
+    }
+}
+
+/// Checks the AVL and size invariants, returning the height.
+#[cfg(test)]
+pub(super) fn check(node: &Node) -> u8 {
+    match node {
+        Node::Leaf { text, info } => {
+            assert!(!text.is_empty());
+            assert_eq!(*info, Info::of(text.as_str()));
+            0
+        }
+        Node::Branch {
+            left,
+            right,
+            info,
+            height,
+        } => {
+            let (left_height, right_height) = (check(left), check(right));
+            assert!(left_height.max(right_height) - left_height.min(right_height) <= 1);
+            assert_eq!(*height, left_height.max(right_height) + 1);
+            assert_eq!(*info, left.info().add(right.info()));
+            *height
+        }
+    }
+}
@@ -0,0 +1,1102 @@
+//! Cheaply clonable sets: [`Set`] and its hashable, immutable counterpart [`FrozenSet`]
+//!
+//! Like Python's `set` and `frozenset`, both support set algebra with operators:
+//!
+//! | Operator | Meaning                                |
+//! |----------|----------------------------------------|
+//! | `a \| b` | union                                  |
+//! | `a & b`  | intersection                           |
+//! | `a - b`  | difference                             |
+//! | `a ^ b`  | symmetric difference                   |
+//! | `a <= b` | `a` is a subset of `b`                 |
+//! | `a < b`  | `a` is a proper subset of `b`          |
+//!
+//! Unlike Python, iteration order is deterministic: items come out in the order they were first
+//! inserted, and results of operators keep the order of their left-hand side.
+//!
+//! # Examples
+//!
+//! ```
+//! use ergo_core::Set;
+//!
+//! let backend = Set::from(["rust", "sql", "docker"]);
+//! let frontend = Set::from(["typescript", "css", "docker"]);
+//!
+//! assert_eq!(&backend & &frontend, Set::from(["docker"]));
+//! assert_eq!(&backend - &frontend, Set::from(["rust", "sql"]));
+//! assert!(Set::from(["rust"]) <= backend);
+//! ```
+
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::iter::{FromIterator, FusedIterator};
+use std::ops;
+use std::sync::Arc;
+
+use crate::dict::table::{self, Table};
+use crate::hash::{hash_with, BuildFastHasher};
+
+/// A cheaply clonable, copy-on-write set, like Python's `set`
+///
+/// Iteration follows insertion order.  Cloning shares the items instead of copying them;
+/// mutating a set whose items are shared first copies them, leaving other sets unchanged.
+///
+/// Items are hashed with [`FastHasher`][crate::hash::FastHasher] by default, which is not
+/// resistant to HashDoS; pick another hasher with [`Set::with_hasher`] for items chosen by
+/// untrusted users.
+///
+/// See the [module documentation][self] for the operators sets support.
+///
+/// # Examples
+///
+/// ```
+/// use ergo_core::{Set, String};
+///
+/// let mut seen = Set::new();
+/// for word in "to be or not to be".split(' ') {
+///     seen.insert(String::from(word));
+/// }
+///
+/// assert_eq!(seen.len(), 4);
+/// assert!(seen.contains("not"));
+/// ```
+pub struct Set<T, S = BuildFastHasher> {
+    table: Arc<Table<T, ()>>,
+    hasher: S,
+}
+
+/// An immutable, hashable set, like Python's `frozenset`
+///
+/// Frozen sets can be stored in other sets or used as [`Dict`][crate::Dict] keys.  Converting
+/// between a [`Set`] and a `FrozenSet` shares the items rather than copying them.
+///
+/// # Examples
+///
+/// ```
+/// use ergo_core::{FrozenSet, Set};
+///
+/// let mut groups = Set::new();
+/// groups.insert(FrozenSet::from(["a", "b"]));
+/// groups.insert(FrozenSet::from(["b", "a"]));
+///
+/// assert_eq!(groups.len(), 1);
+/// ```
+pub struct FrozenSet<T, S = BuildFastHasher>(Set<T, S>);
+
+impl<T> Set<T> {
+    /// Creates a new empty `Set`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::Set::<i32>::new();
+    /// assert!(s.is_empty());
+    /// ```
+    #[inline]
+    pub fn new() -> Self {
+        Self::with_hasher(BuildFastHasher::default())
+    }
+
+    /// Creates a new empty `Set` with room for `capacity` items before growing.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::Set::<i32>::with_capacity(10);
+    /// assert!(s.is_empty());
+    /// ```
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            table: Arc::new(Table::with_capacity(capacity)),
+            hasher: BuildFastHasher::default(),
+        }
+    }
+}
+
+impl<T, S> Set<T, S> {
+    /// Creates a new empty `Set` that hashes items with `hasher`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use std::collections::hash_map::RandomState;
+    ///
+    /// let mut s = ergo_core::Set::with_hasher(RandomState::new());
+    /// s.insert(1);
+    ///
+    /// assert!(s.contains(&1));
+    /// ```
+    #[inline]
+    pub fn with_hasher(hasher: S) -> Self {
+        Self {
+            table: Arc::new(Table::new()),
+            hasher,
+        }
+    }
+
+    /// Returns the number of items.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::Set::from([1, 2, 2]);
+    /// assert_eq!(s.len(), 2);
+    /// ```
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    /// Returns `true` if there are no items.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut s = ergo_core::Set::new();
+    /// assert!(s.is_empty());
+    ///
+    /// s.insert(1);
+    /// assert!(!s.is_empty());
+    /// ```
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterates over the items in insertion order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::Set::from([3, 1, 2, 1]);
+    ///
+    /// let items: Vec<_> = s.iter().collect();
+    /// assert_eq!(items, [&3, &1, &2]);
+    /// ```
+    #[inline]
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter(self.table.iter())
+    }
+
+    /// Removes every item.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut s = ergo_core::Set::from([1, 2]);
+    /// s.clear();
+    ///
+    /// assert!(s.is_empty());
+    /// ```
+    #[inline]
+    pub fn clear(&mut self) {
+        match Arc::get_mut(&mut self.table) {
+            Some(table) => table.clear(),
+            None => self.table = Arc::new(Table::new()),
+        }
+    }
+
+    /// Returns `true` if both sets share the same items, so neither has been modified since one
+    /// was cloned from the other.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::Set::from([1]);
+    /// let mut copy = s.clone();
+    /// assert!(s.is_shared_with(&copy));
+    ///
+    /// copy.insert(2);
+    /// assert!(!s.is_shared_with(&copy));
+    /// ```
+    #[inline]
+    pub fn is_shared_with(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.table, &other.table)
+    }
+
+    /// Makes the set immutable and hashable, without copying the items.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut s = ergo_core::Set::new();
+    /// s.insert("a");
+    ///
+    /// let frozen = s.freeze();
+    /// assert!(frozen.contains("a"));
+    /// ```
+    #[inline]
+    pub fn freeze(self) -> FrozenSet<T, S> {
+        FrozenSet(self)
+    }
+}
+
+impl<T: Hash + Eq, S: BuildHasher> Set<T, S> {
+    /// Returns `true` if the set holds `value`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use ergo_core::{Set, String};
+    ///
+    /// let s = Set::from([String::from("a")]);
+    ///
+    /// assert!(s.contains("a"));
+    /// assert!(!s.contains("b"));
+    /// ```
+    #[inline]
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        !self.is_empty()
+            && self
+                .table
+                .find(hash_with(&self.hasher, value), |item| {
+                    item.borrow() == value
+                })
+                .is_some()
+    }
+
+    /// Returns `true` if every item is also in `other`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::Set::from([1, 2]);
+    ///
+    /// assert!(s.is_subset(&ergo_core::Set::from([1, 2, 3])));
+    /// assert!(!s.is_subset(&ergo_core::Set::from([1, 3])));
+    /// ```
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.len() <= other.len() && self.iter().all(|item| other.contains(item))
+    }
+
+    /// Returns `true` if every item of `other` is also in `self`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::Set::from([1, 2, 3]);
+    ///
+    /// assert!(s.is_superset(&ergo_core::Set::from([1, 2])));
+    /// assert!(!s.is_superset(&ergo_core::Set::from([1, 4])));
+    /// ```
+    #[inline]
+    pub fn is_superset(&self, other: &Self) -> bool {
+        other.is_subset(self)
+    }
+
+    /// Returns `true` if no item is in both sets.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::Set::from([1, 2]);
+    ///
+    /// assert!(s.is_disjoint(&ergo_core::Set::from([3])));
+    /// assert!(!s.is_disjoint(&ergo_core::Set::from([2, 3])));
+    /// ```
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        let (small, large) = if self.len() <= other.len() {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        !small.iter().any(|item| large.contains(item))
+    }
+}
+
+impl<T: Clone + Hash + Eq, S: BuildHasher> Set<T, S> {
+    /// Adds `value`, returning `true` if it wasn't already present.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut s = ergo_core::Set::new();
+    ///
+    /// assert!(s.insert(1));
+    /// assert!(!s.insert(1));
+    /// ```
+    pub fn insert(&mut self, value: T) -> bool {
+        let hash = hash_with(&self.hasher, &value);
+        if self.table.find(hash, |item| *item == value).is_some() {
+            return false;
+        }
+        Arc::make_mut(&mut self.table).push(hash, value, ());
+        true
+    }
+
+    /// Removes `value`, returning `true` if it was present.
+    ///
+    /// Unlike Python's `set.remove`, a missing value is not an error, like `set.discard`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut s = ergo_core::Set::from([1]);
+    ///
+    /// assert!(s.remove(&1));
+    /// assert!(!s.remove(&1));
+    /// ```
+    pub fn remove<Q>(&mut self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if !self.contains(value) {
+            return false;
+        }
+        let hash = hash_with(&self.hasher, value);
+        Arc::make_mut(&mut self.table)
+            .remove(hash, |item| item.borrow() == value)
+            .is_some()
+    }
+
+    /// Removes the most recently inserted item and returns it, or [`None`] if the set is empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut s = ergo_core::Set::from([1, 2]);
+    ///
+    /// assert_eq!(s.pop(), Some(2));
+    /// assert_eq!(s.pop(), Some(1));
+    /// assert_eq!(s.pop(), None);
+    /// ```
+    pub fn pop(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        Arc::make_mut(&mut self.table)
+            .pop()
+            .map(|bucket| bucket.key)
+    }
+
+    /// Adds every item of `iter`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut s = ergo_core::Set::from([1, 2]);
+    /// s.extend(vec![2, 3]);
+    ///
+    /// assert_eq!(s, ergo_core::Set::from([1, 2, 3]));
+    /// ```
+    pub fn extend(&mut self, iter: impl IntoIterator<Item = T>) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+
+    /// Keeps only the items matching `keep`, in the same order.
+    fn filtered(&self, mut keep: impl FnMut(&T) -> bool) -> Self
+    where
+        S: Clone,
+    {
+        let mut filtered = Self::with_hasher(self.hasher.clone());
+        filtered.extend(self.iter().filter(|item| keep(item)).cloned());
+        filtered
+    }
+}
+
+/// Transitional Python API
+impl<T: Clone + Hash + Eq, S: BuildHasher + Clone> Set<T, S> {
+    #[deprecated = "In Rust, we refer to this as `insert`"]
+    pub fn add(&mut self, value: T) {
+        self.insert(value);
+    }
+
+    #[deprecated = "In Rust, we refer to this as `remove`"]
+    pub fn discard<Q>(&mut self, value: &Q)
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.remove(value);
+    }
+
+    #[deprecated = "In Rust, we refer to this as `extend`"]
+    pub fn update(&mut self, iter: impl IntoIterator<Item = T>) {
+        self.extend(iter);
+    }
+
+    #[deprecated = "In Rust, we refer to this as `is_subset`"]
+    pub fn issubset(&self, other: &Self) -> bool {
+        self.is_subset(other)
+    }
+
+    #[deprecated = "In Rust, we refer to this as `is_superset`"]
+    pub fn issuperset(&self, other: &Self) -> bool {
+        self.is_superset(other)
+    }
+
+    #[deprecated = "In Rust, we refer to this as `is_disjoint`"]
+    pub fn isdisjoint(&self, other: &Self) -> bool {
+        self.is_disjoint(other)
+    }
+
+    #[deprecated = "In Rust, we refer to this as `clone`"]
+    pub fn copy(&self) -> Self {
+        self.clone()
+    }
+}
+
+impl<T, S> FrozenSet<T, S> {
+    /// Returns the number of items.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::FrozenSet::from([1, 2, 2]);
+    /// assert_eq!(s.len(), 2);
+    /// ```
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if there are no items.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::FrozenSet::<i32>::default();
+    /// assert!(s.is_empty());
+    /// ```
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Iterates over the items in insertion order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::FrozenSet::from([3, 1, 2, 1]);
+    ///
+    /// let items: Vec<_> = s.iter().collect();
+    /// assert_eq!(items, [&3, &1, &2]);
+    /// ```
+    #[inline]
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.0.iter()
+    }
+
+    /// Returns `true` if both sets share the same items.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::FrozenSet::from([1]);
+    /// assert!(s.is_shared_with(&s.clone()));
+    /// ```
+    #[inline]
+    pub fn is_shared_with(&self, other: &Self) -> bool {
+        self.0.is_shared_with(&other.0)
+    }
+
+    /// Makes a mutable copy of the set, without copying the items until it is modified.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let frozen = ergo_core::FrozenSet::from([1]);
+    ///
+    /// let mut s = frozen.thaw();
+    /// s.insert(2);
+    /// assert_eq!(frozen.len(), 1);
+    /// ```
+    #[inline]
+    pub fn thaw(&self) -> Set<T, S>
+    where
+        T: Clone,
+        S: Clone,
+    {
+        self.0.clone()
+    }
+}
+
+impl<T: Hash + Eq, S: BuildHasher> FrozenSet<T, S> {
+    /// Returns `true` if the set holds `value`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use ergo_core::{FrozenSet, String};
+    ///
+    /// let s = FrozenSet::from([String::from("a")]);
+    ///
+    /// assert!(s.contains("a"));
+    /// assert!(!s.contains("b"));
+    /// ```
+    #[inline]
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.0.contains(value)
+    }
+
+    /// Returns `true` if every item is also in `other`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::FrozenSet::from([1, 2]);
+    ///
+    /// assert!(s.is_subset(&ergo_core::FrozenSet::from([1, 2, 3])));
+    /// ```
+    #[inline]
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.0.is_subset(&other.0)
+    }
+
+    /// Returns `true` if every item of `other` is also in `self`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::FrozenSet::from([1, 2, 3]);
+    ///
+    /// assert!(s.is_superset(&ergo_core::FrozenSet::from([1, 2])));
+    /// ```
+    #[inline]
+    pub fn is_superset(&self, other: &Self) -> bool {
+        self.0.is_superset(&other.0)
+    }
+
+    /// Returns `true` if no item is in both sets.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::FrozenSet::from([1, 2]);
+    ///
+    /// assert!(s.is_disjoint(&ergo_core::FrozenSet::from([3])));
+    /// ```
+    #[inline]
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        self.0.is_disjoint(&other.0)
+    }
+}
+
+impl<T: Clone, S: Clone> Clone for Set<T, S> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            table: self.table.clone(),
+            hasher: self.hasher.clone(),
+        }
+    }
+}
+
+impl<T: Clone, S: Clone> Clone for FrozenSet<T, S> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T, S: Default> Default for Set<T, S> {
+    #[inline]
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<T, S: Default> Default for FrozenSet<T, S> {
+    #[inline]
+    fn default() -> Self {
+        Self(Set::default())
+    }
+}
+
+impl<T: Hash + Eq, S: BuildHasher + Default> FromIterator<T> for Set<T, S> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let iter = iter.into_iter();
+        let hasher = S::default();
+        let mut table = Table::with_capacity(iter.size_hint().0);
+        for value in iter {
+            let hash = hash_with(&hasher, &value);
+            if table.find(hash, |item| *item == value).is_none() {
+                table.push(hash, value, ());
+            }
+        }
+        Self {
+            table: Arc::new(table),
+            hasher,
+        }
+    }
+}
+
+impl<T: Hash + Eq, S: BuildHasher + Default> FromIterator<T> for FrozenSet<T, S> {
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl<T: Hash + Eq> From<Vec<T>> for Set<T> {
+    #[inline]
+    fn from(other: Vec<T>) -> Self {
+        other.into_iter().collect()
+    }
+}
+
+impl<T: Hash + Eq, const N: usize> From<[T; N]> for Set<T> {
+    #[inline]
+    fn from(other: [T; N]) -> Self {
+        Vec::from(other).into_iter().collect()
+    }
+}
+
+impl<T: Hash + Eq> From<Vec<T>> for FrozenSet<T> {
+    #[inline]
+    fn from(other: Vec<T>) -> Self {
+        other.into_iter().collect()
+    }
+}
+
+impl<T: Hash + Eq, const N: usize> From<[T; N]> for FrozenSet<T> {
+    #[inline]
+    fn from(other: [T; N]) -> Self {
+        Vec::from(other).into_iter().collect()
+    }
+}
+
+impl<T, S> From<Set<T, S>> for FrozenSet<T, S> {
+    #[inline]
+    fn from(other: Set<T, S>) -> Self {
+        other.freeze()
+    }
+}
+
+impl<T, S> From<FrozenSet<T, S>> for Set<T, S> {
+    /// Unfreezes the set, without copying the items until it is modified.
+    #[inline]
+    fn from(other: FrozenSet<T, S>) -> Self {
+        other.0
+    }
+}
+
+impl<T: Clone + Hash + Eq, S: BuildHasher> Extend<T> for Set<T, S> {
+    #[inline]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        Set::extend(self, iter);
+    }
+}
+
+impl<'s, T, S> IntoIterator for &'s Set<T, S> {
+    type Item = &'s T;
+    type IntoIter = Iter<'s, T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'s, T, S> IntoIterator for &'s FrozenSet<T, S> {
+    type Item = &'s T;
+    type IntoIter = Iter<'s, T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: Clone, S> IntoIterator for Set<T, S> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    /// Moves the items out, only copying them when they are shared.
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        let table = Arc::try_unwrap(self.table).unwrap_or_else(|table| (*table).clone());
+        IntoIter(table.into_iter())
+    }
+}
+
+impl<T: Clone, S> IntoIterator for FrozenSet<T, S> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+macro_rules! impl_operators {
+    ($set:ident $(, $field:tt)?) => {
+        impl<T, S> ops::BitOr<&$set<T, S>> for &$set<T, S>
+        where
+            T: Clone + Hash + Eq,
+            S: BuildHasher + Clone,
+        {
+            type Output = $set<T, S>;
+
+            /// Returns the items in either set.
+            #[inline]
+            fn bitor(self, rhs: &$set<T, S>) -> $set<T, S> {
+                let mut union = self$(.$field)?.clone();
+                union.extend(rhs.iter().cloned());
+                $set::from(union)
+            }
+        }
+
+        impl<T, S> ops::BitAnd<&$set<T, S>> for &$set<T, S>
+        where
+            T: Clone + Hash + Eq,
+            S: BuildHasher + Clone,
+        {
+            type Output = $set<T, S>;
+
+            /// Returns the items in both sets.
+            #[inline]
+            fn bitand(self, rhs: &$set<T, S>) -> $set<T, S> {
+                $set::from(self$(.$field)?.filtered(|item| rhs.contains(item)))
+            }
+        }
+
+        impl<T, S> ops::Sub<&$set<T, S>> for &$set<T, S>
+        where
+            T: Clone + Hash + Eq,
+            S: BuildHasher + Clone,
+        {
+            type Output = $set<T, S>;
+
+            /// Returns the items in `self` but not `rhs`.
+            #[inline]
+            fn sub(self, rhs: &$set<T, S>) -> $set<T, S> {
+                $set::from(self$(.$field)?.filtered(|item| !rhs.contains(item)))
+            }
+        }
+
+        impl<T, S> ops::BitXor<&$set<T, S>> for &$set<T, S>
+        where
+            T: Clone + Hash + Eq,
+            S: BuildHasher + Clone,
+        {
+            type Output = $set<T, S>;
+
+            /// Returns the items in exactly one of the sets.
+            #[inline]
+            fn bitxor(self, rhs: &$set<T, S>) -> $set<T, S> {
+                let mut difference = self$(.$field)?.filtered(|item| !rhs.contains(item));
+                difference.extend(rhs.iter().filter(|item| !self.contains(*item)).cloned());
+                $set::from(difference)
+            }
+        }
+
+        impl<T: Hash + Eq, S: BuildHasher> PartialEq for $set<T, S> {
+            #[inline]
+            fn eq(&self, other: &Self) -> bool {
+                self.len() == other.len() && self.is_subset(other)
+            }
+        }
+
+        impl<T: Hash + Eq, S: BuildHasher> Eq for $set<T, S> {}
+
+        impl<T: Hash + Eq, S: BuildHasher> PartialOrd for $set<T, S> {
+            /// Orders sets by inclusion: a subset is less than its supersets, and sets that
+            /// each have items the other lacks are unordered.
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                match self.len().cmp(&other.len()) {
+                    Ordering::Less if self.is_subset(other) => Some(Ordering::Less),
+                    Ordering::Equal if self.is_subset(other) => Some(Ordering::Equal),
+                    Ordering::Greater if other.is_subset(self) => Some(Ordering::Greater),
+                    _ => None,
+                }
+            }
+        }
+
+        impl<T: fmt::Debug, S> fmt::Debug for $set<T, S> {
+            #[inline]
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_set().entries(self.iter()).finish()
+            }
+        }
+    };
+}
+
+impl_operators!(Set);
+impl_operators!(FrozenSet, 0);
+
+impl<T, S> ops::BitOrAssign<&Set<T, S>> for Set<T, S>
+where
+    T: Clone + Hash + Eq,
+    S: BuildHasher,
+{
+    /// Adds the items of `rhs`.
+    #[inline]
+    fn bitor_assign(&mut self, rhs: &Self) {
+        self.extend(rhs.iter().cloned());
+    }
+}
+
+impl<T, S> ops::BitAndAssign<&Set<T, S>> for Set<T, S>
+where
+    T: Clone + Hash + Eq,
+    S: BuildHasher + Clone,
+{
+    /// Removes the items missing from `rhs`.
+    #[inline]
+    fn bitand_assign(&mut self, rhs: &Self) {
+        if !self.is_subset(rhs) {
+            *self = &*self & rhs;
+        }
+    }
+}
+
+impl<T, S> ops::SubAssign<&Set<T, S>> for Set<T, S>
+where
+    T: Clone + Hash + Eq,
+    S: BuildHasher + Clone,
+{
+    /// Removes the items in `rhs`.
+    #[inline]
+    fn sub_assign(&mut self, rhs: &Self) {
+        for item in rhs {
+            self.remove(item);
+        }
+    }
+}
+
+impl<T, S> ops::BitXorAssign<&Set<T, S>> for Set<T, S>
+where
+    T: Clone + Hash + Eq,
+    S: BuildHasher + Clone,
+{
+    /// Removes the items in `rhs`, and adds the ones `rhs` has that `self` doesn't.
+    #[inline]
+    fn bitxor_assign(&mut self, rhs: &Self) {
+        *self = &*self ^ rhs;
+    }
+}
+
+impl<T: Hash, S> Hash for FrozenSet<T, S> {
+    /// Hashes the items without regard to their order, so equal sets hash the same.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let combined = self
+            .iter()
+            .map(crate::hash::hash)
+            .fold(0u64, u64::wrapping_add);
+        state.write_usize(self.len());
+        state.write_u64(combined);
+    }
+}
+
+/// Iterates over a [`Set`]'s or [`FrozenSet`]'s items in insertion order
+///
+/// Returned by [`Set::iter`] and [`FrozenSet::iter`].
+#[derive(Clone, Debug)]
+pub struct Iter<'s, T>(table::Iter<'s, T, ()>);
+
+impl<'s, T> Iterator for Iter<'s, T> {
+    type Item = &'s T;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|bucket| &bucket.key)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'s, T> DoubleEndedIterator for Iter<'s, T> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|bucket| &bucket.key)
+    }
+}
+
+impl<'s, T> ExactSizeIterator for Iter<'s, T> {}
+
+impl<'s, T> FusedIterator for Iter<'s, T> {}
+
+/// Moves a [`Set`]'s or [`FrozenSet`]'s items out in insertion order
+///
+/// Returned by their [`IntoIterator`] implementations.
+#[derive(Debug)]
+pub struct IntoIter<T>(table::IntoIter<T, ()>);
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|bucket| bucket.key)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|bucket| bucket.key)
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> FusedIterator for IntoIter<T> {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    fn model(set: &Set<u8>) -> BTreeSet<u8> {
+        set.iter().copied().collect()
+    }
+
+    #[test]
+    fn operators_match_model() {
+        let sets: Vec<Set<u8>> = (0..16u8)
+            .map(|bits| (0..4).filter(|i| bits & (1 << i) != 0).collect())
+            .collect();
+        for a in &sets {
+            for b in &sets {
+                let (x, y) = (model(a), model(b));
+                assert_eq!(model(&(a | b)), &x | &y);
+                assert_eq!(model(&(a & b)), &x & &y);
+                assert_eq!(model(&(a - b)), &x - &y);
+                assert_eq!(model(&(a ^ b)), &x ^ &y);
+                assert_eq!(a <= b, x.is_subset(&y));
+                assert_eq!(a < b, x.is_subset(&y) && x != y);
+                assert_eq!(a >= b, x.is_superset(&y));
+                assert_eq!(a == b, x == y);
+                assert_eq!(a.is_disjoint(b), x.is_disjoint(&y));
+
+                let mut c = a.clone();
+                c |= b;
+                assert_eq!(c, a | b);
+                let mut c = a.clone();
+                c &= b;
+                assert_eq!(c, a & b);
+                let mut c = a.clone();
+                c -= b;
+                assert_eq!(c, a - b);
+                let mut c = a.clone();
+                c ^= b;
+                assert_eq!(c, a ^ b);
+            }
+        }
+    }
+
+    #[test]
+    fn operators_keep_left_order() {
+        let a = Set::from([5, 3, 1, 4]);
+        let b = Set::from([4, 9, 5, 7]);
+
+        let order = |set: Set<i32>| set.into_iter().collect::<Vec<_>>();
+        assert_eq!(order(&a | &b), [5, 3, 1, 4, 9, 7]);
+        assert_eq!(order(&a & &b), [5, 4]);
+        assert_eq!(order(&a - &b), [3, 1]);
+        assert_eq!(order(&a ^ &b), [3, 1, 9, 7]);
+    }
+
+    #[test]
+    fn frozen_sets_hash_regardless_of_order() {
+        let a = FrozenSet::from(["x", "y", "z"]);
+        let b = FrozenSet::from(["z", "x", "y"]);
+        assert_eq!(crate::hash::hash(&a), crate::hash::hash(&b));
+        assert_ne!(crate::hash::hash(&a), crate::hash::hash(&(&a - &b)));
+
+        let nested = Set::from([a, b]);
+        assert_eq!(nested.len(), 1);
+    }
+
+    #[test]
+    fn copy_on_write() {
+        let original: Set<u32> = (0..100).collect();
+        let frozen = original.clone().freeze();
+        let mut copy = frozen.thaw();
+        assert!(copy.is_shared_with(&original));
+
+        assert!(copy.remove(&5));
+        assert!(copy.insert(500));
+        assert!(!copy.is_shared_with(&original));
+        assert!(original.contains(&5));
+        assert!(frozen.contains(&5));
+        assert!(!original.contains(&500));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn discard_borrows_like_remove() {
+        let mut set: Set<crate::String> = ["a", "b"].iter().map(|&s| s.into()).collect();
+        set.discard("a");
+        set.discard("z");
+        assert_eq!(set.len(), 1);
+        assert!(set.contains("b"));
+    }
+}
@@ -0,0 +1,671 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use super::repr::{InlineString, StringInner, StringRef, CAPACITY};
+use super::shared::SharedString;
+use super::{Bytes, FromUtf8Error, StdString};
+
+/// An immutable, cheaply clonable byte string, like Python's `bytes`
+///
+/// `ByteString` shares its representation with [`String`][super::String]: short byte strings are
+/// stored inline and longer ones share a reference-counted buffer, so cloning and slicing never
+/// copy.  Unlike `String`, the content does not need to be UTF-8.
+///
+/// # Examples
+///
+/// ```
+/// let packet = ergo_core::ByteString::from(b"\x01\x02ping\xff");
+///
+/// assert_eq!(packet.find("ping"), Some(2));
+/// assert_eq!(packet.hex(), "010270696e67ff");
+/// assert!(packet.decode().is_err());
+/// ```
+#[derive(Clone)]
+pub struct ByteString(StringInner);
+
+impl ByteString {
+    /// Creates a new empty `ByteString`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::ByteString::new();
+    /// ```
+    #[inline]
+    pub const fn new() -> Self {
+        Self(StringInner::new())
+    }
+
+    /// Returns the length of this `ByteString`, in bytes.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let a = ergo_core::ByteString::from(b"foo");
+    /// assert_eq!(a.byte_len(), 3);
+    /// ```
+    #[inline]
+    pub fn byte_len(&self) -> usize {
+        self.as_bytes().len()
+    }
+
+    /// Returns `true` if `self` has a length of zero bytes.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::ByteString::new();
+    /// assert!(s.is_empty());
+    ///
+    /// let s = ergo_core::ByteString::from(b"not empty");
+    /// assert!(!s.is_empty());
+    /// ```
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.as_bytes().is_empty()
+    }
+
+    /// Returns a subslice of `ByteString`.
+    ///
+    /// This is the non-panicking alternative to indexing. Out of bounds ranges are clamped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let v = ergo_core::ByteString::from(b"Hello World");
+    ///
+    /// assert_eq!(Some(ergo_core::ByteString::from(b"Hell")), v.get(0..4));
+    /// ```
+    #[inline]
+    pub fn get(&self, range: impl std::ops::RangeBounds<usize>) -> Option<Self> {
+        match crate::range::coerce_range(self.byte_len(), range) {
+            Some(range) => self.as_bytes().get(range).map(|s| self.own_bytes(s)),
+            None => Some(Self::new()),
+        }
+    }
+
+    /// Divide one byte string into two at an index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mid > self.byte_len()`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::ByteString::from(b"Per Martin");
+    ///
+    /// let (first, last) = s.split_at(3);
+    ///
+    /// assert_eq!(first, b"Per");
+    /// assert_eq!(last, b" Martin");
+    /// ```
+    #[inline]
+    pub fn split_at(&self, mid: usize) -> (Self, Self) {
+        let (left, right) = self.as_bytes().split_at(mid);
+        (self.own_bytes(left), self.own_bytes(right))
+    }
+
+    /// An iterator over the bytes of a byte string.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let mut bytes = ergo_core::ByteString::from(b"hi").bytes();
+    ///
+    /// assert_eq!(Some(b'h'), bytes.next());
+    /// assert_eq!(Some(b'i'), bytes.next());
+    /// assert_eq!(None, bytes.next());
+    /// ```
+    #[inline]
+    pub fn bytes(&self) -> Bytes {
+        Bytes::new(self.clone())
+    }
+
+    /// Returns the byte index of the first occurrence of `needle`.
+    ///
+    /// An empty `needle` is found at index `0`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::ByteString::from(b"GET /index.html HTTP/1.1");
+    ///
+    /// assert_eq!(s.find(" "), Some(3));
+    /// assert_eq!(s.find(b"HTTP"), Some(16));
+    /// assert_eq!(s.find("POST"), None);
+    /// ```
+    #[inline]
+    pub fn find(&self, needle: impl AsRef<[u8]>) -> Option<usize> {
+        super::search::find(self.as_bytes(), needle.as_ref())
+    }
+
+    /// Returns the byte index of the last occurrence of `needle`.
+    ///
+    /// An empty `needle` is found at the end.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::ByteString::from(b"a/b/c");
+    ///
+    /// assert_eq!(s.rfind("/"), Some(3));
+    /// assert_eq!(s.rfind("?"), None);
+    /// ```
+    #[inline]
+    pub fn rfind(&self, needle: impl AsRef<[u8]>) -> Option<usize> {
+        super::search::rfind(self.as_bytes(), needle.as_ref())
+    }
+
+    /// Returns `true` if `needle` occurs in `self`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::ByteString::from(b"\x00\x01magic\x02");
+    ///
+    /// assert!(s.contains("magic"));
+    /// assert!(!s.contains(b"\xff"));
+    /// ```
+    #[inline]
+    pub fn contains(&self, needle: impl AsRef<[u8]>) -> bool {
+        self.find(needle).is_some()
+    }
+
+    /// Returns `true` if `self` begins with `prefix`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::ByteString::from(b"\x89PNG\r\n");
+    ///
+    /// assert!(s.starts_with(b"\x89PNG"));
+    /// ```
+    #[inline]
+    pub fn starts_with(&self, prefix: impl AsRef<[u8]>) -> bool {
+        self.as_bytes().starts_with(prefix.as_ref())
+    }
+
+    /// Returns `true` if `self` ends with `suffix`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::ByteString::from(b"line\r\n");
+    ///
+    /// assert!(s.ends_with("\r\n"));
+    /// ```
+    #[inline]
+    pub fn ends_with(&self, suffix: impl AsRef<[u8]>) -> bool {
+        self.as_bytes().ends_with(suffix.as_ref())
+    }
+
+    /// Splits the byte string on each occurrence of `sep`.
+    ///
+    /// Consecutive separators produce empty byte strings.  The pieces share `self`'s buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sep` is empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::ByteString::from(b"a,b,,c");
+    ///
+    /// assert_eq!(s.split(","), [&b"a"[..], b"b", b"", b"c"]);
+    /// ```
+    pub fn split(&self, sep: impl AsRef<[u8]>) -> Vec<Self> {
+        let sep = sep.as_ref();
+        assert!(!sep.is_empty(), "empty separator");
+
+        let finder = super::search::Finder::new(sep);
+        let mut pieces = Vec::new();
+        let mut rest = self.as_bytes();
+        while let Some(index) = finder.find(rest) {
+            pieces.push(self.own_bytes(&rest[..index]));
+            rest = &rest[index + sep.len()..];
+        }
+        pieces.push(self.own_bytes(rest));
+        pieces
+    }
+
+    /// Splits the byte string on runs of ASCII whitespace, ignoring leading and trailing
+    /// whitespace.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::ByteString::from(b"  GET\t/  HTTP/1.1\r\n");
+    ///
+    /// assert_eq!(s.split_whitespace(), [&b"GET"[..], b"/", b"HTTP/1.1"]);
+    /// ```
+    pub fn split_whitespace(&self) -> Vec<Self> {
+        self.as_bytes()
+            .split(|b| b.is_ascii_whitespace())
+            .filter(|piece| !piece.is_empty())
+            .map(|piece| self.own_bytes(piece))
+            .collect()
+    }
+
+    /// Appends the given bytes onto the end of this `ByteString`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::ByteString::from(b"\x00\x01");
+    ///
+    /// let s = s.join_bytes(b"\x02");
+    /// assert_eq!(s, b"\x00\x01\x02");
+    /// ```
+    #[inline]
+    #[must_use = "this returns the joined string as a new string, \
+                  without modifying the original"]
+    pub fn join_bytes(&self, bytes: impl AsRef<[u8]>) -> Self {
+        let mut buffer = self.as_bytes().to_vec();
+        buffer.extend_from_slice(bytes.as_ref());
+        Self::from(buffer.as_slice())
+    }
+
+    /// Formats each byte as two lowercase hexadecimal digits.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::ByteString::from(b"\xde\xad\xbe\xef");
+    ///
+    /// assert_eq!(s.hex(), "deadbeef");
+    /// ```
+    pub fn hex(&self) -> super::String {
+        const DIGITS: &[u8; 16] = b"0123456789abcdef";
+        let mut hex = StdString::with_capacity(self.byte_len() * 2);
+        for b in self.as_bytes() {
+            hex.push(DIGITS[(b >> 4) as usize] as char);
+            hex.push(DIGITS[(b & 0xf) as usize] as char);
+        }
+        super::String::from(hex)
+    }
+
+    /// Parses pairs of hexadecimal digits, ignoring ASCII whitespace between pairs.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::ByteString::from_hex("de ad BE EF").unwrap();
+    /// assert_eq!(s, b"\xde\xad\xbe\xef");
+    ///
+    /// let err = ergo_core::ByteString::from_hex("de ag").unwrap_err();
+    /// assert_eq!(err.offset(), 4);
+    /// ```
+    pub fn from_hex(hex: impl AsRef<str>) -> Result<Self, FromHexError> {
+        fn digit(b: u8) -> Option<u8> {
+            match b {
+                b'0'..=b'9' => Some(b - b'0'),
+                b'a'..=b'f' => Some(b - b'a' + 10),
+                b'A'..=b'F' => Some(b - b'A' + 10),
+                _ => None,
+            }
+        }
+
+        let hex = hex.as_ref().as_bytes();
+        let mut bytes = Vec::with_capacity(hex.len() / 2);
+        let mut offset = 0;
+        while offset < hex.len() {
+            if hex[offset].is_ascii_whitespace() {
+                offset += 1;
+                continue;
+            }
+            let high = digit(hex[offset]).ok_or(FromHexError { offset })?;
+            let low = hex
+                .get(offset + 1)
+                .copied()
+                .and_then(digit)
+                .ok_or(FromHexError { offset: offset + 1 })?;
+            bytes.push(high << 4 | low);
+            offset += 2;
+        }
+        Ok(Self::from(bytes))
+    }
+
+    /// Converts to a [`String`][super::String] if the content is valid UTF-8, without copying.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::ByteString::from("héllo");
+    /// assert_eq!(s.decode().unwrap(), "héllo");
+    ///
+    /// let s = ergo_core::ByteString::from(b"h\xe9llo");
+    /// assert_eq!(s.decode().unwrap_err().valid_up_to(), 1);
+    /// ```
+    #[inline]
+    pub fn decode(&self) -> Result<super::String, FromUtf8Error> {
+        match std::str::from_utf8(self.as_bytes()) {
+            Ok(_) => Ok(super::String(self.0.clone())),
+            Err(err) => Err(FromUtf8Error::new(self.clone(), err)),
+        }
+    }
+
+    /// Converts to a [`String`][super::String], replacing invalid UTF-8 sequences with
+    /// `U+FFFD REPLACEMENT CHARACTER`.
+    ///
+    /// Valid UTF-8 is converted without copying.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::ByteString::from(b"h\xe9llo");
+    /// assert_eq!(s.decode_lossy(), "h\u{FFFD}llo");
+    /// ```
+    #[inline]
+    pub fn decode_lossy(&self) -> super::String {
+        match self.decode() {
+            Ok(s) => s,
+            Err(_) => super::String::from(StdString::from_utf8_lossy(self.as_bytes()).as_ref()),
+        }
+    }
+
+    fn own_bytes(&self, subset: &[u8]) -> Self {
+        if subset.is_empty() {
+            Self::new()
+        } else {
+            match self.0.get() {
+                StringRef::Inline(s) => Self(StringInner::from(s.own_bytes(subset))),
+                StringRef::Shared(s) => Self(StringInner::from(s.own_bytes(subset))),
+            }
+        }
+    }
+}
+
+/// Transitional Python API
+impl ByteString {
+    #[deprecated = "In Rust, we refer to this as `from_hex`"]
+    pub fn fromhex(hex: impl AsRef<str>) -> Result<Self, FromHexError> {
+        Self::from_hex(hex)
+    }
+}
+
+/// Interop
+impl ByteString {
+    /// Extracts a byte slice containing the entire `ByteString`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::ByteString::from(b"foo");
+    ///
+    /// assert_eq!(b"foo", s.as_bytes());
+    /// ```
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        match self.0.get() {
+            StringRef::Inline(s) => s.as_bytes(),
+            StringRef::Shared(s) => s.as_bytes(),
+        }
+    }
+}
+
+/// The error returned by [`ByteString::from_hex`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FromHexError {
+    offset: usize,
+}
+
+impl FromHexError {
+    /// The byte offset of the invalid or missing hexadecimal digit
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl fmt::Display for FromHexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid or missing hexadecimal digit at offset {}",
+            self.offset
+        )
+    }
+}
+
+impl std::error::Error for FromHexError {}
+
+impl Default for ByteString {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'s> From<&'s [u8]> for ByteString {
+    #[inline]
+    fn from(other: &'s [u8]) -> Self {
+        match other.len() {
+            0 => Self::new(),
+            len if len <= CAPACITY => Self(StringInner::from(InlineString::from(other))),
+            _ => Self(StringInner::from(SharedString::from(other))),
+        }
+    }
+}
+
+impl<'s, const N: usize> From<&'s [u8; N]> for ByteString {
+    #[inline]
+    fn from(other: &'s [u8; N]) -> Self {
+        other[..].into()
+    }
+}
+
+impl From<Vec<u8>> for ByteString {
+    #[inline]
+    fn from(other: Vec<u8>) -> Self {
+        other.as_slice().into()
+    }
+}
+
+impl<'s> From<&'s str> for ByteString {
+    #[inline]
+    fn from(other: &'s str) -> Self {
+        other.as_bytes().into()
+    }
+}
+
+impl From<StdString> for ByteString {
+    #[inline]
+    fn from(other: StdString) -> Self {
+        other.as_bytes().into()
+    }
+}
+
+impl From<super::String> for ByteString {
+    /// Reinterprets the `String`'s buffer without copying
+    #[inline]
+    fn from(other: super::String) -> Self {
+        Self(other.0)
+    }
+}
+
+impl std::iter::FromIterator<u8> for ByteString {
+    fn from_iter<I: IntoIterator<Item = u8>>(iter: I) -> Self {
+        Vec::from_iter(iter).into()
+    }
+}
+
+impl IntoIterator for ByteString {
+    type Item = u8;
+    type IntoIter = Bytes;
+
+    #[inline]
+    fn into_iter(self) -> Bytes {
+        Bytes::new(self)
+    }
+}
+
+impl IntoIterator for &ByteString {
+    type Item = u8;
+    type IntoIter = Bytes;
+
+    #[inline]
+    fn into_iter(self) -> Bytes {
+        self.bytes()
+    }
+}
+
+/// Formats like a Rust byte string literal
+impl fmt::Debug for ByteString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("b\"")?;
+        for &b in self.as_bytes() {
+            match b {
+                b'\'' => f.write_str("'")?,
+                _ => {
+                    for e in std::ascii::escape_default(b) {
+                        fmt::Write::write_char(f, e as char)?;
+                    }
+                }
+            }
+        }
+        f.write_str("\"")
+    }
+}
+
+impl PartialEq for ByteString {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+
+impl Eq for ByteString {}
+
+impl_eq! { ByteString, [u8] }
+impl_eq! { ByteString, &'a [u8] }
+impl_eq! { ByteString, Vec<u8> }
+
+impl<const N: usize> PartialEq<[u8; N]> for ByteString {
+    #[inline]
+    fn eq(&self, other: &[u8; N]) -> bool {
+        self.as_bytes() == &other[..]
+    }
+}
+
+impl<'a, const N: usize> PartialEq<&'a [u8; N]> for ByteString {
+    #[inline]
+    fn eq(&self, other: &&'a [u8; N]) -> bool {
+        self.as_bytes() == &other[..]
+    }
+}
+
+impl PartialOrd for ByteString {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ByteString {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_bytes().cmp(other.as_bytes())
+    }
+}
+
+impl Hash for ByteString {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_bytes().hash(state);
+    }
+}
+
+impl AsRef<[u8]> for ByteString {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl std::borrow::Borrow<[u8]> for ByteString {
+    #[inline]
+    fn borrow(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl std::ops::Index<std::ops::RangeFull> for ByteString {
+    type Output = [u8];
+
+    #[inline]
+    fn index(&self, _index: std::ops::RangeFull) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn split_edges() {
+        let s = ByteString::from(b",a,,b,");
+        assert_eq!(s.split(","), [&b""[..], b"a", b"", b"b", b""]);
+
+        let s = ByteString::new();
+        assert_eq!(s.split(","), [&b""[..]]);
+
+        let s = ByteString::from(b"a<>b<>");
+        assert_eq!(s.split("<>"), [&b"a"[..], b"b", b""]);
+    }
+
+    #[test]
+    fn split_shares_buffer() {
+        let s = ByteString::from("field,".repeat(10));
+        let pieces = s.split(",");
+        assert_eq!(pieces.len(), 11);
+        let base = s.as_bytes().as_ptr();
+        assert_eq!(pieces[1].as_bytes().as_ptr(), base.wrapping_add(6));
+    }
+
+    #[test]
+    fn debug() {
+        let s = ByteString::from(b"it's \"\x00\xff\"\n");
+        assert_eq!(format!("{:?}", s), r#"b"it's \"\x00\xff\"\n""#);
+    }
+
+    #[test]
+    fn hex_roundtrip() {
+        let s: ByteString = (0..=255).collect();
+        assert_eq!(ByteString::from_hex(s.hex()).unwrap(), s);
+    }
+}
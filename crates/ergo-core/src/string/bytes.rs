@@ -1,10 +1,11 @@
+/// An iterator over the bytes of a [`ByteString`][super::ByteString]
 pub struct Bytes {
-    buffer: super::String,
+    buffer: super::ByteString,
     index: usize,
 }
 
 impl Bytes {
-    pub(super) fn new(buffer: super::String) -> Self {
+    pub(super) fn new(buffer: super::ByteString) -> Self {
         Self { buffer, index: 0 }
     }
 }
@@ -14,18 +15,14 @@ impl Iterator for Bytes {
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        if self.index < self.buffer.byte_len() {
-            let current = self.index;
-            self.index += 1;
-            Some(self.buffer.as_str().as_bytes()[current])
-        } else {
-            None
-        }
+        let current = *self.buffer.as_bytes().get(self.index)?;
+        self.index += 1;
+        Some(current)
     }
 
     #[inline]
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let len = self.buffer.byte_len();
+        let len = self.len();
         (len, Some(len))
     }
 }
@@ -33,7 +30,7 @@ impl Iterator for Bytes {
 impl ExactSizeIterator for Bytes {
     #[inline]
     fn len(&self) -> usize {
-        self.buffer.byte_len()
+        self.buffer.byte_len() - self.index
     }
 }
 
@@ -0,0 +1,61 @@
+/// An iterator over the UTF-16 code units of a [`String`][super::String]
+pub struct EncodeUtf16 {
+    buffer: super::String,
+    index: usize,
+    pending: Option<u16>,
+}
+
+impl EncodeUtf16 {
+    pub(super) fn new(buffer: super::String) -> Self {
+        Self {
+            buffer,
+            index: 0,
+            pending: None,
+        }
+    }
+}
+
+impl Iterator for EncodeUtf16 {
+    type Item = u16;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(low) = self.pending.take() {
+            return Some(low);
+        }
+
+        let c = self.buffer.as_str()[self.index..].chars().next()?;
+        self.index += c.len_utf8();
+        let mut units = [0; 2];
+        match *c.encode_utf16(&mut units) {
+            [unit] => Some(unit),
+            [high, low] => {
+                self.pending = Some(low);
+                Some(high)
+            }
+            _ => unreachable!("chars are 1 or 2 UTF-16 code units"),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // Like `str::EncodeUtf16`: every 1-3 bytes are one code unit, every 4 bytes are two
+        let remaining = self.buffer.byte_len() - self.index;
+        let pending = self.pending.is_some() as usize;
+        (remaining / 3 + pending, Some(remaining + pending))
+    }
+}
+
+impl std::iter::FusedIterator for EncodeUtf16 {}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn matches_std() {
+        let fixture = "a𝄞ß€\u{10FFFF}z";
+        let actual: Vec<u16> = super::super::String::from(fixture).encode_utf16().collect();
+        let expected: Vec<u16> = fixture.encode_utf16().collect();
+        assert_eq!(actual, expected);
+        assert_eq!(super::super::String::from_utf16(&actual).unwrap(), fixture);
+    }
+}
@@ -0,0 +1,113 @@
+use std::fmt;
+
+/// The error returned when converting bytes into a [`String`][super::String] that are not valid
+/// UTF-8
+///
+/// The original bytes can be recovered with [`FromUtf8Error::into_bytes`].
+///
+/// # Examples
+///
+/// ```
+/// let err = ergo_core::String::from_utf8(b"ab\xffcd").unwrap_err();
+///
+/// assert_eq!(err.valid_up_to(), 2);
+/// assert_eq!(err.error_len(), Some(1));
+/// assert_eq!(err.into_bytes(), b"ab\xffcd");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FromUtf8Error {
+    bytes: super::ByteString,
+    error: std::str::Utf8Error,
+}
+
+impl FromUtf8Error {
+    pub(crate) fn new(bytes: super::ByteString, error: std::str::Utf8Error) -> Self {
+        Self { bytes, error }
+    }
+
+    /// The byte offset of the first invalid sequence; everything before it is valid UTF-8.
+    pub fn valid_up_to(&self) -> usize {
+        self.error.valid_up_to()
+    }
+
+    /// The length of the invalid sequence, or [`None`] if the input ended in the middle of a
+    /// sequence.
+    pub fn error_len(&self) -> Option<usize> {
+        self.error.error_len()
+    }
+
+    /// The bytes that failed to convert.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.bytes.as_bytes()
+    }
+
+    /// Returns the bytes that failed to convert.
+    pub fn into_bytes(self) -> super::ByteString {
+        self.bytes
+    }
+
+    /// Interop with [`std::str::from_utf8`].
+    pub fn utf8_error(&self) -> std::str::Utf8Error {
+        self.error
+    }
+}
+
+impl fmt::Display for FromUtf8Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.error, f)
+    }
+}
+
+impl std::error::Error for FromUtf8Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// The error returned when converting UTF-16 into a [`String`][super::String] with an unpaired
+/// surrogate
+///
+/// # Examples
+///
+/// ```
+/// let err = ergo_core::String::from_utf16(&[0x68, 0xD800, 0x69]).unwrap_err();
+///
+/// assert_eq!(err.valid_up_to(), 1);
+/// assert_eq!(err.unpaired_surrogate(), 0xD800);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FromUtf16Error {
+    valid_up_to: usize,
+    unpaired_surrogate: u16,
+}
+
+impl FromUtf16Error {
+    pub(crate) fn new(valid_up_to: usize, unpaired_surrogate: u16) -> Self {
+        Self {
+            valid_up_to,
+            unpaired_surrogate,
+        }
+    }
+
+    /// The offset, in `u16`s, of the unpaired surrogate; everything before it is valid UTF-16.
+    pub fn valid_up_to(&self) -> usize {
+        self.valid_up_to
+    }
+
+    /// The code unit that could not be decoded.
+    pub fn unpaired_surrogate(&self) -> u16 {
+        self.unpaired_surrogate
+    }
+}
+
+impl fmt::Display for FromUtf16Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unpaired surrogate {:#06x} at index {}",
+            self.unpaired_surrogate, self.valid_up_to
+        )
+    }
+}
+
+impl std::error::Error for FromUtf16Error {}
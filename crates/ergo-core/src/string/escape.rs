@@ -0,0 +1,367 @@
+//! Escaping helpers for [`String`][super::String]
+//!
+//! Each returns [`None`] when the input needs no changes, so the caller can clone instead of
+//! copying.
+
+use std::fmt::Write;
+
+use super::StdString;
+
+/// The quote Python's `repr` picks: single quotes unless only double quotes avoid escaping.
+pub(super) fn repr_quote(s: &str) -> char {
+    if s.contains('\'') && !s.contains('"') {
+        '"'
+    } else {
+        '\''
+    }
+}
+
+/// Python's `repr`, with the surrounding quotes.
+pub(super) fn repr(s: &str) -> StdString {
+    let quote = repr_quote(s);
+    let escaped = debug(s);
+    let escaped = escaped.as_deref().unwrap_or(s);
+    let mut repr = StdString::with_capacity(escaped.len() + 2);
+    repr.push(quote);
+    repr.push_str(escaped);
+    repr.push(quote);
+    repr
+}
+
+/// Python's `repr`, without the surrounding quotes.
+pub(super) fn debug(s: &str) -> Option<StdString> {
+    let quote = repr_quote(s);
+    escape(s, |c, escaped| {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c if c == quote => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c if is_printable(c) => return false,
+            c => match c as u32 {
+                code if code < 0x100 => write!(escaped, "\\x{:02x}", code).unwrap(),
+                code if code < 0x10000 => write!(escaped, "\\u{:04x}", code).unwrap(),
+                code => write!(escaped, "\\U{:08x}", code).unwrap(),
+            },
+        }
+        true
+    })
+}
+
+/// Python and Rust agree on which characters are printable, except that Rust also escapes
+/// grapheme extenders when they can't combine with a preceding character.
+fn is_printable(c: char) -> bool {
+    if c.is_ascii() {
+        return !c.is_ascii_control();
+    }
+    let mut buffer = [b' '; 5];
+    let len = c.encode_utf8(&mut buffer[1..]).len();
+    let combined = std::str::from_utf8(&buffer[..=len]).expect("a space and a char");
+    combined.escape_debug().nth(1) != Some('\\')
+}
+
+/// The content of a JSON string, per RFC 8259.
+pub(super) fn json(s: &str) -> Option<StdString> {
+    escape(s, |c, escaped| {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\u{8}' => escaped.push_str("\\b"),
+            '\u{c}' => escaped.push_str("\\f"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(escaped, "\\u{:04x}", c as u32).unwrap(),
+            _ => return false,
+        }
+        true
+    })
+}
+
+/// Python's `html.escape` with `quote=True`.
+pub(super) fn html(s: &str) -> Option<StdString> {
+    escape(s, |c, escaped| {
+        let entity = match c {
+            '&' => "&amp;",
+            '<' => "&lt;",
+            '>' => "&gt;",
+            '"' => "&quot;",
+            '\'' => "&#x27;",
+            _ => return false,
+        };
+        escaped.push_str(entity);
+        true
+    })
+}
+
+/// Named character references understood by [`unescape_html`]
+const ENTITIES: &[(&str, char)] = &[
+    ("amp", '&'),
+    ("lt", '<'),
+    ("gt", '>'),
+    ("quot", '"'),
+    ("apos", '\''),
+    ("nbsp", '\u{a0}'),
+    ("copy", '©'),
+    ("reg", '®'),
+    ("trade", '™'),
+    ("hellip", '…'),
+    ("mdash", '—'),
+    ("ndash", '–'),
+    ("lsquo", '‘'),
+    ("rsquo", '’'),
+    ("ldquo", '“'),
+    ("rdquo", '”'),
+    ("laquo", '«'),
+    ("raquo", '»'),
+    ("middot", '·'),
+    ("bull", '•'),
+    ("deg", '°'),
+    ("plusmn", '±'),
+    ("times", '×'),
+    ("divide", '÷'),
+    ("euro", '€'),
+    ("pound", '£'),
+    ("yen", '¥'),
+    ("cent", '¢'),
+    ("sect", '§'),
+    ("para", '¶'),
+];
+
+/// Python's `html.unescape` for numeric references and the common named references in
+/// [`ENTITIES`]; anything else is left as-is.
+pub(super) fn unescape_html(s: &str) -> Option<StdString> {
+    if !s.contains('&') {
+        return None;
+    }
+
+    let mut unescaped = StdString::with_capacity(s.len());
+    let mut changed = false;
+    let mut rest = s;
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        rest = &rest[start..];
+        match parse_reference(&rest[1..]) {
+            Some((c, len)) => {
+                unescaped.extend(c);
+                rest = &rest[1 + len..];
+                changed = true;
+            }
+            None => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    if changed {
+        Some(unescaped)
+    } else {
+        None
+    }
+}
+
+/// What the C1 control references `&#128;` to `&#159;` stand for, since pages that use them
+/// almost always meant Windows-1252
+const CP1252_REFERENCES: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8d}', 'Ž', '\u{8f}',
+    '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9d}', 'ž', 'Ÿ',
+];
+
+/// Resolves a numeric reference like HTML5 and Python, returning [`None`] for code points that
+/// are dropped.
+fn numeric_reference(code: u32) -> Option<char> {
+    match code {
+        0x80..=0x9f => Some(CP1252_REFERENCES[(code - 0x80) as usize]),
+        // Out of range code points, surrogates and NUL become U+FFFD
+        0 | 0xd800..=0xdfff | 0x11_0000..=u32::MAX => Some(std::char::REPLACEMENT_CHARACTER),
+        // Other control characters and noncharacters are dropped
+        0x1..=0x8 | 0xb | 0xe..=0x1f | 0x7f | 0xfdd0..=0xfdef => None,
+        code if code & 0xfffe == 0xfffe => None,
+        code => std::char::from_u32(code),
+    }
+}
+
+/// Parses the reference after a `&`, returning the character, if any, and how many bytes it
+/// spans.
+fn parse_reference(s: &str) -> Option<(Option<char>, usize)> {
+    if let Some(numeric) = s.strip_prefix('#') {
+        let (radix, digits, prefix_len) = match numeric.strip_prefix(|c| c == 'x' || c == 'X') {
+            Some(hex) => (16, hex, 2),
+            None => (10, numeric, 1),
+        };
+        let digits_len = digits
+            .find(|c: char| !c.is_digit(radix))
+            .unwrap_or(digits.len());
+        if digits_len == 0 {
+            return None;
+        }
+        let code = u32::from_str_radix(&digits[..digits_len], radix).unwrap_or(u32::MAX);
+        let semicolon = digits[digits_len..].starts_with(';') as usize;
+        Some((numeric_reference(code), prefix_len + digits_len + semicolon))
+    } else {
+        let name_len = s.find(';')?;
+        let name = &s[..name_len];
+        ENTITIES
+            .iter()
+            .find(|(entity, _)| *entity == name)
+            .map(|(_, c)| (Some(*c), name_len + 1))
+    }
+}
+
+/// The characters `urllib.parse.quote` leaves alone by default
+fn is_url_safe(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || matches!(b, b'_' | b'.' | b'-' | b'~' | b'/')
+}
+
+/// Python's `urllib.parse.quote` with the default `safe="/"`.
+pub(super) fn percent_encode(s: &str) -> Option<StdString> {
+    if s.bytes().all(is_url_safe) {
+        return None;
+    }
+
+    let mut encoded = StdString::with_capacity(s.len() * 3);
+    for b in s.bytes() {
+        if is_url_safe(b) {
+            encoded.push(b as char);
+        } else {
+            write!(encoded, "%{:02X}", b).unwrap();
+        }
+    }
+    Some(encoded)
+}
+
+/// Python's `urllib.parse.unquote`: malformed escapes are left as-is and invalid UTF-8 becomes
+/// `U+FFFD REPLACEMENT CHARACTER`.
+pub(super) fn percent_decode(s: &str) -> Option<StdString> {
+    if !s.contains('%') {
+        return None;
+    }
+
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = bytes
+            .get(index + 1..index + 3)
+            .filter(|hex| bytes[index] == b'%' && hex.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(b) => {
+                decoded.push(b);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    if decoded == bytes {
+        return None;
+    }
+    Some(StdString::from_utf8_lossy(&decoded).into_owned())
+}
+
+/// Python's `shlex.quote`.
+pub(super) fn shell_quote(s: &str) -> Option<StdString> {
+    let is_safe = |b: u8| b.is_ascii_alphanumeric() || b"_@%+=:,./-".contains(&b);
+    if !s.is_empty() && s.bytes().all(is_safe) {
+        return None;
+    }
+
+    Some(format!("'{}'", s.replace('\'', "'\"'\"'")))
+}
+
+/// Copies `s`, letting `escape` push a replacement for each char or return `false` to keep it.
+fn escape(s: &str, mut escape: impl FnMut(char, &mut StdString) -> bool) -> Option<StdString> {
+    let mut escaped = StdString::new();
+    for (index, c) in s.char_indices() {
+        let len = escaped.len();
+        if escape(c, &mut escaped) {
+            if len == 0 && index != 0 {
+                // First escape: copy everything before it
+                let replacement = escaped.split_off(0);
+                escaped.reserve(s.len() + replacement.len());
+                escaped.push_str(&s[..index]);
+                escaped.push_str(&replacement);
+            }
+        } else if !escaped.is_empty() {
+            escaped.push(c);
+        }
+    }
+    if escaped.is_empty() {
+        None
+    } else {
+        Some(escaped)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn debug_matches_python() {
+        // Expected values are from CPython 3 `repr`, without the quotes
+        let cases = [
+            ("abc", None),
+            ("a'b", None),
+            ("a'b\"c", Some("a\\'b\"c")),
+            ("tab\there\n", Some("tab\\there\\n")),
+            ("\u{0}\u{7f}\u{a0}\u{ad}", Some("\\x00\\x7f\\xa0\\xad")),
+            ("\u{2028}\u{e000}\u{feff}", Some("\\u2028\\ue000\\ufeff")),
+            ("\u{10ffff}", Some("\\U0010ffff")),
+            ("e\u{301}😀", None),
+            ("\u{301}", None),
+            ("back\\slash", Some("back\\\\slash")),
+        ];
+        for (input, expected) in cases.iter() {
+            assert_eq!(debug(input).as_deref(), *expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn escape_helper_keeps_prefix_and_suffix() {
+        assert_eq!(html("a&b<c").as_deref(), Some("a&amp;b&lt;c"));
+        assert_eq!(html("&").as_deref(), Some("&amp;"));
+        assert_eq!(html("ab").as_deref(), None);
+    }
+
+    #[test]
+    fn unescape_html_matches_python() {
+        let cases = [
+            ("&amp;lt;", "&lt;"),
+            ("&#39;&#x27;&#X27;", "'''"),
+            ("&#65", "A"),
+            ("&#0;&#xd800;&#1114112;", "\u{fffd}\u{fffd}\u{fffd}"),
+            ("&#150;&#x80;&#x9f;&#x81;", "–€Ÿ\u{81}"),
+            ("a&#1;&#x7f;&#xfffe;&#x10ffff;b", "ab"),
+            ("&#13;&#127744;", "\r🌀"),
+        ];
+        for (input, expected) in cases.iter() {
+            assert_eq!(
+                unescape_html(input).as_deref(),
+                Some(*expected),
+                "{}",
+                input
+            );
+        }
+        assert_eq!(unescape_html("&unknown; & &#; &#x;"), None);
+    }
+
+    #[test]
+    fn percent_roundtrip() {
+        let fixture = "/path with spaces/ü?q=1&r=%";
+        let encoded = percent_encode(fixture).unwrap();
+        assert_eq!(encoded, "/path%20with%20spaces/%C3%BC%3Fq%3D1%26r%3D%25");
+        assert_eq!(percent_decode(&encoded).as_deref(), Some(fixture));
+        assert_eq!(percent_decode("%zz%+f%4"), None);
+        assert_eq!(percent_decode("%ff").as_deref(), Some("\u{fffd}"));
+    }
+}
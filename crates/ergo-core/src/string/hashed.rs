@@ -0,0 +1,200 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use super::{StdString, String};
+
+/// A [`String`] that remembers its hash
+///
+/// Hashing a `String` reads every byte each time it is looked up.  A `HashedString` hashes once,
+/// with a [`FastHasher`][crate::hash::FastHasher], and feeds that single `u64` to the map's
+/// hasher afterwards, so repeatedly looking up or inserting the same long keys, like in a join,
+/// skips rehashing them.  Comparing for equality checks the hashes first.
+///
+/// A `HashedString` hashes differently from a [`str`], so maps keyed by it are looked up with
+/// `HashedString`s rather than `&str`s.
+///
+/// # Examples
+///
+/// ```
+/// use ergo_core::hash::FastHashMap;
+/// use ergo_core::HashedString;
+///
+/// let customers: Vec<HashedString> = ["alice@example.com", "bob@example.com"]
+///     .iter()
+///     .map(|&email| HashedString::from(email))
+///     .collect();
+///
+/// let mut orders = FastHashMap::default();
+/// for (i, customer) in customers.iter().cycle().take(5).enumerate() {
+///     orders.entry(customer.clone()).or_insert_with(Vec::new).push(i);
+/// }
+///
+/// assert_eq!(orders[&customers[0]], [0, 2, 4]);
+/// ```
+#[derive(Clone)]
+pub struct HashedString {
+    hash: u64,
+    string: String,
+}
+
+impl HashedString {
+    /// Hashes `string`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::HashedString::new("key");
+    /// assert_eq!(s.as_str(), "key");
+    /// ```
+    pub fn new(string: impl Into<String>) -> Self {
+        let string = string.into();
+        Self {
+            hash: crate::hash::hash(string.as_str()),
+            string,
+        }
+    }
+
+    /// Returns the hash computed by [`HashedString::new`].
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::HashedString::new("key");
+    /// assert_eq!(s.precomputed_hash(), ergo_core::hash::hash("key"));
+    /// ```
+    #[inline]
+    pub fn precomputed_hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Returns the underlying `String`.
+    #[inline]
+    pub fn as_string(&self) -> &String {
+        &self.string
+    }
+
+    /// Discards the hash, returning the underlying `String`.
+    #[inline]
+    pub fn into_string(self) -> String {
+        self.string
+    }
+}
+
+/// Interop
+impl HashedString {
+    /// Extracts a string slice containing the entire `HashedString`.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        self.string.as_str()
+    }
+}
+
+impl Default for HashedString {
+    #[inline]
+    fn default() -> Self {
+        Self::new(String::new())
+    }
+}
+
+impl From<String> for HashedString {
+    #[inline]
+    fn from(other: String) -> Self {
+        Self::new(other)
+    }
+}
+
+impl<'s> From<&'s String> for HashedString {
+    #[inline]
+    fn from(other: &'s String) -> Self {
+        Self::new(other.clone())
+    }
+}
+
+impl<'s> From<&'s str> for HashedString {
+    #[inline]
+    fn from(other: &'s str) -> Self {
+        Self::new(other)
+    }
+}
+
+impl From<StdString> for HashedString {
+    #[inline]
+    fn from(other: StdString) -> Self {
+        Self::new(other)
+    }
+}
+
+impl From<HashedString> for String {
+    #[inline]
+    fn from(other: HashedString) -> Self {
+        other.string
+    }
+}
+
+impl fmt::Display for HashedString {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.string, f)
+    }
+}
+
+impl fmt::Debug for HashedString {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.string, f)
+    }
+}
+
+impl PartialEq for HashedString {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.hash == other.hash && self.string == other.string
+    }
+}
+
+impl Eq for HashedString {}
+
+impl_eq! { HashedString, str }
+impl_eq! { HashedString, &'a str }
+impl_eq! { HashedString, StdString }
+impl_eq! { HashedString, String }
+
+impl PartialOrd for HashedString {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for HashedString {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.string.cmp(&other.string)
+    }
+}
+
+impl Hash for HashedString {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash);
+    }
+}
+
+impl AsRef<str> for HashedString {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl std::ops::Index<std::ops::RangeFull> for HashedString {
+    type Output = str;
+
+    #[inline]
+    fn index(&self, _index: std::ops::RangeFull) -> &str {
+        self.as_str()
+    }
+}
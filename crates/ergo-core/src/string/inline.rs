@@ -2,33 +2,50 @@ use std::cmp::Ordering;
 use std::fmt;
 use std::hash::{Hash, Hasher};
 
+use super::repr::Tag;
+
+/// Content stored directly in the `String`, with the length packed into the [`Tag`]
 #[derive(Copy, Clone)]
+#[repr(C)]
 pub(crate) struct InlineString<const N: usize> {
-    len: u8,
     array: [u8; N],
+    tag: Tag,
 }
 
 impl<const N: usize> InlineString<N> {
     pub(crate) fn own_str(&self, subset: &str) -> Self {
-        let (start, end) = super::calculate_subset(self.as_str(), subset);
-        Self::from(&self.as_str()[start..end])
+        self.own_bytes(subset.as_bytes())
+    }
+
+    pub(crate) fn own_bytes(&self, subset: &[u8]) -> Self {
+        let (start, end) = super::calculate_subset(self.as_bytes(), subset);
+        Self::from(&self.as_bytes()[start..end])
     }
 
+    /// Only valid when created from UTF-8
     pub(crate) fn as_str(&self) -> &str {
-        let len = self.len as usize;
-        unsafe { std::str::from_utf8_unchecked(&self.array[..len]) }
+        unsafe { std::str::from_utf8_unchecked(self.as_bytes()) }
+    }
+
+    pub(crate) fn as_bytes(&self) -> &[u8] {
+        &self.array[..self.len()]
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.tag.inline_len().unwrap_or_default()
     }
 }
 
 impl<const N: usize> fmt::Debug for InlineString<N> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        fmt::Debug::fmt(self.as_str(), f)
+        fmt::Debug::fmt(self.as_bytes(), f)
     }
 }
 
 impl<const N: usize> PartialEq for InlineString<N> {
     fn eq(&self, other: &Self) -> bool {
-        self.as_str() == other.as_str()
+        self.as_bytes() == other.as_bytes()
     }
 }
 
@@ -42,27 +59,33 @@ impl<const N: usize> PartialOrd for InlineString<N> {
 
 impl<const N: usize> Ord for InlineString<N> {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.as_str().cmp(other.as_str())
+        self.as_bytes().cmp(other.as_bytes())
     }
 }
 
 impl<const N: usize> Hash for InlineString<N> {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        self.as_str().hash(state);
+        self.as_bytes().hash(state);
     }
 }
 
 impl<'s, const N: usize> From<&'s str> for InlineString<N> {
     fn from(other: &'s str) -> Self {
-        let b = other.as_bytes();
+        Self::from(other.as_bytes())
+    }
+}
+
+impl<'s, const N: usize> From<&'s [u8]> for InlineString<N> {
+    fn from(other: &'s [u8]) -> Self {
+        let b = other;
         let len = b.len();
         debug_assert!(len <= N);
 
         let mut array = [0; N];
         array[..len].copy_from_slice(b);
         Self {
-            len: len as u8,
             array,
+            tag: Tag::inline(len),
         }
     }
 }
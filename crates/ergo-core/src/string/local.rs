@@ -0,0 +1,338 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use super::escape;
+use super::repr::{InlineString, StringInner, StringRef, CAPACITY};
+use super::shared::{Local, SharedString};
+use super::StdString;
+
+/// A single-threaded [`String`][super::String]
+///
+/// `LocalString` has the same layout and inline capacity as `String` but its shared buffers use
+/// non-atomic reference counts, like [`Rc`][std::rc::Rc] compared to [`Arc`][std::sync::Arc].
+/// This makes clones and slices cheaper in tight loops at the cost of not being [`Send`] or
+/// [`Sync`].
+///
+/// Only the core operations are offered; convert to and from `String` around the hot loop.
+/// Conversions are free for inline strings and for shared buffers with no other references,
+/// otherwise the content is copied.
+///
+/// # Examples
+///
+/// ```
+/// let s = ergo_core::String::from("Hello World, from a shared buffer");
+///
+/// let local = ergo_core::LocalString::from(s);
+/// let hello = local.get(..5).unwrap();
+///
+/// let s = ergo_core::String::from(hello);
+/// assert_eq!(s, "Hello");
+/// ```
+#[derive(Clone)]
+pub struct LocalString(StringInner<Local>);
+
+impl LocalString {
+    /// Creates a new empty `LocalString`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::LocalString::new();
+    /// ```
+    #[inline]
+    pub const fn new() -> Self {
+        Self(StringInner::new())
+    }
+
+    /// Returns the length of this `LocalString`, in bytes, not [`char`]s or
+    /// graphemes.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let a = ergo_core::LocalString::from("foo");
+    /// assert_eq!(a.byte_len(), 3);
+    /// ```
+    #[inline]
+    pub fn byte_len(&self) -> usize {
+        self.as_str().len()
+    }
+
+    /// Returns the length of this `LocalString`, in [`char`]s.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let a = ergo_core::LocalString::from("föo");
+    /// assert_eq!(a.char_len(), 3);
+    /// ```
+    #[inline]
+    pub fn char_len(&self) -> usize {
+        self.as_str().chars().count()
+    }
+
+    /// Returns `true` if `self` has a length of zero bytes.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::LocalString::from("");
+    /// assert!(s.is_empty());
+    /// ```
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.as_str().is_empty()
+    }
+
+    /// Returns a subslice of `LocalString`.
+    ///
+    /// This is the non-panicking alternative to indexing. Returns [`None`] whenever equivalent
+    /// indexing operation would panic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let v = ergo_core::LocalString::from("Hello World");
+    ///
+    /// assert_eq!(Some(ergo_core::LocalString::from("Hell")), v.get(0..4));
+    /// ```
+    #[inline]
+    pub fn get(&self, range: impl std::ops::RangeBounds<usize>) -> Option<Self> {
+        match crate::range::coerce_range(self.byte_len(), range) {
+            Some(range) => self.as_str().get(range).map(|s| self.own_str(s)),
+            None => Some(Self::new()),
+        }
+    }
+
+    /// Divide one string slice into two at an index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mid` is not on a UTF-8 code point boundary, or if it is
+    /// past the end of the last code point of the string slice.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::LocalString::from("Per Martin");
+    ///
+    /// let (first, last) = s.split_at(3);
+    ///
+    /// assert_eq!("Per", first);
+    /// assert_eq!(" Martin", last);
+    /// ```
+    #[inline]
+    pub fn split_at(&self, mid: usize) -> (Self, Self) {
+        let (left, right) = self.as_str().split_at(mid);
+        (self.own_str(left), self.own_str(right))
+    }
+
+    /// Returns a string slice with leading whitespace removed.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::LocalString::from(" Hello\tworld\t");
+    /// assert_eq!("Hello\tworld\t", s.trim_start());
+    /// ```
+    #[inline]
+    #[must_use = "this returns the trimmed string as a new string, \
+                  without modifying the original"]
+    pub fn trim_start(&self) -> Self {
+        self.own_str(self.as_str().trim_start())
+    }
+
+    fn own_str(&self, subset: &str) -> Self {
+        if subset.is_empty() {
+            Self::new()
+        } else {
+            match self.0.get() {
+                StringRef::Inline(s) => Self(StringInner::from(s.own_str(subset))),
+                StringRef::Shared(s) => Self(StringInner::from(s.own_str(subset))),
+            }
+        }
+    }
+}
+
+/// Interop
+impl LocalString {
+    /// Extracts a string slice containing the entire `LocalString`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::LocalString::from("foo");
+    ///
+    /// assert_eq!("foo", s.as_str());
+    /// ```
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        match self.0.get() {
+            StringRef::Inline(s) => s.as_str(),
+            StringRef::Shared(s) => s.as_str(),
+        }
+    }
+}
+
+impl Default for LocalString {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'s> From<&'s str> for LocalString {
+    #[inline]
+    fn from(other: &'s str) -> Self {
+        match other.len() {
+            0 => Self::new(),
+            len if len <= CAPACITY => Self(StringInner::from(InlineString::from(other))),
+            _ => Self(StringInner::from(SharedString::<Local>::from(other))),
+        }
+    }
+}
+
+impl From<StdString> for LocalString {
+    #[inline]
+    fn from(other: StdString) -> Self {
+        other.as_str().into()
+    }
+}
+
+impl<'s> From<&'s StdString> for LocalString {
+    #[inline]
+    fn from(other: &'s StdString) -> Self {
+        other.as_str().into()
+    }
+}
+
+impl From<super::String> for LocalString {
+    #[inline]
+    fn from(other: super::String) -> Self {
+        Self(other.0.convert())
+    }
+}
+
+impl From<LocalString> for super::String {
+    #[inline]
+    fn from(other: LocalString) -> Self {
+        Self(other.0.convert())
+    }
+}
+
+impl fmt::Display for LocalString {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+/// Quotes and escapes like Python's `repr`, the same as [`String`][super::String].
+impl fmt::Debug for LocalString {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(&escape::repr(self.as_str()))
+    }
+}
+
+impl PartialEq for LocalString {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for LocalString {}
+
+impl_eq! { LocalString, str }
+impl_eq! { LocalString, &'a str }
+impl_eq! { LocalString, StdString }
+impl_eq! { LocalString, &'a StdString }
+impl_eq! { LocalString, super::String }
+
+impl PartialOrd for LocalString {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for LocalString {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl Hash for LocalString {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+impl AsRef<str> for LocalString {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl std::ops::Index<std::ops::RangeFull> for LocalString {
+    type Output = str;
+
+    #[inline]
+    fn index(&self, _index: std::ops::RangeFull) -> &str {
+        self.as_str()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn unique_buffer_is_reused() {
+        let s = super::super::String::from("Hello World, from a shared buffer");
+        let ptr = s.as_str().as_ptr();
+
+        let local = LocalString::from(s);
+        assert_eq!(local.as_str().as_ptr(), ptr);
+
+        let s = super::super::String::from(local);
+        assert_eq!(s.as_str().as_ptr(), ptr);
+    }
+
+    #[test]
+    fn shared_buffer_is_copied() {
+        let s = super::super::String::from("Hello World, from a shared buffer");
+        let slice = s.get(6..).unwrap();
+
+        let local = LocalString::from(slice);
+        assert_ne!(local.as_str().as_ptr(), s.as_str()[6..].as_ptr());
+        assert_eq!(local, "World, from a shared buffer");
+    }
+
+    #[test]
+    fn debug_matches_string() {
+        for s in ["it's", "say \"hi\"", "tab\t", "\u{0}\u{ff}\u{1f600}"].iter() {
+            let shared = super::super::String::from(*s);
+            let local = LocalString::from(*s);
+            assert_eq!(format!("{:?}", local), format!("{:?}", shared));
+            assert_eq!(format!("{:*^20?}", local), format!("{:*^20?}", shared));
+        }
+        assert_eq!(format!("{:?}", LocalString::from("it's")), "\"it's\"");
+        assert_eq!(format!("{:<6?}|", LocalString::from("ab")), "'ab'  |");
+    }
+}
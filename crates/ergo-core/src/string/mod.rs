@@ -1,6 +1,38 @@
+macro_rules! impl_eq {
+    ($lhs:ty, $rhs: ty) => {
+        #[allow(unused_lifetimes)]
+        impl<'a, 'b> PartialEq<$rhs> for $lhs {
+            #[inline]
+            fn eq(&self, other: &$rhs) -> bool {
+                PartialEq::eq(&self[..], &other[..])
+            }
+        }
+
+        #[allow(unused_lifetimes)]
+        impl<'a, 'b> PartialEq<$lhs> for $rhs {
+            #[inline]
+            fn eq(&self, other: &$lhs) -> bool {
+                PartialEq::eq(&self[..], &other[..])
+            }
+        }
+    };
+}
+
+mod byte_string;
 mod bytes;
+mod casefold_table;
+mod encode_utf16;
+mod error;
+mod escape;
+mod hashed;
 mod inline;
+mod local;
+mod order;
+mod policy;
+mod repr;
+mod search;
 mod shared;
+mod textwrap;
 
 use std::cmp::Ordering;
 use std::fmt;
@@ -8,25 +40,32 @@ use std::hash::{Hash, Hasher};
 use std::iter::FromIterator;
 use std::ops;
 
+use repr::{InlineString, StringInner, StringRef, CAPACITY};
 use shared::SharedString;
 
-const TAG_SIZE: usize = 1;
-const CAPACITY: usize = std::mem::size_of::<SharedString>() - TAG_SIZE;
-type InlineString = inline::InlineString<CAPACITY>;
 type StdString = std::string::String;
 
+pub use byte_string::ByteString;
+pub use byte_string::FromHexError;
 pub use bytes::Bytes;
-
+pub use encode_utf16::EncodeUtf16;
+pub use error::FromUtf16Error;
+pub use error::FromUtf8Error;
+pub use hashed::HashedString;
+pub use local::LocalString;
+pub use order::CaseInsensitive;
+pub use order::Casefolded;
+pub use order::NaturalOrder;
+pub use policy::SlicePolicy;
+
+/// An immutable, cheaply clonable UTF-8 string
+///
+/// A `String` is the same size as [`std::string::String`], with up to 23 bytes (on 64-bit
+/// platforms) stored inline, and `Option<String>` costs nothing extra.  Longer strings share a
+/// reference-counted buffer, so cloning and slicing never copy.
 #[derive(Clone)]
 pub struct String(StringInner);
 
-#[derive(Clone)]
-enum StringInner {
-    Empty,
-    Inline(InlineString),
-    Shared(SharedString),
-}
-
 impl String {
     /// Creates a new empty `String`.
     ///
@@ -44,7 +83,97 @@ impl String {
     /// ```
     #[inline]
     pub const fn new() -> Self {
-        Self(StringInner::Empty)
+        Self(StringInner::new())
+    }
+
+    /// Converts bytes to a `String`, failing if they are not valid UTF-8.
+    ///
+    /// Accepts anything convertible into a [`ByteString`], like `Vec<u8>` or `&[u8]`.  Converting
+    /// a `ByteString` does not copy.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let sparkle_heart = vec![240, 159, 146, 150];
+    /// let s = ergo_core::String::from_utf8(sparkle_heart).unwrap();
+    /// assert_eq!("💖", s);
+    ///
+    /// let err = ergo_core::String::from_utf8(&b"caf\xe9"[..]).unwrap_err();
+    /// assert_eq!(err.valid_up_to(), 3);
+    /// ```
+    #[inline]
+    pub fn from_utf8(bytes: impl Into<ByteString>) -> Result<Self, FromUtf8Error> {
+        bytes.into().decode()
+    }
+
+    /// Converts bytes to a `String`, replacing invalid UTF-8 sequences with
+    /// `U+FFFD REPLACEMENT CHARACTER`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::String::from_utf8_lossy(b"caf\xe9 au lait");
+    /// assert_eq!("caf\u{FFFD} au lait", s);
+    /// ```
+    #[inline]
+    pub fn from_utf8_lossy(bytes: impl Into<ByteString>) -> Self {
+        bytes.into().decode_lossy()
+    }
+
+    /// Decodes UTF-16 into a `String`, failing on unpaired surrogates.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// // 𝄞music
+    /// let v = &[0xD834, 0xDD1E, 0x006d, 0x0075, 0x0073, 0x0069, 0x0063];
+    /// assert_eq!("𝄞music", ergo_core::String::from_utf16(v).unwrap());
+    ///
+    /// // 𝄞mu<invalid>ic
+    /// let v = &[0xD834, 0xDD1E, 0x006d, 0x0075, 0xD800, 0x0069, 0x0063];
+    /// assert_eq!(ergo_core::String::from_utf16(v).unwrap_err().valid_up_to(), 4);
+    /// ```
+    pub fn from_utf16(units: impl AsRef<[u16]>) -> Result<Self, FromUtf16Error> {
+        let units = units.as_ref();
+        let mut buffer = StdString::with_capacity(units.len());
+        let mut offset = 0;
+        for c in std::char::decode_utf16(units.iter().copied()) {
+            match c {
+                Ok(c) => {
+                    buffer.push(c);
+                    offset += c.len_utf16();
+                }
+                Err(err) => {
+                    return Err(FromUtf16Error::new(offset, err.unpaired_surrogate()));
+                }
+            }
+        }
+        Ok(Self::from(buffer))
+    }
+
+    /// Decodes UTF-16 into a `String`, replacing unpaired surrogates with
+    /// `U+FFFD REPLACEMENT CHARACTER`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// // 𝄞mus<invalid>ic<invalid>
+    /// let v = &[0xD834, 0xDD1E, 0x006d, 0x0075, 0x0073, 0xDD1E, 0x0069, 0x0063, 0xD834];
+    ///
+    /// assert_eq!("𝄞mus\u{FFFD}ic\u{FFFD}", ergo_core::String::from_utf16_lossy(v));
+    /// ```
+    pub fn from_utf16_lossy(units: impl AsRef<[u16]>) -> Self {
+        std::char::decode_utf16(units.as_ref().iter().copied())
+            .map(|c| c.unwrap_or(std::char::REPLACEMENT_CHARACTER))
+            .collect()
     }
 
     /// Returns the length of this `String`, in bytes, not [`char`]s or
@@ -125,6 +254,32 @@ impl String {
         }
     }
 
+    /// Like [`String::get`], but lets `policy` decide whether the slice shares `self`'s buffer
+    /// or is copied out of it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ergo_core::string::SlicePolicy;
+    ///
+    /// let file = ergo_core::String::from("id=1234,".repeat(1000));
+    ///
+    /// let id = file.get_with(SlicePolicy::compact(), 3..7).unwrap();
+    /// assert_eq!(id, "1234");
+    /// assert!(!id.is_shared_with(&file));
+    ///
+    /// let id = file.get_with(SlicePolicy::Share, 3..7).unwrap();
+    /// assert!(id.is_shared_with(&file));
+    /// ```
+    #[inline]
+    pub fn get_with(
+        &self,
+        policy: SlicePolicy,
+        range: impl std::ops::RangeBounds<usize>,
+    ) -> Option<Self> {
+        self.get(range).map(|slice| slice.apply(policy))
+    }
+
     /// Divide one string slice into two at an index.
     ///
     /// The argument, `mid`, should be a byte offset from the start of the
@@ -182,7 +337,26 @@ impl String {
     /// ```
     #[inline]
     pub fn bytes(&self) -> Bytes {
-        Bytes::new(self.clone())
+        ByteString::from(self.clone()).bytes()
+    }
+
+    /// Returns an iterator of `u16` over the string encoded as UTF-16.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let text = ergo_core::String::from("Zażółć gęślą jaźń");
+    ///
+    /// let utf8_len = text.byte_len();
+    /// let utf16_len = text.encode_utf16().count();
+    ///
+    /// assert!(utf16_len <= utf8_len);
+    /// ```
+    #[inline]
+    pub fn encode_utf16(&self) -> EncodeUtf16 {
+        EncodeUtf16::new(self.clone())
     }
 
     /// Returns a string slice with leading whitespace removed.
@@ -212,6 +386,98 @@ impl String {
         self.own_str(self.as_str().trim_start())
     }
 
+    /// Returns the byte index of the first occurrence of `needle`.
+    ///
+    /// An empty `needle` is found at index `0`.  The search is vectorized and runs in time
+    /// linear in the length of `self`, so it stays fast on multi-megabyte strings.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::String::from("Löwe 老虎 Léopard");
+    ///
+    /// assert_eq!(s.find("L"), Some(0));
+    /// assert_eq!(s.find("é"), Some(14));
+    /// assert_eq!(s.find("pard"), Some(17));
+    /// assert_eq!(s.find("Tiger"), None);
+    /// ```
+    #[inline]
+    pub fn find(&self, needle: impl AsRef<str>) -> Option<usize> {
+        search::find(self.as_str().as_bytes(), needle.as_ref().as_bytes())
+    }
+
+    /// Returns the byte index of the last occurrence of `needle`.
+    ///
+    /// An empty `needle` is found at the end.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::String::from("src/string/mod.rs");
+    ///
+    /// assert_eq!(s.rfind("/"), Some(10));
+    /// assert_eq!(s.rfind("\\"), None);
+    /// ```
+    #[inline]
+    pub fn rfind(&self, needle: impl AsRef<str>) -> Option<usize> {
+        search::rfind(self.as_str().as_bytes(), needle.as_ref().as_bytes())
+    }
+
+    /// Returns `true` if `needle` occurs in `self`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::String::from("bananas");
+    ///
+    /// assert!(s.contains("nana"));
+    /// assert!(!s.contains("apples"));
+    /// ```
+    #[inline]
+    pub fn contains(&self, needle: impl AsRef<str>) -> bool {
+        self.find(needle).is_some()
+    }
+
+    /// Splits the string on each occurrence of `sep`, like Python's `str.split(sep)`.
+    ///
+    /// Consecutive separators produce empty strings.  The pieces share `self`'s buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sep` is empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::String::from("name,,age");
+    /// assert_eq!(s.split(","), ["name", "", "age"]);
+    ///
+    /// let s = ergo_core::String::from("a -> b -> c");
+    /// assert_eq!(s.split(" -> "), ["a", "b", "c"]);
+    /// ```
+    pub fn split(&self, sep: impl AsRef<str>) -> Vec<Self> {
+        let sep = sep.as_ref();
+        assert!(!sep.is_empty(), "empty separator");
+
+        let finder = search::Finder::new(sep.as_bytes());
+        let mut pieces = Vec::new();
+        let mut rest = self.as_str();
+        while let Some(index) = finder.find(rest.as_bytes()) {
+            pieces.push(self.own_str(&rest[..index]));
+            rest = &rest[index + sep.len()..];
+        }
+        pieces.push(self.own_str(rest));
+        pieces
+    }
+
     /// Appends a given string onto the end of this `String`.
     ///
     /// # Examples
@@ -278,14 +544,501 @@ impl String {
         String::from(self.as_str())
     }
 
+    /// Copies the content out of a larger shared buffer, so `self` no longer keeps the rest of it
+    /// alive.
+    ///
+    /// Strings that already own all of their buffer are cheaply cloned instead.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let file = ergo_core::String::from("key=value\n".repeat(100));
+    /// let key = file.get(..3).unwrap();
+    /// assert!(key.is_shared_with(&file));
+    ///
+    /// let key = key.detach();
+    /// assert!(!key.is_shared_with(&file));
+    /// assert_eq!(key, "key");
+    /// ```
+    #[inline]
+    #[must_use = "this returns the detached string as a new string, \
+                  without modifying the original"]
+    pub fn detach(&self) -> Self {
+        if self.byte_len() < self.backing_len() {
+            String::from(self.as_str())
+        } else {
+            self.clone()
+        }
+    }
+
+    /// Like [`String::detach`] but only copies when the shared buffer is much larger than `self`
+    /// (see [`SlicePolicy::DEFAULT_RATIO`]).
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let file = ergo_core::String::from("key=value\n".repeat(100));
+    ///
+    /// let line = file.get(..9).unwrap().compact();
+    /// assert!(!line.is_shared_with(&file));
+    ///
+    /// let most = file.get(9..).unwrap().compact();
+    /// assert!(most.is_shared_with(&file));
+    /// ```
+    #[inline]
+    #[must_use = "this returns the compacted string as a new string, \
+                  without modifying the original"]
+    pub fn compact(&self) -> Self {
+        self.clone().apply(SlicePolicy::compact())
+    }
+
+    fn apply(self, policy: SlicePolicy) -> Self {
+        if policy.should_copy(self.byte_len(), self.backing_len()) {
+            String::from(self.as_str())
+        } else {
+            self
+        }
+    }
+
+    /// Returns the length of the buffer backing this `String`, in bytes.
+    ///
+    /// For slices of a shared buffer, this is the length of the whole buffer, all of which is kept
+    /// alive by the slice.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let file = ergo_core::String::from("key=value\n".repeat(100));
+    /// let key = file.get(..3).unwrap();
+    /// assert_eq!(key.byte_len(), 3);
+    /// assert_eq!(key.backing_len(), 1000);
+    /// ```
+    #[inline]
+    pub fn backing_len(&self) -> usize {
+        match self.0.get() {
+            StringRef::Inline(s) => s.as_str().len(),
+            StringRef::Shared(s) => s.backing_len(),
+        }
+    }
+
+    /// Returns `true` if `self` and `other` are backed by the same shared buffer.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let file = ergo_core::String::from("key=value\n".repeat(100));
+    /// let (head, tail) = file.split_at(500);
+    /// assert!(head.is_shared_with(&tail));
+    ///
+    /// let copy = ergo_core::String::from(file.as_str());
+    /// assert!(!copy.is_shared_with(&file));
+    /// ```
+    #[inline]
+    pub fn is_shared_with(&self, other: &Self) -> bool {
+        match (self.0.get(), other.0.get()) {
+            (StringRef::Shared(s), StringRef::Shared(other)) => s.is_shared_with(other),
+            _ => false,
+        }
+    }
+
+    /// Escapes the string like Python's `repr`, without the surrounding quotes.
+    ///
+    /// Backslashes, `\t`, `\n`, `\r` and non-printable characters are escaped with Python's
+    /// `\xNN`, `\uNNNN` and `\UNNNNNNNN` forms, as well as the quote `repr` would surround
+    /// the string with.  The [`Debug`][fmt::Debug] output adds the quotes.
+    ///
+    /// Returns a clone of `self` when nothing needs escaping.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::String::from("tab\there\u{7f}");
+    /// assert_eq!(s.escape_debug(), "tab\\there\\x7f");
+    ///
+    /// let s = ergo_core::String::from("it's");
+    /// assert_eq!(s.escape_debug(), "it's");
+    /// assert_eq!(format!("{:?}", s), "\"it's\"");
+    /// ```
+    #[inline]
+    pub fn escape_debug(&self) -> Self {
+        self.escaped(escape::debug)
+    }
+
+    /// Escapes the string for use inside a JSON string literal.
+    ///
+    /// Returns a clone of `self` when nothing needs escaping.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::String::from("say \"hi\"\n");
+    /// assert_eq!(s.escape_json(), "say \\\"hi\\\"\\n");
+    /// ```
+    #[inline]
+    pub fn escape_json(&self) -> Self {
+        self.escaped(escape::json)
+    }
+
+    /// Escapes `&`, `<`, `>`, `"` and `'` like Python's `html.escape`.
+    ///
+    /// Returns a clone of `self` when nothing needs escaping.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::String::from("<a href='x'>Tom & Jerry</a>");
+    /// assert_eq!(
+    ///     s.escape_html(),
+    ///     "&lt;a href=&#x27;x&#x27;&gt;Tom &amp; Jerry&lt;/a&gt;"
+    /// );
+    /// ```
+    #[inline]
+    pub fn escape_html(&self) -> Self {
+        self.escaped(escape::html)
+    }
+
+    /// Replaces HTML character references, like Python's `html.unescape`.
+    ///
+    /// Numeric references (`&#39;`, `&#x27;`) are all supported, with `&#128;` to `&#159;`
+    /// read as Windows-1252 and invalid code points replaced or dropped like Python does; named
+    /// references are limited to common ones like `&amp;`, `&nbsp;` and `&hellip;`.  Unknown
+    /// references are left as-is.
+    ///
+    /// Returns a clone of `self` when nothing needs unescaping.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::String::from("Tom &amp; Jerry&#x27;s &hellip;");
+    /// assert_eq!(s.unescape_html(), "Tom & Jerry's …");
+    /// ```
+    #[inline]
+    pub fn unescape_html(&self) -> Self {
+        self.escaped(escape::unescape_html)
+    }
+
+    /// Percent-encodes the UTF-8 bytes like Python's `urllib.parse.quote`.
+    ///
+    /// ASCII letters, digits, `_.-~` and `/` are left alone.
+    ///
+    /// Returns a clone of `self` when nothing needs encoding.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::String::from("/search/café au lait");
+    /// assert_eq!(s.percent_encode(), "/search/caf%C3%A9%20au%20lait");
+    /// ```
+    #[inline]
+    pub fn percent_encode(&self) -> Self {
+        self.escaped(escape::percent_encode)
+    }
+
+    /// Decodes percent-encoded bytes like Python's `urllib.parse.unquote`.
+    ///
+    /// Malformed escapes are left as-is, and decoded bytes that are not valid UTF-8 become
+    /// `U+FFFD REPLACEMENT CHARACTER`.
+    ///
+    /// Returns a clone of `self` when nothing needs decoding.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::String::from("caf%C3%A9%20100%");
+    /// assert_eq!(s.percent_decode(), "café 100%");
+    /// ```
+    #[inline]
+    pub fn percent_decode(&self) -> Self {
+        self.escaped(escape::percent_decode)
+    }
+
+    /// Quotes the string as a single POSIX shell word, like Python's `shlex.quote`.
+    ///
+    /// Returns a clone of `self` when it is already safe.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::String::from("it's here");
+    /// assert_eq!(s.shell_quote(), r#"'it'"'"'s here'"#);
+    ///
+    /// let s = ergo_core::String::from("/tmp/file.txt");
+    /// assert_eq!(s.shell_quote(), "/tmp/file.txt");
+    ///
+    /// let s = ergo_core::String::new();
+    /// assert_eq!(s.shell_quote(), "''");
+    /// ```
+    #[inline]
+    pub fn shell_quote(&self) -> Self {
+        self.escaped(escape::shell_quote)
+    }
+
+    /// Wraps the string into lines of at most `width` [`char`]s, like Python's `textwrap.wrap`.
+    ///
+    /// Lines break between words and after hyphens in hyphenated words; words longer than
+    /// `width` are broken up.  Tabs are expanded and all whitespace, including newlines, becomes
+    /// a single space each, with whitespace at the start and end of lines dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` is zero.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::String::from("The quick brown fox jumps over the well-known dog.");
+    /// assert_eq!(
+    ///     s.wrap(16),
+    ///     ["The quick brown", "fox jumps over", "the well-known", "dog."]
+    /// );
+    /// ```
+    pub fn wrap(&self, width: usize) -> Vec<Self> {
+        textwrap::wrap(self.as_str(), width, None)
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+
+    /// Wraps the string like [`String::wrap`], joining the lines with `\n`, like Python's
+    /// `textwrap.fill`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` is zero.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::String::from("Usage: tool [OPTIONS] <INPUT>...");
+    /// assert_eq!(s.fill(20), "Usage: tool\n[OPTIONS] <INPUT>...");
+    /// ```
+    pub fn fill(&self, width: usize) -> Self {
+        String::from(textwrap::wrap(self.as_str(), width, None).join("\n"))
+    }
+
+    /// Collapses whitespace and truncates to fit in `width` [`char`]s, like Python's
+    /// `textwrap.shorten`.
+    ///
+    /// Whole words are dropped from the end to make room for `placeholder`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` is zero or too small for `placeholder`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::String::from("Hello  world!");
+    /// assert_eq!(s.shorten(12, " [...]"), "Hello world!");
+    /// assert_eq!(s.shorten(11, " [...]"), "Hello [...]");
+    /// ```
+    pub fn shorten(&self, width: usize, placeholder: impl AsRef<str>) -> Self {
+        let collapsed = self
+            .as_str()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        let lines = textwrap::wrap(&collapsed, width, Some((1, placeholder.as_ref())));
+        lines
+            .into_iter()
+            .next()
+            .map(String::from)
+            .unwrap_or_default()
+    }
+
+    /// Removes the leading spaces and tabs common to every line, like Python's
+    /// `textwrap.dedent`.
+    ///
+    /// Lines with only spaces and tabs are emptied and don't count towards the common margin.
+    ///
+    /// Returns a clone of `self` when nothing changes.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::String::from("
+    ///     fn main() {
+    ///         println!();
+    ///     }
+    /// ");
+    /// assert_eq!(s.dedent(), "\nfn main() {\n    println!();\n}\n");
+    /// ```
+    pub fn dedent(&self) -> Self {
+        self.escaped(textwrap::dedent)
+    }
+
+    /// Adds `prefix` to the start of every line that isn't only whitespace, like Python's
+    /// `textwrap.indent`.
+    ///
+    /// Returns a clone of `self` when nothing changes.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::String::from("first\n\nsecond\n");
+    /// assert_eq!(s.indent("> "), "> first\n\n> second\n");
+    /// ```
+    pub fn indent(&self, prefix: impl AsRef<str>) -> Self {
+        self.escaped(|s| textwrap::indent(s, prefix.as_ref()))
+    }
+
+    /// Returns `true` if the string matches a shell-style wildcard `pattern`, like Python's
+    /// `fnmatch.fnmatchcase`.
+    ///
+    /// See [`fnmatch`][crate::fnmatch] for the syntax, and compile a
+    /// [`Pattern`][crate::fnmatch::Pattern] to match many strings or ignore case.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::String::from("http.get.latency_ms");
+    ///
+    /// assert!(s.fnmatch("http.*.latency_*"));
+    /// assert!(!s.fnmatch("db.*"));
+    /// ```
+    #[inline]
+    pub fn fnmatch(&self, pattern: impl AsRef<str>) -> bool {
+        crate::fnmatch::fnmatch(self, pattern)
+    }
+
+    /// Compares strings with embedded numbers by their value, so `file2` sorts before `file10`.
+    ///
+    /// Runs of ASCII digits compare numerically, with fewer leading zeros first when the values
+    /// are equal; everything else compares by [`char`].  Wrap strings in [`NaturalOrder`] to use
+    /// this as a sort key or in a [`BTreeMap`][std::collections::BTreeMap].
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use std::cmp::Ordering;
+    ///
+    /// let s = ergo_core::String::from("v1.10.0");
+    ///
+    /// assert_eq!(s.cmp_natural("v1.9.2"), Ordering::Greater);
+    /// assert_eq!(s.as_str().cmp("v1.9.2"), Ordering::Less);
+    /// ```
+    #[inline]
+    pub fn cmp_natural(&self, other: impl AsRef<str>) -> Ordering {
+        order::cmp_natural(self.as_str(), other.as_ref())
+    }
+
+    /// Compares strings by their lowercase [`char`]s.
+    ///
+    /// See [`CaseInsensitive`] for a sort key.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use std::cmp::Ordering;
+    ///
+    /// let s = ergo_core::String::from("Apple");
+    ///
+    /// assert_eq!(s.cmp_case_insensitive("apple"), Ordering::Equal);
+    /// assert_eq!(s.cmp_case_insensitive("banana"), Ordering::Less);
+    /// ```
+    #[inline]
+    pub fn cmp_case_insensitive(&self, other: impl AsRef<str>) -> Ordering {
+        order::lowercase(self.as_str()).cmp(order::lowercase(other.as_ref()))
+    }
+
+    /// Compares strings by their [`casefold`][String::casefold]s.
+    ///
+    /// See [`Casefolded`] for a sort key.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use std::cmp::Ordering;
+    ///
+    /// let s = ergo_core::String::from("Straße");
+    ///
+    /// assert_eq!(s.cmp_casefolded("STRASSE"), Ordering::Equal);
+    /// assert_ne!(s.cmp_case_insensitive("STRASSE"), Ordering::Equal);
+    /// ```
+    #[inline]
+    pub fn cmp_casefolded(&self, other: impl AsRef<str>) -> Ordering {
+        order::casefold(self.as_str()).cmp(order::casefold(other.as_ref()))
+    }
+
+    /// Returns the Unicode case folding, like Python's `str.casefold`.
+    ///
+    /// This is like [`to_lowercase`][str::to_lowercase] but more aggressive, for caseless
+    /// matching: `ß` becomes `ss`, and final `ς` becomes `σ`.  Returns a clone of `self` when
+    /// nothing changes.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// let s = ergo_core::String::from("Großstraße");
+    /// assert_eq!(s.casefold(), "grossstrasse");
+    /// ```
+    pub fn casefold(&self) -> Self {
+        self.escaped(|s| {
+            let folded: StdString = order::casefold(s).collect();
+            if folded == s {
+                None
+            } else {
+                Some(folded)
+            }
+        })
+    }
+
+    fn escaped(&self, escape: impl FnOnce(&str) -> Option<StdString>) -> Self {
+        match escape(self.as_str()) {
+            Some(escaped) => String::from(escaped),
+            None => self.clone(),
+        }
+    }
+
     fn own_str(&self, subset: &str) -> Self {
         if subset.is_empty() {
             String::new()
         } else {
-            match &self.0 {
-                StringInner::Empty => String::new(),
-                StringInner::Inline(s) => s.own_str(subset).into(),
-                StringInner::Shared(s) => s.own_str(subset).into(),
+            match self.0.get() {
+                StringRef::Inline(s) => s.own_str(subset).into(),
+                StringRef::Shared(s) => s.own_str(subset).into(),
             }
         }
     }
@@ -294,40 +1047,7 @@ impl String {
         &self,
         range: impl std::ops::RangeBounds<usize>,
     ) -> Option<std::ops::RangeInclusive<usize>> {
-        let len = self.byte_len();
-        if len == 0 {
-            return None;
-        }
-
-        let range_start = match range.start_bound() {
-            std::ops::Bound::Included(s) => *s,
-            std::ops::Bound::Excluded(s) => {
-                if *s == usize::MAX {
-                    return None;
-                } else {
-                    s + 1
-                }
-            }
-            std::ops::Bound::Unbounded => 0,
-        };
-        let range_end = match range.end_bound() {
-            std::ops::Bound::Included(s) => *s,
-            std::ops::Bound::Excluded(s) => {
-                if *s == 0 {
-                    return None;
-                } else {
-                    s - 1
-                }
-            }
-            std::ops::Bound::Unbounded => usize::MAX,
-        }
-        .min(len - 1);
-
-        if len <= range_start || range_end < range_start {
-            None
-        } else {
-            Some(range_start..=range_end)
-        }
+        crate::range::coerce_range(self.byte_len(), range)
     }
 }
 
@@ -337,6 +1057,11 @@ impl String {
     pub fn lstrip(&self) -> Self {
         self.trim_start()
     }
+
+    #[deprecated = "In Rust, we format with `{:?}`"]
+    pub fn repr(&self) -> Self {
+        String::from(format!("{:?}", self))
+    }
 }
 
 /// Interop
@@ -354,15 +1079,14 @@ impl String {
     /// ```
     #[inline]
     pub fn as_str(&self) -> &str {
-        match &self.0 {
-            StringInner::Empty => "",
-            StringInner::Inline(s) => s.as_str(),
-            StringInner::Shared(s) => s.as_str(),
+        match self.0.get() {
+            StringRef::Inline(s) => s.as_str(),
+            StringRef::Shared(s) => s.as_str(),
         }
     }
 }
 
-pub(crate) fn calculate_subset(s: &str, subset: &str) -> (usize, usize) {
+pub(crate) fn calculate_subset(s: &[u8], subset: &[u8]) -> (usize, usize) {
     unsafe {
         let self_start = s.as_ptr();
         let self_end = self_start.add(s.len());
@@ -388,14 +1112,14 @@ impl Default for String {
 impl From<InlineString> for String {
     #[inline]
     fn from(other: InlineString) -> Self {
-        Self(StringInner::Inline(other))
+        Self(StringInner::from(other))
     }
 }
 
 impl From<SharedString> for String {
     #[inline]
     fn from(other: SharedString) -> Self {
-        Self(StringInner::Shared(other))
+        Self(StringInner::from(other))
     }
 }
 
@@ -439,6 +1163,13 @@ impl<'s> From<&'s StdString> for String {
     }
 }
 
+impl<'s> From<&'s String> for String {
+    #[inline]
+    fn from(other: &'s String) -> Self {
+        other.clone()
+    }
+}
+
 impl FromIterator<char> for String {
     fn from_iter<I: IntoIterator<Item = char>>(iter: I) -> String {
         let s = StdString::from_iter(iter);
@@ -486,7 +1217,7 @@ impl FromIterator<StdString> for String {
 /// let b = ergo_core::String::from(" world");
 /// let c = &a + &b + "foo";
 /// ```
-impl<'s, S: AsRef<str>> std::ops::Add<S> for &'s String {
+impl<S: AsRef<str>> std::ops::Add<S> for &String {
     type Output = String;
 
     #[inline]
@@ -576,10 +1307,18 @@ impl fmt::Display for String {
     }
 }
 
+/// Quotes and escapes like Python's `repr`, then pads to the width like [`str`]'s `Display`.
+///
+/// # Examples
+///
+/// ```
+/// let s = ergo_core::String::from("line\n");
+/// assert_eq!(format!("{:?}", s), "'line\\n'");
+/// assert_eq!(format!("{:>10?}", s), "  'line\\n'");
+/// ```
 impl fmt::Debug for String {
-    #[inline]
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        fmt::Debug::fmt(self.as_str(), f)
+        f.pad(&escape::repr(self.as_str()))
     }
 }
 
@@ -591,26 +1330,6 @@ impl PartialEq for String {
 
 impl Eq for String {}
 
-macro_rules! impl_eq {
-    ($lhs:ty, $rhs: ty) => {
-        #[allow(unused_lifetimes)]
-        impl<'a, 'b> PartialEq<$rhs> for $lhs {
-            #[inline]
-            fn eq(&self, other: &$rhs) -> bool {
-                PartialEq::eq(&self[..], &other[..])
-            }
-        }
-
-        #[allow(unused_lifetimes)]
-        impl<'a, 'b> PartialEq<$lhs> for $rhs {
-            #[inline]
-            fn eq(&self, other: &$lhs) -> bool {
-                PartialEq::eq(&self[..], &other[..])
-            }
-        }
-    };
-}
-
 impl_eq! { String, str }
 impl_eq! { String, &'a str }
 impl_eq! { String, StdString }
@@ -641,6 +1360,20 @@ impl AsRef<str> for String {
     }
 }
 
+impl std::borrow::Borrow<str> for String {
+    #[inline]
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<[u8]> for String {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self.as_str().as_bytes()
+    }
+}
+
 #[cfg(test)]
 mod test_coerce_range {
     use super::*;
@@ -0,0 +1,213 @@
+//! Alternative orderings for strings
+//!
+//! These are locale-free: they depend only on Unicode data, not on the user's language.
+
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+
+use super::casefold_table::CASEFOLD;
+
+/// Compares digit runs by their numeric value, and everything else by [`char`].
+pub(super) fn cmp_natural(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a, b);
+    // Equal numbers with different leading zeros only break ties, like `1` < `01`
+    let mut tie = Ordering::Equal;
+    loop {
+        let (x, y) = match (a.chars().next(), b.chars().next()) {
+            (None, None) => return tie,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) => (x, y),
+        };
+
+        if x.is_ascii_digit() && y.is_ascii_digit() {
+            let a_len = a.bytes().take_while(u8::is_ascii_digit).count();
+            let b_len = b.bytes().take_while(u8::is_ascii_digit).count();
+            let a_digits = a[..a_len].trim_start_matches('0');
+            let b_digits = b[..b_len].trim_start_matches('0');
+            let ordering = a_digits
+                .len()
+                .cmp(&b_digits.len())
+                .then_with(|| a_digits.cmp(b_digits));
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+            if tie == Ordering::Equal {
+                tie = a_len.cmp(&b_len);
+            }
+            a = &a[a_len..];
+            b = &b[b_len..];
+        } else {
+            if x != y {
+                return x.cmp(&y);
+            }
+            a = &a[x.len_utf8()..];
+            b = &b[y.len_utf8()..];
+        }
+    }
+}
+
+pub(super) fn lowercase(s: &str) -> impl Iterator<Item = char> + '_ {
+    s.chars().flat_map(char::to_lowercase)
+}
+
+pub(super) fn casefold(s: &str) -> impl Iterator<Item = char> + '_ {
+    s.chars().flat_map(Fold::new)
+}
+
+/// The case folding of one [`char`]
+enum Fold {
+    Special(std::str::Chars<'static>),
+    Lower(std::char::ToLowercase),
+}
+
+impl Fold {
+    fn new(c: char) -> Self {
+        match CASEFOLD.binary_search_by_key(&c, |(special, _)| *special) {
+            Ok(index) => Fold::Special(CASEFOLD[index].1.chars()),
+            Err(_) => Fold::Lower(c.to_lowercase()),
+        }
+    }
+}
+
+impl Iterator for Fold {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        match self {
+            Fold::Special(chars) => chars.next(),
+            Fold::Lower(chars) => chars.next(),
+        }
+    }
+}
+
+macro_rules! impl_order {
+    ($name:ident, |$a:ident, $b:ident| $cmp:expr, |$s:ident| $key:expr) => {
+        impl<S: AsRef<str>> PartialEq for $name<S> {
+            fn eq(&self, other: &Self) -> bool {
+                self.cmp(other) == Ordering::Equal
+            }
+        }
+
+        impl<S: AsRef<str>> Eq for $name<S> {}
+
+        impl<S: AsRef<str>> PartialOrd for $name<S> {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl<S: AsRef<str>> Ord for $name<S> {
+            fn cmp(&self, other: &Self) -> Ordering {
+                let ($a, $b) = (self.0.as_ref(), other.0.as_ref());
+                $cmp
+            }
+        }
+
+        impl<S: AsRef<str>> Hash for $name<S> {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                let $s = self.0.as_ref();
+                $key.for_each(|c: char| c.hash(state));
+            }
+        }
+
+        impl<S> From<S> for $name<S> {
+            fn from(other: S) -> Self {
+                Self(other)
+            }
+        }
+    };
+}
+
+/// Orders strings with embedded numbers by their value, so `file2` sorts before `file10`
+///
+/// Runs of ASCII digits compare numerically; everything else compares by [`char`], so this is
+/// still case-sensitive.  See [`String::cmp_natural`][super::String::cmp_natural].
+///
+/// # Examples
+///
+/// ```
+/// use ergo_core::string::NaturalOrder;
+///
+/// let mut files = vec!["file10.txt", "file2.txt", "file1.txt"];
+/// files.sort_by_key(|f| NaturalOrder(*f));
+///
+/// assert_eq!(files, ["file1.txt", "file2.txt", "file10.txt"]);
+/// ```
+#[derive(Copy, Clone, Debug, Default)]
+pub struct NaturalOrder<S>(pub S);
+
+impl_order!(NaturalOrder, |a, b| cmp_natural(a, b), |s| s.chars());
+
+/// Orders and compares strings ignoring case, by their lowercase [`char`]s
+///
+/// Equal keys in a [`BTreeMap`][std::collections::BTreeMap] or
+/// [`HashMap`][std::collections::HashMap] differ only in case.  See [`Casefolded`] to also
+/// match forms like `ß` and `ss`.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::BTreeMap;
+/// use ergo_core::string::CaseInsensitive;
+///
+/// let mut headers = BTreeMap::new();
+/// headers.insert(CaseInsensitive("Content-Type"), "text/plain");
+///
+/// assert_eq!(headers.get(&CaseInsensitive("content-type")), Some(&"text/plain"));
+/// ```
+#[derive(Copy, Clone, Debug, Default)]
+pub struct CaseInsensitive<S>(pub S);
+
+impl_order!(
+    CaseInsensitive,
+    |a, b| lowercase(a).cmp(lowercase(b)),
+    |s| lowercase(s)
+);
+
+/// Orders and compares strings by their Unicode case folding, like Python's `str.casefold`
+///
+/// # Examples
+///
+/// ```
+/// use ergo_core::string::{CaseInsensitive, Casefolded};
+///
+/// assert_ne!(CaseInsensitive("STRASSE"), CaseInsensitive("straße"));
+/// assert_eq!(Casefolded("STRASSE"), Casefolded("straße"));
+/// ```
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Casefolded<S>(pub S);
+
+impl_order!(Casefolded, |a, b| casefold(a).cmp(casefold(b)), |s| {
+    casefold(s)
+});
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn natural_is_a_total_order() {
+        let mut fixture = vec!["a01", "a1", "a", "a001b", "a1b", "a10", "a2", "B", "a0"];
+        fixture.sort_by(|a, b| cmp_natural(a, b));
+        assert_eq!(
+            fixture,
+            ["B", "a", "a0", "a1", "a01", "a1b", "a001b", "a2", "a10"]
+        );
+        assert_eq!(cmp_natural("a01", "a1"), Ordering::Greater);
+        assert_eq!(cmp_natural("a1", "a1"), Ordering::Equal);
+        assert_eq!(
+            cmp_natural("99999999999999999999999", "100000000000000000000000"),
+            Ordering::Less
+        );
+    }
+
+    #[test]
+    fn casefold_table_is_sorted() {
+        assert!(CASEFOLD.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert_eq!(
+            casefold("ΣΊΣΥΦΟΣ").collect::<std::string::String>(),
+            "σίσυφοσ"
+        );
+    }
+}
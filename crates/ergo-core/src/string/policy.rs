@@ -0,0 +1,92 @@
+/// How [`String::get_with`][super::String::get_with] treats the backing buffer of a shared
+/// [`String`][super::String]
+///
+/// Slices share the buffer of the `String` they come from, keeping all of it alive.  A short field
+/// sliced out of a large file will pin the whole file in memory.  A compacting policy copies small
+/// slices out of large buffers instead.
+///
+/// # Examples
+///
+/// ```
+/// use ergo_core::string::SlicePolicy;
+///
+/// let file = ergo_core::String::from("id=1234,".repeat(1000));
+///
+/// let id = file.get_with(SlicePolicy::compact(), 3..7).unwrap();
+/// assert!(!id.is_shared_with(&file));
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SlicePolicy {
+    /// Slices always share the backing buffer
+    Share,
+    /// Slices are copied out when the backing buffer is more than `ratio` times larger
+    Compact { ratio: usize },
+}
+
+impl SlicePolicy {
+    /// The ratio used by [`String::compact`][super::String::compact]
+    pub const DEFAULT_RATIO: usize = 4;
+
+    /// Copy slices out when the backing buffer is more than [`DEFAULT_RATIO`][Self::DEFAULT_RATIO]
+    /// times larger.
+    pub const fn compact() -> Self {
+        Self::Compact {
+            ratio: Self::DEFAULT_RATIO,
+        }
+    }
+
+    pub(crate) fn should_copy(self, len: usize, backing_len: usize) -> bool {
+        match self {
+            Self::Share => false,
+            Self::Compact { ratio } => len.saturating_mul(ratio) < backing_len,
+        }
+    }
+}
+
+impl Default for SlicePolicy {
+    /// Slices share the buffer, which is what [`String::get`][super::String::get] does.
+    #[inline]
+    fn default() -> Self {
+        Self::Share
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::String;
+
+    #[test]
+    fn ratio_threshold() {
+        assert!(!SlicePolicy::Share.should_copy(1, 1000));
+        assert!(SlicePolicy::compact().should_copy(24, 100));
+        assert!(!SlicePolicy::compact().should_copy(25, 100));
+        assert!(!SlicePolicy::compact().should_copy(usize::MAX, 100));
+    }
+
+    #[test]
+    fn get_with_applies_policy_per_call() {
+        let file = String::from("key=value\n".repeat(100));
+
+        let shared = file.get(..3).unwrap();
+        let compacted = file.get_with(SlicePolicy::compact(), ..3).unwrap();
+        assert!(shared.is_shared_with(&file));
+        assert!(!compacted.is_shared_with(&file));
+        assert_eq!(compacted.backing_len(), 3);
+        assert_eq!(shared, compacted);
+
+        // A slice too large for the ratio keeps sharing
+        let most = file.get_with(SlicePolicy::compact(), 10..).unwrap();
+        assert!(most.is_shared_with(&file));
+        let most = file
+            .get_with(SlicePolicy::Compact { ratio: 2 }, 10..)
+            .unwrap();
+        assert!(most.is_shared_with(&file));
+        let tail = file
+            .get_with(SlicePolicy::Compact { ratio: 2 }, 600..)
+            .unwrap();
+        assert!(!tail.is_shared_with(&file));
+
+        assert!(file.get(..3).unwrap().is_shared_with(&file));
+    }
+}
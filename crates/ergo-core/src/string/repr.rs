@@ -0,0 +1,246 @@
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+
+use super::inline;
+use super::shared::{Atomic, Local, RefCount, SharedString};
+
+pub(crate) const TAG_SIZE: usize = std::mem::size_of::<Tag>();
+pub(crate) const CAPACITY: usize = std::mem::size_of::<SharedString>() - TAG_SIZE;
+pub(crate) type InlineString = inline::InlineString<CAPACITY>;
+
+/// Discriminant stored in the last byte of every representation
+///
+/// Inline strings pack their length into the tag, leaving the remaining bytes for content.  The
+/// unused values give `Option<String>` a niche.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+#[allow(dead_code)] // Inline variants are constructed through `Tag::inline`
+pub(crate) enum Tag {
+    Inline0 = 0,
+    Inline1,
+    Inline2,
+    Inline3,
+    Inline4,
+    Inline5,
+    Inline6,
+    Inline7,
+    Inline8,
+    Inline9,
+    Inline10,
+    Inline11,
+    Inline12,
+    Inline13,
+    Inline14,
+    Inline15,
+    Inline16,
+    Inline17,
+    Inline18,
+    Inline19,
+    Inline20,
+    Inline21,
+    Inline22,
+    Inline23,
+    Shared = 0xFF,
+}
+
+impl Tag {
+    const MAX_INLINE_LEN: usize = Tag::Inline23 as usize;
+
+    #[inline]
+    pub(crate) fn inline(len: usize) -> Self {
+        assert!(len <= Self::MAX_INLINE_LEN);
+        // SAFETY: `Inline0..=Inline23` are contiguous, starting at 0
+        unsafe { std::mem::transmute(len as u8) }
+    }
+
+    #[inline]
+    pub(crate) fn inline_len(self) -> Option<usize> {
+        match self {
+            Tag::Shared => None,
+            tag => Some(tag as usize),
+        }
+    }
+}
+
+/// Space for either an [`InlineString`] or a [`SharedString`], discriminated by the trailing
+/// [`Tag`]
+///
+/// Both representations are `#[repr(C)]`, end in their `Tag` and are exactly as large as this
+/// type, so we can reinterpret the bytes based on the tag.
+#[repr(C)]
+pub(crate) struct StringInner<C: RefCount = Atomic> {
+    _align: [usize; 0],
+    _count: PhantomData<C>,
+    payload: [MaybeUninit<u8>; CAPACITY],
+    tag: Tag,
+}
+
+pub(crate) enum StringRef<'s, C: RefCount = Atomic> {
+    Inline(&'s InlineString),
+    Shared(&'s SharedString<C>),
+}
+
+impl<C: RefCount> StringInner<C> {
+    #[inline]
+    pub(crate) const fn new() -> Self {
+        Self {
+            _align: [],
+            _count: PhantomData,
+            payload: [MaybeUninit::new(0); CAPACITY],
+            tag: Tag::Inline0,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn get(&self) -> StringRef<'_, C> {
+        let ptr = self as *const Self;
+        // SAFETY: the tag tells us which representation was transmuted into `self`
+        unsafe {
+            match self.tag {
+                Tag::Shared => StringRef::Shared(&*(ptr as *const SharedString<C>)),
+                _ => StringRef::Inline(&*(ptr as *const InlineString)),
+            }
+        }
+    }
+
+    /// Switch reference counting strategy, only copying when the buffer is shared
+    #[inline]
+    pub(crate) fn convert<D: RefCount>(self) -> StringInner<D> {
+        match self.get() {
+            StringRef::Inline(s) => StringInner::from(*s),
+            StringRef::Shared(_) => {
+                let this = std::mem::ManuallyDrop::new(self);
+                // SAFETY: the tag says we hold a `SharedString` and `this` won't release it
+                let shared =
+                    unsafe { std::ptr::read(&*this as *const Self as *const SharedString<C>) };
+                StringInner::from(shared.convert::<D>())
+            }
+        }
+    }
+}
+
+impl<C: RefCount> From<InlineString> for StringInner<C> {
+    #[inline]
+    fn from(other: InlineString) -> Self {
+        // SAFETY: see `StringInner`
+        unsafe { transmute_repr(other) }
+    }
+}
+
+impl<C: RefCount> From<SharedString<C>> for StringInner<C> {
+    #[inline]
+    fn from(other: SharedString<C>) -> Self {
+        // SAFETY: see `StringInner`; ownership of the reference count moves into `Self`
+        unsafe { transmute_repr(other) }
+    }
+}
+
+/// `std::mem::transmute` for types only known to be the same size through `const_assert_eq`
+///
+/// # Safety
+///
+/// `R` must be one of the representations of `StringInner<C>`
+#[inline]
+unsafe fn transmute_repr<R, C: RefCount>(other: R) -> StringInner<C> {
+    debug_assert_eq!(
+        std::mem::size_of::<R>(),
+        std::mem::size_of::<StringInner<C>>()
+    );
+    let other = std::mem::ManuallyDrop::new(other);
+    std::ptr::read(&*other as *const R as *const StringInner<C>)
+}
+
+impl<C: RefCount> Clone for StringInner<C> {
+    #[inline]
+    fn clone(&self) -> Self {
+        match self.get() {
+            StringRef::Inline(s) => Self::from(*s),
+            StringRef::Shared(s) => Self::from(s.clone()),
+        }
+    }
+}
+
+impl<C: RefCount> Drop for StringInner<C> {
+    #[inline]
+    fn drop(&mut self) {
+        if self.tag == Tag::Shared {
+            // SAFETY: the tag says we hold a `SharedString` which we are responsible for releasing
+            unsafe { std::ptr::drop_in_place(self as *mut Self as *mut SharedString<C>) }
+        }
+    }
+}
+
+/// Compile-time check without requiring `const` panics
+macro_rules! const_assert_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        const _: [(); $left] = [(); $right];
+    };
+}
+
+const_assert_eq!(
+    std::mem::size_of::<InlineString>(),
+    std::mem::size_of::<StringInner>()
+);
+const_assert_eq!(
+    std::mem::size_of::<SharedString>(),
+    std::mem::size_of::<StringInner>()
+);
+const_assert_eq!(
+    std::mem::size_of::<SharedString<Local>>(),
+    std::mem::size_of::<StringInner>()
+);
+// Buffers change reference counting strategy in-place
+const_assert_eq!(
+    std::mem::size_of::<<Atomic as RefCount>::Counter>(),
+    std::mem::size_of::<<Local as RefCount>::Counter>()
+);
+const_assert_eq!(
+    std::mem::size_of::<StringInner<Local>>(),
+    std::mem::size_of::<StringInner>()
+);
+const_assert_eq!(
+    std::mem::size_of::<StringInner>(),
+    std::mem::size_of::<std::string::String>()
+);
+const_assert_eq!(
+    std::mem::size_of::<Option<StringInner>>(),
+    std::mem::size_of::<StringInner>()
+);
+const_assert_eq!(
+    std::mem::align_of::<StringInner>(),
+    std::mem::align_of::<SharedString>()
+);
+// `Tag` must be able to encode every inline length
+const _: usize = Tag::MAX_INLINE_LEN - CAPACITY;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::String;
+
+    #[test]
+    fn inline_boundary() {
+        let source = "x".repeat(CAPACITY + 1);
+        for len in 0..=CAPACITY + 1 {
+            let s = String::from(&source[..len]);
+            assert_eq!(s, &source[..len]);
+            match s.0.get() {
+                StringRef::Inline(_) => assert!(len <= CAPACITY),
+                StringRef::Shared(_) => assert!(CAPACITY < len),
+            }
+        }
+    }
+
+    #[test]
+    fn shared_slices_outlive_parent() {
+        let source = "Hello World, this is a shared buffer";
+        let parent = String::from(source);
+        let child = parent.get(6..).unwrap();
+        let grandchild = child.get(..5).unwrap();
+        let clone = child.clone();
+        drop(parent);
+        assert_eq!(child, &source[6..]);
+        assert_eq!(clone, &source[6..]);
+        assert_eq!(grandchild, "World");
+    }
+}
@@ -0,0 +1,613 @@
+//! Substring search over bytes
+//!
+//! Single bytes are found with a vectorized `memchr`: SSE2 on `x86_64`, and a word-at-a-time
+//! scalar loop elsewhere.  Longer needles use a packed-pair prefilter, which looks for two rare
+//! bytes of the needle at their offsets at once, and verify each candidate.  When candidates keep
+//! failing, like with `aaa…ab` in `aaa…a`, the search switches to Two-Way, which is linear in
+//! the haystack for any needle.  Searching from the end uses `memchr`'s reverse Two-Way, so it
+//! is linear too.
+
+use std::convert::TryInto;
+
+/// Haystacks shorter than this go straight to Two-Way; the prefilter wouldn't pay for itself.
+const SHORT_HAYSTACK: usize = 64;
+
+/// Returns the index of the first occurrence of `needle` in `haystack`.
+///
+/// An empty `needle` is found at `0`.
+pub(super) fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    Finder::new(needle).find(haystack)
+}
+
+/// Returns the index of the last occurrence of `needle` in `haystack`.
+///
+/// An empty `needle` is found at the end.
+pub(super) fn rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    match needle {
+        [] => Some(haystack.len()),
+        [byte] => memrchr(*byte, haystack),
+        _ => memchr::memmem::FinderRev::new(needle).rfind(haystack),
+    }
+}
+
+/// A needle, preprocessed to find it in many haystacks
+#[derive(Clone, Debug)]
+pub(super) struct Finder<'n> {
+    needle: &'n [u8],
+    kind: Kind,
+}
+
+#[derive(Clone, Debug)]
+enum Kind {
+    Empty,
+    Byte(u8),
+    Substring { pair: Pair, two_way: TwoWay },
+}
+
+impl<'n> Finder<'n> {
+    pub(super) fn new(needle: &'n [u8]) -> Self {
+        let kind = match needle {
+            [] => Kind::Empty,
+            [byte] => Kind::Byte(*byte),
+            _ => Kind::Substring {
+                pair: Pair::new(needle),
+                two_way: TwoWay::new(needle),
+            },
+        };
+        Self { needle, kind }
+    }
+
+    pub(super) fn find(&self, haystack: &[u8]) -> Option<usize> {
+        let needle = self.needle;
+        match &self.kind {
+            Kind::Empty => Some(0),
+            Kind::Byte(byte) => memchr(*byte, haystack),
+            Kind::Substring { .. } if haystack.len() < needle.len() => None,
+            Kind::Substring { two_way, .. } if haystack.len() < SHORT_HAYSTACK => {
+                two_way.find(haystack, needle, 0)
+            }
+            Kind::Substring { pair, two_way } => {
+                let mut at = 0;
+                let mut verified = 0;
+                while let Some(candidate) = pair.find_candidate(haystack, needle.len(), at) {
+                    if &haystack[candidate..candidate + needle.len()] == needle {
+                        return Some(candidate);
+                    }
+                    at = candidate + 1;
+                    verified += needle.len();
+                    // Verifying is costing more than scanning, so stop relying on luck
+                    if 2 * at + SHORT_HAYSTACK * 16 < verified {
+                        return two_way.find(haystack, needle, at);
+                    }
+                }
+                None
+            }
+        }
+    }
+}
+
+/// Two bytes of the needle, at different offsets, that are unlikely to occur together
+#[derive(Clone, Debug)]
+struct Pair {
+    index1: usize,
+    byte1: u8,
+    index2: usize,
+    byte2: u8,
+}
+
+impl Pair {
+    fn new(needle: &[u8]) -> Self {
+        debug_assert!(2 <= needle.len());
+        let rarest = |exclude: Option<usize>| {
+            (0..needle.len())
+                .filter(|&i| Some(i) != exclude)
+                .min_by_key(|&i| {
+                    // Prefer a different byte to the first pick, so the pair says more
+                    let repeat = matches!(exclude, Some(e) if needle[e] == needle[i]);
+                    (repeat, rank(needle[i]))
+                })
+                .expect("needle has at least two bytes")
+        };
+        let index1 = rarest(None);
+        let index2 = rarest(Some(index1));
+        Self {
+            index1,
+            byte1: needle[index1],
+            index2,
+            byte2: needle[index2],
+        }
+    }
+
+    /// Returns the first start, from `at` on, where both bytes of the pair match and a needle of
+    /// `len` fits.
+    fn find_candidate(&self, haystack: &[u8], len: usize, at: usize) -> Option<usize> {
+        let last_start = haystack.len().checked_sub(len)?;
+        let mut start = at;
+
+        #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+        {
+            if let Some(found) = sse2::find_pair(self, haystack, &mut start, last_start) {
+                return Some(found);
+            }
+        }
+
+        while start <= last_start {
+            let offset = start + self.index1;
+            let window = &haystack[offset..=last_start + self.index1];
+            let found = memchr(self.byte1, window)? + start;
+            if haystack[found + self.index2] == self.byte2 {
+                return Some(found);
+            }
+            start = found + 1;
+        }
+        None
+    }
+}
+
+/// Estimates how common a byte is in text and binary data; lower is rarer.
+fn rank(byte: u8) -> u8 {
+    match byte {
+        b' ' | b'e' | b't' | b'a' | b'o' | b'i' | b'n' | b's' | b'r' | b'h' => 255,
+        b'a'..=b'z' => 220,
+        b'\n' | b'\r' | b'\t' | b',' | b'.' | b'"' | b'/' | b':' | b'=' | b'-' | b'_' => 200,
+        0 | 0xff => 190,
+        b'0'..=b'9' => 180,
+        b'A'..=b'Z' => 160,
+        0x80..=0xfe => 120,
+        _ => 100,
+    }
+}
+
+/// The Two-Way string matching algorithm, by Crochemore and Perrin
+///
+/// The needle is split at a critical factorization `needle[..crit_pos]`, `needle[crit_pos..]`.
+/// Matching the right half first, then the left, lets a mismatch shift by enough to never
+/// re-examine more than a constant number of haystack bytes.
+#[derive(Clone, Debug)]
+struct TwoWay {
+    crit_pos: usize,
+    period: usize,
+    /// Whether the needle is not periodic, so there is nothing to remember between shifts
+    long_period: bool,
+    /// A cheap approximation of the set of bytes in the needle, by their low 6 bits
+    byteset: u64,
+}
+
+impl TwoWay {
+    fn new(needle: &[u8]) -> Self {
+        let (crit_pos_less, period_less) = maximal_suffix(needle, false);
+        let (crit_pos_greater, period_greater) = maximal_suffix(needle, true);
+        let (crit_pos, period) = if crit_pos_greater < crit_pos_less {
+            (crit_pos_less, period_less)
+        } else {
+            (crit_pos_greater, period_greater)
+        };
+        let byteset = needle
+            .iter()
+            .fold(0, |set, &byte| set | (1 << (byte & 0x3f)));
+
+        if needle[..crit_pos] == needle[period..period + crit_pos] {
+            Self {
+                crit_pos,
+                period,
+                long_period: false,
+                byteset,
+            }
+        } else {
+            Self {
+                crit_pos,
+                period: crit_pos.max(needle.len() - crit_pos) + 1,
+                long_period: true,
+                byteset,
+            }
+        }
+    }
+
+    fn find(&self, haystack: &[u8], needle: &[u8], start: usize) -> Option<usize> {
+        let mut position = start;
+        // How much of the needle's start is known to match, after a shift by the period
+        let mut memory = 0;
+        'search: loop {
+            let tail = *haystack.get(position + needle.len() - 1)?;
+            if self.byteset & (1 << (tail & 0x3f)) == 0 {
+                position += needle.len();
+                memory = 0;
+                continue 'search;
+            }
+
+            let right_start = if self.long_period {
+                self.crit_pos
+            } else {
+                self.crit_pos.max(memory)
+            };
+            for i in right_start..needle.len() {
+                if needle[i] != haystack[position + i] {
+                    position += i - self.crit_pos + 1;
+                    memory = 0;
+                    continue 'search;
+                }
+            }
+
+            let left_end = if self.long_period { 0 } else { memory };
+            for i in (left_end..self.crit_pos).rev() {
+                if needle[i] != haystack[position + i] {
+                    position += self.period;
+                    if !self.long_period {
+                        memory = needle.len() - self.period;
+                    }
+                    continue 'search;
+                }
+            }
+
+            return Some(position);
+        }
+    }
+}
+
+/// Returns the start and period of the maximal suffix of `needle`, in lexicographic order or,
+/// with `reversed`, in reverse order.
+fn maximal_suffix(needle: &[u8], reversed: bool) -> (usize, usize) {
+    let mut left = 0;
+    let mut right = 1;
+    let mut offset = 0;
+    let mut period = 1;
+    while let Some(&a) = needle.get(right + offset) {
+        let b = needle[left + offset];
+        if (a < b && !reversed) || (b < a && reversed) {
+            // The suffix at `right` is smaller, so the period is everything so far
+            right += offset + 1;
+            offset = 0;
+            period = right - left;
+        } else if a == b {
+            if offset + 1 == period {
+                right += offset + 1;
+                offset = 0;
+            } else {
+                offset += 1;
+            }
+        } else {
+            // The suffix at `right` is larger, so start over from there
+            left = right;
+            right += 1;
+            offset = 0;
+            period = 1;
+        }
+    }
+    (left, period)
+}
+
+const WORD: usize = std::mem::size_of::<usize>();
+const LO: usize = usize::MAX / 255;
+const HI: usize = LO << 7;
+
+/// Returns `true` if any byte of `word` is zero.
+#[inline]
+fn has_zero_byte(word: usize) -> bool {
+    word.wrapping_sub(LO) & !word & HI != 0
+}
+
+/// Returns the index of the first `byte` in `haystack`.
+pub(super) fn memchr(byte: u8, haystack: &[u8]) -> Option<usize> {
+    #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+    {
+        sse2::memchr(byte, haystack)
+    }
+    #[cfg(not(all(target_arch = "x86_64", target_feature = "sse2")))]
+    {
+        memchr_fallback(byte, haystack)
+    }
+}
+
+/// Returns the index of the last `byte` in `haystack`.
+pub(super) fn memrchr(byte: u8, haystack: &[u8]) -> Option<usize> {
+    #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+    {
+        sse2::memrchr(byte, haystack)
+    }
+    #[cfg(not(all(target_arch = "x86_64", target_feature = "sse2")))]
+    {
+        memrchr_fallback(byte, haystack)
+    }
+}
+
+/// Compares a word at a time, checking whether any byte of `word ^ repeated` is zero.
+#[cfg_attr(all(target_arch = "x86_64", target_feature = "sse2"), allow(dead_code))]
+fn memchr_fallback(byte: u8, haystack: &[u8]) -> Option<usize> {
+    let repeated = LO * byte as usize;
+    let mut chunks = haystack.chunks_exact(WORD);
+    let mut offset = 0;
+    for chunk in &mut chunks {
+        let word = usize::from_ne_bytes(chunk.try_into().unwrap());
+        if has_zero_byte(word ^ repeated) {
+            break;
+        }
+        offset += WORD;
+    }
+    haystack[offset..]
+        .iter()
+        .position(|&b| b == byte)
+        .map(|i| offset + i)
+}
+
+#[cfg_attr(all(target_arch = "x86_64", target_feature = "sse2"), allow(dead_code))]
+fn memrchr_fallback(byte: u8, haystack: &[u8]) -> Option<usize> {
+    let repeated = LO * byte as usize;
+    let mut chunks = haystack.rchunks_exact(WORD);
+    let mut end = haystack.len();
+    for chunk in &mut chunks {
+        let word = usize::from_ne_bytes(chunk.try_into().unwrap());
+        if has_zero_byte(word ^ repeated) {
+            break;
+        }
+        end -= WORD;
+    }
+    haystack[..end].iter().rposition(|&b| b == byte)
+}
+
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+mod sse2 {
+    use std::arch::x86_64::{
+        __m128i, _mm_and_si128, _mm_cmpeq_epi8, _mm_loadu_si128, _mm_movemask_epi8, _mm_or_si128,
+        _mm_set1_epi8,
+    };
+
+    use super::Pair;
+
+    const LANES: usize = 16;
+
+    /// Returns a bit per byte of `haystack[at..at + 16]` that equals `needle`.
+    ///
+    /// # Safety
+    ///
+    /// `at + 16` must be at most `haystack.len()`.
+    #[inline]
+    unsafe fn matches(haystack: &[u8], at: usize, needle: __m128i) -> u32 {
+        debug_assert!(at + LANES <= haystack.len());
+        let chunk = _mm_loadu_si128(haystack.as_ptr().add(at) as *const __m128i);
+        _mm_movemask_epi8(_mm_cmpeq_epi8(chunk, needle)) as u32
+    }
+
+    pub(super) fn memchr(byte: u8, haystack: &[u8]) -> Option<usize> {
+        if haystack.len() < LANES {
+            return haystack.iter().position(|&b| b == byte);
+        }
+        // SAFETY: every load ends within `haystack`
+        unsafe {
+            let needle = _mm_set1_epi8(byte as i8);
+            let mut at = 0;
+            while at + 4 * LANES <= haystack.len() {
+                let loads = [
+                    _mm_loadu_si128(haystack.as_ptr().add(at) as *const __m128i),
+                    _mm_loadu_si128(haystack.as_ptr().add(at + LANES) as *const __m128i),
+                    _mm_loadu_si128(haystack.as_ptr().add(at + 2 * LANES) as *const __m128i),
+                    _mm_loadu_si128(haystack.as_ptr().add(at + 3 * LANES) as *const __m128i),
+                ];
+                let any = _mm_or_si128(
+                    _mm_or_si128(
+                        _mm_cmpeq_epi8(loads[0], needle),
+                        _mm_cmpeq_epi8(loads[1], needle),
+                    ),
+                    _mm_or_si128(
+                        _mm_cmpeq_epi8(loads[2], needle),
+                        _mm_cmpeq_epi8(loads[3], needle),
+                    ),
+                );
+                if _mm_movemask_epi8(any) != 0 {
+                    break;
+                }
+                at += 4 * LANES;
+            }
+            while at + LANES <= haystack.len() {
+                let mask = matches(haystack, at, needle);
+                if mask != 0 {
+                    return Some(at + mask.trailing_zeros() as usize);
+                }
+                at += LANES;
+            }
+            if at < haystack.len() {
+                // Overlap the last full chunk rather than finishing byte by byte
+                let last = haystack.len() - LANES;
+                let mask = matches(haystack, last, needle) >> (at - last);
+                if mask != 0 {
+                    return Some(at + mask.trailing_zeros() as usize);
+                }
+            }
+            None
+        }
+    }
+
+    pub(super) fn memrchr(byte: u8, haystack: &[u8]) -> Option<usize> {
+        if haystack.len() < LANES {
+            return haystack.iter().rposition(|&b| b == byte);
+        }
+        // SAFETY: every load ends within `haystack`
+        unsafe {
+            let needle = _mm_set1_epi8(byte as i8);
+            let mut end = haystack.len();
+            while LANES <= end {
+                let mask = matches(haystack, end - LANES, needle);
+                if mask != 0 {
+                    return Some(end - LANES + 31 - mask.leading_zeros() as usize);
+                }
+                end -= LANES;
+            }
+            if 0 < end {
+                // Overlap the first full chunk
+                let mask = matches(haystack, 0, needle) & ((1 << end) - 1);
+                if mask != 0 {
+                    return Some(31 - mask.leading_zeros() as usize);
+                }
+            }
+            None
+        }
+    }
+
+    /// Scans 16 starts at a time from `*start`, leaving the rest to the caller.
+    pub(super) fn find_pair(
+        pair: &Pair,
+        haystack: &[u8],
+        start: &mut usize,
+        last_start: usize,
+    ) -> Option<usize> {
+        // SAFETY: a needle fits at every start up to `last_start`, so both loads end within
+        // `haystack` while `*start + 16` starts remain
+        unsafe {
+            let byte1 = _mm_set1_epi8(pair.byte1 as i8);
+            let byte2 = _mm_set1_epi8(pair.byte2 as i8);
+            while *start + LANES <= last_start + 1 {
+                let chunk1 =
+                    _mm_loadu_si128(haystack.as_ptr().add(*start + pair.index1) as *const __m128i);
+                let chunk2 =
+                    _mm_loadu_si128(haystack.as_ptr().add(*start + pair.index2) as *const __m128i);
+                let both =
+                    _mm_and_si128(_mm_cmpeq_epi8(chunk1, byte1), _mm_cmpeq_epi8(chunk2, byte2));
+                let mask = _mm_movemask_epi8(both);
+                if mask != 0 {
+                    return Some(*start + mask.trailing_zeros() as usize);
+                }
+                *start += LANES;
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::{xorshift, SEED};
+
+    fn naive_find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        if needle.is_empty() {
+            return Some(0);
+        }
+        haystack
+            .windows(needle.len())
+            .position(|window| window == needle)
+    }
+
+    fn naive_rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        if needle.is_empty() {
+            return Some(haystack.len());
+        }
+        haystack
+            .windows(needle.len())
+            .rposition(|window| window == needle)
+    }
+
+    /// Returns `len` pseudo-random bytes from `alphabet`.
+    fn bytes(state: &mut u32, len: usize, alphabet: &[u8]) -> Vec<u8> {
+        (0..len)
+            .map(|_| alphabet[xorshift(state) as usize % alphabet.len()])
+            .collect()
+    }
+
+    #[test]
+    fn memchr_matches_naive() {
+        let haystack: Vec<u8> = (0..200).map(|i| (i % 7) as u8).collect();
+        for len in 0..haystack.len() {
+            for start in (0..len).step_by(13) {
+                let haystack = &haystack[start..len];
+                for byte in 0..8 {
+                    let expected = haystack.iter().position(|&b| b == byte);
+                    assert_eq!(memchr(byte, haystack), expected);
+                    assert_eq!(memchr_fallback(byte, haystack), expected);
+                    let expected = haystack.iter().rposition(|&b| b == byte);
+                    assert_eq!(memrchr(byte, haystack), expected);
+                    assert_eq!(memrchr_fallback(byte, haystack), expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn find_matches_naive() {
+        let mut state = SEED;
+        for alphabet in [&b"ab"[..], b"abc", b"a\x00\xff", b"abcdefghij"].iter() {
+            for haystack_len in [0, 5, 63, 64, 65, 100, 300].iter() {
+                let haystack = bytes(&mut state, *haystack_len, alphabet);
+                for needle_len in 0..10 {
+                    for _ in 0..10 {
+                        let needle = bytes(&mut state, needle_len, alphabet);
+                        assert_eq!(
+                            find(&haystack, &needle),
+                            naive_find(&haystack, &needle),
+                            "{:?} in {:?}",
+                            needle,
+                            haystack
+                        );
+                        assert_eq!(
+                            rfind(&haystack, &needle),
+                            naive_rfind(&haystack, &needle),
+                            "{:?} in {:?}",
+                            needle,
+                            haystack
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn periodic_needles() {
+        let mut haystack = vec![b'a'; 10_000];
+        let cases: [&[u8]; 5] = [
+            b"aab",
+            b"aaaaaaaaab",
+            b"baaaaaaaaa",
+            b"abaabaab",
+            b"aaaaaaaaaa",
+        ];
+        for needle in cases.iter() {
+            assert_eq!(
+                find(&haystack, needle),
+                naive_find(&haystack, needle),
+                "{:?}",
+                needle
+            );
+        }
+        haystack.extend_from_slice(b"aaab");
+        assert_eq!(find(&haystack, b"aaaaaaaaab"), Some(10_004 - 10));
+        assert_eq!(find(&haystack, b"abaabaab"), None);
+
+        // Every candidate of the pair fails to verify, so the search falls back to Two-Way
+        let mut haystack = b"ab".repeat(5_000);
+        let mut needle = b"ab".repeat(10);
+        needle.push(b'b');
+        assert_eq!(find(&haystack, &needle), None);
+        haystack.push(b'b');
+        assert_eq!(find(&haystack, &needle), Some(10_000 - 20));
+    }
+
+    #[test]
+    fn rfind_stays_linear() {
+        // Checking every `a` for a match would take quadratic time
+        let mut haystack = vec![b'a'; 1_000_000];
+        let mut needle = vec![b'b'];
+        needle.extend_from_slice(&[b'a'; 10_000]);
+        assert_eq!(rfind(&haystack, &needle), None);
+        haystack[10] = b'b';
+        assert_eq!(rfind(&haystack, &needle), Some(10));
+    }
+
+    #[test]
+    fn two_way_matches_naive() {
+        let mut state = SEED;
+        for alphabet in [&b"ab"[..], b"abc"].iter() {
+            let haystack = bytes(&mut state, 500, alphabet);
+            for needle_len in 2..20 {
+                for _ in 0..20 {
+                    let needle = bytes(&mut state, needle_len, alphabet);
+                    for start in [0, 1, 17, 250].iter() {
+                        let expected = naive_find(&haystack[*start..], &needle).map(|i| i + start);
+                        assert_eq!(
+                            TwoWay::new(&needle).find(&haystack, &needle, *start),
+                            expected
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,69 +1,306 @@
+use std::alloc::Layout;
+use std::cell::Cell;
 use std::cmp::Ordering;
 use std::fmt;
 use std::hash::{Hash, Hasher};
-use std::sync::Arc;
+use std::marker::PhantomData;
+use std::ptr::NonNull;
+use std::sync::atomic::{self, AtomicUsize};
 
-#[derive(Clone)]
-pub(crate) struct SharedString {
-    buffer: Arc<str>,
-    start: usize,
+use super::repr::{Tag, TAG_SIZE};
+
+const START_SIZE: usize = std::mem::size_of::<usize>() - TAG_SIZE;
+const MAX_START: usize = (1 << (8 * START_SIZE)) - 1;
+
+/// Reference counting strategy for a [`SharedString`]
+pub(crate) trait RefCount {
+    /// Must be `usize`-sized so buffers can change strategy in-place
+    type Counter;
+
+    fn new() -> Self::Counter;
+
+    /// Returns the previous count
+    fn increment(count: &Self::Counter) -> usize;
+
+    /// Returns the previous count; when `1`, the caller may release the buffer
+    fn decrement(count: &Self::Counter) -> usize;
+
+    fn is_unique(count: &Self::Counter) -> bool;
+}
+
+/// Thread-safe reference counting, like `Arc`
+pub(crate) struct Atomic;
+
+impl RefCount for Atomic {
+    type Counter = AtomicUsize;
+
+    #[inline]
+    fn new() -> Self::Counter {
+        AtomicUsize::new(1)
+    }
+
+    #[inline]
+    fn increment(count: &Self::Counter) -> usize {
+        // Relaxed is enough, like `Arc`: new references can only be made from existing ones
+        count.fetch_add(1, atomic::Ordering::Relaxed)
+    }
+
+    #[inline]
+    fn decrement(count: &Self::Counter) -> usize {
+        let old = count.fetch_sub(1, atomic::Ordering::Release);
+        if old == 1 {
+            atomic::fence(atomic::Ordering::Acquire);
+        }
+        old
+    }
+
+    #[inline]
+    fn is_unique(count: &Self::Counter) -> bool {
+        count.load(atomic::Ordering::Acquire) == 1
+    }
+}
+
+/// Single-threaded reference counting, like `Rc`
+pub(crate) struct Local(PhantomData<*const ()>);
+
+impl RefCount for Local {
+    type Counter = Cell<usize>;
+
+    #[inline]
+    fn new() -> Self::Counter {
+        Cell::new(1)
+    }
+
+    #[inline]
+    fn increment(count: &Self::Counter) -> usize {
+        let old = count.get();
+        count.set(old + 1);
+        old
+    }
+
+    #[inline]
+    fn decrement(count: &Self::Counter) -> usize {
+        let old = count.get();
+        count.set(old - 1);
+        old
+    }
+
+    #[inline]
+    fn is_unique(count: &Self::Counter) -> bool {
+        count.get() == 1
+    }
+}
+
+/// A slice of a reference-counted, heap-allocated buffer
+///
+/// Unlike `Arc<str>`, the pointer is thin: the buffer's length lives in the [`Header`], making
+/// room for the slice's offset and the [`Tag`].  The offset is truncated to fit; slices starting
+/// further in than that get their own copy.
+#[repr(C)]
+pub(crate) struct SharedString<C: RefCount = Atomic> {
+    header: NonNull<Header<C>>,
     len: usize,
+    start: [u8; START_SIZE],
+    tag: Tag,
 }
 
-impl SharedString {
+impl<C: RefCount> SharedString<C> {
     pub(crate) fn own_str(&self, subset: &str) -> Self {
-        let (start, end) = super::calculate_subset(self.as_str(), subset);
+        self.own_bytes(subset.as_bytes())
+    }
+
+    pub(crate) fn own_bytes(&self, subset: &[u8]) -> Self {
+        let (start, end) = super::calculate_subset(self.as_bytes(), subset);
         let len = end - start;
+        let start = self.start() + start;
+        if MAX_START < start {
+            return Self::from(subset);
+        }
+
         let mut sub = self.clone();
-        sub.start = start;
+        sub.start = encode_start(start);
         sub.len = len;
         sub
     }
 
+    /// Switch reference counting strategy, only copying when the buffer is shared
+    pub(crate) fn convert<D: RefCount>(self) -> SharedString<D> {
+        if C::is_unique(&self.header().count) {
+            let other = SharedString {
+                header: self.header.cast::<Header<D>>(),
+                len: self.len,
+                start: self.start,
+                tag: self.tag,
+            };
+            std::mem::forget(self);
+            other
+        } else {
+            SharedString::from(self.as_bytes())
+        }
+    }
+
+    /// Only valid when created from UTF-8
     pub(crate) fn as_str(&self) -> &str {
-        let end = self.start + self.len;
-        &self.buffer.as_ref()[self.start..end]
+        unsafe { std::str::from_utf8_unchecked(self.as_bytes()) }
+    }
+
+    pub(crate) fn as_bytes(&self) -> &[u8] {
+        unsafe {
+            let data = Header::data(self.header).add(self.start());
+            std::slice::from_raw_parts(data, self.len)
+        }
+    }
+
+    #[inline]
+    pub(crate) fn backing_len(&self) -> usize {
+        self.header().len
+    }
+
+    #[inline]
+    pub(crate) fn is_shared_with(&self, other: &Self) -> bool {
+        self.header == other.header
+    }
+
+    #[inline]
+    fn start(&self) -> usize {
+        let mut bytes = [0; std::mem::size_of::<usize>()];
+        bytes[..START_SIZE].copy_from_slice(&self.start);
+        usize::from_le_bytes(bytes)
+    }
+
+    #[inline]
+    fn header(&self) -> &Header<C> {
+        unsafe { self.header.as_ref() }
+    }
+}
+
+#[inline]
+fn encode_start(start: usize) -> [u8; START_SIZE] {
+    debug_assert!(start <= MAX_START);
+    let mut bytes = [0; START_SIZE];
+    bytes.copy_from_slice(&start.to_le_bytes()[..START_SIZE]);
+    bytes
+}
+
+impl<C: RefCount> Clone for SharedString<C> {
+    #[inline]
+    fn clone(&self) -> Self {
+        let old = C::increment(&self.header().count);
+        if isize::MAX as usize <= old {
+            std::process::abort();
+        }
+        Self {
+            header: self.header,
+            len: self.len,
+            start: self.start,
+            tag: self.tag,
+        }
+    }
+}
+
+impl<C: RefCount> Drop for SharedString<C> {
+    #[inline]
+    fn drop(&mut self) {
+        if C::decrement(&self.header().count) != 1 {
+            return;
+        }
+        unsafe { Header::deallocate(self.header) }
     }
 }
 
-impl fmt::Debug for SharedString {
+impl<C: RefCount> fmt::Debug for SharedString<C> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        fmt::Debug::fmt(self.as_str(), f)
+        fmt::Debug::fmt(self.as_bytes(), f)
     }
 }
 
-impl PartialEq for SharedString {
+impl<C: RefCount> PartialEq for SharedString<C> {
     fn eq(&self, other: &Self) -> bool {
-        self.as_str() == other.as_str()
+        self.as_bytes() == other.as_bytes()
     }
 }
 
-impl Eq for SharedString {}
+impl<C: RefCount> Eq for SharedString<C> {}
 
-impl PartialOrd for SharedString {
+impl<C: RefCount> PartialOrd for SharedString<C> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
 
-impl Ord for SharedString {
+impl<C: RefCount> Ord for SharedString<C> {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.as_str().cmp(other.as_str())
+        self.as_bytes().cmp(other.as_bytes())
     }
 }
 
-impl Hash for SharedString {
+impl<C: RefCount> Hash for SharedString<C> {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        self.as_str().hash(state);
+        self.as_bytes().hash(state);
     }
 }
 
-impl<'s> From<&'s str> for SharedString {
+impl<'s, C: RefCount> From<&'s str> for SharedString<C> {
     fn from(other: &'s str) -> Self {
+        Self::from(other.as_bytes())
+    }
+}
+
+impl<'s, C: RefCount> From<&'s [u8]> for SharedString<C> {
+    fn from(other: &'s [u8]) -> Self {
         Self {
-            buffer: Arc::from(other),
-            start: 0,
+            header: Header::allocate(other),
             len: other.len(),
+            start: encode_start(0),
+            tag: Tag::Shared,
         }
     }
 }
+
+/// Prefix of the allocation, followed by the bytes of the buffer
+#[repr(C)]
+struct Header<C: RefCount> {
+    count: C::Counter,
+    len: usize,
+}
+
+impl<C: RefCount> Header<C> {
+    fn layout(len: usize) -> Layout {
+        Layout::new::<Self>()
+            .extend(Layout::array::<u8>(len).expect("buffer too large"))
+            .expect("buffer too large")
+            .0
+            .pad_to_align()
+    }
+
+    fn allocate(bytes: &[u8]) -> NonNull<Self> {
+        let layout = Self::layout(bytes.len());
+        unsafe {
+            let ptr = std::alloc::alloc(layout) as *mut Self;
+            let ptr = match NonNull::new(ptr) {
+                Some(ptr) => ptr,
+                None => std::alloc::handle_alloc_error(layout),
+            };
+            ptr.as_ptr().write(Self {
+                count: C::new(),
+                len: bytes.len(),
+            });
+            let data = Self::data(ptr);
+            std::ptr::copy_nonoverlapping(bytes.as_ptr(), data, bytes.len());
+            ptr
+        }
+    }
+
+    /// # Safety
+    ///
+    /// `header` must have come from [`Header::allocate`] and have no remaining references
+    unsafe fn deallocate(header: NonNull<Self>) {
+        let layout = Self::layout(header.as_ref().len);
+        std::alloc::dealloc(header.as_ptr() as *mut u8, layout);
+    }
+
+    #[inline]
+    fn data(header: NonNull<Self>) -> *mut u8 {
+        unsafe { header.as_ptr().add(1) as *mut u8 }
+    }
+}
@@ -0,0 +1,298 @@
+//! A port of Python's `textwrap` with its default options
+//!
+//! Widths count [`char`]s, like Python's `len`.
+
+use super::StdString;
+
+const TAB_SIZE: usize = 8;
+
+fn char_len(s: &str) -> usize {
+    s.chars().count()
+}
+
+fn is_blank(s: &str) -> bool {
+    s.chars().all(char::is_whitespace)
+}
+
+/// Wraps `text` into lines of at most `width` chars, ending with a `placeholder` if lines are
+/// dropped past `max_lines`.
+pub(super) fn wrap(text: &str, width: usize, max_lines: Option<(usize, &str)>) -> Vec<StdString> {
+    assert!(0 < width, "invalid width {} (must be > 0)", width);
+    if let Some((_, placeholder)) = max_lines {
+        assert!(
+            char_len(placeholder.trim_start()) <= width,
+            "placeholder too large for max width"
+        );
+    }
+
+    let text = munge_whitespace(text);
+    let mut chunks: Vec<&str> = split_chunks(&text);
+    chunks.reverse();
+
+    let mut lines: Vec<StdString> = Vec::new();
+    while !chunks.is_empty() {
+        let mut line: Vec<&str> = Vec::new();
+        let mut line_len = 0;
+
+        // Leading whitespace is dropped, except at the start of the text
+        if !lines.is_empty() && matches!(chunks.last(), Some(chunk) if is_blank(chunk)) {
+            chunks.pop();
+        }
+
+        while let Some(chunk) = chunks.last() {
+            let len = char_len(chunk);
+            if width < line_len + len {
+                break;
+            }
+            line.push(chunk);
+            line_len += len;
+            chunks.pop();
+        }
+
+        if matches!(chunks.last(), Some(chunk) if width < char_len(chunk)) {
+            let space_left = if line_len < width {
+                width - line_len
+            } else {
+                1
+            };
+            let chunk = chunks.pop().expect("checked above");
+            let (head, tail) = split_long_word(chunk, space_left);
+            line.push(head);
+            line_len += char_len(head);
+            chunks.push(tail);
+        }
+
+        if matches!(line.last(), Some(chunk) if is_blank(chunk)) {
+            line_len -= char_len(line.pop().expect("checked above"));
+        }
+
+        if line.is_empty() {
+            continue;
+        }
+
+        let (max, placeholder) = match max_lines {
+            Some(max_lines) => max_lines,
+            None => {
+                lines.push(line.concat());
+                continue;
+            }
+        };
+        let only_whitespace_left = chunks.len() == 1 && is_blank(chunks[0]);
+        if lines.len() + 1 < max || (chunks.is_empty() || only_whitespace_left) && line_len <= width
+        {
+            lines.push(line.concat());
+            continue;
+        }
+
+        // Truncate, making room for the placeholder
+        let placeholder_len = char_len(placeholder);
+        while let Some(chunk) = line.last() {
+            if !is_blank(chunk) && line_len + placeholder_len <= width {
+                line.push(placeholder);
+                lines.push(line.concat());
+                return lines;
+            }
+            line_len -= char_len(chunk);
+            line.pop();
+        }
+        if let Some(previous) = lines.last_mut() {
+            let trimmed = previous.trim_end();
+            if char_len(trimmed) + placeholder_len <= width {
+                *previous = format!("{}{}", trimmed, placeholder);
+                return lines;
+            }
+        }
+        lines.push(placeholder.trim_start().to_owned());
+        return lines;
+    }
+    lines
+}
+
+/// Expands tabs and turns every whitespace char into a space.
+fn munge_whitespace(text: &str) -> StdString {
+    let mut munged = StdString::with_capacity(text.len());
+    let mut column = 0;
+    for c in text.chars() {
+        match c {
+            '\t' => {
+                let spaces = TAB_SIZE - column % TAB_SIZE;
+                munged.extend((0..spaces).map(|_| ' '));
+                column += spaces;
+            }
+            '\n' | '\r' => {
+                munged.push(' ');
+                column = 0;
+            }
+            c if c.is_whitespace() => {
+                munged.push(' ');
+                column += 1;
+            }
+            c => {
+                munged.push(c);
+                column += 1;
+            }
+        }
+    }
+    munged
+}
+
+/// Splits into runs of whitespace and words, breaking hyphenated words after each hyphen.
+fn split_chunks(text: &str) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    let mut previous: Option<char> = None;
+    let mut chars = text.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        if let Some(previous) = previous {
+            if previous.is_whitespace() != c.is_whitespace() {
+                chunks.push(&text[start..index]);
+                start = index;
+            }
+        }
+        let next = chars.peek().map(|(_, c)| *c);
+        let breaks_after_hyphen = c == '-'
+            && matches!(previous, Some(c) if c.is_alphabetic())
+            && matches!(next, Some(c) if c.is_alphabetic());
+        if breaks_after_hyphen {
+            let end = index + c.len_utf8();
+            chunks.push(&text[start..end]);
+            start = end;
+        }
+        previous = Some(c);
+    }
+    if start < text.len() {
+        chunks.push(&text[start..]);
+    }
+    chunks
+}
+
+/// Breaks a word that can't fit on any line, preferring to break after a hyphen.
+fn split_long_word(chunk: &str, space_left: usize) -> (&str, &str) {
+    let mut end = chunk
+        .char_indices()
+        .nth(space_left)
+        .map_or(chunk.len(), |(index, _)| index);
+    if let Some(hyphen) = chunk[..end].rfind('-') {
+        if 0 < hyphen && chunk[..hyphen].contains(|c| c != '-') {
+            end = hyphen + 1;
+        }
+    }
+    chunk.split_at(end)
+}
+
+/// Removes the common leading spaces and tabs from every line, emptying blank lines.
+pub(super) fn dedent(text: &str) -> Option<StdString> {
+    let is_margin = |c: char| c == ' ' || c == '\t';
+    let mut margin: Option<&str> = None;
+    for line in text.split('\n') {
+        let content = line.trim_start_matches(is_margin);
+        if content.is_empty() {
+            continue;
+        }
+        let indent = &line[..line.len() - content.len()];
+        margin = Some(match margin {
+            None => indent,
+            Some(margin) => {
+                let common = margin
+                    .bytes()
+                    .zip(indent.bytes())
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                &margin[..common]
+            }
+        });
+    }
+    let margin = margin.unwrap_or("");
+
+    let mut changed = false;
+    let mut dedented = StdString::with_capacity(text.len());
+    for (i, line) in text.split('\n').enumerate() {
+        if 0 < i {
+            dedented.push('\n');
+        }
+        if line.trim_start_matches(is_margin).is_empty() {
+            changed |= !line.is_empty();
+        } else {
+            changed |= !margin.is_empty();
+            dedented.push_str(&line[margin.len()..]);
+        }
+    }
+    if changed {
+        Some(dedented)
+    } else {
+        None
+    }
+}
+
+/// Adds `prefix` to the start of every line that isn't blank.
+pub(super) fn indent(text: &str, prefix: &str) -> Option<StdString> {
+    let mut changed = false;
+    let mut indented = StdString::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        if !is_blank(line) {
+            indented.push_str(prefix);
+            changed |= !prefix.is_empty();
+        }
+        indented.push_str(line);
+    }
+    if changed {
+        Some(indented)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn wrap_matches_python() {
+        // Expected values are from CPython 3.12 `textwrap.wrap`
+        let fixture = "The quick brown fox jumps over the lazy dog's well-known back-yard.";
+        assert_eq!(
+            wrap(fixture, 12, None),
+            [
+                "The quick",
+                "brown fox",
+                "jumps over",
+                "the lazy",
+                "dog's well-",
+                "known back-",
+                "yard.",
+            ]
+        );
+        assert_eq!(
+            wrap("  leading\tand  trailing  ", 10, None),
+            ["  leading", "and", "trailing"]
+        );
+        assert_eq!(
+            wrap("supercalifragilistic", 6, None),
+            ["superc", "alifra", "gilist", "ic"]
+        );
+        assert_eq!(
+            wrap("a long-hyphenated-word", 9, None),
+            ["a long-hy", "phenated-", "word"]
+        );
+        assert!(wrap("   ", 5, None).is_empty());
+    }
+
+    #[test]
+    fn max_lines_matches_python() {
+        let fixture = "Hello  world!  How are you?";
+        assert_eq!(wrap(fixture, 12, Some((1, " [...]"))), ["Hello [...]"]);
+        assert_eq!(wrap(fixture, 11, Some((1, " [...]"))), ["Hello [...]"]);
+        assert_eq!(wrap(fixture, 10, Some((1, " [...]"))), ["[...]"]);
+        assert_eq!(wrap(fixture, 12, Some((2, "..."))), ["Hello", "world!..."]);
+    }
+
+    #[test]
+    fn dedent_matches_python() {
+        assert_eq!(
+            dedent("    def f():\n        pass\n  \n").as_deref(),
+            Some("def f():\n    pass\n\n")
+        );
+        assert_eq!(dedent("\tmixed\n    spaces\n"), None);
+        assert_eq!(dedent("none\n  here\n"), None);
+    }
+}
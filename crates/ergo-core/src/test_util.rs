@@ -0,0 +1,14 @@
+//! Helpers shared by the unit tests
+
+/// The state pseudo-random tests start from, so failures reproduce
+pub(crate) const SEED: u32 = 0x2545_f491;
+
+/// Advances a xorshift generator and returns its new state.
+///
+/// Tests use this rather than depending on a random number crate.
+pub(crate) fn xorshift(state: &mut u32) -> u32 {
+    *state ^= *state << 13;
+    *state ^= *state >> 17;
+    *state ^= *state << 5;
+    *state
+}
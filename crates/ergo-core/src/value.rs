@@ -0,0 +1,1225 @@
+//! Dynamically typed data, like a Python variable: [`Value`]
+//!
+//! A [`Value`] holds whatever a JSON document or CSV cell might: nothing, a bool, a number, a
+//! string, or a list or dict of more values.  It behaves like the Python object it stands for:
+//!
+//! | Python                   | Rust                                    |
+//! |--------------------------|-----------------------------------------|
+//! | `if v:`                  | `if v.is_truthy()`                      |
+//! | `v["name"]`, `v[-1]`     | `v["name"]`, `v[-1]`, or `v.get(..)`    |
+//! | `a + b`                  | `(&a + &b)?`                            |
+//! | `a // b`                 | `a.floor_div(&b)?`                      |
+//! | `1 == 1.0`               | `Value::from(1) == Value::from(1.0)`    |
+//! | `type(v).__name__`       | `v.type_name()`                         |
+//! | `v` as an `int`          | `i64::try_from(v)?`                     |
+//!
+//! Arithmetic returns a [`Result`], failing with an [`Error`] where Python would raise
+//! `TypeError` or `ZeroDivisionError`.  Indexing with `[]` panics on a missing key or position,
+//! where Python would raise `KeyError` or `IndexError`; [`Value::get`] returns an [`Option`]
+//! instead.
+//!
+//! Ints are `i64` rather than Python's unbounded `int`, so arithmetic leaving that range fails
+//! with [`Error::Overflow`].  Converting to a Rust int only accepts a [`Value::Int`]: unlike
+//! Python's `int(v)`, it doesn't truncate floats or count bools.
+//!
+//! # Examples
+//!
+//! ```
+//! use ergo_core::{Dict, String, Value};
+//! use std::convert::TryFrom;
+//!
+//! let mut row = Dict::new();
+//! row.insert(String::from("name"), Value::from("widget"));
+//! row.insert(String::from("price"), Value::from(2.5));
+//! row.insert(String::from("tags"), Value::from(vec!["new", "sale"]));
+//! let row = Value::from(row);
+//!
+//! let total = (&row["price"] * &Value::from(4))?;
+//! assert_eq!(total, Value::from(10));
+//! assert_eq!(row["tags"][-1], Value::from("sale"));
+//! assert!(row.get("discount").is_none());
+//!
+//! let tags = Vec::<String>::try_from(row["tags"].clone())?;
+//! assert_eq!(tags, ["new", "sale"]);
+//! # Ok::<(), ergo_core::value::Error>(())
+//! ```
+
+use std::cmp::Ordering;
+use std::convert::{TryFrom, TryInto};
+use std::fmt;
+use std::iter::FromIterator;
+use std::ops;
+
+use crate::{Dict, List, String};
+
+type StdString = std::string::String;
+
+/// A dynamically typed value, like a Python object from JSON
+///
+/// See the [module documentation][self] for how values mirror Python.
+///
+/// # Examples
+///
+/// ```
+/// use ergo_core::Value;
+///
+/// let cells = ["3", "", "4.5"].iter().map(|cell| match cell.parse::<i64>() {
+///     Ok(n) => Value::from(n),
+///     Err(_) if cell.is_empty() => Value::None,
+///     Err(_) => Value::from(cell.parse::<f64>().unwrap()),
+/// });
+///
+/// let mut sum = Value::from(0);
+/// for cell in cells.filter(Value::is_truthy) {
+///     sum = (&sum + &cell)?;
+/// }
+///
+/// assert_eq!(sum, Value::from(7.5));
+/// # Ok::<(), ergo_core::value::Error>(())
+/// ```
+#[derive(Clone)]
+pub enum Value {
+    /// Python's `None`, or JSON's `null`
+    None,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    List(List<Value>),
+    Dict(Dict<String, Value>),
+}
+
+/// The error returned by operations on [`Value`]s of the wrong type, or that would divide by
+/// zero or overflow
+///
+/// # Examples
+///
+/// ```
+/// use ergo_core::value::Error;
+/// use ergo_core::Value;
+///
+/// let err = (&Value::from(1) + &Value::from("a")).unwrap_err();
+///
+/// assert_eq!(err.to_string(), "unsupported operand type(s) for +: 'int' and 'str'");
+/// assert_eq!(Value::from(1) / Value::from(0), Err(Error::ZeroDivision));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// An operation on values of the wrong type, like Python's `TypeError`
+    Type(StdString),
+    /// Division or modulo by zero, like Python's `ZeroDivisionError`
+    ZeroDivision,
+    /// Integer arithmetic or a conversion whose result does not fit, where Python would
+    /// switch to a big integer
+    Overflow,
+    /// A result too large to allocate, like Python's `MemoryError`
+    Memory,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Type(message) => f.write_str(message),
+            Error::ZeroDivision => f.write_str("division by zero"),
+            Error::Overflow => f.write_str("integer overflow"),
+            Error::Memory => f.write_str("out of memory"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// A number, with bools counted as ints like in Python
+#[derive(Copy, Clone)]
+enum Number {
+    Int(i64),
+    Float(f64),
+}
+
+impl Number {
+    fn to_f64(self) -> f64 {
+        match self {
+            Number::Int(i) => i as f64,
+            Number::Float(f) => f,
+        }
+    }
+
+    /// Compares exactly, even for ints too large to convert to floats losslessly.
+    fn partial_cmp(self, other: Self) -> Option<Ordering> {
+        match (self, other) {
+            (Number::Int(a), Number::Int(b)) => Some(a.cmp(&b)),
+            (Number::Float(a), Number::Float(b)) => a.partial_cmp(&b),
+            (Number::Int(a), Number::Float(b)) => cmp_int_float(a, b),
+            (Number::Float(a), Number::Int(b)) => cmp_int_float(b, a).map(Ordering::reverse),
+        }
+    }
+}
+
+fn cmp_int_float(int: i64, float: f64) -> Option<Ordering> {
+    // 2^63, the first float above every `i64`
+    const LIMIT: f64 = 9_223_372_036_854_775_808.0;
+    if float.is_nan() {
+        None
+    } else if float >= LIMIT {
+        Some(Ordering::Less)
+    } else if float < -LIMIT {
+        Some(Ordering::Greater)
+    } else {
+        let whole = float.trunc();
+        match int.cmp(&(whole as i64)) {
+            Ordering::Equal => 0.0.partial_cmp(&(float - whole)),
+            ordering => Some(ordering),
+        }
+    }
+}
+
+/// Returns `a % b` with the sign of `b`, like Python.
+fn py_mod_float(a: f64, b: f64) -> f64 {
+    let rem = a % b;
+    if rem == 0.0 {
+        0.0f64.copysign(b)
+    } else if (rem < 0.0) != (b < 0.0) {
+        rem + b
+    } else {
+        rem
+    }
+}
+
+/// Writes a float like Python's `repr`, which has a signed exponent of at least two digits.
+fn repr_float(x: f64, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    // Both languages use the shortest round-tripping digits, and switch to scientific notation
+    // outside of 1e-4 <= |x| < 1e16
+    if x == 0.0 || !x.is_finite() || (1e-4..1e16).contains(&x.abs()) {
+        return write!(f, "{:?}", x);
+    }
+    let scientific = format!("{:e}", x);
+    let (mantissa, exponent) = scientific.split_once('e').expect("an exponent");
+    let exponent: i32 = exponent.parse().expect("an integer exponent");
+    let sign = if exponent < 0 { '-' } else { '+' };
+    write!(f, "{}e{}{:02}", mantissa, sign, exponent.abs())
+}
+
+impl Value {
+    /// Returns the name of the Python type this value stands for.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use ergo_core::Value;
+    ///
+    /// assert_eq!(Value::None.type_name(), "NoneType");
+    /// assert_eq!(Value::from(1.5).type_name(), "float");
+    /// assert_eq!(Value::from(vec![1]).type_name(), "list");
+    /// ```
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::None => "NoneType",
+            Value::Bool(_) => "bool",
+            Value::Int(_) => "int",
+            Value::Float(_) => "float",
+            Value::Str(_) => "str",
+            Value::List(_) => "list",
+            Value::Dict(_) => "dict",
+        }
+    }
+
+    /// Returns `true` if this is [`Value::None`].
+    #[inline]
+    pub fn is_none(&self) -> bool {
+        matches!(self, Value::None)
+    }
+
+    /// Returns whether the value counts as true in an `if`, like Python's `bool(v)`.
+    ///
+    /// `None`, `false`, zero and empty strings, lists and dicts are false; everything else is
+    /// true.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use ergo_core::Value;
+    ///
+    /// assert!(Value::from("no").is_truthy());
+    /// assert!(!Value::from("").is_truthy());
+    /// assert!(!Value::from(0.0).is_truthy());
+    /// assert!(!Value::None.is_truthy());
+    /// ```
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Value::None => false,
+            Value::Bool(b) => *b,
+            Value::Int(i) => *i != 0,
+            Value::Float(f) => *f != 0.0,
+            Value::Str(s) => !s.is_empty(),
+            Value::List(l) => !l.is_empty(),
+            Value::Dict(d) => !d.is_empty(),
+        }
+    }
+
+    /// Returns the bool, or [`None`] if this is not a [`Value::Bool`].
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use ergo_core::Value;
+    ///
+    /// assert_eq!(Value::from(true).as_bool(), Some(true));
+    /// assert_eq!(Value::from(1).as_bool(), None);
+    /// ```
+    #[inline]
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Returns the int, or [`None`] if this is not a [`Value::Int`].
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use ergo_core::Value;
+    ///
+    /// assert_eq!(Value::from(7).as_int(), Some(7));
+    /// assert_eq!(Value::from(7.0).as_int(), None);
+    /// ```
+    #[inline]
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            Value::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// Returns the float, or [`None`] if this is not a [`Value::Float`].
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use ergo_core::Value;
+    ///
+    /// assert_eq!(Value::from(0.5).as_float(), Some(0.5));
+    /// assert_eq!(Value::from(1).as_float(), None);
+    /// ```
+    #[inline]
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            Value::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+
+    /// Returns the string, or [`None`] if this is not a [`Value::Str`].
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use ergo_core::Value;
+    ///
+    /// assert_eq!(Value::from("hi").as_str(), Some("hi"));
+    /// assert_eq!(Value::None.as_str(), None);
+    /// ```
+    #[inline]
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Str(s) => Some(s.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Returns the list, or [`None`] if this is not a [`Value::List`].
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use ergo_core::Value;
+    ///
+    /// assert_eq!(Value::from(vec![1, 2]).as_list().map(|l| l.len()), Some(2));
+    /// ```
+    #[inline]
+    pub fn as_list(&self) -> Option<&List<Value>> {
+        match self {
+            Value::List(l) => Some(l),
+            _ => None,
+        }
+    }
+
+    /// Returns the dict, or [`None`] if this is not a [`Value::Dict`].
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use ergo_core::{Dict, String, Value};
+    ///
+    /// let v = Value::from(Dict::from([(String::from("a"), Value::from(1))]));
+    /// assert_eq!(v.as_dict().map(|d| d.len()), Some(1));
+    /// ```
+    #[inline]
+    pub fn as_dict(&self) -> Option<&Dict<String, Value>> {
+        match self {
+            Value::Dict(d) => Some(d),
+            _ => None,
+        }
+    }
+
+    /// Returns the item for a key of a dict or a position in a list, or [`None`] if there is
+    /// none.
+    ///
+    /// Like Python, negative positions count back from the end.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use ergo_core::Value;
+    ///
+    /// let v = Value::from(vec!["a", "b"]);
+    ///
+    /// assert_eq!(v.get(0), Some(&Value::from("a")));
+    /// assert_eq!(v.get(-1), Some(&Value::from("b")));
+    /// assert_eq!(v.get(2), None);
+    /// assert_eq!(v.get("key"), None);
+    /// ```
+    #[inline]
+    pub fn get<I: ValueIndex>(&self, index: I) -> Option<&Value> {
+        index.index_into(self)
+    }
+
+    /// Returns `self // other`, division rounded towards negative infinity, like Python.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use ergo_core::Value;
+    ///
+    /// assert_eq!(Value::from(7).floor_div(&Value::from(-2))?, Value::from(-4));
+    /// assert_eq!(Value::from(7.5).floor_div(&Value::from(2))?, Value::from(3.0));
+    /// # Ok::<(), ergo_core::value::Error>(())
+    /// ```
+    pub fn floor_div(&self, other: &Value) -> Result<Value, Error> {
+        self.arithmetic(
+            other,
+            "//",
+            |a, b| {
+                if b == 0 {
+                    return Err(Error::ZeroDivision);
+                }
+                let quotient = a.checked_div(b).ok_or(Error::Overflow)?;
+                let rounded_up = a % b != 0 && (a < 0) != (b < 0);
+                Ok(Value::Int(quotient - rounded_up as i64))
+            },
+            |a, b| {
+                if b == 0.0 {
+                    return Err(Error::ZeroDivision);
+                }
+                // Like CPython, divide out the exact remainder so the quotient rounds correctly
+                let rem = a % b;
+                let mut div = (a - rem) / b;
+                if rem != 0.0 && (b < 0.0) != (rem < 0.0) {
+                    div -= 1.0;
+                }
+                if div == 0.0 {
+                    // A zero quotient takes the sign of the exact one
+                    return Ok(Value::Float(0.0_f64.copysign(a / b)));
+                }
+                let mut floor = div.floor();
+                if div - floor > 0.5 {
+                    floor += 1.0;
+                }
+                Ok(Value::Float(floor))
+            },
+        )
+    }
+
+    fn number(&self) -> Option<Number> {
+        match self {
+            Value::Bool(b) => Some(Number::Int(*b as i64)),
+            Value::Int(i) => Some(Number::Int(*i)),
+            Value::Float(f) => Some(Number::Float(*f)),
+            _ => None,
+        }
+    }
+
+    /// Applies `int` to two ints or bools, or `float` to two numbers of which at least one is a
+    /// float.
+    fn arithmetic(
+        &self,
+        other: &Value,
+        op: &str,
+        int: impl FnOnce(i64, i64) -> Result<Value, Error>,
+        float: impl FnOnce(f64, f64) -> Result<Value, Error>,
+    ) -> Result<Value, Error> {
+        match (self.number(), other.number()) {
+            (Some(Number::Int(a)), Some(Number::Int(b))) => int(a, b),
+            (Some(a), Some(b)) => float(a.to_f64(), b.to_f64()),
+            _ => Err(self.unsupported(op, other)),
+        }
+    }
+
+    fn unsupported(&self, op: &str, other: &Value) -> Error {
+        Error::Type(format!(
+            "unsupported operand type(s) for {}: '{}' and '{}'",
+            op,
+            self.type_name(),
+            other.type_name()
+        ))
+    }
+
+    /// Returns the number of repetitions for `seq * n`, if this is an int or bool.
+    fn repetitions(&self) -> Option<usize> {
+        match self.number()? {
+            Number::Int(n) => Some(n.max(0) as usize),
+            Number::Float(_) => None,
+        }
+    }
+
+    /// Writes the value like Python's `repr`.
+    fn repr(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::None => f.write_str("None"),
+            Value::Bool(true) => f.write_str("True"),
+            Value::Bool(false) => f.write_str("False"),
+            Value::Int(i) => write!(f, "{}", i),
+            Value::Float(x) if x.is_nan() => f.write_str("nan"),
+            Value::Float(x) => repr_float(*x, f),
+            Value::Str(s) => write!(f, "{:?}", s),
+            Value::List(l) => {
+                f.write_str("[")?;
+                for (i, item) in l.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    item.repr(f)?;
+                }
+                f.write_str("]")
+            }
+            Value::Dict(d) => {
+                f.write_str("{")?;
+                for (i, (key, value)) in d.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{:?}: ", key)?;
+                    value.repr(f)?;
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+/// Types that can index into a [`Value`]: strings look up keys of a dict, and integers look up
+/// positions in a list, counting back from the end when negative
+///
+/// Used by [`Value::get`] and by indexing with `[]`.
+pub trait ValueIndex {
+    /// Returns the item this index selects from `value`, or [`None`] if there is none.
+    fn index_into<'v>(&self, value: &'v Value) -> Option<&'v Value>;
+}
+
+impl ValueIndex for str {
+    #[inline]
+    fn index_into<'v>(&self, value: &'v Value) -> Option<&'v Value> {
+        value.as_dict()?.get(self)
+    }
+}
+
+impl ValueIndex for String {
+    #[inline]
+    fn index_into<'v>(&self, value: &'v Value) -> Option<&'v Value> {
+        self.as_str().index_into(value)
+    }
+}
+
+impl ValueIndex for usize {
+    #[inline]
+    fn index_into<'v>(&self, value: &'v Value) -> Option<&'v Value> {
+        value.as_list()?.as_slice().get(*self)
+    }
+}
+
+macro_rules! impl_value_index_signed {
+    ($($int:ty),*) => {
+        $(
+            impl ValueIndex for $int {
+                #[inline]
+                fn index_into<'v>(&self, value: &'v Value) -> Option<&'v Value> {
+                    let list = value.as_list()?;
+                    let index = isize::try_from(*self).ok()?;
+                    let index = crate::range::py_index(list.len(), index)?;
+                    Some(&list[index])
+                }
+            }
+        )*
+    };
+}
+
+impl_value_index_signed!(i32, i64, isize);
+
+impl<I: ValueIndex + ?Sized> ValueIndex for &I {
+    #[inline]
+    fn index_into<'v>(&self, value: &'v Value) -> Option<&'v Value> {
+        (**self).index_into(value)
+    }
+}
+
+impl<I: ValueIndex> ops::Index<I> for Value {
+    type Output = Value;
+
+    /// Returns the item for a key of a dict or a position in a list.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no such item, like Python raises `KeyError` or `IndexError`.
+    #[inline]
+    fn index(&self, index: I) -> &Value {
+        match index.index_into(self) {
+            Some(value) => value,
+            None => panic!("no such key or position in {} `Value`", self.type_name()),
+        }
+    }
+}
+
+impl<'a> ops::Add for &'a Value {
+    type Output = Result<Value, Error>;
+
+    /// Adds numbers, or concatenates strings or lists.
+    fn add(self, other: &'a Value) -> Result<Value, Error> {
+        match (self, other) {
+            (Value::Str(a), Value::Str(b)) => Ok(Value::Str(a + b)),
+            (Value::List(a), Value::List(b)) => {
+                Ok(Value::List(a.iter().chain(b.iter()).cloned().collect()))
+            }
+            _ => self.arithmetic(
+                other,
+                "+",
+                |a, b| a.checked_add(b).map(Value::Int).ok_or(Error::Overflow),
+                |a, b| Ok(Value::Float(a + b)),
+            ),
+        }
+    }
+}
+
+impl<'a> ops::Sub for &'a Value {
+    type Output = Result<Value, Error>;
+
+    fn sub(self, other: &'a Value) -> Result<Value, Error> {
+        self.arithmetic(
+            other,
+            "-",
+            |a, b| a.checked_sub(b).map(Value::Int).ok_or(Error::Overflow),
+            |a, b| Ok(Value::Float(a - b)),
+        )
+    }
+}
+
+impl<'a> ops::Mul for &'a Value {
+    type Output = Result<Value, Error>;
+
+    /// Multiplies numbers, or repeats a string or list an int number of times.
+    ///
+    /// Repeating fails with [`Error::Memory`] instead of aborting when the result can't be
+    /// allocated.
+    fn mul(self, other: &'a Value) -> Result<Value, Error> {
+        match (self, other) {
+            (Value::Str(s), n) | (n, Value::Str(s)) if n.repetitions().is_some() => {
+                let count = n.repetitions().unwrap_or_default();
+                if s.is_empty() || count == 0 {
+                    return Ok(Value::Str(String::new()));
+                }
+                let len = s.byte_len().checked_mul(count).ok_or(Error::Overflow)?;
+                let mut repeated = StdString::new();
+                repeated.try_reserve_exact(len).map_err(|_| Error::Memory)?;
+                for _ in 0..count {
+                    repeated.push_str(s.as_str());
+                }
+                Ok(Value::Str(String::from(repeated)))
+            }
+            (Value::List(l), n) | (n, Value::List(l)) if n.repetitions().is_some() => {
+                let count = n.repetitions().unwrap_or_default();
+                if l.is_empty() || count == 0 {
+                    return Ok(Value::List(List::new()));
+                }
+                let len = l.len().checked_mul(count).ok_or(Error::Overflow)?;
+                let mut repeated = Vec::new();
+                repeated.try_reserve_exact(len).map_err(|_| Error::Memory)?;
+                for _ in 0..count {
+                    repeated.extend(l.iter().cloned());
+                }
+                Ok(Value::List(List::from(repeated)))
+            }
+            _ => self.arithmetic(
+                other,
+                "*",
+                |a, b| a.checked_mul(b).map(Value::Int).ok_or(Error::Overflow),
+                |a, b| Ok(Value::Float(a * b)),
+            ),
+        }
+    }
+}
+
+impl<'a> ops::Div for &'a Value {
+    type Output = Result<Value, Error>;
+
+    /// Divides numbers, always returning a float like Python's `/`.
+    fn div(self, other: &'a Value) -> Result<Value, Error> {
+        let divide = |a: f64, b: f64| {
+            if b == 0.0 {
+                Err(Error::ZeroDivision)
+            } else {
+                Ok(Value::Float(a / b))
+            }
+        };
+        self.arithmetic(other, "/", |a, b| divide(a as f64, b as f64), divide)
+    }
+}
+
+impl<'a> ops::Rem for &'a Value {
+    type Output = Result<Value, Error>;
+
+    /// Returns the remainder with the sign of `other`, like Python's `%`.
+    fn rem(self, other: &'a Value) -> Result<Value, Error> {
+        self.arithmetic(
+            other,
+            "%",
+            |a, b| {
+                if b == 0 {
+                    return Err(Error::ZeroDivision);
+                }
+                let rem = a.wrapping_rem(b);
+                let adjust = rem != 0 && (rem < 0) != (b < 0);
+                Ok(Value::Int(if adjust { rem + b } else { rem }))
+            },
+            |a, b| {
+                if b == 0.0 {
+                    return Err(Error::ZeroDivision);
+                }
+                Ok(Value::Float(py_mod_float(a, b)))
+            },
+        )
+    }
+}
+
+impl ops::Neg for &Value {
+    type Output = Result<Value, Error>;
+
+    fn neg(self) -> Result<Value, Error> {
+        match self.number() {
+            Some(Number::Int(i)) => i.checked_neg().map(Value::Int).ok_or(Error::Overflow),
+            Some(Number::Float(f)) => Ok(Value::Float(-f)),
+            None => Err(Error::Type(format!(
+                "bad operand type for unary -: '{}'",
+                self.type_name()
+            ))),
+        }
+    }
+}
+
+macro_rules! impl_owned_operator {
+    ($($trait:ident $method:ident),*) => {
+        $(
+            impl ops::$trait for Value {
+                type Output = Result<Value, Error>;
+
+                #[inline]
+                fn $method(self, other: Value) -> Result<Value, Error> {
+                    ops::$trait::$method(&self, &other)
+                }
+            }
+        )*
+    };
+}
+
+impl_owned_operator!(Add add, Sub sub, Mul mul, Div div, Rem rem);
+
+impl ops::Neg for Value {
+    type Output = Result<Value, Error>;
+
+    #[inline]
+    fn neg(self) -> Result<Value, Error> {
+        -&self
+    }
+}
+
+impl Default for Value {
+    #[inline]
+    fn default() -> Self {
+        Value::None
+    }
+}
+
+impl From<()> for Value {
+    #[inline]
+    fn from(_: ()) -> Self {
+        Value::None
+    }
+}
+
+impl From<bool> for Value {
+    #[inline]
+    fn from(other: bool) -> Self {
+        Value::Bool(other)
+    }
+}
+
+macro_rules! impl_from_int {
+    ($($int:ty),*) => {
+        $(
+            impl From<$int> for Value {
+                #[inline]
+                fn from(other: $int) -> Self {
+                    Value::Int(other.into())
+                }
+            }
+        )*
+    };
+}
+
+impl_from_int!(i8, i16, i32, i64, u8, u16, u32);
+
+impl From<f32> for Value {
+    #[inline]
+    fn from(other: f32) -> Self {
+        Value::Float(other.into())
+    }
+}
+
+impl From<f64> for Value {
+    #[inline]
+    fn from(other: f64) -> Self {
+        Value::Float(other)
+    }
+}
+
+impl<'s> From<&'s str> for Value {
+    #[inline]
+    fn from(other: &'s str) -> Self {
+        Value::Str(String::from(other))
+    }
+}
+
+impl From<String> for Value {
+    #[inline]
+    fn from(other: String) -> Self {
+        Value::Str(other)
+    }
+}
+
+impl From<StdString> for Value {
+    #[inline]
+    fn from(other: StdString) -> Self {
+        Value::Str(String::from(other))
+    }
+}
+
+impl From<List<Value>> for Value {
+    #[inline]
+    fn from(other: List<Value>) -> Self {
+        Value::List(other)
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    #[inline]
+    fn from(other: Vec<T>) -> Self {
+        other.into_iter().collect()
+    }
+}
+
+impl From<Dict<String, Value>> for Value {
+    #[inline]
+    fn from(other: Dict<String, Value>) -> Self {
+        Value::Dict(other)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    /// Converts [`None`] to [`Value::None`].
+    #[inline]
+    fn from(other: Option<T>) -> Self {
+        other.map_or(Value::None, Into::into)
+    }
+}
+
+impl<T: Into<Value>> FromIterator<T> for Value {
+    /// Collects the items into a [`Value::List`].
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Value::List(iter.into_iter().map(Into::into).collect())
+    }
+}
+
+impl Value {
+    /// Returns the error for converting this value to `expected`.
+    fn mismatch(&self, expected: &str) -> Error {
+        Error::Type(format!("expected {}, found {}", expected, self.type_name()))
+    }
+}
+
+impl TryFrom<Value> for bool {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(other: Value) -> Result<Self, Error> {
+        other.as_bool().ok_or_else(|| other.mismatch("bool"))
+    }
+}
+
+macro_rules! impl_try_into_int {
+    ($($int:ty),*) => {
+        $(
+            impl TryFrom<Value> for $int {
+                type Error = Error;
+
+                #[inline]
+                fn try_from(other: Value) -> Result<Self, Error> {
+                    let int = other.as_int().ok_or_else(|| other.mismatch("int"))?;
+                    int.try_into().map_err(|_| Error::Overflow)
+                }
+            }
+        )*
+    };
+}
+
+impl_try_into_int!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl TryFrom<Value> for f64 {
+    type Error = Error;
+
+    /// Converts floats and, like Python's `float`, ints.
+    #[inline]
+    fn try_from(other: Value) -> Result<Self, Error> {
+        match other {
+            Value::Float(f) => Ok(f),
+            Value::Int(i) => Ok(i as f64),
+            _ => Err(other.mismatch("float")),
+        }
+    }
+}
+
+impl TryFrom<Value> for String {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(other: Value) -> Result<Self, Error> {
+        match other {
+            Value::Str(s) => Ok(s),
+            _ => Err(other.mismatch("str")),
+        }
+    }
+}
+
+impl TryFrom<Value> for StdString {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(other: Value) -> Result<Self, Error> {
+        String::try_from(other).map(|s| StdString::from(s.as_str()))
+    }
+}
+
+impl TryFrom<Value> for List<Value> {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(other: Value) -> Result<Self, Error> {
+        match other {
+            Value::List(l) => Ok(l),
+            _ => Err(other.mismatch("list")),
+        }
+    }
+}
+
+impl TryFrom<Value> for Dict<String, Value> {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(other: Value) -> Result<Self, Error> {
+        match other {
+            Value::Dict(d) => Ok(d),
+            _ => Err(other.mismatch("dict")),
+        }
+    }
+}
+
+impl<T: TryFrom<Value, Error = Error>> TryFrom<Value> for Vec<T> {
+    type Error = Error;
+
+    /// Converts each item of a list.
+    fn try_from(other: Value) -> Result<Self, Error> {
+        List::try_from(other)?
+            .into_iter()
+            .map(T::try_from)
+            .collect()
+    }
+}
+
+impl<T: TryFrom<Value, Error = Error>> TryFrom<Value> for Option<T> {
+    type Error = Error;
+
+    /// Converts [`Value::None`] to [`None`], and anything else with `T`'s conversion.
+    #[inline]
+    fn try_from(other: Value) -> Result<Self, Error> {
+        match other {
+            Value::None => Ok(None),
+            other => T::try_from(other).map(Some),
+        }
+    }
+}
+
+impl fmt::Debug for Value {
+    /// Formats the value like Python's `repr`.
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.repr(f)
+    }
+}
+
+impl fmt::Display for Value {
+    /// Formats the value like Python's `str`: strings without quotes, and everything else like
+    /// `repr`.
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Str(s) => f.write_str(s.as_str()),
+            _ => self.repr(f),
+        }
+    }
+}
+
+impl PartialEq for Value {
+    /// Compares like Python's `==`, where numbers of different types can be equal.
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::None, Value::None) => true,
+            (Value::Str(a), Value::Str(b)) => a == b,
+            (Value::List(a), Value::List(b)) => a == b,
+            (Value::Dict(a), Value::Dict(b)) => a == b,
+            _ => match (self.number(), other.number()) {
+                (Some(a), Some(b)) => a.partial_cmp(b) == Some(Ordering::Equal),
+                _ => false,
+            },
+        }
+    }
+}
+
+impl PartialOrd for Value {
+    /// Orders numbers, strings and lists like Python's `<`, returning [`None`] where Python
+    /// would raise `TypeError`.
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Str(a), Value::Str(b)) => a.partial_cmp(b),
+            (Value::List(a), Value::List(b)) => a.partial_cmp(b),
+            (Value::None, Value::None) => Some(Ordering::Equal),
+            (Value::Dict(a), Value::Dict(b)) if a == b => Some(Ordering::Equal),
+            _ => self.number()?.partial_cmp(other.number()?),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn int(i: i64) -> Value {
+        Value::from(i)
+    }
+
+    fn float(f: f64) -> Value {
+        Value::from(f)
+    }
+
+    #[test]
+    fn division_matches_python() {
+        // (a, b, a // b, a % b), from CPython
+        let ints = [
+            (7, 2, 3, 1),
+            (-7, 2, -4, 1),
+            (7, -2, -4, -1),
+            (-7, -2, 3, -1),
+            (6, 3, 2, 0),
+            (-6, 3, -2, 0),
+            (i64::MIN, 1, i64::MIN, 0),
+            (i64::MIN, -3, 3_074_457_345_618_258_602, -2),
+        ];
+        for &(a, b, div, rem) in &ints {
+            assert_eq!(int(a).floor_div(&int(b)), Ok(int(div)), "{} // {}", a, b);
+            assert_eq!(int(a) % int(b), Ok(int(rem)), "{} % {}", a, b);
+        }
+        assert_eq!(int(i64::MIN).floor_div(&int(-1)), Err(Error::Overflow));
+        assert_eq!(int(i64::MIN) % int(-1), Ok(int(0)));
+
+        let floats = [
+            (7.5, 2.0, 3.0, 1.5),
+            (-7.5, 2.0, -4.0, 0.5),
+            (7.5, -2.0, -4.0, -0.5),
+            (1.0, 0.1, 9.0, 0.09999999999999995),
+            (-4.0, 2.0, -2.0, 0.0),
+            (4.0, -2.0, -2.0, -0.0),
+        ];
+        for &(a, b, div, rem) in &floats {
+            assert_eq!(
+                float(a).floor_div(&float(b)),
+                Ok(float(div)),
+                "{} // {}",
+                a,
+                b
+            );
+            assert_eq!(float(a) % float(b), Ok(float(rem)), "{} % {}", a, b);
+        }
+
+        // Zero remainders take the sign of the divisor
+        assert_eq!((float(-4.0) % float(2.0)).unwrap().to_string(), "0.0");
+        assert_eq!((float(4.0) % float(-2.0)).unwrap().to_string(), "-0.0");
+
+        // Zero quotients take the sign of the exact quotient
+        let floor_div = |a: f64, b: f64| float(a).floor_div(&float(b)).unwrap().to_string();
+        assert_eq!(floor_div(-0.0, 1.0), "-0.0");
+        assert_eq!(floor_div(-0.0, -1.0), "0.0");
+        assert_eq!(floor_div(0.5, 2.0), "0.0");
+        assert_eq!(floor_div(0.5, -2.0), "-1.0");
+
+        assert_eq!(int(1) / int(0), Err(Error::ZeroDivision));
+        assert_eq!(float(1.0) % float(0.0), Err(Error::ZeroDivision));
+        assert_eq!(
+            int(1).floor_div(&Value::from(false)),
+            Err(Error::ZeroDivision)
+        );
+        assert_eq!(int(7) / int(2), Ok(float(3.5)));
+    }
+
+    #[test]
+    fn operators_check_types() {
+        assert_eq!(Value::from("ab") * int(3), Ok(Value::from("ababab")));
+        assert_eq!(int(-1) * Value::from("ab"), Ok(Value::from("")));
+        assert_eq!(Value::from("") * int(i64::MAX), Ok(Value::from("")));
+        assert_eq!(
+            Value::from(Vec::<i64>::new()) * int(i64::MAX),
+            Ok(Value::from(Vec::<i64>::new()))
+        );
+        assert_eq!(Value::from("abc") * int(i64::MAX), Err(Error::Overflow));
+        assert_eq!(Value::from("ab") * int(i64::MAX / 4), Err(Error::Memory));
+        assert_eq!(Value::from(vec![1]) * int(i64::MAX), Err(Error::Memory));
+        assert_eq!(
+            Value::from(vec![1, 2]) * int(i64::MAX / 4),
+            Err(Error::Memory)
+        );
+        assert_eq!(
+            Value::from(vec![1, 2]) * int(2),
+            Ok(Value::from(vec![1, 2, 1, 2]))
+        );
+        assert_eq!(
+            Value::from(vec![1, 2]) + Value::from(vec![3]),
+            Ok(Value::from(vec![1, 2, 3]))
+        );
+        assert_eq!(
+            Value::from(true) * Value::from(vec![0]),
+            Ok(Value::from(vec![0]))
+        );
+        assert_eq!(Value::from(true) + Value::from(true), Ok(int(2)));
+        assert_eq!(int(i64::MAX) + int(1), Err(Error::Overflow));
+        assert_eq!(-Value::from(true), Ok(int(-1)));
+
+        let err = (Value::from("ab") * float(2.0)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unsupported operand type(s) for *: 'str' and 'float'"
+        );
+        let err = (-Value::None).unwrap_err();
+        assert_eq!(err.to_string(), "bad operand type for unary -: 'NoneType'");
+    }
+
+    #[test]
+    fn numbers_compare_exactly() {
+        assert_eq!(int(1), float(1.0));
+        assert_eq!(int(1), Value::from(true));
+        assert_ne!(int(1), Value::from("1"));
+        assert_ne!(float(f64::NAN), float(f64::NAN));
+
+        // 2^53 + 1 is not a float, so a lossy comparison would find these equal
+        let big = (1 << 53) + 1;
+        assert!(int(big) > float(big as f64));
+        assert!(float(-0.5) < int(0));
+        assert!(int(i64::MAX) < float(9.3e18));
+        assert!(int(i64::MIN) == float(-9_223_372_036_854_775_808.0));
+        assert_eq!(int(1).partial_cmp(&Value::from("a")), None);
+        assert!(Value::from(vec![1, 2]) < Value::from(vec![1, 3]));
+    }
+
+    #[test]
+    fn formats_like_python() {
+        let mut d = Dict::new();
+        d.insert(String::from("name"), Value::from("it's"));
+        d.insert(String::from("scores"), Value::from(vec![1.0, 2.5]));
+        d.insert(String::from("ok"), Value::from(true));
+        d.insert(String::from("missing"), Value::None);
+        let v = Value::from(d);
+
+        assert_eq!(
+            v.to_string(),
+            r#"{'name': "it's", 'scores': [1.0, 2.5], 'ok': True, 'missing': None}"#
+        );
+        assert_eq!(Value::from("a").to_string(), "a");
+        assert_eq!(format!("{:?}", Value::from("a")), "'a'");
+        assert_eq!(float(f64::NAN).to_string(), "nan");
+        assert_eq!(float(f64::NEG_INFINITY).to_string(), "-inf");
+
+        // From CPython's `repr`
+        let floats = [
+            (1e20, "1e+20"),
+            (1e-5, "1e-05"),
+            (-1.5e-7, "-1.5e-07"),
+            (1.2345e100, "1.2345e+100"),
+            (1e16, "1e+16"),
+            (1e15, "1000000000000000.0"),
+            (1e-4, "0.0001"),
+            (-0.0, "-0.0"),
+            (5e-324, "5e-324"),
+        ];
+        for &(x, repr) in &floats {
+            assert_eq!(float(x).to_string(), repr);
+        }
+    }
+
+    #[test]
+    fn conversions_round_trip() {
+        let v = Value::from(vec![Some(1), None, Some(3)]);
+        let back: Vec<Option<i64>> = v.clone().try_into().unwrap();
+        assert_eq!(back, [Some(1), None, Some(3)]);
+
+        let err = Vec::<i64>::try_from(v).unwrap_err();
+        assert_eq!(err.to_string(), "expected int, found NoneType");
+        assert_eq!(u8::try_from(int(256)), Err(Error::Overflow));
+        assert_eq!(f64::try_from(int(2)), Ok(2.0));
+        assert_eq!(
+            StdString::try_from(Value::from("x")),
+            Ok(StdString::from("x"))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "no such key or position in list `Value`")]
+    fn indexing_past_the_end_panics() {
+        let _ = &Value::from(vec![1])[1];
+    }
+}
@@ -0,0 +1,182 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+/// What the string metrics count as one element
+///
+/// The free functions like [`levenshtein`][crate::levenshtein] use [`Unit::Char`].  Comparing
+/// [`Unit::Grapheme`]s treats user-perceived characters, like an `e` with a combining accent,
+/// as one element.
+///
+/// # Examples
+///
+/// ```
+/// use ergo_difflib::Unit;
+///
+/// let (composed, decomposed) = ("café", "cafe\u{301}");
+///
+/// assert_eq!(Unit::Char.levenshtein(composed, decomposed), 2);
+/// assert_eq!(Unit::Grapheme.levenshtein(composed, decomposed), 1);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Unit {
+    /// Unicode scalar values
+    Char,
+    /// Extended grapheme clusters
+    Grapheme,
+}
+
+impl Unit {
+    /// Splits `s` into elements.
+    pub(crate) fn split(self, s: &str) -> Vec<&str> {
+        match self {
+            Unit::Char => s
+                .char_indices()
+                .map(|(i, c)| &s[i..i + c.len_utf8()])
+                .collect(),
+            Unit::Grapheme => s.graphemes(true).collect(),
+        }
+    }
+
+    /// The number of insertions, deletions and substitutions to turn `a` into `b`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// assert_eq!(ergo_difflib::Unit::Char.levenshtein("kitten", "sitting"), 3);
+    /// ```
+    pub fn levenshtein(self, a: impl AsRef<str>, b: impl AsRef<str>) -> usize {
+        let (a, b) = (self.split(a.as_ref()), self.split(b.as_ref()));
+        let mut previous: Vec<usize> = (0..=b.len()).collect();
+        let mut current = vec![0; b.len() + 1];
+        for (i, x) in a.iter().enumerate() {
+            current[0] = i + 1;
+            for (j, y) in b.iter().enumerate() {
+                let substitution = previous[j] + (x != y) as usize;
+                current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+            }
+            std::mem::swap(&mut previous, &mut current);
+        }
+        previous[b.len()]
+    }
+
+    /// Like [`Unit::levenshtein`], also counting swapping two adjacent elements as one edit.
+    ///
+    /// This is the optimal string alignment variant: no element is edited more than once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ergo_difflib::Unit;
+    ///
+    /// assert_eq!(Unit::Char.levenshtein("form", "from"), 2);
+    /// assert_eq!(Unit::Char.damerau_levenshtein("form", "from"), 1);
+    /// ```
+    pub fn damerau_levenshtein(self, a: impl AsRef<str>, b: impl AsRef<str>) -> usize {
+        let (a, b) = (self.split(a.as_ref()), self.split(b.as_ref()));
+        let width = b.len() + 1;
+        let mut d = vec![0; (a.len() + 1) * width];
+        for i in 0..=a.len() {
+            d[i * width] = i;
+        }
+        for (j, cell) in d.iter_mut().enumerate().take(width) {
+            *cell = j;
+        }
+        for i in 1..=a.len() {
+            for j in 1..=b.len() {
+                let cost = (a[i - 1] != b[j - 1]) as usize;
+                let mut best = (d[(i - 1) * width + j] + 1)
+                    .min(d[i * width + j - 1] + 1)
+                    .min(d[(i - 1) * width + j - 1] + cost);
+                if 1 < i && 1 < j && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                    best = best.min(d[(i - 2) * width + j - 2] + 1);
+                }
+                d[i * width + j] = best;
+            }
+        }
+        d[a.len() * width + b.len()]
+    }
+
+    /// The Jaro similarity, from 0.0 for nothing in common to 1.0 for equal strings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let similarity = ergo_difflib::Unit::Char.jaro("MARTHA", "MARHTA");
+    /// assert!((similarity - 0.9444).abs() < 1e-4);
+    /// ```
+    pub fn jaro(self, a: impl AsRef<str>, b: impl AsRef<str>) -> f64 {
+        let (a, b) = (self.split(a.as_ref()), self.split(b.as_ref()));
+        if a.is_empty() && b.is_empty() {
+            return 1.0;
+        }
+        if a.is_empty() || b.is_empty() {
+            return 0.0;
+        }
+
+        let window = (a.len().max(b.len()) / 2).saturating_sub(1);
+        let mut a_matched = vec![false; a.len()];
+        let mut b_matched = vec![false; b.len()];
+        let mut matches = 0;
+        for (i, x) in a.iter().enumerate() {
+            let start = i.saturating_sub(window);
+            let end = (i + window + 1).min(b.len());
+            for j in start..end {
+                if !b_matched[j] && *x == b[j] {
+                    a_matched[i] = true;
+                    b_matched[j] = true;
+                    matches += 1;
+                    break;
+                }
+            }
+        }
+        if matches == 0 {
+            return 0.0;
+        }
+
+        let a_order = a.iter().zip(&a_matched).filter(|(_, m)| **m);
+        let b_order = b.iter().zip(&b_matched).filter(|(_, m)| **m);
+        let transpositions = a_order
+            .zip(b_order)
+            .filter(|((x, _), (y, _))| x != y)
+            .count()
+            / 2;
+
+        let matches = matches as f64;
+        (matches / a.len() as f64
+            + matches / b.len() as f64
+            + (matches - transpositions as f64) / matches)
+            / 3.0
+    }
+
+    /// The Jaro-Winkler similarity, which boosts [`Unit::jaro`] for strings sharing a prefix of
+    /// up to 4 elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let similarity = ergo_difflib::Unit::Char.jaro_winkler("MARTHA", "MARHTA");
+    /// assert!((similarity - 0.9611).abs() < 1e-4);
+    /// ```
+    pub fn jaro_winkler(self, a: impl AsRef<str>, b: impl AsRef<str>) -> f64 {
+        let (a, b) = (a.as_ref(), b.as_ref());
+        let jaro = self.jaro(a, b);
+        let prefix = self
+            .split(a)
+            .iter()
+            .zip(self.split(b).iter())
+            .take(4)
+            .take_while(|(x, y)| x == y)
+            .count();
+        jaro + prefix as f64 * 0.1 * (1.0 - jaro)
+    }
+
+    /// The [`SequenceMatcher::ratio`][crate::SequenceMatcher::ratio] of `a` and `b`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// assert_eq!(ergo_difflib::Unit::Char.ratio("abcd", "bcde"), 0.75);
+    /// ```
+    pub fn ratio(self, a: impl AsRef<str>, b: impl AsRef<str>) -> f64 {
+        crate::SequenceMatcher::new(self.split(a.as_ref()), self.split(b.as_ref())).ratio()
+    }
+}
@@ -0,0 +1,211 @@
+//! Sequence comparison and fuzzy matching, mirroring Python's `difflib`
+//!
+//! # Examples
+//!
+//! ```
+//! let commands = ["build", "bench", "check", "clean", "test"];
+//!
+//! assert_eq!(ergo_difflib::get_close_matches("tets", &commands, 3, 0.6), ["test"]);
+//! assert_eq!(ergo_difflib::levenshtein("chek", "check"), 1);
+//! ```
+
+mod distance;
+mod sequence_matcher;
+
+pub use distance::Unit;
+pub use sequence_matcher::Match;
+pub use sequence_matcher::Opcode;
+pub use sequence_matcher::SequenceMatcher;
+pub use sequence_matcher::Tag;
+
+use ergo_core::String;
+
+/// See [`Unit::levenshtein`]; compares [`char`]s.
+pub fn levenshtein(a: impl AsRef<str>, b: impl AsRef<str>) -> usize {
+    Unit::Char.levenshtein(a, b)
+}
+
+/// See [`Unit::damerau_levenshtein`]; compares [`char`]s.
+pub fn damerau_levenshtein(a: impl AsRef<str>, b: impl AsRef<str>) -> usize {
+    Unit::Char.damerau_levenshtein(a, b)
+}
+
+/// See [`Unit::jaro`]; compares [`char`]s.
+pub fn jaro(a: impl AsRef<str>, b: impl AsRef<str>) -> f64 {
+    Unit::Char.jaro(a, b)
+}
+
+/// See [`Unit::jaro_winkler`]; compares [`char`]s.
+pub fn jaro_winkler(a: impl AsRef<str>, b: impl AsRef<str>) -> f64 {
+    Unit::Char.jaro_winkler(a, b)
+}
+
+/// See [`Unit::ratio`]; compares [`char`]s.
+pub fn ratio(a: impl AsRef<str>, b: impl AsRef<str>) -> f64 {
+    Unit::Char.ratio(a, b)
+}
+
+/// Returns up to `n` of the `possibilities` with a [`ratio`] to `word` of at least `cutoff`, best
+/// first, like Python's `difflib.get_close_matches`.
+///
+/// Python's defaults are `n = 3` and `cutoff = 0.6`.
+///
+/// # Examples
+///
+/// ```
+/// let words = ["ape", "apple", "peach", "puppy"];
+///
+/// assert_eq!(
+///     ergo_difflib::get_close_matches("appel", &words, 3, 0.6),
+///     ["apple", "ape"]
+/// );
+/// ```
+pub fn get_close_matches<S: AsRef<str>>(
+    word: impl AsRef<str>,
+    possibilities: impl IntoIterator<Item = S>,
+    n: usize,
+    cutoff: f64,
+) -> Vec<String> {
+    let mut matcher = SequenceMatcher::new(Vec::new(), word.as_ref().chars());
+    let mut scored: Vec<(f64, String)> = Vec::new();
+    for possibility in possibilities {
+        let possibility = possibility.as_ref();
+        matcher.set_seq1(possibility.chars());
+        if cutoff <= matcher.real_quick_ratio() && cutoff <= matcher.quick_ratio() {
+            let score = matcher.ratio();
+            if cutoff <= score {
+                scored.push((score, String::from(possibility)));
+            }
+        }
+    }
+    // Best first, with ties broken by the greater string like Python's `heapq.nlargest`
+    scored.sort_by(|(score_a, a), (score_b, b)| {
+        score_b
+            .partial_cmp(score_a)
+            .expect("ratios are not NaN")
+            .then_with(|| b.cmp(a))
+    });
+    scored.into_iter().take(n).map(|(_, s)| s).collect()
+}
+
+/// Compares two lists of lines, producing a unified diff with `n` lines of context, like
+/// Python's `difflib.unified_diff`.
+///
+/// The lines should not include line terminators; each output line ends with `\n`.  Identical
+/// inputs produce an empty diff.
+///
+/// # Examples
+///
+/// ```
+/// let before = ["one", "two", "three"];
+/// let after = ["one", "2", "three", "four"];
+///
+/// let diff = ergo_difflib::unified_diff(&before, &after, "before.txt", "after.txt", 3);
+/// assert_eq!(
+///     diff,
+///     "--- before.txt\n\
+///      +++ after.txt\n\
+///      @@ -1,3 +1,4 @@\n\
+///      \x20one\n\
+///      -two\n\
+///      +2\n\
+///      \x20three\n\
+///      +four\n"
+/// );
+/// ```
+pub fn unified_diff<S: AsRef<str>>(
+    a: &[S],
+    b: &[S],
+    fromfile: impl AsRef<str>,
+    tofile: impl AsRef<str>,
+    n: usize,
+) -> String {
+    let a: Vec<&str> = a.iter().map(AsRef::as_ref).collect();
+    let b: Vec<&str> = b.iter().map(AsRef::as_ref).collect();
+    let matcher = SequenceMatcher::new(a.iter().copied(), b.iter().copied());
+
+    let mut diff = std::string::String::new();
+    for (i, group) in matcher.get_grouped_opcodes(n).iter().enumerate() {
+        if i == 0 {
+            diff.push_str(&format!("--- {}\n", fromfile.as_ref()));
+            diff.push_str(&format!("+++ {}\n", tofile.as_ref()));
+        }
+        let (first, last) = (&group[0], &group[group.len() - 1]);
+        diff.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            format_range(first.a.start, last.a.end),
+            format_range(first.b.start, last.b.end)
+        ));
+        for opcode in group {
+            if opcode.tag == Tag::Equal {
+                for line in &a[opcode.a.clone()] {
+                    diff.push_str(&format!(" {}\n", line));
+                }
+                continue;
+            }
+            if opcode.tag == Tag::Replace || opcode.tag == Tag::Delete {
+                for line in &a[opcode.a.clone()] {
+                    diff.push_str(&format!("-{}\n", line));
+                }
+            }
+            if opcode.tag == Tag::Replace || opcode.tag == Tag::Insert {
+                for line in &b[opcode.b.clone()] {
+                    diff.push_str(&format!("+{}\n", line));
+                }
+            }
+        }
+    }
+    String::from(diff)
+}
+
+/// Formats a hunk's range like Python's `_format_range_unified`.
+fn format_range(start: usize, stop: usize) -> std::string::String {
+    let length = stop - start;
+    match length {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, length),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ratio_matches_python() {
+        // Expected values are from CPython 3.12 `SequenceMatcher(None, a, b).ratio()`
+        assert_eq!(ratio("", ""), 1.0);
+        assert_eq!(ratio("abc", ""), 0.0);
+        assert!(
+            (ratio(
+                "private Thread currentThread;",
+                "private volatile Thread currentThread;"
+            ) - 0.8656716417910447)
+                .abs()
+                < 1e-12
+        );
+    }
+
+    #[test]
+    fn autojunk_matches_python() {
+        // Once `b` has 200 elements, `a`, `b` and `x` are all too popular to start a match
+        let a = "x".repeat(10) + &"ab".repeat(100);
+        let b = "ab".repeat(100) + &"x".repeat(10);
+        assert_eq!(ratio(&a, &b), 0.0);
+        assert_eq!(ratio(&a[..20], &b[..20]), 0.5);
+    }
+
+    #[test]
+    fn unified_diff_matches_python() {
+        let a: Vec<std::string::String> = (1..=20).map(|i| i.to_string()).collect();
+        let mut b = a.clone();
+        b[1] = "two".into();
+        b.remove(17);
+        let diff = unified_diff(&a, &b, "a", "b", 2);
+        let expected = "--- a\n+++ b\n@@ -1,4 +1,4 @@\n 1\n-2\n+two\n 3\n 4\n\
+                        @@ -16,5 +16,4 @@\n 16\n 17\n-18\n 19\n 20\n";
+        assert_eq!(diff, expected);
+        assert_eq!(unified_diff(&a, &a, "a", "b", 3), "");
+    }
+}
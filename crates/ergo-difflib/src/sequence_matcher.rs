@@ -0,0 +1,339 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::ops::Range;
+
+/// A matching block: `a[a..a + size] == b[b..b + size]`
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Match {
+    pub a: usize,
+    pub b: usize,
+    pub size: usize,
+}
+
+/// How to turn one range of `a` into the matching range of `b`
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Tag {
+    Equal,
+    Replace,
+    Delete,
+    Insert,
+}
+
+/// An edit from [`SequenceMatcher::get_opcodes`]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Opcode {
+    pub tag: Tag,
+    pub a: Range<usize>,
+    pub b: Range<usize>,
+}
+
+impl Opcode {
+    fn new(tag: Tag, a: Range<usize>, b: Range<usize>) -> Self {
+        Self { tag, a, b }
+    }
+}
+
+/// Compares two sequences, like Python's `difflib.SequenceMatcher`
+///
+/// This finds the longest contiguous matching block, then recursively does the same on either
+/// side of it.  Like Python, when `b` has 200 or more elements, elements making up more than 1% of
+/// it are considered too popular to start a match.
+///
+/// # Examples
+///
+/// ```
+/// use ergo_difflib::{SequenceMatcher, Tag};
+///
+/// let matcher = SequenceMatcher::new("qabxcd".chars(), "abycdf".chars());
+/// assert!((matcher.ratio() - 0.6667).abs() < 1e-4);
+///
+/// let tags: Vec<Tag> = matcher.get_opcodes().iter().map(|op| op.tag).collect();
+/// assert_eq!(
+///     tags,
+///     [Tag::Delete, Tag::Equal, Tag::Replace, Tag::Equal, Tag::Insert]
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct SequenceMatcher<T> {
+    a: Vec<T>,
+    b: Vec<T>,
+    b2j: HashMap<T, Vec<usize>>,
+}
+
+impl<T: Eq + Hash + Clone> SequenceMatcher<T> {
+    /// Prepares to compare `a` against `b`.
+    pub fn new(a: impl IntoIterator<Item = T>, b: impl IntoIterator<Item = T>) -> Self {
+        let b: Vec<T> = b.into_iter().collect();
+        let b2j = index_popular(&b);
+        Self {
+            a: a.into_iter().collect(),
+            b,
+            b2j,
+        }
+    }
+
+    /// Replaces `a`, keeping the analysis of `b`, like Python's `set_seq1`.
+    ///
+    /// Comparing many sequences against one is cheapest with that one as `b`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut matcher = ergo_difflib::SequenceMatcher::new("abcd".chars(), "bcde".chars());
+    /// matcher.set_seq1("bcd".chars());
+    /// assert_eq!(matcher.ratio(), 6.0 / 7.0);
+    /// ```
+    pub fn set_seq1(&mut self, a: impl IntoIterator<Item = T>) {
+        self.a.clear();
+        self.a.extend(a);
+    }
+
+    /// Finds the longest matching block in `a[a_range]` and `b[b_range]`, preferring the one
+    /// that starts earliest in `a`, then in `b`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ergo_difflib::{Match, SequenceMatcher};
+    ///
+    /// let matcher = SequenceMatcher::new(" abcd".chars(), "abcd abcd".chars());
+    /// assert_eq!(
+    ///     matcher.find_longest_match(0..5, 0..9),
+    ///     Match { a: 0, b: 4, size: 5 }
+    /// );
+    /// ```
+    pub fn find_longest_match(&self, a_range: Range<usize>, b_range: Range<usize>) -> Match {
+        let (alo, ahi, blo, bhi) = (a_range.start, a_range.end, b_range.start, b_range.end);
+        let (mut besti, mut bestj, mut bestsize) = (alo, blo, 0);
+
+        // `j2len[j]` is the length of the match ending with `a[i - 1]` and `b[j]`
+        let mut j2len: HashMap<usize, usize> = HashMap::new();
+        for i in alo..ahi {
+            let mut next_j2len = HashMap::new();
+            if let Some(indices) = self.b2j.get(&self.a[i]) {
+                for &j in indices {
+                    if j < blo {
+                        continue;
+                    }
+                    if bhi <= j {
+                        break;
+                    }
+                    let k = j.checked_sub(1).and_then(|j| j2len.get(&j)).unwrap_or(&0) + 1;
+                    next_j2len.insert(j, k);
+                    if bestsize < k {
+                        besti = i + 1 - k;
+                        bestj = j + 1 - k;
+                        bestsize = k;
+                    }
+                }
+            }
+            j2len = next_j2len;
+        }
+
+        // Popular elements can't start a match, but can extend one
+        while alo < besti && blo < bestj && self.a[besti - 1] == self.b[bestj - 1] {
+            besti -= 1;
+            bestj -= 1;
+            bestsize += 1;
+        }
+        while besti + bestsize < ahi
+            && bestj + bestsize < bhi
+            && self.a[besti + bestsize] == self.b[bestj + bestsize]
+        {
+            bestsize += 1;
+        }
+
+        Match {
+            a: besti,
+            b: bestj,
+            size: bestsize,
+        }
+    }
+
+    /// Returns the matching blocks in order, ending with a `size` 0 sentinel at the end of both
+    /// sequences.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ergo_difflib::{Match, SequenceMatcher};
+    ///
+    /// let matcher = SequenceMatcher::new("abxcd".chars(), "abcd".chars());
+    /// assert_eq!(
+    ///     matcher.get_matching_blocks(),
+    ///     [
+    ///         Match { a: 0, b: 0, size: 2 },
+    ///         Match { a: 3, b: 2, size: 2 },
+    ///         Match { a: 5, b: 4, size: 0 },
+    ///     ]
+    /// );
+    /// ```
+    pub fn get_matching_blocks(&self) -> Vec<Match> {
+        let (la, lb) = (self.a.len(), self.b.len());
+        let mut queue = vec![(0..la, 0..lb)];
+        let mut blocks = Vec::new();
+        while let Some((a_range, b_range)) = queue.pop() {
+            let m = self.find_longest_match(a_range.clone(), b_range.clone());
+            if 0 < m.size {
+                blocks.push(m);
+                if a_range.start < m.a && b_range.start < m.b {
+                    queue.push((a_range.start..m.a, b_range.start..m.b));
+                }
+                if m.a + m.size < a_range.end && m.b + m.size < b_range.end {
+                    queue.push((m.a + m.size..a_range.end, m.b + m.size..b_range.end));
+                }
+            }
+        }
+        blocks.sort();
+
+        // Merge adjacent blocks
+        let mut merged: Vec<Match> = Vec::with_capacity(blocks.len() + 1);
+        for m in blocks {
+            match merged.last_mut() {
+                Some(last) if last.a + last.size == m.a && last.b + last.size == m.b => {
+                    last.size += m.size;
+                }
+                _ => merged.push(m),
+            }
+        }
+        merged.push(Match {
+            a: la,
+            b: lb,
+            size: 0,
+        });
+        merged
+    }
+
+    /// Returns the edits that turn `a` into `b`.
+    pub fn get_opcodes(&self) -> Vec<Opcode> {
+        let (mut i, mut j) = (0, 0);
+        let mut opcodes = Vec::new();
+        for m in self.get_matching_blocks() {
+            let tag = match (i < m.a, j < m.b) {
+                (true, true) => Some(Tag::Replace),
+                (true, false) => Some(Tag::Delete),
+                (false, true) => Some(Tag::Insert),
+                (false, false) => None,
+            };
+            if let Some(tag) = tag {
+                opcodes.push(Opcode::new(tag, i..m.a, j..m.b));
+            }
+            i = m.a + m.size;
+            j = m.b + m.size;
+            if 0 < m.size {
+                opcodes.push(Opcode::new(Tag::Equal, m.a..i, m.b..j));
+            }
+        }
+        opcodes
+    }
+
+    /// Groups the opcodes into hunks with up to `n` lines of context, like Python's
+    /// `get_grouped_opcodes`.
+    pub fn get_grouped_opcodes(&self, n: usize) -> Vec<Vec<Opcode>> {
+        let mut codes = self.get_opcodes();
+        if codes.is_empty() {
+            codes.push(Opcode::new(Tag::Equal, 0..1, 0..1));
+        }
+        // Trim the context at the ends
+        if let Some(first) = codes.first_mut() {
+            if first.tag == Tag::Equal {
+                first.a.start = first.a.start.max(first.a.end.saturating_sub(n));
+                first.b.start = first.b.start.max(first.b.end.saturating_sub(n));
+            }
+        }
+        if let Some(last) = codes.last_mut() {
+            if last.tag == Tag::Equal {
+                last.a.end = last.a.end.min(last.a.start + n);
+                last.b.end = last.b.end.min(last.b.start + n);
+            }
+        }
+
+        let mut groups = Vec::new();
+        let mut group = Vec::new();
+        for mut code in codes {
+            // Split long runs of equal lines into the end of one hunk and the start of the next
+            if code.tag == Tag::Equal && 2 * n < code.a.len() {
+                group.push(Opcode::new(
+                    Tag::Equal,
+                    code.a.start..code.a.end.min(code.a.start + n),
+                    code.b.start..code.b.end.min(code.b.start + n),
+                ));
+                groups.push(std::mem::take(&mut group));
+                code.a.start = code.a.start.max(code.a.end - n);
+                code.b.start = code.b.start.max(code.b.end - n);
+            }
+            group.push(code);
+        }
+        let only_context = group.len() == 1 && group[0].tag == Tag::Equal;
+        if !(group.is_empty() || only_context) {
+            groups.push(group);
+        }
+        groups
+    }
+
+    /// Measures the similarity as `2 * matches / total elements`, from 0.0 to 1.0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let matcher = ergo_difflib::SequenceMatcher::new("abcd".chars(), "bcde".chars());
+    /// assert_eq!(matcher.ratio(), 0.75);
+    /// ```
+    pub fn ratio(&self) -> f64 {
+        let matches: usize = self.get_matching_blocks().iter().map(|m| m.size).sum();
+        calculate_ratio(matches, self.a.len() + self.b.len())
+    }
+
+    /// An upper bound on [`SequenceMatcher::ratio`], ignoring order.
+    pub fn quick_ratio(&self) -> f64 {
+        let mut available: HashMap<&T, usize> = HashMap::new();
+        for x in &self.b {
+            *available.entry(x).or_insert(0) += 1;
+        }
+        let mut matches = 0;
+        for x in &self.a {
+            if let Some(count) = available.get_mut(x) {
+                if 0 < *count {
+                    *count -= 1;
+                    matches += 1;
+                }
+            }
+        }
+        calculate_ratio(matches, self.a.len() + self.b.len())
+    }
+
+    /// An upper bound on [`SequenceMatcher::quick_ratio`], from the lengths alone.
+    pub fn real_quick_ratio(&self) -> f64 {
+        let (la, lb) = (self.a.len(), self.b.len());
+        calculate_ratio(la.min(lb), la + lb)
+    }
+}
+
+/// Maps each element of `b` to its indices, leaving out popular elements like Python's
+/// `autojunk`.
+fn index_popular<T: Eq + Hash + Clone>(b: &[T]) -> HashMap<T, Vec<usize>> {
+    let mut b2j: HashMap<T, Vec<usize>> = HashMap::new();
+    for (j, x) in b.iter().enumerate() {
+        b2j.entry(x.clone()).or_default().push(j);
+    }
+    if 200 <= b.len() {
+        let threshold = b.len() / 100 + 1;
+        let popular: HashSet<T> = b2j
+            .iter()
+            .filter(|(_, indices)| threshold < indices.len())
+            .map(|(x, _)| x.clone())
+            .collect();
+        for x in popular {
+            b2j.remove(&x);
+        }
+    }
+    b2j
+}
+
+fn calculate_ratio(matches: usize, len: usize) -> f64 {
+    if len == 0 {
+        1.0
+    } else {
+        2.0 * matches as f64 / len as f64
+    }
+}
@@ -0,0 +1,33 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use crate::{Error, Pattern};
+
+/// Like Python's `re._MAXCACHE`
+const MAX_CACHE: usize = 512;
+
+static CACHE: OnceLock<Mutex<HashMap<ergo_core::String, Pattern>>> = OnceLock::new();
+
+/// Compiles `pattern`, reusing an earlier compilation when possible.
+pub(crate) fn get(pattern: &str) -> Result<Pattern, Error> {
+    if let Some(compiled) = lock().get(pattern) {
+        return Ok(compiled.clone());
+    }
+
+    // Compile outside the lock; racing threads compile the same pattern at worst
+    let compiled = Pattern::new(pattern)?;
+    let mut cache = lock();
+    if MAX_CACHE <= cache.len() {
+        cache.clear();
+    }
+    cache.insert(ergo_core::String::from(pattern), compiled.clone());
+    Ok(compiled)
+}
+
+fn lock() -> std::sync::MutexGuard<'static, HashMap<ergo_core::String, Pattern>> {
+    // The cache is always left consistent, so a panic elsewhere doesn't invalidate it
+    CACHE
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
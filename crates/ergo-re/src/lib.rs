@@ -0,0 +1,421 @@
+//! Regular expressions over ergo strings, mirroring Python's `re`
+//!
+//! Patterns use the [`regex`] crate's syntax, which covers most of Python's, including
+//! `(?P<name>...)` groups, but not look-around or backreferences.  The module-level functions
+//! compile patterns through a process-wide cache, like Python does.
+//!
+//! Matched text shares the haystack's buffer rather than copying it, and positions are byte
+//! offsets.
+//!
+//! # Examples
+//!
+//! ```
+//! let line = ergo_core::String::from("2024-01-31 ERROR disk full on /dev/sda1");
+//!
+//! let m = ergo_re::search(r"(?P<level>[A-Z]+) (?P<message>.*)", &line).unwrap().unwrap();
+//! assert_eq!(m.name("level").unwrap(), "ERROR");
+//! assert_eq!(m.name("message").unwrap(), "disk full on /dev/sda1");
+//!
+//! let redacted = ergo_re::sub(r"/dev/\w+", "<device>", &line).unwrap();
+//! assert_eq!(redacted, "2024-01-31 ERROR disk full on <device>");
+//! ```
+
+mod cache;
+mod matches;
+mod non_empty;
+mod replace;
+
+pub use matches::Match;
+pub use matches::Matches;
+pub use regex::Error;
+pub use replace::Replace;
+
+use ergo_core::String;
+use regex_automata::meta;
+use regex_syntax::hir::{Hir, Look};
+
+/// A compiled regular expression, like Python's `re.Pattern`
+///
+/// Cloning is cheap; clones share the compiled program.
+///
+/// # Examples
+///
+/// ```
+/// let pattern = ergo_re::compile(r"\d+").unwrap();
+///
+/// assert_eq!(pattern.findall("1 fish, 2 fish, 10 fish"), ["1", "2", "10"]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Pattern {
+    regex: regex::Regex,
+    anchored: meta::Regex,
+    full: meta::Regex,
+    /// Finds a non-empty match where an empty one was just found, see [`Pattern::finditer`]
+    non_empty: Option<meta::Regex>,
+}
+
+impl Pattern {
+    /// Compiles `pattern`, bypassing the cache.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// assert!(ergo_re::Pattern::new(r"[a-z]+").is_ok());
+    /// assert!(ergo_re::Pattern::new(r"[a-z").is_err());
+    /// ```
+    pub fn new(pattern: impl AsRef<str>) -> Result<Self, Error> {
+        let pattern = pattern.as_ref();
+        let regex = regex::Regex::new(pattern)?;
+        // Anchor the parsed pattern rather than its text, which a trailing `#` comment in
+        // verbose mode would swallow
+        let hir = regex_syntax::Parser::new()
+            .parse(pattern)
+            .map_err(|err| Error::Syntax(err.to_string()))?;
+        let non_empty = non_empty::non_empty(&hir)
+            .map(|hir| build(&hir))
+            .transpose()?;
+        let start = Hir::look(Look::Start);
+        let anchored = Hir::concat(vec![start.clone(), hir.clone()]);
+        let full = Hir::concat(vec![start, hir, Hir::look(Look::End)]);
+        Ok(Self {
+            regex,
+            anchored: build(&anchored)?,
+            full: build(&full)?,
+            non_empty,
+        })
+    }
+
+    /// The pattern this was compiled from.
+    pub fn as_str(&self) -> &str {
+        self.regex.as_str()
+    }
+
+    /// The number of capturing groups, not counting the implicit whole-match group.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let pattern = ergo_re::compile(r"(\w+)@(\w+)").unwrap();
+    /// assert_eq!(pattern.groups(), 2);
+    /// ```
+    pub fn groups(&self) -> usize {
+        self.regex.captures_len() - 1
+    }
+
+    pub(crate) fn group_index(&self, name: &str) -> Option<usize> {
+        self.regex
+            .capture_names()
+            .position(|group| group == Some(name))
+    }
+
+    /// Finds the first match anywhere in `string`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let pattern = ergo_re::compile(r"\d+").unwrap();
+    ///
+    /// let m = pattern.search("order 66").unwrap();
+    /// assert_eq!(m.group(0).unwrap(), "66");
+    /// assert_eq!(m.span(), (6, 8));
+    /// ```
+    pub fn search(&self, string: impl Into<String>) -> Option<Match> {
+        Match::find(&self.regex, string.into(), self.clone())
+    }
+
+    /// Matches at the start of `string`, like Python's `Pattern.match`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let pattern = ergo_re::compile(r"\d+").unwrap();
+    ///
+    /// assert!(pattern.match_("66 orders").is_some());
+    /// assert!(pattern.match_("order 66").is_none());
+    /// ```
+    pub fn match_(&self, string: impl Into<String>) -> Option<Match> {
+        Match::find_anchored(&self.anchored, string.into(), self.clone())
+    }
+
+    /// Matches all of `string`.
+    ///
+    /// Unlike checking the span of [`Pattern::match_`], alternatives are retried to cover the
+    /// whole string.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let pattern = ergo_re::compile(r"a|ab").unwrap();
+    ///
+    /// assert_eq!(pattern.match_("ab").unwrap().group(0).unwrap(), "a");
+    /// assert_eq!(pattern.fullmatch("ab").unwrap().group(0).unwrap(), "ab");
+    /// ```
+    pub fn fullmatch(&self, string: impl Into<String>) -> Option<Match> {
+        Match::find_anchored(&self.full, string.into(), self.clone())
+    }
+
+    /// Iterates over all non-overlapping matches.
+    ///
+    /// Like Python, empty matches are included, including those right after a non-empty match.
+    /// After an empty match, a non-empty match at the same position comes next if there is one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let pattern = ergo_re::compile(r"(\w)(\d)").unwrap();
+    ///
+    /// let spans: Vec<_> = pattern.finditer("a1 b2 c").map(|m| m.span()).collect();
+    /// assert_eq!(spans, [(0, 2), (3, 5)]);
+    /// ```
+    pub fn finditer(&self, string: impl Into<String>) -> Matches {
+        Matches::new(self.clone(), string.into())
+    }
+
+    /// Returns all non-overlapping matches.
+    ///
+    /// Like Python, this is the whole match when there are no groups and the group when there
+    /// is one, with unmatched groups as empty strings.  With several groups, the whole match is
+    /// returned; use [`Pattern::finditer`] to get at each group.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let pattern = ergo_re::compile(r"(\w+)=\d+").unwrap();
+    ///
+    /// assert_eq!(pattern.findall("a=1, bc=23"), ["a", "bc"]);
+    /// ```
+    pub fn findall(&self, string: impl Into<String>) -> Vec<String> {
+        let group = if self.groups() == 1 { 1 } else { 0 };
+        self.finditer(string)
+            .map(|m| m.group(group).unwrap_or_default())
+            .collect()
+    }
+
+    /// Replaces all non-overlapping matches with `repl`.
+    ///
+    /// `repl` is either a template, where `\1` or `\g<name>` insert groups like in Python, or a
+    /// closure computing the replacement from each [`Match`].
+    ///
+    /// Returns `string` itself when nothing matches.
+    ///
+    /// # Errors
+    ///
+    /// Like Python, fails if the template refers to a group the pattern doesn't have or has a
+    /// bad escape, even when nothing matches.  See [`Match::expand`] for the template syntax.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let pattern = ergo_re::compile(r"(?P<key>\w+)=(?P<value>\w+)").unwrap();
+    ///
+    /// assert_eq!(pattern.sub(r"\g<value>=\1", "a=1 b=2").unwrap(), "1=a 2=b");
+    /// assert!(pattern.sub(r"\3", "a=1").is_err());
+    ///
+    /// let upper = |m: &ergo_re::Match| m.group(0).unwrap().as_str().to_uppercase();
+    /// assert_eq!(pattern.sub(upper, "a=x").unwrap(), "A=X");
+    /// ```
+    pub fn sub(&self, mut repl: impl Replace, string: impl Into<String>) -> Result<String, Error> {
+        let template = match repl.template() {
+            Some(template) => Some(replace::parse(template, self)?),
+            None => None,
+        };
+        let string = string.into();
+        let mut matches = self.finditer(&string).peekable();
+        if matches.peek().is_none() {
+            return Ok(string);
+        }
+
+        let mut replaced = std::string::String::with_capacity(string.byte_len());
+        let mut last = 0;
+        for m in matches {
+            let (start, end) = m.span();
+            replaced.push_str(&string.as_str()[last..start]);
+            match &template {
+                Some(pieces) => replace::expand(pieces, &m, &mut replaced),
+                None => repl.replace_append(&m, &mut replaced),
+            }
+            last = end;
+        }
+        replaced.push_str(&string.as_str()[last..]);
+        Ok(String::from(replaced))
+    }
+
+    /// Splits `string` by the matches.
+    ///
+    /// Like Python, groups in the pattern are included between the pieces, with unmatched groups
+    /// as empty strings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let pattern = ergo_re::compile(r"\s*,\s*").unwrap();
+    /// assert_eq!(pattern.split("a , b,c"), ["a", "b", "c"]);
+    ///
+    /// let pattern = ergo_re::compile(r"(,)").unwrap();
+    /// assert_eq!(pattern.split("a,b"), ["a", ",", "b"]);
+    /// ```
+    pub fn split(&self, string: impl Into<String>) -> Vec<String> {
+        let string = string.into();
+        let mut pieces = Vec::new();
+        let mut last = 0;
+        for m in self.finditer(&string) {
+            let (start, end) = m.span();
+            pieces.push(string.get(last..start).unwrap_or_default());
+            for group in 1..=self.groups() {
+                pieces.push(m.group(group).unwrap_or_default());
+            }
+            last = end;
+        }
+        pieces.push(string.get(last..).unwrap_or_default());
+        pieces
+    }
+}
+
+fn build(hir: &Hir) -> Result<meta::Regex, Error> {
+    meta::Regex::builder()
+        .build_from_hir(hir)
+        .map_err(|err| match err.size_limit() {
+            Some(limit) => Error::CompiledTooBig(limit),
+            None => Error::Syntax(err.to_string()),
+        })
+}
+
+/// Compiles `pattern` through the process-wide cache.
+///
+/// Up to 512 patterns are kept, like Python's `re`; when full, the cache is cleared.
+///
+/// # Examples
+///
+/// ```
+/// let a = ergo_re::compile(r"\w+").unwrap();
+/// let b = ergo_re::compile(r"\w+").unwrap();
+///
+/// assert_eq!(a.as_str(), b.as_str());
+/// ```
+pub fn compile(pattern: impl AsRef<str>) -> Result<Pattern, Error> {
+    cache::get(pattern.as_ref())
+}
+
+/// See [`Pattern::search`].
+pub fn search(pattern: impl AsRef<str>, string: impl Into<String>) -> Result<Option<Match>, Error> {
+    Ok(compile(pattern)?.search(string))
+}
+
+/// See [`Pattern::match_`].
+pub fn match_(pattern: impl AsRef<str>, string: impl Into<String>) -> Result<Option<Match>, Error> {
+    Ok(compile(pattern)?.match_(string))
+}
+
+/// See [`Pattern::fullmatch`].
+pub fn fullmatch(
+    pattern: impl AsRef<str>,
+    string: impl Into<String>,
+) -> Result<Option<Match>, Error> {
+    Ok(compile(pattern)?.fullmatch(string))
+}
+
+/// See [`Pattern::finditer`].
+pub fn finditer(pattern: impl AsRef<str>, string: impl Into<String>) -> Result<Matches, Error> {
+    Ok(compile(pattern)?.finditer(string))
+}
+
+/// See [`Pattern::findall`].
+pub fn findall(pattern: impl AsRef<str>, string: impl Into<String>) -> Result<Vec<String>, Error> {
+    Ok(compile(pattern)?.findall(string))
+}
+
+/// See [`Pattern::sub`].
+pub fn sub(
+    pattern: impl AsRef<str>,
+    repl: impl Replace,
+    string: impl Into<String>,
+) -> Result<String, Error> {
+    compile(pattern)?.sub(repl, string)
+}
+
+/// See [`Pattern::split`].
+pub fn split(pattern: impl AsRef<str>, string: impl Into<String>) -> Result<Vec<String>, Error> {
+    Ok(compile(pattern)?.split(string))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn empty_matches_like_python() {
+        // Expected values are from CPython 3.12
+        assert_eq!(findall(r"\w*", "ab cd").unwrap(), ["ab", "", "cd", ""]);
+        assert_eq!(sub("x*", "-", "abxd").unwrap(), "-a-b--d-");
+        assert_eq!(split(r"\b", "a b").unwrap(), ["", "a", " ", "b", ""]);
+        assert_eq!(findall("", "é").unwrap(), ["", ""]);
+    }
+
+    #[test]
+    fn non_empty_match_after_empty_one() {
+        // Expected values are from CPython 3.12
+        assert_eq!(
+            findall(r"a*|b", "bab").unwrap(),
+            ["", "b", "a", "", "b", ""]
+        );
+        assert_eq!(sub(r"a*|b", "-", "bab").unwrap(), "------");
+        assert_eq!(
+            findall(r"\b|\w+", "ab cd").unwrap(),
+            ["", "ab", "", "", "cd", ""]
+        );
+        assert_eq!(findall(r"x??y?", "xy").unwrap(), ["", "xy", ""]);
+
+        let spans: Vec<_> = finditer(r"(a)?|(b)", "b")
+            .unwrap()
+            .map(|m| m.groups())
+            .collect();
+        assert_eq!(
+            spans,
+            [
+                vec![None, None],
+                vec![None, Some(String::from("b"))],
+                vec![None, None]
+            ]
+        );
+    }
+
+    #[test]
+    fn templates_like_python() {
+        assert_eq!(sub("a", r"\0", "xay").unwrap(), "x\0y");
+        assert_eq!(sub("(a)", r"\101\1", "xay").unwrap(), "xAay");
+        assert!(sub("a", r"\1", "xyz").is_err());
+        assert!(sub("a", r"\g<name>", "xyz").is_err());
+        assert!(compile("(a)")
+            .unwrap()
+            .search("a")
+            .unwrap()
+            .expand(r"\2")
+            .is_err());
+    }
+
+    #[test]
+    fn verbose_patterns_anchor() {
+        let pattern = Pattern::new("(?x) (a+) # some a's").unwrap();
+        assert_eq!(pattern.match_("aab").unwrap().group(1).unwrap(), "aa");
+        assert!(pattern.match_("baa").is_none());
+        assert_eq!(pattern.fullmatch("aaa").unwrap().span(), (0, 3));
+        assert!(pattern.fullmatch("aab").is_none());
+
+        // Groups keep their numbers and names
+        let pattern = Pattern::new("(?x) (?P<word>a|ab) (c)? # trailing").unwrap();
+        let m = pattern.fullmatch("ab").unwrap();
+        assert_eq!(m.name("word").unwrap(), "ab");
+        assert_eq!(m.groups(), [Some(String::from("ab")), None]);
+    }
+
+    #[test]
+    fn matches_share_haystack() {
+        let haystack =
+            String::from("a haystack long enough to not be stored inline, with a needle");
+        let m = search("needle", &haystack).unwrap().unwrap();
+        assert!(m.group(0).unwrap().is_shared_with(&haystack));
+
+        let pieces = split(", ", &haystack).unwrap();
+        assert!(pieces[0].is_shared_with(&haystack));
+    }
+}
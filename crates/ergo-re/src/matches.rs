@@ -0,0 +1,258 @@
+use ergo_core::String;
+
+use crate::{replace, Error, Pattern};
+
+/// A successful match, like Python's `re.Match`
+///
+/// Groups share the buffer of the string that was searched.
+///
+/// # Examples
+///
+/// ```
+/// let m = ergo_re::search(r"(\w+)@(?P<domain>\w+)", "mail bob@example now").unwrap().unwrap();
+///
+/// assert_eq!(m.group(0).unwrap(), "bob@example");
+/// assert_eq!(m.group(1).unwrap(), "bob");
+/// assert_eq!(m.name("domain").unwrap(), "example");
+/// assert_eq!(m.span(), (5, 16));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Match {
+    pattern: Pattern,
+    string: String,
+    spans: Vec<Option<(usize, usize)>>,
+}
+
+impl Match {
+    pub(crate) fn find(regex: &regex::Regex, string: String, pattern: Pattern) -> Option<Self> {
+        let mut locations = regex.capture_locations();
+        regex.captures_read(&mut locations, string.as_str())?;
+        Some(Self::new(pattern, string, &locations))
+    }
+
+    /// Finds a match of a pattern anchored with [`Pattern::new`].
+    pub(crate) fn find_anchored(
+        regex: &regex_automata::meta::Regex,
+        string: String,
+        pattern: Pattern,
+    ) -> Option<Self> {
+        let mut captures = regex.create_captures();
+        regex.captures(string.as_str(), &mut captures);
+        Self::from_captures(pattern, string, &captures)
+    }
+
+    /// Finds a non-empty match starting at `start`, with [`Pattern`]'s `non_empty` regex.
+    fn find_non_empty(
+        regex: &regex_automata::meta::Regex,
+        string: String,
+        start: usize,
+        pattern: Pattern,
+    ) -> Option<Self> {
+        let input = regex_automata::Input::new(string.as_str())
+            .range(start..)
+            .anchored(regex_automata::Anchored::Yes);
+        let mut captures = regex.create_captures();
+        regex.search_captures(&input, &mut captures);
+        Self::from_captures(pattern, string, &captures)
+    }
+
+    fn from_captures(
+        pattern: Pattern,
+        string: String,
+        captures: &regex_automata::util::captures::Captures,
+    ) -> Option<Self> {
+        if !captures.is_match() {
+            return None;
+        }
+        // Rewritten patterns may have lost groups that can only match empty
+        let spans = (0..=pattern.groups())
+            .map(|i| captures.get_group(i).map(|span| (span.start, span.end)))
+            .collect();
+        Some(Self {
+            pattern,
+            string,
+            spans,
+        })
+    }
+
+    fn new(pattern: Pattern, string: String, locations: &regex::CaptureLocations) -> Self {
+        let spans = (0..locations.len()).map(|i| locations.get(i)).collect();
+        Self {
+            pattern,
+            string,
+            spans,
+        }
+    }
+
+    /// The text matched by group `index`, where `0` is the whole match.
+    ///
+    /// Returns [`None`] if the group did not participate in the match or doesn't exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let m = ergo_re::search(r"(a)|(b)", "b").unwrap().unwrap();
+    ///
+    /// assert_eq!(m.group(1), None);
+    /// assert_eq!(m.group(2).unwrap(), "b");
+    /// assert_eq!(m.group(3), None);
+    /// ```
+    pub fn group(&self, index: usize) -> Option<String> {
+        let (start, end) = self.spans.get(index).copied().flatten()?;
+        self.string.get(start..end)
+    }
+
+    /// The text matched by the group named `name`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let m = ergo_re::search(r"(?P<year>\d{4})", "in 1999").unwrap().unwrap();
+    ///
+    /// assert_eq!(m.name("year").unwrap(), "1999");
+    /// assert_eq!(m.name("month"), None);
+    /// ```
+    pub fn name(&self, name: impl AsRef<str>) -> Option<String> {
+        let index = self.pattern.group_index(name.as_ref())?;
+        self.group(index)
+    }
+
+    /// Expands a replacement template with this match's groups, like Python's `Match.expand`.
+    ///
+    /// `\1` or `\g<1>` insert a group by number and `\g<name>` by name, with groups that didn't
+    /// participate inserting nothing.  `\0` and three-digit escapes like `\101` are octal
+    /// character codes, and `\n`, `\t`, `\\` and the other usual escapes work as in Python.
+    ///
+    /// # Errors
+    ///
+    /// Fails on references to groups the pattern doesn't have and on bad escapes like `\q`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let m = ergo_re::search(r"(?P<key>\w+)=(\w+)", "a=1").unwrap().unwrap();
+    ///
+    /// assert_eq!(m.expand(r"\2\0\g<key>").unwrap(), "1\0a");
+    /// assert!(m.expand(r"\g<value>").is_err());
+    /// ```
+    pub fn expand(&self, template: impl AsRef<str>) -> Result<String, Error> {
+        let pieces = replace::parse(template.as_ref(), &self.pattern)?;
+        let mut expanded = std::string::String::new();
+        replace::expand(&pieces, self, &mut expanded);
+        Ok(String::from(expanded))
+    }
+
+    /// All groups except the whole match, with [`None`] for groups that did not participate.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let m = ergo_re::search(r"(\d+)(x)?", "12").unwrap().unwrap();
+    ///
+    /// assert_eq!(m.groups(), [Some(ergo_core::String::from("12")), None]);
+    /// ```
+    pub fn groups(&self) -> Vec<Option<String>> {
+        (1..self.spans.len()).map(|i| self.group(i)).collect()
+    }
+
+    /// The byte offset where the match starts.
+    pub fn start(&self) -> usize {
+        self.span().0
+    }
+
+    /// The byte offset where the match ends.
+    pub fn end(&self) -> usize {
+        self.span().1
+    }
+
+    /// The byte offsets of the whole match.
+    pub fn span(&self) -> (usize, usize) {
+        self.spans[0].expect("group 0 always participates")
+    }
+
+    /// The string that was searched.
+    pub fn string(&self) -> String {
+        self.string.clone()
+    }
+
+    /// The pattern that matched.
+    pub fn re(&self) -> Pattern {
+        self.pattern.clone()
+    }
+}
+
+/// An iterator over the matches of a [`Pattern`], created with [`Pattern::finditer`]
+pub struct Matches {
+    pattern: Pattern,
+    string: String,
+    locations: regex::CaptureLocations,
+    next_start: Option<usize>,
+    /// Whether the last match was empty and ended at `next_start`
+    after_empty: bool,
+}
+
+impl Matches {
+    pub(crate) fn new(pattern: Pattern, string: String) -> Self {
+        let locations = pattern.regex.capture_locations();
+        Self {
+            pattern,
+            string,
+            locations,
+            next_start: Some(0),
+            after_empty: false,
+        }
+    }
+}
+
+impl Iterator for Matches {
+    type Item = Match;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut start = self.next_start?;
+        let haystack = self.string.as_str();
+        if self.after_empty {
+            // Like Python, prefer a non-empty match where the empty one was, and only then move
+            // past it so the same empty match isn't found again
+            self.after_empty = false;
+            if let Some(regex) = &self.pattern.non_empty {
+                let found =
+                    Match::find_non_empty(regex, self.string.clone(), start, self.pattern.clone());
+                if let Some(found) = found {
+                    self.next_start = Some(found.end());
+                    return Some(found);
+                }
+            }
+            match haystack[start..].chars().next() {
+                Some(c) => start += c.len_utf8(),
+                None => {
+                    self.next_start = None;
+                    return None;
+                }
+            }
+        }
+
+        let found = self
+            .pattern
+            .regex
+            .captures_read_at(&mut self.locations, haystack, start);
+        let (match_start, match_end) = match found {
+            Some(found) => (found.start(), found.end()),
+            None => {
+                self.next_start = None;
+                return None;
+            }
+        };
+
+        // An empty match right after a non-empty one is allowed, as in Python 3.7+
+        self.next_start = Some(match_end);
+        self.after_empty = match_start == match_end;
+
+        Some(Match::new(
+            self.pattern.clone(),
+            self.string.clone(),
+            &self.locations,
+        ))
+    }
+}
+
+impl std::iter::FusedIterator for Matches {}
@@ -0,0 +1,107 @@
+//! Rewrites a parsed pattern to match only its non-empty matches
+//!
+//! After an empty match, Python's `re` looks for a non-empty match at the same position before
+//! moving on: `a*|b` finds `""` and then `"b"` at the start of `"bab"`.  The `regex` crate can't
+//! backtrack into a match it has already found, so instead the pattern is rewritten, keeping
+//! the order alternatives are preferred in.  The exception is a part preferring to match
+//! nothing, like a lazy `x??`, followed by a part that can match something: its non-empty
+//! match is tried first, which can change which groups take part, as with `(x??)(x??)`.
+
+use regex_syntax::hir::{Capture, Hir, HirKind, Repetition};
+
+/// Returns a pattern matching the non-empty matches of `hir`, or [`None`] if it has none.
+pub(crate) fn non_empty(hir: &Hir) -> Option<Hir> {
+    match hir.properties().minimum_len() {
+        // Never matches
+        None => return None,
+        Some(0) => {}
+        Some(_) => return Some(hir.clone()),
+    }
+    if hir.properties().maximum_len() == Some(0) {
+        return None;
+    }
+
+    match hir.kind() {
+        HirKind::Repetition(repetition) => {
+            // Empty iterations don't change the match, so start with a non-empty one
+            let first = non_empty(&repetition.sub)?;
+            let rest = Hir::repetition(Repetition {
+                min: repetition.min.saturating_sub(1),
+                max: repetition.max.map(|max| max - 1),
+                greedy: repetition.greedy,
+                sub: repetition.sub.clone(),
+            });
+            Some(Hir::concat(vec![first, rest]))
+        }
+        HirKind::Capture(capture) => Some(Hir::capture(Capture {
+            index: capture.index,
+            name: capture.name.clone(),
+            sub: Box::new(non_empty(&capture.sub)?),
+        })),
+        HirKind::Concat(items) => non_empty_concat(items),
+        HirKind::Alternation(items) => {
+            let items: Vec<_> = items.iter().filter_map(non_empty).collect();
+            if items.is_empty() {
+                None
+            } else {
+                Some(Hir::alternation(items))
+            }
+        }
+        HirKind::Empty | HirKind::Literal(_) | HirKind::Class(_) | HirKind::Look(_) => None,
+    }
+}
+
+/// Either the first item matches something, or it matches nothing and the rest doesn't.
+fn non_empty_concat(items: &[Hir]) -> Option<Hir> {
+    let (first, rest) = items.split_first()?;
+    let mut alternatives = Vec::new();
+    if let Some(first) = non_empty(first) {
+        let mut concat = vec![first];
+        concat.extend(rest.iter().cloned());
+        alternatives.push(Hir::concat(concat));
+    }
+    if let (Some(first), Some(rest)) = (empty(first), non_empty_concat(rest)) {
+        alternatives.push(Hir::concat(vec![first, rest]));
+    }
+    if alternatives.is_empty() {
+        None
+    } else {
+        Some(Hir::alternation(alternatives))
+    }
+}
+
+/// Returns a pattern matching the empty matches of `hir`, or [`None`] if it has none.
+///
+/// Assertions like `\b` are kept, since they decide whether the empty match is possible.
+fn empty(hir: &Hir) -> Option<Hir> {
+    if hir.properties().minimum_len() != Some(0) {
+        return None;
+    }
+    if hir.properties().maximum_len() == Some(0) {
+        return Some(hir.clone());
+    }
+
+    match hir.kind() {
+        HirKind::Repetition(repetition) if repetition.min == 0 => Some(Hir::empty()),
+        HirKind::Repetition(repetition) => empty(&repetition.sub),
+        HirKind::Capture(capture) => Some(Hir::capture(Capture {
+            index: capture.index,
+            name: capture.name.clone(),
+            sub: Box::new(empty(&capture.sub)?),
+        })),
+        HirKind::Concat(items) => items
+            .iter()
+            .map(empty)
+            .collect::<Option<_>>()
+            .map(Hir::concat),
+        HirKind::Alternation(items) => {
+            let items: Vec<_> = items.iter().filter_map(empty).collect();
+            if items.is_empty() {
+                None
+            } else {
+                Some(Hir::alternation(items))
+            }
+        }
+        HirKind::Empty | HirKind::Literal(_) | HirKind::Class(_) | HirKind::Look(_) => None,
+    }
+}
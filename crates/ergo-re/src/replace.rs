@@ -0,0 +1,280 @@
+use crate::{Error, Match, Pattern};
+
+type StdString = std::string::String;
+
+/// A replacement for [`Pattern::sub`][crate::Pattern::sub]
+///
+/// Implemented for templates (`&str` and strings) and for closures taking a [`Match`] and
+/// returning the replacement text.
+pub trait Replace {
+    /// The template text, if this replacement is a template rather than a closure.
+    ///
+    /// [`Pattern::sub`][crate::Pattern::sub] parses templates once, up front, so invalid ones
+    /// fail even when nothing matches, like in Python.
+    fn template(&self) -> Option<&str> {
+        None
+    }
+
+    /// Appends the replacement for `m` to `dst`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this is a template that isn't valid for `m`'s pattern.  Use
+    /// [`Match::expand`] to get the error instead.
+    fn replace_append(&mut self, m: &Match, dst: &mut StdString);
+}
+
+impl Replace for &str {
+    fn template(&self) -> Option<&str> {
+        Some(self)
+    }
+
+    fn replace_append(&mut self, m: &Match, dst: &mut StdString) {
+        expand_or_panic(self, m, dst);
+    }
+}
+
+impl Replace for StdString {
+    fn template(&self) -> Option<&str> {
+        Some(self)
+    }
+
+    fn replace_append(&mut self, m: &Match, dst: &mut StdString) {
+        expand_or_panic(self, m, dst);
+    }
+}
+
+impl Replace for &StdString {
+    fn template(&self) -> Option<&str> {
+        Some(self)
+    }
+
+    fn replace_append(&mut self, m: &Match, dst: &mut StdString) {
+        expand_or_panic(self, m, dst);
+    }
+}
+
+impl Replace for ergo_core::String {
+    fn template(&self) -> Option<&str> {
+        Some(self.as_str())
+    }
+
+    fn replace_append(&mut self, m: &Match, dst: &mut StdString) {
+        expand_or_panic(self.as_str(), m, dst);
+    }
+}
+
+impl Replace for &ergo_core::String {
+    fn template(&self) -> Option<&str> {
+        Some(self.as_str())
+    }
+
+    fn replace_append(&mut self, m: &Match, dst: &mut StdString) {
+        expand_or_panic(self.as_str(), m, dst);
+    }
+}
+
+impl<F, T> Replace for F
+where
+    F: FnMut(&Match) -> T,
+    T: AsRef<str>,
+{
+    fn replace_append(&mut self, m: &Match, dst: &mut StdString) {
+        dst.push_str(self(m).as_ref());
+    }
+}
+
+fn expand_or_panic(template: &str, m: &Match, dst: &mut StdString) {
+    match parse(template, &m.re()) {
+        Ok(pieces) => expand(&pieces, m, dst),
+        Err(err) => panic!("invalid replacement template: {}", err),
+    }
+}
+
+/// A piece of a parsed template
+#[derive(Debug, PartialEq)]
+pub(crate) enum Piece {
+    Text(StdString),
+    Group(usize),
+}
+
+/// Parses a Python replacement template, failing like Python's `re` does.
+///
+/// `\1`, `\g<1>` and `\g<name>` insert groups.  `\0` and three-digit escapes like `\101` are
+/// octal character codes, and `\n`, `\t`, `\r`, `\a`, `\b`, `\f`, `\v` and `\\` are the usual
+/// escapes.  Other escaped ASCII letters are errors, while other escaped characters are kept
+/// with their backslash.
+pub(crate) fn parse(template: &str, pattern: &Pattern) -> Result<Vec<Piece>, Error> {
+    let error = |message: StdString| Err(Error::Syntax(message));
+    let mut pieces = Vec::new();
+    let mut text = StdString::new();
+    let mut rest = template;
+    while let Some(backslash) = rest.find('\\') {
+        text.push_str(&rest[..backslash]);
+        rest = &rest[backslash + 1..];
+        let position = template.len() - rest.len() - 1;
+
+        let mut group = None;
+        let bytes = rest.as_bytes();
+        let is_octal = |i: usize| matches!(bytes.get(i), Some(b'0'..=b'7'));
+        let is_digit = |i: usize| bytes.get(i).is_some_and(u8::is_ascii_digit);
+        let octal = if bytes.first() == Some(&b'0') {
+            1 + (1..3).take_while(|&i| is_octal(i)).count()
+        } else if (0..3).all(is_octal) {
+            3
+        } else {
+            0
+        };
+        if 0 < octal {
+            let code = u32::from_str_radix(&rest[..octal], 8).expect("octal digits");
+            if 0o377 < code {
+                return error(format!(
+                    "octal escape value \\{} outside of range 0-0o377 at position {}",
+                    &rest[..octal],
+                    position
+                ));
+            }
+            text.push(std::char::from_u32(code).expect("a latin-1 code point"));
+            rest = &rest[octal..];
+            continue;
+        } else if is_digit(0) {
+            let digits = if is_digit(1) { 2 } else { 1 };
+            group = Some((&rest[..digits], position + 1));
+            rest = &rest[digits..];
+        } else if let Some(reference) = rest.strip_prefix("g<") {
+            let close = match reference.find('>') {
+                Some(close) => close,
+                None => {
+                    return error(format!(
+                        "missing >, unterminated name at position {}",
+                        position + 3
+                    ))
+                }
+            };
+            let name = &reference[..close];
+            if name.is_empty() {
+                return error(format!("missing group name at position {}", position + 3));
+            }
+            group = Some((name, position + 3));
+            rest = &reference[close + 1..];
+        }
+
+        if let Some((name, position)) = group {
+            let index = match name.parse::<usize>() {
+                Ok(index) if index <= pattern.groups() => index,
+                Ok(_) => {
+                    return error(format!(
+                        "invalid group reference {} at position {}",
+                        name, position
+                    ))
+                }
+                Err(_) => match pattern.group_index(name) {
+                    Some(index) => index,
+                    None => return error(format!("unknown group name '{}'", name)),
+                },
+            };
+            if !text.is_empty() {
+                pieces.push(Piece::Text(std::mem::take(&mut text)));
+            }
+            pieces.push(Piece::Group(index));
+            continue;
+        }
+
+        let mut chars = rest.chars();
+        match chars.next() {
+            Some('n') => text.push('\n'),
+            Some('t') => text.push('\t'),
+            Some('r') => text.push('\r'),
+            Some('a') => text.push('\u{7}'),
+            Some('b') => text.push('\u{8}'),
+            Some('f') => text.push('\u{c}'),
+            Some('v') => text.push('\u{b}'),
+            Some('\\') => text.push('\\'),
+            Some(c) if c.is_ascii_alphabetic() => {
+                return error(format!("bad escape \\{} at position {}", c, position))
+            }
+            Some(c) => {
+                text.push('\\');
+                text.push(c);
+            }
+            None => {
+                return error(format!(
+                    "bad escape (end of pattern) at position {}",
+                    position
+                ))
+            }
+        }
+        rest = chars.as_str();
+    }
+    text.push_str(rest);
+    if !text.is_empty() {
+        pieces.push(Piece::Text(text));
+    }
+    Ok(pieces)
+}
+
+/// Appends a parsed template to `dst`, with groups that didn't participate left empty.
+pub(crate) fn expand(pieces: &[Piece], m: &Match, dst: &mut StdString) {
+    for piece in pieces {
+        match piece {
+            Piece::Text(text) => dst.push_str(text),
+            Piece::Group(index) => {
+                if let Some(group) = m.group(*index) {
+                    dst.push_str(group.as_str());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parses_like_python() {
+        let pattern = Pattern::new(r"(?P<first>a)(b)").unwrap();
+        let text = |s: &str| Piece::Text(s.into());
+        let cases = [
+            (r"\0", vec![text("\0")]),
+            (r"x\012y", vec![text("x\ny")]),
+            (r"\101\1", vec![text("A"), Piece::Group(1)]),
+            (
+                r"\2\g<0>\g<first>",
+                vec![Piece::Group(2), Piece::Group(0), Piece::Group(1)],
+            ),
+            (r"\1a\1", vec![Piece::Group(1), text("a"), Piece::Group(1)]),
+            (r"\a\v\-\\", vec![text("\u{7}\u{b}\\-\\")]),
+        ];
+        for (template, expected) in cases.iter() {
+            assert_eq!(
+                &parse(template, &pattern).unwrap(),
+                expected,
+                "{}",
+                template
+            );
+        }
+
+        // Messages from CPython 3.12's `re.sub`
+        let errors = [
+            (r"x\3", "invalid group reference 3 at position 2"),
+            (r"\10", "invalid group reference 10 at position 1"),
+            (r"\g<3>", "invalid group reference 3 at position 3"),
+            (r"\g<second>", "unknown group name 'second'"),
+            (r"\g<1", "missing >, unterminated name at position 3"),
+            (r"\g<>", "missing group name at position 3"),
+            (r"\q", "bad escape \\q at position 0"),
+            (
+                r"\400",
+                "octal escape value \\400 outside of range 0-0o377 at position 0",
+            ),
+            ("\\", "bad escape (end of pattern) at position 0"),
+        ];
+        for (template, message) in errors.iter() {
+            match parse(template, &pattern) {
+                Err(Error::Syntax(actual)) => assert_eq!(actual, *message, "{}", template),
+                other => panic!("{}: expected an error, got {:?}", template, other),
+            }
+        }
+    }
+}
@@ -1 +1,13 @@
 pub use ergo_core::*;
+
+#[cfg(feature = "codecs")]
+#[doc(inline)]
+pub use ergo_codecs as codecs;
+
+#[cfg(feature = "difflib")]
+#[doc(inline)]
+pub use ergo_difflib as difflib;
+
+#[cfg(feature = "regex")]
+#[doc(inline)]
+pub use ergo_re as re;